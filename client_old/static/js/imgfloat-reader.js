@@ -1,6 +1,8 @@
 const TARGET_FPS = 60;
 const MS_PER_FRAME = 1000 / TARGET_FPS;
+const DEFAULT_RECONNECT_DELAY_MS = 1000;
 const TWITCH_CHANNEL = window.location.hash.substring(1);
+const OVERLAY_TOKEN = new URLSearchParams(window.location.search).get("token");
 
 let loaded = false;
 let last_mouse_move_ms = 0;
@@ -13,6 +15,7 @@ let ctx;
 let canvas;
 /** @type {WebSocket} */
 let socket;
+let reconnect_delay_ms = DEFAULT_RECONNECT_DELAY_MS;
 let live_assets = [];
 
 function draw() {
@@ -50,19 +53,15 @@ function resize_canvas() {
     canvas.height = window.innerHeight;
 }
 
-document.addEventListener("DOMContentLoaded", async () => {
-    if (!TWITCH_CHANNEL) {
-        window.location.href = "/";
-        return;
-    }
-
-    const protocol = window.location.protocol === "https:" ? "wss" : "ws";
-    const hostname = window.location.port === "" ? window.location.hostname : `${window.location.hostname}:${window.location.port}`;
-    const socket_url = `${protocol}://${hostname}/ws/read/${TWITCH_CHANNEL}`;
-    console.log(`connecting to ${socket_url}`);
+function connect(socket_url) {
+    reconnect_delay_ms = DEFAULT_RECONNECT_DELAY_MS;
     socket = new WebSocket(socket_url);
     socket.onmessage = (event) => {
         const state = JSON.parse(event.data);
+        if (state.Restarting) {
+            reconnect_delay_ms = state.Restarting.reconnect_in_ms;
+            return;
+        }
         if (state.New) {
             live_assets = state.New.assets.map((a) => {
                 const image = new Image();
@@ -86,8 +85,21 @@ document.addEventListener("DOMContentLoaded", async () => {
             console.error("Unknown state", state);
         }
     }
-    socket.onclose = () => socket = new WebSocket(socket_url);
-    socket.onerror = () => socket = new WebSocket(socket_url);
+    socket.onclose = () => setTimeout(() => connect(socket_url), reconnect_delay_ms);
+}
+
+document.addEventListener("DOMContentLoaded", async () => {
+    if (!TWITCH_CHANNEL) {
+        window.location.href = "/";
+        return;
+    }
+
+    const protocol = window.location.protocol === "https:" ? "wss" : "ws";
+    const hostname = window.location.port === "" ? window.location.hostname : `${window.location.hostname}:${window.location.port}`;
+    const token_query = OVERLAY_TOKEN ? `?token=${encodeURIComponent(OVERLAY_TOKEN)}` : "";
+    const socket_url = `${protocol}://${hostname}/ws/read/${TWITCH_CHANNEL}${token_query}`;
+    console.log(`connecting to ${socket_url}`);
+    connect(socket_url);
 
     canvas = document.getElementById("imgfloat");
     ctx = canvas.getContext("2d");
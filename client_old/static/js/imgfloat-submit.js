@@ -0,0 +1,30 @@
+const TWITCH_CHANNEL = window.location.hash.substring(1);
+
+async function submit_image() {
+    const file = document.getElementById("submission-file").files[0];
+    const status = document.getElementById("submission-status");
+    if (!file) {
+        return;
+    }
+    const body = new FormData();
+    body.append("file", file);
+    await fetch(`/api/submissions/${TWITCH_CHANNEL}`, { method: "POST", body })
+        .then((r) => {
+            if (r.status === 401) {
+                window.location.href = "/auth/login";
+                throw new Error();
+            }
+            if (r.status === 429) {
+                throw new Error("You already have several submissions waiting for review.");
+            }
+            if (!r.ok) {
+                throw new Error(`Server error: ${r.status}`);
+            }
+            status.innerText = "Submitted! The channel team will review it shortly.";
+        })
+        .catch((error) => status.innerText = error.message);
+}
+
+document.addEventListener("DOMContentLoaded", () => {
+    document.getElementById("submission-channel").innerText = TWITCH_CHANNEL;
+});
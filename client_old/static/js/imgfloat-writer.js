@@ -1,5 +1,7 @@
+const DEFAULT_RECONNECT_DELAY_MS = 1000;
 const TWITCH_CHANNEL = window.location.hash.substring(1);
 const HOSTNAME = window.location.hostname;
+const CHANNEL_ROLES = ["viewer_submitter", "editor", "manager"];
 
 let loaded = false;
 let last_mouse_move_ms = 0;
@@ -12,6 +14,7 @@ let ctx;
 let canvas;
 /** @type {WebSocket} */
 let socket;
+let reconnect_delay_ms = DEFAULT_RECONNECT_DELAY_MS;
 let live_assets = [];
 let selected_asset_id;
 let is_dragging = false;
@@ -79,6 +82,8 @@ function close_assets_window() {
 function update_dom_with_new_settings(settings) {
     document.getElementById("settings.fps-target").value = settings.fps_target;
     document.getElementById("settings.background-opacity").value = settings.background_opacity;
+    document.getElementById("settings.chat-commands").checked = settings.chat_commands;
+    document.getElementById("settings.sync-moderators").checked = settings.sync_moderators;
     document.getElementById("dim").style.backgroundColor = `rgba(0, 0, 0, ${(100 - settings.background_opacity) / 100})`;
     ms_per_frame = 1000 / settings.fps_target;
     document.getElementById("settings").classList.remove("show");
@@ -91,6 +96,8 @@ async function save_settings() {
         body: JSON.stringify({
             fps_target: Number.parseInt(document.getElementById("settings.fps-target").value, 10),
             background_opacity: Number.parseInt(document.getElementById("settings.background-opacity").value, 10),
+            chat_commands: document.getElementById("settings.chat-commands").checked,
+            sync_moderators: document.getElementById("settings.sync-moderators").checked,
         }),
     })
         .then((r) => {
@@ -104,6 +111,79 @@ async function save_settings() {
         .then(update_dom_with_new_settings);
 }
 
+async function regenerate_overlay_token() {
+    await fetch("/api/settings/overlay-token", { method: "POST" })
+        .then((r) => {
+            if (!r.ok) {
+                throw r.status;
+            }
+            return r.json();
+        })
+        .then(({ token }) => {
+            const link = `${window.location.origin}/read.html?token=${encodeURIComponent(token)}#${TWITCH_CHANNEL}`;
+            document.getElementById("overlay-link").value = link;
+        })
+        .catch(alert);
+}
+
+async function revoke_overlay_token() {
+    await fetch("/api/settings/overlay-token", { method: "DELETE" })
+        .then(() => document.getElementById("overlay-link").value = "")
+        .catch(alert);
+}
+
+async function refresh_channel_admins() {
+    let channel_admins = await fetch("/api/channel-admins")
+        .then((r) => r.json())
+        .catch(() => []);
+    document.getElementById("channel-admin-list").innerHTML = channel_admins
+        .map((admin) => {
+            const roles = CHANNEL_ROLES
+                .map((role) => `<option value="${role}" ${role === admin.role ? "selected" : ""}>${role.replace("_", " ")}</option>`)
+                .join("");
+            return `<div class="channel-admin"><span>${admin.username}</span><em>${admin.source}</em><select onchange="set_channel_admin_role('${admin.username}', this.value)">${roles}</select><i class="bi bi-x" onclick="remove_channel_admin('${admin.username}')"></i></div>`;
+        })
+        .join("\n");
+}
+
+async function add_channel_admin() {
+    const input = document.getElementById("channel-admin-name");
+    await fetch("/api/channel-admins", { method: "POST", body: input.value })
+        .then((r) => {
+            if (!r.ok) {
+                throw r.status;
+            }
+            input.value = "";
+        })
+        .catch(alert);
+    await refresh_channel_admins();
+}
+
+async function set_channel_admin_role(username, role) {
+    await fetch(`/api/channel-admins/${username}`, {
+        headers: { "Content-Type": "application/json" },
+        method: "PUT",
+        body: JSON.stringify({ role }),
+    }).catch(alert);
+    await refresh_channel_admins();
+}
+
+async function remove_channel_admin(username) {
+    await fetch(`/api/channel-admins/${username}`, { method: "DELETE" }).catch(alert);
+    await refresh_channel_admins();
+}
+
+async function sync_moderators() {
+    await fetch("/api/channel-admins/sync", { method: "POST" })
+        .then((r) => {
+            if (!r.ok) {
+                throw r.status;
+            }
+        })
+        .catch(alert);
+    await refresh_channel_admins();
+}
+
 function cancel_settings() {
     document.getElementById("settings").classList.remove("show");
 }
@@ -153,7 +233,9 @@ async function add_asset(filename) {
 }
 
 async function refresh_file_list() {
-    let image_list = await fetch(`/api/assets/${TWITCH_CHANNEL}`).then((r) => r.json());
+    let image_list = await fetch(`/api/assets/${TWITCH_CHANNEL}?per_page=200`)
+        .then((r) => r.json())
+        .then((page) => page.assets);
     image_list.sort();
     let image_list_html = image_list
         .map((asset) => `<div class="asset"><i class="bi bi-file-earmark-image"></i><span onclick="add_asset('${asset.filename}')">${asset.filename}</span></div>`)
@@ -176,6 +258,41 @@ async function refresh_file_list() {
     `;
 }
 
+async function refresh_submission_list() {
+    let submissions = await fetch(`/api/submissions/${TWITCH_CHANNEL}`)
+        .then((r) => r.json())
+        .catch(() => []);
+    // the filename and submitter come from viewers, so never parse them as html
+    const list = document.getElementById("submission-list");
+    list.replaceChildren(...submissions.map((submission) => {
+        const element = document.createElement("div");
+        element.className = "submission";
+        const image = document.createElement("img");
+        image.setAttribute("src", `/api/submissions/${TWITCH_CHANNEL}/${submission.id}/file`);
+        image.setAttribute("alt", submission.original_filename);
+        const submitter = document.createElement("span");
+        submitter.textContent = submission.submitter;
+        element.append(image, submitter);
+        for (const [decision, label] of [["approve", "Approve"], ["approve_and_show", "Approve and show"], ["reject", "Reject"]]) {
+            const button = document.createElement("button");
+            button.textContent = label;
+            button.addEventListener("click", () => review_submission(submission.id, decision));
+            element.append(button);
+        }
+        return element;
+    }));
+}
+
+async function review_submission(id, decision) {
+    await fetch(`/api/submissions/${TWITCH_CHANNEL}/${id}`, {
+        headers: { "Content-Type": "application/json" },
+        method: "POST",
+        body: JSON.stringify({ decision }),
+    }).catch(alert);
+    await refresh_submission_list();
+    await refresh_file_list();
+}
+
 function open_file_dialog() {
     document.getElementById("asset-upload-file").click();
 }
@@ -211,6 +328,30 @@ document.addEventListener("keyup", (event) => {
     }
 });
 
+function connect(socket_url) {
+    reconnect_delay_ms = DEFAULT_RECONNECT_DELAY_MS;
+    socket = new WebSocket(socket_url);
+    socket.onmessage = (event) => {
+        const state = JSON.parse(event.data);
+        if (state.Restarting) {
+            reconnect_delay_ms = state.Restarting.reconnect_in_ms;
+            return;
+        }
+        if (state.SubmissionReceived || state.SubmissionReviewed) {
+            refresh_submission_list();
+            refresh_file_list();
+            return;
+        }
+        selected_asset_id = undefined;
+        live_assets = state.assets.map((a) => {
+            const image = new Image();
+            image.src = a.url;
+            return { id: a.id, x: a.x, y: a.y, w: a.w, h: a.h, image }
+        })
+    }
+    socket.onclose = () => setTimeout(() => connect(socket_url), reconnect_delay_ms);
+}
+
 document.addEventListener("DOMContentLoaded", async () => {
     if (!TWITCH_CHANNEL) {
         window.location.href = "/";
@@ -228,18 +369,7 @@ document.addEventListener("DOMContentLoaded", async () => {
     const hostname = window.location.port === "" ? window.location.hostname : `${window.location.hostname}:${window.location.port}`;
     const socket_url = `${protocol}://${hostname}/ws/write/${TWITCH_CHANNEL}`;
     console.log(`connecting to ${socket_url}`);
-    socket = new WebSocket(socket_url);
-    socket.onmessage = (event) => {
-        selected_asset_id = undefined;
-        const state = JSON.parse(event.data);
-        live_assets = state.assets.map((a) => {
-            const image = new Image();
-            image.src = a.url;
-            return { id: a.id, x: a.x, y: a.y, w: a.w, h: a.h, image }
-        })
-    }
-    socket.onclose = () => socket = new WebSocket(socket_url);
-    socket.onerror = () => socket = new WebSocket(socket_url);
+    connect(socket_url);
 
     canvas = document.getElementById("imgfloat");
     ctx = canvas.getContext("2d");
@@ -250,6 +380,8 @@ document.addEventListener("DOMContentLoaded", async () => {
     setInterval(update_fps, 1000);
 
     refresh_file_list();
+    refresh_submission_list();
+    refresh_channel_admins();
     document.querySelectorAll(".is-loading").forEach((n) => n.classList.remove("is-loading"));
     document.getElementById("twitch-iframe").setAttribute("src", `https://player.twitch.tv/?channel=${TWITCH_CHANNEL}&autoplay=true&muted=true&parent=${HOSTNAME}`);
 
@@ -0,0 +1,8 @@
+DROP INDEX assets_username_uploaded_at;
+DROP TABLE asset_tags;
+
+ALTER TABLE assets DROP COLUMN uploaded_at;
+ALTER TABLE assets DROP COLUMN folder_id;
+
+DROP TABLE tags;
+DROP TABLE folders;
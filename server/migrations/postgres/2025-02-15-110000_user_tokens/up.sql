@@ -0,0 +1,10 @@
+CREATE TABLE user_tokens (
+    username VARCHAR NOT NULL,
+    access_token VARCHAR NOT NULL,
+    refresh_token VARCHAR,
+    expires_at BIGINT NOT NULL,
+    scope VARCHAR NOT NULL,
+    token_type VARCHAR NOT NULL,
+    PRIMARY KEY(username),
+    FOREIGN KEY(username) REFERENCES users(username)
+)
@@ -0,0 +1,6 @@
+ALTER TABLE user_settings DROP COLUMN sync_moderators;
+
+DELETE FROM channel_admins WHERE username NOT IN (SELECT username FROM users);
+ALTER TABLE channel_admins DROP COLUMN source;
+ALTER TABLE channel_admins
+    ADD CONSTRAINT channel_admins_username_fkey FOREIGN KEY(username) REFERENCES users(username);
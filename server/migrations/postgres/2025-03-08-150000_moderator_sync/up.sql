@@ -0,0 +1,4 @@
+ALTER TABLE channel_admins DROP CONSTRAINT channel_admins_username_fkey;
+ALTER TABLE channel_admins ADD COLUMN source VARCHAR NOT NULL DEFAULT 'manual';
+
+ALTER TABLE user_settings ADD COLUMN sync_moderators BOOLEAN NOT NULL DEFAULT FALSE;
@@ -0,0 +1 @@
+ALTER TABLE channel_admins DROP COLUMN role;
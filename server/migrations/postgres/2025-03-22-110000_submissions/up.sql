@@ -0,0 +1,16 @@
+CREATE TABLE submissions (
+    id SERIAL PRIMARY KEY,
+    broadcaster_username VARCHAR NOT NULL,
+    submitter VARCHAR NOT NULL,
+    local_filename VARCHAR NOT NULL UNIQUE,
+    original_filename VARCHAR NOT NULL,
+    checksum VARCHAR NOT NULL,
+    content_type VARCHAR NOT NULL,
+    status VARCHAR NOT NULL DEFAULT 'pending',
+    submitted_at BIGINT NOT NULL,
+    reviewed_by VARCHAR,
+    reviewed_at BIGINT,
+    FOREIGN KEY(broadcaster_username) REFERENCES users(username)
+);
+
+CREATE INDEX submissions_broadcaster_status ON submissions(broadcaster_username, status);
@@ -0,0 +1,12 @@
+CREATE TABLE api_tokens (
+    id SERIAL PRIMARY KEY,
+    username VARCHAR NOT NULL,
+    name VARCHAR NOT NULL,
+    token_hash VARCHAR NOT NULL UNIQUE,
+    scopes VARCHAR NOT NULL,
+    created_at BIGINT NOT NULL,
+    expires_at BIGINT,
+    last_used_at BIGINT,
+    FOREIGN KEY(username) REFERENCES users(username)
+);
+CREATE INDEX api_tokens_username ON api_tokens(username);
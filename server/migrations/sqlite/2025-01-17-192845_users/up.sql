@@ -0,0 +1,4 @@
+CREATE TABLE users (
+  username VARCHAR NOT NULL,
+  PRIMARY KEY(username)
+)
@@ -0,0 +1,21 @@
+DROP INDEX assets_username_uploaded_at;
+DROP TABLE asset_tags;
+
+CREATE TABLE assets_without_folders (
+    local_filename VARCHAR NOT NULL,
+    original_filename VARCHAR NOT NULL,
+    checksum VARCHAR NOT NULL,
+    content_type VARCHAR NOT NULL,
+    username VARCHAR NOT NULL,
+    PRIMARY KEY(local_filename),
+    FOREIGN KEY(username) REFERENCES users(username),
+    UNIQUE(local_filename),
+    UNIQUE(checksum)
+);
+INSERT INTO assets_without_folders
+    SELECT local_filename, original_filename, checksum, content_type, username FROM assets;
+DROP TABLE assets;
+ALTER TABLE assets_without_folders RENAME TO assets;
+
+DROP TABLE tags;
+DROP TABLE folders;
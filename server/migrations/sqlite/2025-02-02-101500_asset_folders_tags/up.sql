@@ -0,0 +1,28 @@
+CREATE TABLE folders (
+    id INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+    username VARCHAR NOT NULL,
+    name VARCHAR NOT NULL,
+    FOREIGN KEY(username) REFERENCES users(username),
+    UNIQUE(username, name)
+);
+
+CREATE TABLE tags (
+    id INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+    username VARCHAR NOT NULL,
+    name VARCHAR NOT NULL,
+    FOREIGN KEY(username) REFERENCES users(username),
+    UNIQUE(username, name)
+);
+
+CREATE TABLE asset_tags (
+    local_filename VARCHAR NOT NULL,
+    tag_id INTEGER NOT NULL,
+    PRIMARY KEY(local_filename, tag_id),
+    FOREIGN KEY(local_filename) REFERENCES assets(local_filename) ON DELETE CASCADE,
+    FOREIGN KEY(tag_id) REFERENCES tags(id) ON DELETE CASCADE
+);
+
+ALTER TABLE assets ADD COLUMN folder_id INTEGER REFERENCES folders(id) ON DELETE SET NULL;
+ALTER TABLE assets ADD COLUMN uploaded_at BIGINT NOT NULL DEFAULT 0;
+
+CREATE INDEX assets_username_uploaded_at ON assets(username, uploaded_at);
@@ -0,0 +1,8 @@
+CREATE TABLE sessions (
+    id VARCHAR NOT NULL,
+    data TEXT NOT NULL,
+    expiry_date BIGINT NOT NULL,
+    PRIMARY KEY(id)
+);
+
+CREATE INDEX sessions_expiry_date ON sessions(expiry_date);
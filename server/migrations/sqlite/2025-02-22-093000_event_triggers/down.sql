@@ -0,0 +1,2 @@
+DROP TABLE eventsub_subscriptions;
+DROP TABLE event_triggers;
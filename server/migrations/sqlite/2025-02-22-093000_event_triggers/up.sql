@@ -0,0 +1,25 @@
+CREATE TABLE event_triggers (
+    id INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+    username VARCHAR NOT NULL,
+    event_type VARCHAR NOT NULL,
+    reward_id VARCHAR,
+    asset_filename VARCHAR NOT NULL,
+    x REAL NOT NULL,
+    y REAL NOT NULL,
+    w REAL NOT NULL,
+    h REAL NOT NULL,
+    duration_seconds INTEGER NOT NULL,
+    FOREIGN KEY(username) REFERENCES users(username),
+    FOREIGN KEY(asset_filename) REFERENCES assets(local_filename) ON DELETE CASCADE
+);
+
+CREATE INDEX event_triggers_username_event_type ON event_triggers(username, event_type);
+
+CREATE TABLE eventsub_subscriptions (
+    id VARCHAR NOT NULL PRIMARY KEY,
+    username VARCHAR NOT NULL,
+    event_type VARCHAR NOT NULL,
+    status VARCHAR NOT NULL,
+    FOREIGN KEY(username) REFERENCES users(username),
+    UNIQUE(username, event_type)
+);
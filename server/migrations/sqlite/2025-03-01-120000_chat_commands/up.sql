@@ -0,0 +1 @@
+ALTER TABLE user_settings ADD COLUMN chat_commands BOOLEAN NOT NULL DEFAULT 0;
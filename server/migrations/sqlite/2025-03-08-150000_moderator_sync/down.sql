@@ -0,0 +1,14 @@
+ALTER TABLE user_settings DROP COLUMN sync_moderators;
+
+DELETE FROM channel_admins WHERE username NOT IN (SELECT username FROM users);
+CREATE TABLE channel_admins_by_user (
+    username VARCHAR NOT NULL,
+    broadcaster_username VARCHAR NOT NULL,
+    PRIMARY KEY(username, broadcaster_username),
+    FOREIGN KEY(username) REFERENCES users(username),
+    FOREIGN KEY(broadcaster_username) REFERENCES users(username)
+);
+INSERT INTO channel_admins_by_user
+    SELECT username, broadcaster_username FROM channel_admins;
+DROP TABLE channel_admins;
+ALTER TABLE channel_admins_by_user RENAME TO channel_admins;
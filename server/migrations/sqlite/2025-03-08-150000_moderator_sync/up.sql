@@ -0,0 +1,13 @@
+CREATE TABLE channel_admins_by_login (
+    username VARCHAR NOT NULL,
+    broadcaster_username VARCHAR NOT NULL,
+    source VARCHAR NOT NULL DEFAULT 'manual',
+    PRIMARY KEY(username, broadcaster_username),
+    FOREIGN KEY(broadcaster_username) REFERENCES users(username)
+);
+INSERT INTO channel_admins_by_login (username, broadcaster_username)
+    SELECT username, broadcaster_username FROM channel_admins;
+DROP TABLE channel_admins;
+ALTER TABLE channel_admins_by_login RENAME TO channel_admins;
+
+ALTER TABLE user_settings ADD COLUMN sync_moderators BOOLEAN NOT NULL DEFAULT 0;
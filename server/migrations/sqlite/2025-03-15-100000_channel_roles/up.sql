@@ -0,0 +1 @@
+ALTER TABLE channel_admins ADD COLUMN role VARCHAR NOT NULL DEFAULT 'editor';
@@ -0,0 +1,7 @@
+CREATE TABLE overlay_tokens (
+    username VARCHAR NOT NULL,
+    token_hash VARCHAR NOT NULL,
+    created_at BIGINT NOT NULL,
+    PRIMARY KEY(username),
+    FOREIGN KEY(username) REFERENCES users(username)
+)
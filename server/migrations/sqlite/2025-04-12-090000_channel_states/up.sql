@@ -0,0 +1,7 @@
+CREATE TABLE channel_states (
+    username VARCHAR NOT NULL,
+    state TEXT NOT NULL,
+    saved_at BIGINT NOT NULL,
+    PRIMARY KEY(username),
+    FOREIGN KEY(username) REFERENCES users(username)
+)
@@ -0,0 +1,329 @@
+use std::{
+    collections::{hash_map::Entry, HashMap},
+    io::{Read, Write},
+};
+
+use sha2::{Digest, Sha256};
+use uuid::Uuid;
+
+use axum::http::StatusCode;
+
+use crate::{
+    domain::{
+        db::{Database, DbError},
+        message::ImgfloatState,
+        ApiError,
+    },
+    models::{
+        Asset, AssetArchiveManifest, AssetImportSummary, UnownedAsset, UnownedAssetMetadata, User,
+    },
+};
+
+#[derive(Debug)]
+pub enum AssetArchiveError {
+    Io(std::io::Error),
+    MissingManifest,
+    InvalidManifest(serde_json::Error),
+    UnsupportedVersion(u32),
+    MissingFile(String),
+    EntryTooLarge(String),
+    ChecksumMismatch(String),
+    UnsupportedContentType(String),
+    InvalidMetadata(String),
+    Database(DbError),
+}
+
+impl From<AssetArchiveError> for ApiError {
+    fn from(error: AssetArchiveError) -> ApiError {
+        match error {
+            AssetArchiveError::Io(_) => ApiError::storage("unable to read or write asset files"),
+            AssetArchiveError::Database(error) => error.into(),
+            AssetArchiveError::MissingManifest => {
+                ApiError::bad_request("invalid_archive", "archive has no manifest")
+            }
+            AssetArchiveError::InvalidManifest(error) => {
+                ApiError::bad_request("invalid_archive", format!("invalid manifest: {error}"))
+            }
+            AssetArchiveError::UnsupportedVersion(version) => ApiError::bad_request(
+                "unsupported_archive_version",
+                format!("archive version {version} is not supported"),
+            ),
+            AssetArchiveError::MissingFile(filename) => {
+                ApiError::bad_request("invalid_archive", format!("archive is missing {filename}"))
+            }
+            AssetArchiveError::EntryTooLarge(path) => {
+                ApiError::bad_request("invalid_archive", format!("{path} is too large"))
+            }
+            AssetArchiveError::ChecksumMismatch(filename) => ApiError::bad_request(
+                "checksum_mismatch",
+                format!("checksum of {filename} does not match the manifest"),
+            ),
+            AssetArchiveError::UnsupportedContentType(filename) => ApiError::new(
+                StatusCode::UNSUPPORTED_MEDIA_TYPE,
+                "unsupported_media_type",
+                format!("{filename} has an unsupported content type"),
+            ),
+            AssetArchiveError::InvalidMetadata(message) => {
+                ApiError::bad_request("invalid_metadata", message)
+            }
+        }
+    }
+}
+
+impl From<std::io::Error> for AssetArchiveError {
+    fn from(value: std::io::Error) -> Self {
+        Self::Io(value)
+    }
+}
+
+pub struct AssetArchive {
+    pub manifest: AssetArchiveManifest,
+    files: HashMap<String, Vec<u8>>,
+}
+
+impl AssetArchive {
+    pub const MAX_SIZE: u64 = 512 * 1024 * 1024;
+
+    /// Makes sure every file `write` will add can be read, so an export can
+    /// fail before anything was sent.
+    pub fn check_files(
+        manifest: &AssetArchiveManifest,
+        asset_dir: &str,
+    ) -> Result<(), AssetArchiveError> {
+        for entry in &manifest.assets {
+            let asset_path = format!("{asset_dir}/{}", entry.filename);
+            let is_file = std::fs::File::open(&asset_path)
+                .and_then(|file| file.metadata())
+                .map(|metadata| metadata.is_file())
+                .inspect_err(|error| {
+                    tracing::error!(?error, ?asset_path, "unable to open asset")
+                })?;
+            if !is_file {
+                tracing::error!(?asset_path, "asset is not a file");
+                return Err(AssetArchiveError::Io(std::io::Error::new(
+                    std::io::ErrorKind::InvalidInput,
+                    format!("{asset_path} is not a file"),
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    pub fn write(
+        writer: impl Write,
+        manifest: &AssetArchiveManifest,
+        asset_dir: &str,
+    ) -> Result<(), AssetArchiveError> {
+        let mut builder = tar::Builder::new(writer);
+        let manifest_json =
+            serde_json::to_vec_pretty(manifest).map_err(AssetArchiveError::InvalidManifest)?;
+        let mut header = tar::Header::new_gnu();
+        header.set_size(manifest_json.len() as u64);
+        header.set_mode(0o644);
+        header.set_mtime(manifest.exported_at.max(0) as u64);
+        builder.append_data(
+            &mut header,
+            AssetArchiveManifest::FILENAME,
+            manifest_json.as_slice(),
+        )?;
+
+        for entry in &manifest.assets {
+            let asset_path = format!("{asset_dir}/{}", entry.filename);
+            let file = std::fs::File::open(&asset_path).inspect_err(|error| {
+                tracing::error!(?error, ?asset_path, "unable to open asset")
+            })?;
+            let mut header = tar::Header::new_gnu();
+            header.set_size(file.metadata()?.len());
+            header.set_mode(0o644);
+            header.set_mtime(entry.uploaded_at.max(0) as u64);
+            builder.append_data(
+                &mut header,
+                format!("{}{}", AssetArchiveManifest::ASSET_PREFIX, entry.filename),
+                file,
+            )?;
+        }
+        builder.into_inner()?.flush()?;
+        Ok(())
+    }
+
+    pub fn read(data: impl Read) -> Result<Self, AssetArchiveError> {
+        let mut archive = tar::Archive::new(data);
+        let mut manifest = None;
+        let mut files = HashMap::new();
+        for entry in archive.entries()? {
+            let mut entry = entry?;
+            if !entry.header().entry_type().is_file() {
+                continue;
+            }
+            let path = entry.path()?.to_string_lossy().to_string();
+            // the header's size comes from the client, so it is only trusted
+            // as far as the limit on the whole request
+            if entry.size() > Self::MAX_SIZE {
+                tracing::error!(?path, size = entry.size(), "archive entry too large");
+                return Err(AssetArchiveError::EntryTooLarge(path));
+            }
+            let mut contents = Vec::new();
+            entry
+                .by_ref()
+                .take(Self::MAX_SIZE)
+                .read_to_end(&mut contents)?;
+            if path == AssetArchiveManifest::FILENAME {
+                manifest = Some(
+                    serde_json::from_slice::<AssetArchiveManifest>(&contents)
+                        .map_err(AssetArchiveError::InvalidManifest)?,
+                );
+            } else if let Some(filename) = path.strip_prefix(AssetArchiveManifest::ASSET_PREFIX) {
+                files.insert(filename.to_string(), contents);
+            } else {
+                tracing::warn!(?path, "ignoring unknown archive entry");
+            }
+        }
+        let archive = Self {
+            manifest: manifest.ok_or(AssetArchiveError::MissingManifest)?,
+            files,
+        };
+        archive.validate()?;
+        Ok(archive)
+    }
+
+    fn validate(&self) -> Result<(), AssetArchiveError> {
+        if self.manifest.version != AssetArchiveManifest::VERSION {
+            tracing::error!(
+                version = self.manifest.version,
+                "unsupported archive version"
+            );
+            return Err(AssetArchiveError::UnsupportedVersion(self.manifest.version));
+        }
+        for entry in &self.manifest.assets {
+            let data = self
+                .files
+                .get(&entry.filename)
+                .ok_or_else(|| AssetArchiveError::MissingFile(entry.filename.clone()))?;
+            let mut hasher = Sha256::new();
+            hasher.update(data);
+            if format!("{:x}", hasher.finalize()) != entry.checksum {
+                tracing::error!(?entry, "archive checksum mismatch");
+                return Err(AssetArchiveError::ChecksumMismatch(entry.filename.clone()));
+            }
+        }
+        Ok(())
+    }
+
+    pub async fn import(
+        self,
+        database: &dyn Database,
+        owner: &User,
+        asset_dir: &str,
+    ) -> Result<(AssetImportSummary, Vec<ImgfloatState>), AssetArchiveError> {
+        let mut summary = AssetImportSummary::default();
+        let mut renamed: HashMap<String, String> = HashMap::new();
+        let mut imports = Vec::new();
+        let mut checksums: HashMap<&str, String> = HashMap::new();
+        // everything is checked before the first file is written, so a bad
+        // entry can't leave a partial import behind
+        for entry in &self.manifest.assets {
+            if !UnownedAsset::is_supported_content_type(&entry.content_type) {
+                tracing::error!(?entry, "unsupported content type in archive");
+                return Err(AssetArchiveError::UnsupportedContentType(
+                    entry.filename.clone(),
+                ));
+            }
+            let metadata = UnownedAssetMetadata {
+                folder: entry.folder.clone(),
+                tags: entry.tags.clone(),
+            }
+            .validate()
+            .map_err(|name| {
+                AssetArchiveError::InvalidMetadata(format!(
+                    "{}: invalid folder or tag {name}",
+                    entry.filename
+                ))
+            })?;
+
+            let local_filename = match checksums.entry(&entry.checksum) {
+                Entry::Occupied(imported) => {
+                    tracing::debug!(?entry, "skipping asset repeated in archive");
+                    renamed.insert(entry.filename.clone(), imported.get().clone());
+                    summary.duplicates.push(entry.filename.clone());
+                    continue;
+                }
+                Entry::Vacant(checksum) => {
+                    let existing = database
+                        .get_asset_by_checksum(&entry.checksum)
+                        .map_err(AssetArchiveError::Database)?;
+                    match existing {
+                        Some(existing) if existing.username == owner.username => {
+                            tracing::debug!(?entry, ?existing, "skipping duplicate asset");
+                            checksum.insert(existing.local_filename.clone());
+                            renamed.insert(entry.filename.clone(), existing.local_filename);
+                            summary.duplicates.push(entry.filename.clone());
+                            continue;
+                        }
+                        Some(_) => {
+                            tracing::warn!(?entry, "asset checksum belongs to another channel");
+                            summary.conflicts.push(entry.filename.clone());
+                            continue;
+                        }
+                        None => {}
+                    }
+                    let local_filename = match entry.filename.rsplit_once('.') {
+                        Some((_, extension))
+                            if extension.chars().all(|c| c.is_ascii_alphanumeric()) =>
+                        {
+                            format!("{}.{}", Uuid::new_v4(), extension)
+                        }
+                        _ => Uuid::new_v4().to_string(),
+                    };
+                    checksum.insert(local_filename.clone());
+                    local_filename
+                }
+            };
+            let asset = Asset {
+                local_filename,
+                original_filename: entry.original_filename.clone(),
+                checksum: entry.checksum.clone(),
+                content_type: entry.content_type.clone(),
+                username: owner.username.clone(),
+                folder_id: None,
+                uploaded_at: entry.uploaded_at,
+            };
+            imports.push((entry, asset, metadata));
+        }
+
+        for (entry, asset, metadata) in imports {
+            let asset_path = format!("{asset_dir}/{}", asset.local_filename);
+            tokio::fs::write(&asset_path, &self.files[&entry.filename])
+                .await
+                .inspect_err(|error| {
+                    tracing::error!(?error, ?asset_path, "unable to write file")
+                })?;
+            let created = database
+                .create_asset(&asset)
+                .and_then(|asset| database.set_asset_metadata(&asset, &metadata));
+            if let Err(error) = created {
+                let _ = tokio::fs::remove_file(&asset_path).await;
+                return Err(AssetArchiveError::Database(error));
+            }
+            renamed.insert(entry.filename.clone(), asset.local_filename);
+            summary.imported.push(entry.filename.clone());
+        }
+
+        let scenes: Vec<ImgfloatState> = self
+            .manifest
+            .scenes
+            .into_iter()
+            .map(|mut scene| {
+                for asset in scene.assets.iter_mut() {
+                    let filename = asset.url.rsplit('/').next().unwrap_or_default();
+                    if let Some(local_filename) = renamed.get(filename) {
+                        asset.url = format!("/api/assets/{}/{}", owner.username, local_filename);
+                    }
+                }
+                scene
+            })
+            .collect();
+        summary.scenes_restored = scenes.len();
+        tracing::info!(?owner, ?summary, "imported asset archive");
+        Ok((summary, scenes))
+    }
+}
@@ -0,0 +1,52 @@
+use std::{path::PathBuf, time::SystemTime};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct StoredAsset {
+    pub filename: String,
+    pub modified: SystemTime,
+}
+
+pub trait AssetStorage: Send + Sync {
+    fn list(&self) -> std::io::Result<Vec<StoredAsset>>;
+    fn read(&self, filename: &str) -> std::io::Result<Vec<u8>>;
+    fn delete(&self, filename: &str) -> std::io::Result<()>;
+}
+
+#[derive(Debug, Clone)]
+pub struct FilesystemAssetStorage {
+    directory: PathBuf,
+}
+
+impl FilesystemAssetStorage {
+    pub fn new(directory: impl Into<PathBuf>) -> Self {
+        Self {
+            directory: directory.into(),
+        }
+    }
+}
+
+impl AssetStorage for FilesystemAssetStorage {
+    fn list(&self) -> std::io::Result<Vec<StoredAsset>> {
+        let mut stored_assets = vec![];
+        for entry in std::fs::read_dir(&self.directory)? {
+            let entry = entry?;
+            let metadata = entry.metadata()?;
+            if !metadata.is_file() {
+                continue;
+            }
+            stored_assets.push(StoredAsset {
+                filename: entry.file_name().to_string_lossy().to_string(),
+                modified: metadata.modified()?,
+            });
+        }
+        Ok(stored_assets)
+    }
+
+    fn read(&self, filename: &str) -> std::io::Result<Vec<u8>> {
+        std::fs::read(self.directory.join(filename))
+    }
+
+    fn delete(&self, filename: &str) -> std::io::Result<()> {
+        std::fs::remove_file(self.directory.join(filename))
+    }
+}
@@ -0,0 +1,43 @@
+use std::{
+    collections::VecDeque,
+    sync::{Arc, RwLock},
+};
+
+use tokio::sync::Mutex;
+
+use super::{Backplane, BackplaneError, BackplaneMessage, BackplaneSubscriber};
+
+/// Delivers messages in-process before `publish` returns. With a single
+/// attached controller this is the plain in-memory broadcast; several
+/// controllers attached to one instance behave like separate servers.
+#[derive(Default)]
+pub struct LocalBackplane {
+    subscribers: RwLock<Vec<Arc<dyn BackplaneSubscriber>>>,
+    delivery: Mutex<()>,
+}
+
+impl LocalBackplane {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[async_trait::async_trait]
+impl Backplane for LocalBackplane {
+    async fn publish(&self, message: BackplaneMessage) -> Result<(), BackplaneError> {
+        // one delivery at a time keeps the order identical for every subscriber
+        let _delivery = self.delivery.lock().await;
+        let subscribers = self.subscribers.read().unwrap().clone();
+        let mut pending = VecDeque::from([message]);
+        while let Some(message) = pending.pop_front() {
+            for subscriber in &subscribers {
+                pending.extend(subscriber.receive(message.clone()).await);
+            }
+        }
+        Ok(())
+    }
+
+    fn attach(&self, subscriber: Arc<dyn BackplaneSubscriber>) {
+        self.subscribers.write().unwrap().push(subscriber);
+    }
+}
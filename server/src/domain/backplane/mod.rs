@@ -0,0 +1,103 @@
+pub mod local;
+pub mod redis;
+
+pub use local::LocalBackplane;
+pub use redis::RedisBackplane;
+
+use std::sync::Arc;
+
+use super::message::{ImgfloatAsset, ImgfloatAssetStateMessage, ImgfloatState};
+
+/// A channel event together with the instance that published it.
+#[derive(Clone, Debug, serde::Deserialize, serde::Serialize)]
+pub struct BackplaneMessage {
+    pub origin: String,
+    pub username: String,
+    pub event: ChannelEvent,
+}
+
+#[derive(Clone, Debug, serde::Deserialize, serde::Serialize)]
+pub enum ChannelEvent {
+    /// A writer's change, forwarded to readers and applied to the state.
+    State(ImgfloatAssetStateMessage),
+    /// Appends an asset to the state, creating it if needed.
+    Show(ImgfloatAsset),
+    /// Replaces the state wholesale, e.g. when an owner answers a
+    /// `SnapshotRequest`.
+    Restore(ImgfloatState),
+    /// A serialized `ImgfloatWriterNotification`.
+    Notification(String),
+    /// Sent by an instance that has no state for the channel.
+    SnapshotRequest,
+    /// Disconnects the channel's readers, e.g. when its overlay token
+    /// changes.
+    CloseReaders,
+    /// Sent by an owner whose channel went idle. Instances with readers or
+    /// writers take over the state, the others forget it.
+    Release,
+}
+
+#[derive(Debug)]
+pub enum BackplaneError {
+    UnknownBackplane(String),
+    Serialization(serde_json::Error),
+    Redis(::redis::RedisError),
+}
+
+impl std::fmt::Display for BackplaneError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::UnknownBackplane(url) => write!(f, "unknown backplane: {url}"),
+            Self::Serialization(error) => write!(f, "invalid backplane message: {error}"),
+            Self::Redis(error) => write!(f, "redis backplane failed: {error}"),
+        }
+    }
+}
+
+impl std::error::Error for BackplaneError {}
+
+impl From<serde_json::Error> for BackplaneError {
+    fn from(error: serde_json::Error) -> Self {
+        Self::Serialization(error)
+    }
+}
+
+impl From<::redis::RedisError> for BackplaneError {
+    fn from(error: ::redis::RedisError) -> Self {
+        Self::Redis(error)
+    }
+}
+
+/// Fans channel events out to every server instance.
+///
+/// Each instance applies state changes only as the backplane delivers them,
+/// its own included. That delivery order is the same everywhere, so the
+/// instances' state caches stay identical.
+#[async_trait::async_trait]
+pub trait Backplane: Send + Sync {
+    async fn publish(&self, message: BackplaneMessage) -> Result<(), BackplaneError>;
+
+    fn attach(&self, subscriber: Arc<dyn BackplaneSubscriber>);
+}
+
+#[async_trait::async_trait]
+pub trait BackplaneSubscriber: Send + Sync {
+    /// Handles a delivered message and returns the replies to publish.
+    async fn receive(&self, message: BackplaneMessage) -> Vec<BackplaneMessage>;
+
+    /// Called after the backplane may have dropped messages, e.g. on
+    /// reconnect. Returns the messages to publish to catch up again.
+    async fn resync(&self) -> Vec<BackplaneMessage>;
+}
+
+/// `local` keeps events in-process, `redis://` and `rediss://` URLs share them
+/// through a Redis-compatible server.
+pub async fn connect(url: &str) -> Result<Arc<dyn Backplane>, BackplaneError> {
+    match url {
+        "local" => Ok(Arc::new(LocalBackplane::new())),
+        url if url.starts_with("redis://") || url.starts_with("rediss://") => {
+            Ok(Arc::new(RedisBackplane::connect(url).await?))
+        }
+        url => Err(BackplaneError::UnknownBackplane(url.to_string())),
+    }
+}
@@ -0,0 +1,120 @@
+use std::{
+    sync::{Arc, RwLock},
+    time::Duration,
+};
+
+use futures::StreamExt;
+use redis::{aio::ConnectionManager, AsyncCommands};
+
+use super::{Backplane, BackplaneError, BackplaneMessage, BackplaneSubscriber};
+
+type Subscribers = Arc<RwLock<Vec<Arc<dyn BackplaneSubscriber>>>>;
+
+/// Shares messages through one pub/sub channel on a Redis-compatible server.
+/// The server delivers a channel's messages to every subscriber in the order
+/// it accepted them, which is the ordering `Backplane` requires.
+pub struct RedisBackplane {
+    connection: ConnectionManager,
+    subscribers: Subscribers,
+}
+
+impl RedisBackplane {
+    pub const CHANNEL: &'static str = "imgfloat:backplane";
+    const RECONNECT_DELAY: Duration = Duration::from_secs(1);
+
+    pub async fn connect(url: &str) -> Result<Self, BackplaneError> {
+        let client = redis::Client::open(url)?;
+        let connection = client.get_connection_manager().await?;
+        let pubsub = Self::subscribe(&client).await?;
+        let subscribers = Subscribers::default();
+        tokio::spawn(Self::relay(
+            client,
+            connection.clone(),
+            pubsub,
+            Arc::clone(&subscribers),
+        ));
+        Ok(Self {
+            connection,
+            subscribers,
+        })
+    }
+
+    async fn subscribe(client: &redis::Client) -> Result<redis::aio::PubSub, BackplaneError> {
+        let mut pubsub = client.get_async_pubsub().await?;
+        pubsub.subscribe(Self::CHANNEL).await?;
+        Ok(pubsub)
+    }
+
+    fn decode(message: &redis::Msg) -> Result<BackplaneMessage, BackplaneError> {
+        let payload: String = message.get_payload()?;
+        Ok(serde_json::from_str(&payload)?)
+    }
+
+    async fn send(
+        connection: &mut ConnectionManager,
+        message: &BackplaneMessage,
+    ) -> Result<(), BackplaneError> {
+        let payload = serde_json::to_string(message)?;
+        connection
+            .publish::<_, _, ()>(Self::CHANNEL, payload)
+            .await?;
+        Ok(())
+    }
+
+    async fn relay(
+        client: redis::Client,
+        mut connection: ConnectionManager,
+        mut pubsub: redis::aio::PubSub,
+        subscribers: Subscribers,
+    ) {
+        loop {
+            let mut messages = pubsub.into_on_message();
+            while let Some(message) = messages.next().await {
+                let message = match Self::decode(&message) {
+                    Ok(message) => message,
+                    Err(error) => {
+                        tracing::error!(?error, "unreadable backplane message");
+                        continue;
+                    }
+                };
+                let current = subscribers.read().unwrap().clone();
+                for subscriber in current {
+                    for reply in subscriber.receive(message.clone()).await {
+                        if let Err(error) = Self::send(&mut connection, &reply).await {
+                            tracing::error!(?error, "unable to publish backplane reply");
+                        }
+                    }
+                }
+            }
+
+            tracing::warn!("backplane subscription lost");
+            pubsub = loop {
+                tokio::time::sleep(Self::RECONNECT_DELAY).await;
+                match Self::subscribe(&client).await {
+                    Ok(pubsub) => break pubsub,
+                    Err(error) => tracing::warn!(?error, "unable to resubscribe to backplane"),
+                }
+            };
+            tracing::info!("backplane subscription restored");
+            let current = subscribers.read().unwrap().clone();
+            for subscriber in current {
+                for message in subscriber.resync().await {
+                    if let Err(error) = Self::send(&mut connection, &message).await {
+                        tracing::error!(?error, "unable to publish backplane resync");
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl Backplane for RedisBackplane {
+    async fn publish(&self, message: BackplaneMessage) -> Result<(), BackplaneError> {
+        Self::send(&mut self.connection.clone(), &message).await
+    }
+
+    fn attach(&self, subscriber: Arc<dyn BackplaneSubscriber>) {
+        self.subscribers.write().unwrap().push(subscriber);
+    }
+}
@@ -1,38 +1,293 @@
-use std::collections::HashMap;
+use std::{
+    collections::HashMap,
+    sync::Arc,
+    time::{Duration, Instant},
+};
 
-use axum::extract::ws::{Message, WebSocket};
-use futures::{SinkExt, StreamExt};
-use tokio::sync::{broadcast, RwLock};
+use axum::extract::ws::{close_code, CloseFrame, Message, WebSocket};
+use futures::{Sink, SinkExt};
+use tokio::sync::{broadcast, watch, RwLock};
 
-use crate::domain::message::ImgfloatAssetStateMessage;
+use crate::{
+    domain::message::ImgfloatAssetStateMessage,
+    models::{Capability, StoredChannelState},
+};
 
-use super::message::ImgfloatState;
+use super::{
+    backplane::{Backplane, BackplaneMessage, BackplaneSubscriber, ChannelEvent, LocalBackplane},
+    db::Database,
+    heartbeat::{Heartbeat, HeartbeatConfig},
+    message::{ImgfloatAsset, ImgfloatServerMessage, ImgfloatState, ImgfloatWriterNotification},
+    rate_limit::{RateLimit, RateLimitConfig, TokenBucket},
+};
 
 pub struct ChannelController {
-    channels: RwLock<HashMap<String, broadcast::Sender<String>>>,
-    state_cache: RwLock<HashMap<String, ImgfloatState>>,
+    hub: Arc<ChannelHub>,
+    backplane: Arc<dyn Backplane>,
+    writer_rate_limit: RateLimit,
+    heartbeat: HeartbeatConfig,
+    restarting: watch::Sender<Option<Duration>>,
+    sockets: watch::Sender<usize>,
+    idle_state_ttl: Duration,
 }
 
 impl ChannelController {
+    /// How long a channel's state is kept after its last reader and writer
+    /// left this instance, so a reloading overlay finds it again.
+    pub const IDLE_STATE_TTL: Duration = Duration::from_secs(600);
+
     pub fn new() -> Self {
+        let hub = Arc::new(ChannelHub::new(uuid::Uuid::new_v4().to_string()));
+        let backplane = Arc::new(LocalBackplane::new());
+        backplane.attach(Arc::clone(&hub) as Arc<dyn BackplaneSubscriber>);
         Self {
-            channels: RwLock::new(HashMap::new()),
-            state_cache: RwLock::new(HashMap::new()),
+            hub,
+            backplane,
+            writer_rate_limit: RateLimitConfig::default().writer_messages,
+            heartbeat: HeartbeatConfig::default(),
+            restarting: watch::Sender::new(None),
+            sockets: watch::Sender::new(0),
+            idle_state_ttl: Self::IDLE_STATE_TTL,
+        }
+    }
+
+    pub fn with_idle_state_ttl(mut self, ttl: Duration) -> Self {
+        self.idle_state_ttl = ttl;
+        self
+    }
+
+    pub fn with_writer_rate_limit(mut self, limit: RateLimit) -> Self {
+        self.writer_rate_limit = limit;
+        self
+    }
+
+    pub fn with_heartbeat(mut self, heartbeat: HeartbeatConfig) -> Self {
+        self.heartbeat = heartbeat;
+        self
+    }
+
+    /// Shares this controller's channels with every other instance attached
+    /// to `backplane`.
+    pub fn with_backplane(mut self, backplane: Arc<dyn Backplane>) -> Self {
+        backplane.attach(Arc::clone(&self.hub) as Arc<dyn BackplaneSubscriber>);
+        self.backplane = backplane;
+        self
+    }
+
+    pub fn instance_id(&self) -> &str {
+        &self.hub.instance_id
+    }
+
+    async fn send(&self, username: &str, event: ChannelEvent) {
+        let message = BackplaneMessage {
+            origin: self.hub.instance_id.clone(),
+            username: username.to_string(),
+            event,
+        };
+        if let Err(error) = self.backplane.publish(message).await {
+            tracing::error!(?error, ?username, "unable to publish to backplane");
+        }
+    }
+
+    pub async fn notify_writers(&self, username: &str, notification: ImgfloatWriterNotification) {
+        match serde_json::to_string(&notification) {
+            Ok(notification_str) => {
+                self.send(username, ChannelEvent::Notification(notification_str))
+                    .await
+            }
+            Err(error) => tracing::error!(?error, ?username, "unable to serialize notification"),
+        }
+    }
+
+    pub async fn subscribe_notifications(&self, username: &str) -> broadcast::Receiver<String> {
+        ChannelHub::subscribe(&self.hub.notifications, username).await
+    }
+
+    /// Channels with at least one reader or writer subscribed on this instance.
+    pub async fn connected_channels(&self) -> Vec<String> {
+        let mut connected: Vec<String> = self.hub.channels.read().await.keys().cloned().collect();
+        for username in self.hub.notifications.read().await.keys() {
+            if !connected.contains(username) {
+                connected.push(username.clone());
+            }
+        }
+        connected.sort();
+        connected
+    }
+
+    pub async fn get_state(&self, username: &str) -> Option<ImgfloatState> {
+        self.hub.state_cache.read().await.get(username).cloned()
+    }
+
+    pub async fn restore_state(&self, username: &str, state: ImgfloatState) {
+        self.send(username, ChannelEvent::Restore(state)).await;
+    }
+
+    pub async fn publish(&self, username: &str, message: ImgfloatAssetStateMessage) {
+        self.send(username, ChannelEvent::State(message)).await;
+    }
+
+    pub async fn show_asset(&self, username: &str, asset: ImgfloatAsset) {
+        self.send(username, ChannelEvent::Show(asset)).await;
+    }
+
+    pub async fn get_asset(&self, username: &str, id: &str) -> Option<ImgfloatAsset> {
+        self.hub
+            .state_cache
+            .read()
+            .await
+            .get(username)?
+            .assets
+            .iter()
+            .find(|asset| asset.id == id)
+            .cloned()
+    }
+
+    pub async fn hide_asset(&self, username: &str, id: &str) {
+        self.publish(username, ImgfloatAssetStateMessage::Delete(id.to_string()))
+            .await;
+    }
+
+    /// Asks the channel's owner for its state. The answer arrives like any
+    /// other state change.
+    pub async fn request_state(&self, username: &str) {
+        self.send(username, ChannelEvent::SnapshotRequest).await;
+    }
+
+    /// Closes every reader of the channel on every instance, so overlays
+    /// have to authorize again.
+    pub async fn close_readers(&self, username: &str) {
+        self.send(username, ChannelEvent::CloseReaders).await;
+    }
+
+    /// Tells every reader and writer to reconnect after `reconnect_in`,
+    /// closes their sockets and waits until all of them are gone. Sockets
+    /// opened afterwards are closed the same way.
+    pub async fn shutdown(&self, reconnect_in: Duration) {
+        self.restarting.send_replace(Some(reconnect_in));
+        self.sockets
+            .subscribe()
+            .wait_for(|count| *count == 0)
+            .await
+            .ok();
+    }
+
+    /// Saves every state this instance owns, so the next start can pick up
+    /// where it left off.
+    pub async fn save_states(&self, database: &dyn Database) {
+        for (username, state) in self.hub.owned_states().await {
+            let saved = StoredChannelState::new(&username, &state)
+                .map_err(|error| tracing::error!(?error, ?username, "unable to serialize state"))
+                .and_then(|stored| {
+                    database.save_channel_state(&stored).map_err(|error| {
+                        tracing::error!(?error, ?username, "unable to save channel state")
+                    })
+                });
+            if saved.is_ok() {
+                tracing::info!(?username, "saved channel state");
+            }
         }
     }
 
-    pub async fn add_reader(&self, socket: WebSocket, username: &str) {
-        let mut receiver = self
-            .channels
+    /// Restores and forgets the states saved by `save_states`.
+    pub async fn restore_saved_states(&self, database: &dyn Database) {
+        let saved = match database.get_channel_states() {
+            Ok(saved) => saved,
+            Err(error) => {
+                tracing::error!(?error, "unable to load saved channel states");
+                return;
+            }
+        };
+        for stored in saved {
+            match stored.parse() {
+                Ok(state) => self.restore_state(&stored.username, state).await,
+                Err(error) => {
+                    tracing::error!(?error, username = ?stored.username, "unreadable saved state")
+                }
+            }
+            database.delete_channel_state(&stored.username).ok();
+        }
+    }
+
+    /// Releases a reader's or writer's subscription and, once the channel
+    /// has stayed idle for `idle_state_ttl`, forgets its state.
+    async fn leave(
+        &self,
+        senders: &RwLock<HashMap<String, broadcast::Sender<String>>>,
+        username: &str,
+    ) {
+        if !ChannelHub::release(senders, username).await {
+            return;
+        }
+        let left_at = Instant::now();
+        self.hub
+            .last_left
             .write()
             .await
-            .entry(username.to_owned())
-            .or_insert_with(|| broadcast::channel(100).0)
-            .subscribe();
+            .insert(username.to_string(), left_at);
+        let hub = Arc::clone(&self.hub);
+        let backplane = Arc::clone(&self.backplane);
+        let ttl = self.idle_state_ttl;
+        let username = username.to_string();
+        tokio::spawn(async move {
+            tokio::time::sleep(ttl).await;
+            if let Some(release) = hub.evict_if_idle(&username, left_at).await {
+                if let Err(error) = backplane.publish(release).await {
+                    tracing::error!(?error, ?username, "unable to release channel state");
+                }
+            }
+        });
+    }
+
+    fn track_socket(&self) -> SocketGuard<'_> {
+        self.sockets.send_modify(|count| *count += 1);
+        SocketGuard(&self.sockets)
+    }
+
+    async fn restarting(restarting: &mut watch::Receiver<Option<Duration>>) -> Duration {
+        let reconnect_in = restarting
+            .wait_for(Option::is_some)
+            .await
+            .map(|reconnect_in| reconnect_in.unwrap_or_default());
+        match reconnect_in {
+            Ok(reconnect_in) => reconnect_in,
+            Err(_) => std::future::pending().await,
+        }
+    }
+
+    async fn send_restarting<S>(socket: &mut S, reconnect_in: Duration)
+    where
+        S: Sink<Message> + Unpin,
+        S::Error: std::fmt::Debug,
+    {
+        let message = ImgfloatServerMessage::Restarting {
+            reconnect_in_ms: reconnect_in.as_millis() as u64,
+        };
+        match serde_json::to_string(&message) {
+            Ok(message_str) => {
+                if let Err(error) = socket.send(Message::Text(message_str)).await {
+                    tracing::debug!(?error, "unable to send restart message");
+                }
+            }
+            Err(error) => tracing::error!(?error, "unable to serialize restart message"),
+        }
+        socket
+            .send(Message::Close(Some(CloseFrame {
+                code: close_code::RESTART,
+                reason: "server restarting".into(),
+            })))
+            .await
+            .ok();
+    }
 
-        let (mut ws_sender, mut ws_receiver) = socket.split();
-        if let Some(current_state) = self.state_cache.read().await.get(username) {
-            let state_message: ImgfloatAssetStateMessage = current_state.into();
+    pub async fn add_reader(&self, mut socket: WebSocket, username: &str) {
+        let _socket = self.track_socket();
+        let mut restarting = self.restarting.subscribe();
+        let mut heartbeat = Heartbeat::new(self.heartbeat);
+        let mut receiver = ChannelHub::subscribe(&self.hub.channels, username).await;
+
+        if let Some(current_state) = self.get_state(username).await {
+            let state_message: ImgfloatAssetStateMessage = (&current_state).into();
             tracing::debug!(?username, ?current_state, "serving local state");
             let state_message = match serde_json::to_string(&state_message)
                 .inspect_err(|error| tracing::error!(?error, "state could not be serialized"))
@@ -41,60 +296,85 @@ impl ChannelController {
                 Ok(m) => m,
                 Err(_) => return,
             };
-            if let Err(error) = ws_sender.send(state_message).await {
-                tracing::error!(?error, ?current_state, "unable to send cache message");
+            if !heartbeat.send(&mut socket, state_message).await {
+                tracing::error!(?username, ?current_state, "unable to send cache message");
                 return;
             }
         } else {
             tracing::info!(?username, "no cached state available");
+            self.request_state(username).await;
         }
 
-        let send_task = tokio::spawn(async move {
-            while let Ok(msg) = receiver.recv().await {
-                let message = Message::Text(msg);
-                if let Err(error) = ws_sender.send(message.clone()).await {
-                    tracing::error!(?error, ?message, "unable to send state message");
+        loop {
+            tokio::select! {
+                msg = receiver.recv() => match msg {
+                    Ok(msg) => {
+                        if !heartbeat.send(&mut socket, Message::Text(msg)).await {
+                            tracing::error!(?username, "unable to send state message");
+                            break;
+                        }
+                    }
+                    Err(broadcast::error::RecvError::Closed) => {
+                        tracing::info!(?username, "closing reader");
+                        socket
+                            .send(Message::Close(Some(CloseFrame {
+                                code: close_code::POLICY,
+                                reason: "access revoked".into(),
+                            })))
+                            .await
+                            .ok();
+                        break;
+                    }
+                    Err(broadcast::error::RecvError::Lagged(_)) => break,
+                },
+                msg = socket.recv() => match msg {
+                    Some(Ok(Message::Close(_))) => {
+                        tracing::debug!(?username, "reader socket closed");
+                        break;
+                    }
+                    Some(Ok(_)) => heartbeat.seen(),
+                    _ => break,
+                },
+                ping = heartbeat.next_ping() => match ping {
+                    Some(ping) => {
+                        if !heartbeat.send(&mut socket, ping).await {
+                            break;
+                        }
+                    }
+                    None => {
+                        tracing::info!(?username, "reader timed out");
+                        break;
+                    }
+                },
+                reconnect_in = Self::restarting(&mut restarting) => {
+                    Self::send_restarting(&mut socket, reconnect_in).await;
                     break;
                 }
             }
-        });
-
-        while let Some(Ok(msg)) = ws_receiver.next().await {
-            if let Message::Close(_) = msg {
-                tracing::debug!(?username, "reader socket closed");
-                break;
-            }
         }
 
-        send_task.abort();
+        drop(receiver);
+        self.leave(&self.hub.channels, username).await;
         tracing::debug!(?username, "reader disconnected");
     }
 
-    pub async fn add_writer(&self, mut socket: WebSocket, username: &str) {
-        let sender = self
-            .channels
-            .write()
-            .await
-            .entry(username.to_string())
-            .or_insert_with(|| broadcast::channel(100).0)
-            .clone();
-
-        if let Some(state) = self.state_cache.read().await.get(username) {
+    pub async fn add_writer(
+        &self,
+        mut socket: WebSocket,
+        username: &str,
+        capabilities: Vec<Capability>,
+    ) {
+        let _socket = self.track_socket();
+        let mut restarting = self.restarting.subscribe();
+        let mut heartbeat = Heartbeat::new(self.heartbeat);
+        let mut notifications = self.subscribe_notifications(username).await;
+        if let Some(state) = self.get_state(username).await {
             tracing::info!(?username, ?state, "sending cache to writer");
-            match serde_json::to_string(state) {
+            match serde_json::to_string(&state) {
                 Ok(json_str) => {
-                    socket
-                        .send(Message::Text(json_str))
-                        .await
-                        .inspect_err(|error| {
-                            tracing::error!(
-                                ?username,
-                                ?state,
-                                ?error,
-                                "unable to send initial state"
-                            )
-                        })
-                        .ok();
+                    if !heartbeat.send(&mut socket, Message::Text(json_str)).await {
+                        tracing::error!(?username, ?state, "unable to send initial state");
+                    }
                 }
                 Err(error) => {
                     tracing::error!(
@@ -107,69 +387,77 @@ impl ChannelController {
             };
         } else {
             tracing::info!(?username, "no cached state available");
+            self.request_state(username).await;
         }
 
-        while let Some(Ok(msg)) = socket.next().await {
+        let mut bucket = TokenBucket::new(self.writer_rate_limit, Instant::now());
+        loop {
+            let msg = tokio::select! {
+                notification = notifications.recv() => {
+                    match notification {
+                        Ok(notification) => {
+                            if !heartbeat.send(&mut socket, Message::Text(notification)).await {
+                                tracing::error!(?username, "unable to send writer notification");
+                                break;
+                            }
+                        }
+                        Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                            tracing::warn!(?username, ?skipped, "writer notifications lagged");
+                        }
+                        Err(broadcast::error::RecvError::Closed) => break,
+                    }
+                    continue;
+                }
+                ping = heartbeat.next_ping() => {
+                    match ping {
+                        Some(ping) => {
+                            if !heartbeat.send(&mut socket, ping).await {
+                                break;
+                            }
+                        }
+                        None => {
+                            tracing::info!(?username, "writer timed out");
+                            break;
+                        }
+                    }
+                    continue;
+                }
+                reconnect_in = Self::restarting(&mut restarting) => {
+                    tracing::info!(?username, "closing writer for restart");
+                    Self::send_restarting(&mut socket, reconnect_in).await;
+                    break;
+                }
+                msg = socket.recv() => match msg {
+                    Some(Ok(msg)) => msg,
+                    _ => break,
+                },
+            };
+            heartbeat.seen();
             match msg {
                 Message::Text(state_str) => {
-                    if sender.receiver_count() == 0 {
-                        tracing::debug!("skipping broadcast (no readers)");
-                    } else if let Err(error) = sender.send(state_str.clone()) {
-                        tracing::error!(?error, "error sending message");
-                    } else {
-                        tracing::debug!(
-                            channel_count = sender.receiver_count(),
-                            "propagated message"
-                        );
+                    if let Err(retry_after) = bucket.try_take(Instant::now()) {
+                        tracing::warn!(?username, ?retry_after, "writer rate limit exceeded");
+                        socket
+                            .send(Message::Close(Some(CloseFrame {
+                                code: close_code::POLICY,
+                                reason: "rate limit exceeded".into(),
+                            })))
+                            .await
+                            .ok();
+                        break;
                     }
                     match serde_json::from_str::<ImgfloatAssetStateMessage>(&state_str) {
-                        Ok(state) => match state {
-                            ImgfloatAssetStateMessage::Delete(id) => {
-                                let mut cache = self.state_cache.write().await;
-                                if let Some(user_state) = cache.get_mut(username) {
-                                    user_state.assets.retain(|asset| asset.id != id);
-                                } else {
-                                    tracing::warn!(
-                                        ?cache,
-                                        ?username,
-                                        ?id,
-                                        "unable to apply remove asset on missing state"
-                                    )
-                                }
-                            }
-                            ImgfloatAssetStateMessage::New(new_state) => {
-                                self.state_cache
-                                    .write()
-                                    .await
-                                    .insert(username.to_string(), new_state);
-                            }
-                            ImgfloatAssetStateMessage::Update(new_asset) => {
-                                let mut cache = self.state_cache.write().await;
-                                if let Some(user_state) = cache.get_mut(username) {
-                                    if let Some(asset) =
-                                        user_state.assets.iter_mut().find(|a| a.id == new_asset.id)
-                                    {
-                                        asset.x = new_asset.x;
-                                        asset.y = new_asset.y;
-                                        asset.w = new_asset.w;
-                                        asset.h = new_asset.h;
-                                        asset.url = new_asset.url;
-                                        tracing::debug!(
-                                            ?asset,
-                                            ?username,
-                                            "applied partial asset state update"
-                                        );
-                                    }
-                                } else {
-                                    tracing::warn!(
-                                        ?cache,
-                                        ?username,
-                                        ?new_asset,
-                                        "unable to apply partial update to missing state"
-                                    )
-                                }
-                            }
-                        },
+                        Ok(state) if capabilities.contains(&state.required_capability()) => {
+                            self.publish(username, state).await
+                        }
+                        Ok(state) => {
+                            tracing::warn!(
+                                ?username,
+                                ?capabilities,
+                                capability = ?state.required_capability(),
+                                "writer message denied"
+                            );
+                        }
                         Err(error) => {
                             tracing::error!(?state_str, ?error, "could not de-serialize state");
                         }
@@ -184,6 +472,337 @@ impl ChannelController {
             }
         }
 
+        drop(notifications);
+        self.leave(&self.hub.notifications, username).await;
         tracing::debug!(?username, "writer socket closed");
     }
 }
+
+/// Counts a reader or writer for as long as its handler runs.
+struct SocketGuard<'a>(&'a watch::Sender<usize>);
+
+impl Drop for SocketGuard<'_> {
+    fn drop(&mut self) {
+        self.0.send_modify(|count| *count -= 1);
+    }
+}
+
+/// The instance-local side of the controller. Its caches only change when
+/// the backplane delivers a message, so every instance applies the same
+/// changes in the same order.
+///
+/// A channel is owned by the instance whose message last changed its state.
+/// Only the owner answers `SnapshotRequest`s, and an instance that may have
+/// missed messages drops every state it doesn't own and asks for it again.
+/// An owner whose channel went idle releases it to the instances still
+/// using it instead of just dropping it.
+struct ChannelHub {
+    instance_id: String,
+    channels: RwLock<HashMap<String, broadcast::Sender<String>>>,
+    notifications: RwLock<HashMap<String, broadcast::Sender<String>>>,
+    state_cache: RwLock<HashMap<String, ImgfloatState>>,
+    owners: RwLock<HashMap<String, String>>,
+    /// When the last local reader or writer of a channel left.
+    last_left: RwLock<HashMap<String, Instant>>,
+}
+
+impl ChannelHub {
+    fn new(instance_id: String) -> Self {
+        Self {
+            instance_id,
+            channels: RwLock::new(HashMap::new()),
+            notifications: RwLock::new(HashMap::new()),
+            state_cache: RwLock::new(HashMap::new()),
+            owners: RwLock::new(HashMap::new()),
+            last_left: RwLock::new(HashMap::new()),
+        }
+    }
+
+    /// Subscribes under the write lock, so `release` can't remove the
+    /// channel in between.
+    async fn subscribe(
+        senders: &RwLock<HashMap<String, broadcast::Sender<String>>>,
+        username: &str,
+    ) -> broadcast::Receiver<String> {
+        senders
+            .write()
+            .await
+            .entry(username.to_string())
+            .or_insert_with(|| broadcast::channel(100).0)
+            .subscribe()
+    }
+
+    /// Forgets the channel once its last subscriber is gone. Returns whether
+    /// nothing is subscribed anymore.
+    async fn release(
+        senders: &RwLock<HashMap<String, broadcast::Sender<String>>>,
+        username: &str,
+    ) -> bool {
+        let mut senders = senders.write().await;
+        match senders.get(username) {
+            Some(sender) if sender.receiver_count() > 0 => false,
+            Some(_) => {
+                senders.remove(username);
+                tracing::debug!(?username, "removed idle channel");
+                true
+            }
+            None => true,
+        }
+    }
+
+    /// Drops the channel's state unless a reader or writer joined, or one
+    /// left again, since `left_at`. An owned state stays until the returned
+    /// `Release` comes back, so instances still using it can take it over.
+    async fn evict_if_idle(&self, username: &str, left_at: Instant) -> Option<BackplaneMessage> {
+        // held so nobody can subscribe while the state goes away
+        let channels = self.channels.read().await;
+        let notifications = self.notifications.read().await;
+        if channels.contains_key(username) || notifications.contains_key(username) {
+            return None;
+        }
+        let mut last_left = self.last_left.write().await;
+        if last_left.get(username) != Some(&left_at) {
+            return None;
+        }
+        last_left.remove(username);
+        if self.is_owner(username).await {
+            return Some(BackplaneMessage {
+                origin: self.instance_id.clone(),
+                username: username.to_string(),
+                event: ChannelEvent::Release,
+            });
+        }
+        self.forget(username).await;
+        None
+    }
+
+    /// Takes over a released state while a local reader or writer still
+    /// uses it, and forgets it otherwise.
+    async fn handle_release(&self, username: &str) {
+        let channels = self.channels.read().await;
+        let notifications = self.notifications.read().await;
+        if !channels.contains_key(username) && !notifications.contains_key(username) {
+            self.forget(username).await;
+        } else if self.state_cache.read().await.contains_key(username) {
+            tracing::debug!(?username, "taking over released channel state");
+            self.set_owner(username, &self.instance_id).await;
+        }
+    }
+
+    async fn forget(&self, username: &str) {
+        self.state_cache.write().await.remove(username);
+        self.owners.write().await.remove(username);
+        tracing::debug!(?username, "evicted idle channel state");
+    }
+
+    async fn sender(
+        senders: &RwLock<HashMap<String, broadcast::Sender<String>>>,
+        username: &str,
+    ) -> Option<broadcast::Sender<String>> {
+        senders.read().await.get(username).cloned()
+    }
+
+    async fn broadcast(&self, username: &str, message: &ImgfloatAssetStateMessage) {
+        let Some(sender) = Self::sender(&self.channels, username)
+            .await
+            .filter(|sender| sender.receiver_count() > 0)
+        else {
+            tracing::debug!(?username, "skipping broadcast (no readers)");
+            return;
+        };
+        match serde_json::to_string(message) {
+            Ok(message_str) => {
+                if let Err(error) = sender.send(message_str) {
+                    tracing::error!(?error, ?username, "error sending message");
+                }
+            }
+            Err(error) => tracing::error!(?error, ?username, "unable to serialize message"),
+        }
+    }
+
+    async fn set_owner(&self, username: &str, origin: &str) {
+        self.owners
+            .write()
+            .await
+            .insert(username.to_string(), origin.to_string());
+    }
+
+    async fn is_owner(&self, username: &str) -> bool {
+        self.owners.read().await.get(username) == Some(&self.instance_id)
+    }
+
+    async fn owned_states(&self) -> Vec<(String, ImgfloatState)> {
+        let owners = self.owners.read().await;
+        self.state_cache
+            .read()
+            .await
+            .iter()
+            .filter(|(username, _)| owners.get(*username) == Some(&self.instance_id))
+            .map(|(username, state)| (username.clone(), state.clone()))
+            .collect()
+    }
+
+    fn snapshot_request(&self, username: &str) -> BackplaneMessage {
+        BackplaneMessage {
+            origin: self.instance_id.clone(),
+            username: username.to_string(),
+            event: ChannelEvent::SnapshotRequest,
+        }
+    }
+
+    /// Returns false when a partial update found no state to apply to.
+    async fn apply_message(&self, username: &str, message: ImgfloatAssetStateMessage) -> bool {
+        match message {
+            ImgfloatAssetStateMessage::Delete(id) => {
+                let mut cache = self.state_cache.write().await;
+                if let Some(user_state) = cache.get_mut(username) {
+                    user_state.assets.retain(|asset| asset.id != id);
+                } else {
+                    tracing::warn!(
+                        ?cache,
+                        ?username,
+                        ?id,
+                        "unable to apply remove asset on missing state"
+                    );
+                    return false;
+                }
+            }
+            ImgfloatAssetStateMessage::New(new_state) => {
+                self.state_cache
+                    .write()
+                    .await
+                    .insert(username.to_string(), new_state);
+            }
+            ImgfloatAssetStateMessage::Update(new_asset) => {
+                let mut cache = self.state_cache.write().await;
+                if let Some(user_state) = cache.get_mut(username) {
+                    if let Some(asset) = user_state.assets.iter_mut().find(|a| a.id == new_asset.id)
+                    {
+                        asset.x = new_asset.x;
+                        asset.y = new_asset.y;
+                        asset.w = new_asset.w;
+                        asset.h = new_asset.h;
+                        asset.theta = new_asset.theta;
+                        asset.url = new_asset.url;
+                        tracing::debug!(?asset, ?username, "applied partial asset state update");
+                    }
+                } else {
+                    tracing::warn!(
+                        ?cache,
+                        ?username,
+                        ?new_asset,
+                        "unable to apply partial update to missing state"
+                    );
+                    return false;
+                }
+            }
+        }
+        true
+    }
+}
+
+#[async_trait::async_trait]
+impl BackplaneSubscriber for ChannelHub {
+    async fn receive(&self, message: BackplaneMessage) -> Vec<BackplaneMessage> {
+        let BackplaneMessage {
+            origin,
+            username,
+            event,
+        } = message;
+        match event {
+            ChannelEvent::State(state_message) => {
+                // applied first, so a reader that subscribes in between gets
+                // the change from the cache or the broadcast
+                if !self.apply_message(&username, state_message.clone()).await {
+                    return vec![self.snapshot_request(&username)];
+                }
+                self.set_owner(&username, &origin).await;
+                self.broadcast(&username, &state_message).await;
+            }
+            ChannelEvent::Show(asset) => {
+                let state = {
+                    let mut cache = self.state_cache.write().await;
+                    let state = cache
+                        .entry(username.clone())
+                        .or_insert(ImgfloatState { assets: vec![] });
+                    state.assets.push(asset);
+                    state.clone()
+                };
+                self.set_owner(&username, &origin).await;
+                self.broadcast(&username, &ImgfloatAssetStateMessage::New(state))
+                    .await;
+            }
+            ChannelEvent::Restore(state) => {
+                let state_message: ImgfloatAssetStateMessage = (&state).into();
+                self.state_cache
+                    .write()
+                    .await
+                    .insert(username.clone(), state);
+                self.set_owner(&username, &origin).await;
+                self.broadcast(&username, &state_message).await;
+            }
+            ChannelEvent::Notification(notification_str) => {
+                match Self::sender(&self.notifications, &username)
+                    .await
+                    .filter(|sender| sender.receiver_count() > 0)
+                {
+                    Some(sender) => {
+                        if let Err(error) = sender.send(notification_str) {
+                            tracing::error!(?error, ?username, "error sending notification");
+                        }
+                    }
+                    None => tracing::debug!(?username, "skipping notification (no writers)"),
+                }
+            }
+            ChannelEvent::CloseReaders => {
+                // dropping the sender ends every reader's subscription
+                if self.channels.write().await.remove(&username).is_some() {
+                    tracing::info!(?username, "closing readers");
+                }
+            }
+            ChannelEvent::Release => self.handle_release(&username).await,
+            ChannelEvent::SnapshotRequest => {
+                if origin == self.instance_id || !self.is_owner(&username).await {
+                    return vec![];
+                }
+                if let Some(state) = self.state_cache.read().await.get(&username).cloned() {
+                    tracing::debug!(?username, requester = ?origin, "answering snapshot request");
+                    return vec![BackplaneMessage {
+                        origin: self.instance_id.clone(),
+                        username,
+                        event: ChannelEvent::Restore(state),
+                    }];
+                }
+            }
+        }
+        vec![]
+    }
+
+    async fn resync(&self) -> Vec<BackplaneMessage> {
+        let owned: Vec<String> = self
+            .owners
+            .read()
+            .await
+            .iter()
+            .filter(|(_, owner)| **owner == self.instance_id)
+            .map(|(username, _)| username.clone())
+            .collect();
+        let mut dropped = vec![];
+        self.state_cache.write().await.retain(|username, _| {
+            let keep = owned.contains(username);
+            if !keep {
+                dropped.push(username.clone());
+            }
+            keep
+        });
+        self.owners
+            .write()
+            .await
+            .retain(|username, _| owned.contains(username));
+        tracing::info!(?dropped, "dropped channel states after backplane resync");
+        dropped
+            .iter()
+            .map(|username| self.snapshot_request(username))
+            .collect()
+    }
+}
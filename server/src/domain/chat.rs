@@ -0,0 +1,349 @@
+use std::{collections::HashSet, sync::Arc, time::Duration};
+
+use tokio::{
+    io::{AsyncBufReadExt, AsyncRead, AsyncWrite, AsyncWriteExt, BufReader},
+    net::TcpStream,
+};
+
+use crate::{
+    models::{AssetSearchQuery, Capability, User, UserFacingAsset},
+    twitch::irc::IrcMessage,
+};
+
+use super::{
+    db::{Database, DbError},
+    message::ImgfloatAsset,
+    token_store::TokenStoreError,
+    ChannelController, PermissionService, TwitchTokenStore,
+};
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum ChatCommand {
+    Show(String),
+    Hide(String),
+}
+
+impl ChatCommand {
+    pub fn parse(text: &str) -> Option<Self> {
+        let mut words = text.split_whitespace();
+        let command = words.next()?;
+        let name = words.next()?.to_string();
+        match command.to_lowercase().as_str() {
+            "!show" => Some(Self::Show(name)),
+            "!hide" => Some(Self::Hide(name)),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        match self {
+            Self::Show(name) | Self::Hide(name) => name,
+        }
+    }
+
+    pub fn required_capability(&self) -> Capability {
+        match self {
+            Self::Show(_) => Capability::PlaceAssets,
+            Self::Hide(_) => Capability::DeleteAssets,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum ChatError {
+    Io(std::io::Error),
+    Tls(tokio_native_tls::native_tls::Error),
+    TokenStore(TokenStoreError),
+    Database(DbError),
+}
+
+#[derive(Clone, Debug)]
+pub struct ChatConfig {
+    pub host: String,
+    pub port: u16,
+    pub tls: bool,
+    pub sync_interval: Duration,
+    pub reconnect_delay: Duration,
+}
+
+pub struct ChatBot {
+    database: Arc<dyn Database>,
+    permissions: PermissionService,
+    controller: Arc<ChannelController>,
+    config: ChatConfig,
+    bot_account: Option<(Arc<TwitchTokenStore>, String)>,
+}
+
+impl ChatBot {
+    pub fn new(
+        database: Arc<dyn Database>,
+        controller: Arc<ChannelController>,
+        config: ChatConfig,
+    ) -> Self {
+        Self {
+            permissions: PermissionService::new(Arc::clone(&database)),
+            database,
+            controller,
+            config,
+            bot_account: None,
+        }
+    }
+
+    pub fn with_bot_account(mut self, token_store: Arc<TwitchTokenStore>, username: &str) -> Self {
+        self.bot_account = Some((token_store, username.to_string()));
+        self
+    }
+
+    pub async fn run(self) {
+        loop {
+            if let Err(error) = self.connect().await {
+                tracing::error!(?error, "chat connection failed");
+            }
+            tracing::info!(delay = ?self.config.reconnect_delay, "reconnecting to chat");
+            tokio::time::sleep(self.config.reconnect_delay).await;
+        }
+    }
+
+    pub async fn connect(&self) -> Result<(), ChatError> {
+        let address = format!("{}:{}", self.config.host, self.config.port);
+        tracing::info!(?address, tls = self.config.tls, "connecting to chat");
+        let stream = TcpStream::connect(&address).await.map_err(ChatError::Io)?;
+        if self.config.tls {
+            let connector =
+                tokio_native_tls::native_tls::TlsConnector::new().map_err(ChatError::Tls)?;
+            let stream = tokio_native_tls::TlsConnector::from(connector)
+                .connect(&self.config.host, stream)
+                .await
+                .map_err(ChatError::Tls)?;
+            self.session(stream).await
+        } else {
+            self.session(stream).await
+        }
+    }
+
+    async fn session<S>(&self, stream: S) -> Result<(), ChatError>
+    where
+        S: AsyncRead + AsyncWrite + Unpin,
+    {
+        let (reader, mut writer) = tokio::io::split(stream);
+        let mut lines = BufReader::new(reader).lines();
+        let (password, nick) = self.credentials().await?;
+        Self::send(&mut writer, "CAP REQ :twitch.tv/tags twitch.tv/commands").await?;
+        Self::send(&mut writer, &format!("PASS {password}")).await?;
+        Self::send(&mut writer, &format!("NICK {nick}")).await?;
+
+        let mut joined = HashSet::new();
+        let mut sync = tokio::time::interval(self.config.sync_interval);
+        loop {
+            tokio::select! {
+                _ = sync.tick() => {
+                    self.sync_channels(&mut writer, &mut joined).await?;
+                }
+                line = lines.next_line() => {
+                    let Some(line) = line.map_err(ChatError::Io)? else {
+                        tracing::info!("chat connection closed");
+                        return Ok(());
+                    };
+                    let Some(message) = IrcMessage::parse(&line) else {
+                        tracing::warn!(?line, "unparsable chat message");
+                        continue;
+                    };
+                    match message.command.as_str() {
+                        "PING" => {
+                            let token = message.params.first().cloned().unwrap_or_default();
+                            Self::send(&mut writer, &format!("PONG :{token}")).await?;
+                        }
+                        "RECONNECT" => {
+                            tracing::info!("chat server requested reconnect");
+                            return Ok(());
+                        }
+                        "PRIVMSG" => {
+                            self.handle_message(&message).await;
+                        }
+                        _ => tracing::trace!(?message, "chat message"),
+                    }
+                }
+            }
+        }
+    }
+
+    pub async fn handle_message(&self, message: &IrcMessage) -> Option<ChatCommand> {
+        let command = ChatCommand::parse(message.text()?)?;
+        let channel = message.channel()?;
+        let broadcaster = self.database.get_user(channel).ok()??;
+        if !self
+            .is_permitted(&broadcaster, message, command.required_capability())
+            .await
+        {
+            tracing::debug!(user = ?message.nick(), ?channel, "chat command not permitted");
+            return None;
+        }
+        let asset = self.find_asset(&broadcaster, command.name()).await?;
+        let url = format!("/api/assets/{}/{}", broadcaster.username, asset.filename);
+        match &command {
+            ChatCommand::Show(_) => {
+                let placed = self
+                    .controller
+                    .get_state(&broadcaster.username)
+                    .await
+                    .and_then(|state| {
+                        state
+                            .assets
+                            .into_iter()
+                            .rfind(|asset| asset.url.ends_with(&url))
+                    });
+                let asset = match placed {
+                    // shown again where it was put in the scene
+                    Some(placed) => ImgfloatAsset {
+                        id: uuid::Uuid::new_v4().to_string(),
+                        url,
+                        ..placed
+                    },
+                    None => ImgfloatAsset {
+                        id: uuid::Uuid::new_v4().to_string(),
+                        x: 0.0,
+                        y: 0.0,
+                        w: ImgfloatAsset::DEFAULT_SIZE,
+                        h: ImgfloatAsset::DEFAULT_SIZE,
+                        theta: 0.0,
+                        url,
+                    },
+                };
+                self.controller
+                    .show_asset(&broadcaster.username, asset)
+                    .await;
+            }
+            ChatCommand::Hide(_) => {
+                let shown_ids = self
+                    .controller
+                    .get_state(&broadcaster.username)
+                    .await
+                    .map(|state| state.assets)
+                    .unwrap_or_default()
+                    .into_iter()
+                    .filter(|asset| asset.url.ends_with(&url))
+                    .map(|asset| asset.id);
+                for id in shown_ids {
+                    self.controller.hide_asset(&broadcaster.username, &id).await;
+                }
+            }
+        }
+        tracing::info!(user = ?message.nick(), ?channel, ?command, "applied chat command");
+        Some(command)
+    }
+
+    /// The broadcaster and anyone with a broadcaster, moderator or VIP badge
+    /// may both `!show` and `!hide`, whatever role `channel_admins` gives
+    /// them. Everyone else needs a channel admin role that allows the
+    /// command's capability, which is `DeleteAssets` for `!hide`.
+    async fn is_permitted(
+        &self,
+        broadcaster: &User,
+        message: &IrcMessage,
+        capability: Capability,
+    ) -> bool {
+        let Some(nick) = message.nick() else {
+            return false;
+        };
+        if nick == broadcaster.username
+            || message
+                .badges()
+                .iter()
+                .any(|badge| matches!(*badge, "broadcaster" | "moderator" | "vip"))
+        {
+            return true;
+        }
+        self.permissions
+            .role(nick, broadcaster)
+            .await
+            .is_some_and(|role| role.allows(capability))
+    }
+
+    async fn find_asset(&self, broadcaster: &User, name: &str) -> Option<UserFacingAsset> {
+        let database = &self.database;
+        let by_tag = AssetSearchQuery {
+            tag: Some(name.to_lowercase()),
+            per_page: Some(1),
+            ..Default::default()
+        };
+        if let Some(asset) = database
+            .search_assets(broadcaster, &by_tag)
+            .ok()?
+            .assets
+            .into_iter()
+            .next()
+        {
+            return Some(asset);
+        }
+        let by_name = AssetSearchQuery {
+            name: Some(name.to_string()),
+            per_page: Some(AssetSearchQuery::MAX_PER_PAGE),
+            ..Default::default()
+        };
+        database
+            .search_assets(broadcaster, &by_name)
+            .ok()?
+            .assets
+            .into_iter()
+            .find(|asset| {
+                let stem = asset
+                    .original_filename
+                    .rsplit_once('.')
+                    .map_or(asset.original_filename.as_str(), |(stem, _)| stem);
+                stem.eq_ignore_ascii_case(name)
+            })
+    }
+
+    async fn sync_channels<W>(
+        &self,
+        writer: &mut W,
+        joined: &mut HashSet<String>,
+    ) -> Result<(), ChatError>
+    where
+        W: AsyncWrite + Unpin,
+    {
+        let wanted: HashSet<String> = self
+            .database
+            .get_chat_channels()
+            .map_err(ChatError::Database)?
+            .into_iter()
+            .collect();
+        for channel in wanted.difference(joined) {
+            tracing::info!(?channel, "joining chat");
+            Self::send(writer, &format!("JOIN #{channel}")).await?;
+        }
+        for channel in joined.difference(&wanted) {
+            tracing::info!(?channel, "leaving chat");
+            Self::send(writer, &format!("PART #{channel}")).await?;
+        }
+        *joined = wanted;
+        Ok(())
+    }
+
+    async fn credentials(&self) -> Result<(String, String), ChatError> {
+        match &self.bot_account {
+            Some((token_store, username)) => {
+                let tokens = token_store
+                    .get_tokens(username)
+                    .await
+                    .map_err(ChatError::TokenStore)?;
+                Ok((format!("oauth:{}", tokens.access_token), username.clone()))
+            }
+            None => Ok((
+                "anonymous".to_string(),
+                format!("justinfan{}", uuid::Uuid::new_v4().as_u128() % 100_000),
+            )),
+        }
+    }
+
+    async fn send<W>(writer: &mut W, line: &str) -> Result<(), ChatError>
+    where
+        W: AsyncWrite + Unpin,
+    {
+        writer
+            .write_all(format!("{line}\r\n").as_bytes())
+            .await
+            .map_err(ChatError::Io)?;
+        writer.flush().await.map_err(ChatError::Io)
+    }
+}
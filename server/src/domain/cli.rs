@@ -0,0 +1,30 @@
+use std::path::PathBuf;
+
+#[derive(Debug, clap::Parser)]
+#[command(version, about = "Image overlay server for Twitch streamers")]
+pub struct Cli {
+    /// Don't apply pending migrations; refuse to start unless the schema is current.
+    #[arg(long, global = true)]
+    pub no_migrate: bool,
+    /// Read settings from this TOML file; environment variables override it.
+    #[arg(long, global = true)]
+    pub config: Option<PathBuf>,
+    /// Print the effective configuration with secrets redacted and exit.
+    #[arg(long, global = true)]
+    pub print_config: bool,
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(Debug, clap::Subcommand)]
+pub enum Command {
+    /// Reconcile the assets table against the asset directory and exit.
+    CheckStorage {
+        /// Delete files that have no asset row once they are older than the grace period.
+        #[arg(long)]
+        delete_orphans: bool,
+        /// Minimum age in seconds before an orphaned file may be deleted.
+        #[arg(long, default_value_t = 86400)]
+        grace_period: u64,
+    },
+}
@@ -0,0 +1,632 @@
+use std::{
+    cell::RefCell,
+    collections::{HashMap, HashSet},
+    path::Path,
+    time::Duration,
+};
+
+use serde::Serializer;
+
+use crate::twitch::TwitchCredentials;
+
+use super::{
+    AppSessionStore, EnvVar, HeartbeatConfig, RateLimit, RateLimitConfig, ShutdownConfig,
+    TokenCipher,
+};
+
+const REDACTED: &str = "REDACTED";
+
+#[derive(Debug, PartialEq)]
+pub struct ConfigError {
+    pub key: String,
+    pub reason: String,
+}
+
+impl std::fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}", self.key, self.reason)
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+#[derive(Debug, PartialEq)]
+pub struct ConfigErrors(pub Vec<ConfigError>);
+
+impl std::fmt::Display for ConfigErrors {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let errors: Vec<String> = self.0.iter().map(ConfigError::to_string).collect();
+        write!(f, "invalid configuration: {}", errors.join("; "))
+    }
+}
+
+impl std::error::Error for ConfigErrors {}
+
+impl From<ConfigError> for ConfigErrors {
+    fn from(error: ConfigError) -> Self {
+        Self(vec![error])
+    }
+}
+
+/// Settings from a TOML file, each overridden by its environment variable.
+pub struct ConfigSource {
+    file: toml::Table,
+    env: HashMap<String, String>,
+}
+
+impl ConfigSource {
+    pub fn new(
+        file: &str,
+        env: impl IntoIterator<Item = (String, String)>,
+    ) -> Result<Self, ConfigError> {
+        let file = file.parse::<toml::Table>().map_err(|error| ConfigError {
+            key: "config file".to_string(),
+            reason: error.to_string(),
+        })?;
+        Ok(Self {
+            file,
+            env: env.into_iter().collect(),
+        })
+    }
+
+    /// Reads the file at `path`, if any, and the process environment.
+    pub fn from_environment(path: Option<&Path>) -> Result<Self, ConfigError> {
+        let Some(path) = path else {
+            return Self::new("", std::env::vars());
+        };
+        std::fs::read_to_string(path)
+            .map_err(|error| error.to_string())
+            .and_then(|file| Self::new(&file, std::env::vars()).map_err(|error| error.reason))
+            .map_err(|reason| ConfigError {
+                key: path.display().to_string(),
+                reason,
+            })
+    }
+
+    fn lookup(&self, setting: &str) -> Option<String> {
+        let mut parts = setting.split('.');
+        let mut value = self.file.get(parts.next()?)?;
+        for part in parts {
+            value = value.as_table()?.get(part)?;
+        }
+        match value {
+            toml::Value::String(value) => Some(value.clone()),
+            value => Some(value.to_string()),
+        }
+    }
+
+    fn settings(table: &toml::Table, prefix: &str) -> Vec<String> {
+        table
+            .iter()
+            .flat_map(|(key, value)| {
+                let setting = format!("{prefix}{key}");
+                match value.as_table() {
+                    Some(table) => Self::settings(table, &format!("{setting}.")),
+                    None => vec![setting],
+                }
+            })
+            .collect()
+    }
+}
+
+/// Reads settings from a source, remembering which ones it asked for and
+/// every problem it ran into.
+struct ConfigLoader<'a> {
+    source: &'a ConfigSource,
+    known: RefCell<HashSet<&'static str>>,
+    errors: RefCell<Vec<ConfigError>>,
+}
+
+impl<'a> ConfigLoader<'a> {
+    fn new(source: &'a ConfigSource) -> Self {
+        Self {
+            source,
+            known: RefCell::default(),
+            errors: RefCell::default(),
+        }
+    }
+
+    fn var(&self, setting: &'static str, key: &str) -> EnvVar<String> {
+        self.known.borrow_mut().insert(setting);
+        EnvVar::from_value(key, self.source.env.get(key).cloned())
+            .or_setting(setting, self.source.lookup(setting))
+    }
+
+    fn take<T>(&self, var: EnvVar<T>) -> Option<T> {
+        var.into_result()
+            .map_err(|error| self.errors.borrow_mut().push(error))
+            .ok()
+    }
+
+    fn finish<T>(self, loaded: Option<T>) -> Result<T, ConfigErrors> {
+        let mut errors = self.errors.into_inner();
+        let known = self.known.into_inner();
+        for setting in ConfigSource::settings(&self.source.file, "") {
+            if !known.contains(setting.as_str()) {
+                errors.push(ConfigError {
+                    key: setting,
+                    reason: "unknown setting".to_string(),
+                });
+            }
+        }
+        match loaded {
+            Some(loaded) if errors.is_empty() => Ok(loaded),
+            _ => Err(ConfigErrors(errors)),
+        }
+    }
+}
+
+/// Everything the server reads at startup. Each setting lives under its TOML
+/// key and can be overridden by the environment variable next to it.
+#[derive(Clone, serde::Serialize)]
+pub struct Config {
+    /// `DATABASE_URL`
+    #[serde(serialize_with = "redact_url")]
+    pub database_url: String,
+    /// `ASSET_DIRECTORY`
+    pub asset_directory: String,
+    /// `STATIC_DIRECTORY`
+    pub static_directory: String,
+    /// `NOT_FOUND_PAGE`
+    pub not_found_page: String,
+    /// `SESSION_STORE`: `database` or `memory`
+    pub session_store: String,
+    /// `SESSION_CLEANUP_INTERVAL`, in seconds
+    pub session_cleanup_interval: u64,
+    /// `BACKPLANE_URL`: `local` or a `redis://` url
+    #[serde(serialize_with = "redact_url")]
+    pub backplane_url: String,
+    /// `TOKEN_ENCRYPTION_KEY`
+    #[serde(serialize_with = "redact")]
+    pub token_encryption_key: String,
+    /// `TOKEN_REFRESH_INTERVAL`, in seconds
+    pub token_refresh_interval: u64,
+    /// `MODERATOR_SYNC_INTERVAL`, in seconds
+    pub moderator_sync_interval: u64,
+    pub http: HttpSettings,
+    pub twitch: TwitchSettings,
+    pub chat: ChatSettings,
+    pub asset_gc: AssetGcSettings,
+    pub rate_limits: RateLimitConfig,
+    pub heartbeat: HeartbeatConfig,
+    pub shutdown: ShutdownConfig,
+}
+
+impl Config {
+    pub fn load(source: &ConfigSource) -> Result<Self, ConfigErrors> {
+        let loader = ConfigLoader::new(source);
+        let loaded = Self::read(&loader);
+        loader.finish(loaded)
+    }
+
+    /// The effective configuration as TOML, with secrets replaced.
+    pub fn redacted(&self) -> Result<String, toml::ser::Error> {
+        toml::to_string(self)
+    }
+
+    fn read(loader: &ConfigLoader) -> Option<Self> {
+        let database_url = loader.take(
+            loader
+                .var("database_url", "DATABASE_URL")
+                .ensure_non_empty(),
+        );
+        let asset_directory = loader.take(
+            loader
+                .var("asset_directory", "ASSET_DIRECTORY")
+                .ensure_directory(),
+        );
+        let static_directory = loader.take(
+            loader
+                .var("static_directory", "STATIC_DIRECTORY")
+                .ensure_directory(),
+        );
+        let not_found_page =
+            loader.take(loader.var("not_found_page", "NOT_FOUND_PAGE").ensure_file());
+        let session_store = loader.take(
+            loader
+                .var("session_store", "SESSION_STORE")
+                .with_default_value("database")
+                .ensure(
+                    |kind| match AppSessionStore::KINDS.contains(&kind.as_str()) {
+                        true => Ok(()),
+                        false => Err(format!("unknown session store {kind:?}")),
+                    },
+                ),
+        );
+        let session_cleanup_interval = loader.take(
+            loader
+                .var("session_cleanup_interval", "SESSION_CLEANUP_INTERVAL")
+                .with_default_value("3600")
+                .parse::<u64>()
+                .ensure_positive(),
+        );
+        let backplane_url = loader.take(
+            loader
+                .var("backplane_url", "BACKPLANE_URL")
+                .with_default_value("local")
+                .ensure(|url| {
+                    match url == "local"
+                        || url.starts_with("redis://")
+                        || url.starts_with("rediss://")
+                    {
+                        true => Ok(()),
+                        false => Err(format!("unknown backplane {url:?}")),
+                    }
+                }),
+        );
+        let token_encryption_key = loader.take(
+            loader
+                .var("token_encryption_key", "TOKEN_ENCRYPTION_KEY")
+                .ensure(|key| {
+                    TokenCipher::from_base64(key)
+                        .map(|_| ())
+                        .map_err(|_| "expected 32 base64 encoded bytes".to_string())
+                }),
+        );
+        let token_refresh_interval = loader.take(
+            loader
+                .var("token_refresh_interval", "TOKEN_REFRESH_INTERVAL")
+                .with_default_value("600")
+                .parse::<u64>()
+                .ensure_positive(),
+        );
+        let moderator_sync_interval = loader.take(
+            loader
+                .var("moderator_sync_interval", "MODERATOR_SYNC_INTERVAL")
+                .with_default_value("900")
+                .parse::<u64>()
+                .ensure_positive(),
+        );
+        let http = HttpSettings::read(loader);
+        let twitch = TwitchSettings::read(loader);
+        let chat = ChatSettings::read(loader);
+        let asset_gc = AssetGcSettings::read(loader);
+        let rate_limits = Self::read_rate_limits(loader);
+        let heartbeat = Self::read_heartbeat(loader);
+        let shutdown = Self::read_shutdown(loader);
+        Some(Self {
+            database_url: database_url?,
+            asset_directory: asset_directory?,
+            static_directory: static_directory?,
+            not_found_page: not_found_page?,
+            session_store: session_store?,
+            session_cleanup_interval: session_cleanup_interval?,
+            backplane_url: backplane_url?,
+            token_encryption_key: token_encryption_key?,
+            token_refresh_interval: token_refresh_interval?,
+            moderator_sync_interval: moderator_sync_interval?,
+            http: http?,
+            twitch: twitch?,
+            chat: chat?,
+            asset_gc: asset_gc?,
+            rate_limits: rate_limits?,
+            heartbeat: heartbeat?,
+            shutdown: shutdown?,
+        })
+    }
+
+    fn read_rate_limits(loader: &ConfigLoader) -> Option<RateLimitConfig> {
+        let rate_limit = |setting, key, default| {
+            loader.take(
+                loader
+                    .var(setting, key)
+                    .with_default_value(default)
+                    .parse::<RateLimit>(),
+            )
+        };
+        let uploads = rate_limit("rate_limits.uploads", "UPLOAD_RATE_LIMIT", "20/60");
+        let sockets = rate_limit("rate_limits.sockets", "SOCKET_RATE_LIMIT", "30/60");
+        let writer_messages = rate_limit(
+            "rate_limits.writer_messages",
+            "WRITER_MESSAGE_RATE_LIMIT",
+            "120/2",
+        );
+        Some(RateLimitConfig {
+            uploads: uploads?,
+            sockets: sockets?,
+            writer_messages: writer_messages?,
+        })
+    }
+
+    fn read_heartbeat(loader: &ConfigLoader) -> Option<HeartbeatConfig> {
+        let interval = loader.take(
+            loader
+                .var("heartbeat.interval", "HEARTBEAT_INTERVAL")
+                .with_default_value("15")
+                .parse::<u64>()
+                .ensure_positive(),
+        );
+        let timeout = loader.take(
+            loader
+                .var("heartbeat.timeout", "HEARTBEAT_TIMEOUT")
+                .with_default_value("45")
+                .parse::<u64>()
+                .ensure(|timeout| match interval {
+                    Some(interval) if *timeout <= interval => Err(format!(
+                        "must be longer than the heartbeat interval of {interval}s"
+                    )),
+                    _ => Ok(()),
+                }),
+        );
+        Some(HeartbeatConfig {
+            interval: Duration::from_secs(interval?),
+            timeout: Duration::from_secs(timeout?),
+        })
+    }
+
+    fn read_shutdown(loader: &ConfigLoader) -> Option<ShutdownConfig> {
+        let timeout = loader.take(
+            loader
+                .var("shutdown.timeout_ms", "SHUTDOWN_TIMEOUT_MS")
+                .with_default_value("10000")
+                .parse::<u64>(),
+        );
+        let reconnect_delay = loader.take(
+            loader
+                .var("shutdown.reconnect_delay_ms", "RECONNECT_DELAY_MS")
+                .with_default_value("2000")
+                .parse::<u64>(),
+        );
+        Some(ShutdownConfig {
+            timeout: Duration::from_millis(timeout?),
+            reconnect_delay: Duration::from_millis(reconnect_delay?),
+        })
+    }
+}
+
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct HttpSettings {
+    /// `HTTP_HOST`
+    pub host: String,
+    /// `HTTP_PORT`
+    pub port: u16,
+}
+
+impl HttpSettings {
+    fn read(loader: &ConfigLoader) -> Option<Self> {
+        let host = loader.take(
+            loader
+                .var("http.host", "HTTP_HOST")
+                .with_default_value("127.0.0.1"),
+        );
+        let port = loader.take(
+            loader
+                .var("http.port", "HTTP_PORT")
+                .with_default_value("3000")
+                .parse::<u16>(),
+        );
+        Some(Self {
+            host: host?,
+            port: port?,
+        })
+    }
+}
+
+#[derive(Clone, serde::Serialize)]
+pub struct TwitchSettings {
+    /// `TWITCH_CLIENT_ID`
+    pub client_id: String,
+    /// `TWITCH_CLIENT_SECRET`
+    #[serde(serialize_with = "redact")]
+    pub client_secret: String,
+    /// `TWITCH_REDIRECT_URI`
+    pub redirect_uri: String,
+    /// `TWITCH_OAUTH_URL`
+    pub oauth_url: String,
+    /// `TWITCH_HELIX_URL`
+    pub helix_url: String,
+    /// `TWITCH_EVENTSUB_SECRET`
+    #[serde(serialize_with = "redact")]
+    pub eventsub_secret: String,
+    /// `TWITCH_EVENTSUB_CALLBACK_URL`: empty to disable event triggers
+    pub eventsub_callback_url: String,
+}
+
+impl TwitchSettings {
+    pub fn credentials(&self) -> TwitchCredentials {
+        TwitchCredentials {
+            client_id: self.client_id.clone(),
+            client_secret: self.client_secret.clone(),
+            redirect_uri: self.redirect_uri.clone(),
+        }
+    }
+
+    /// Whether Twitch can deliver events to us, i.e. a callback url is set.
+    pub fn eventsub_enabled(&self) -> bool {
+        !self.eventsub_callback_url.is_empty()
+    }
+
+    fn read(loader: &ConfigLoader) -> Option<Self> {
+        let required = |setting, key| loader.take(loader.var(setting, key).ensure_non_empty());
+        let client_id = required("twitch.client_id", "TWITCH_CLIENT_ID");
+        let client_secret = required("twitch.client_secret", "TWITCH_CLIENT_SECRET");
+        let redirect_uri = required("twitch.redirect_uri", "TWITCH_REDIRECT_URI");
+        let oauth_url = loader.take(
+            loader
+                .var("twitch.oauth_url", "TWITCH_OAUTH_URL")
+                .with_default_value("https://id.twitch.tv/oauth2"),
+        );
+        let helix_url = loader.take(
+            loader
+                .var("twitch.helix_url", "TWITCH_HELIX_URL")
+                .with_default_value("https://api.twitch.tv/helix"),
+        );
+        let eventsub_callback_url = loader.take(
+            loader
+                .var(
+                    "twitch.eventsub_callback_url",
+                    "TWITCH_EVENTSUB_CALLBACK_URL",
+                )
+                .with_default_value(""),
+        );
+        let eventsub_enabled = eventsub_callback_url
+            .as_ref()
+            .is_some_and(|url| !url.is_empty());
+        let eventsub_secret = loader.take(
+            loader
+                .var("twitch.eventsub_secret", "TWITCH_EVENTSUB_SECRET")
+                .with_default_value("")
+                .ensure(|secret| match (secret.len(), eventsub_enabled) {
+                    (0, false) | (10..=100, true) => Ok(()),
+                    (0, true) => Err("must be set along with the callback url".to_string()),
+                    (_, false) => Err("has no callback url to be used with".to_string()),
+                    _ => Err("must be 10 to 100 characters long".to_string()),
+                }),
+        );
+        Some(Self {
+            client_id: client_id?,
+            client_secret: client_secret?,
+            redirect_uri: redirect_uri?,
+            oauth_url: oauth_url?,
+            helix_url: helix_url?,
+            eventsub_secret: eventsub_secret?,
+            eventsub_callback_url: eventsub_callback_url?,
+        })
+    }
+}
+
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct ChatSettings {
+    /// `CHAT_ENABLED`
+    pub enabled: bool,
+    /// `CHAT_HOST`
+    pub host: String,
+    /// `CHAT_PORT`
+    pub port: u16,
+    /// `CHAT_TLS`
+    pub tls: bool,
+    /// `CHAT_BOT_USERNAME`: empty to reply as each broadcaster
+    pub bot_username: String,
+    /// `CHAT_SYNC_INTERVAL`, in seconds between checks for channels that
+    /// turned chat commands on or off
+    pub sync_interval: u64,
+    /// `CHAT_RECONNECT_DELAY`, in seconds
+    pub reconnect_delay: u64,
+}
+
+impl ChatSettings {
+    fn read(loader: &ConfigLoader) -> Option<Self> {
+        let enabled = loader.take(
+            loader
+                .var("chat.enabled", "CHAT_ENABLED")
+                .with_default_value("true")
+                .parse::<bool>(),
+        );
+        let host = loader.take(
+            loader
+                .var("chat.host", "CHAT_HOST")
+                .with_default_value("irc.chat.twitch.tv"),
+        );
+        let port = loader.take(
+            loader
+                .var("chat.port", "CHAT_PORT")
+                .with_default_value("6697")
+                .parse::<u16>(),
+        );
+        let tls = loader.take(
+            loader
+                .var("chat.tls", "CHAT_TLS")
+                .with_default_value("true")
+                .parse::<bool>(),
+        );
+        let bot_username = loader.take(
+            loader
+                .var("chat.bot_username", "CHAT_BOT_USERNAME")
+                .with_default_value(""),
+        );
+        let sync_interval = loader.take(
+            loader
+                .var("chat.sync_interval", "CHAT_SYNC_INTERVAL")
+                .with_default_value("60")
+                .parse::<u64>()
+                .ensure_positive(),
+        );
+        let reconnect_delay = loader.take(
+            loader
+                .var("chat.reconnect_delay", "CHAT_RECONNECT_DELAY")
+                .with_default_value("5")
+                .parse::<u64>()
+                .ensure_positive(),
+        );
+        Some(Self {
+            enabled: enabled?,
+            host: host?,
+            port: port?,
+            tls: tls?,
+            bot_username: bot_username?,
+            sync_interval: sync_interval?,
+            reconnect_delay: reconnect_delay?,
+        })
+    }
+}
+
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct AssetGcSettings {
+    /// `ASSET_GC_INTERVAL`, in seconds; 0 disables the collection
+    pub interval: u64,
+    /// `ASSET_GC_GRACE_PERIOD`, in seconds
+    pub grace_period: u64,
+    /// `ASSET_GC_DELETE_ORPHANS`
+    pub delete_orphans: bool,
+}
+
+impl AssetGcSettings {
+    fn read(loader: &ConfigLoader) -> Option<Self> {
+        let interval = loader.take(
+            loader
+                .var("asset_gc.interval", "ASSET_GC_INTERVAL")
+                .with_default_value("3600")
+                .parse::<u64>(),
+        );
+        let grace_period = loader.take(
+            loader
+                .var("asset_gc.grace_period", "ASSET_GC_GRACE_PERIOD")
+                .with_default_value("86400")
+                .parse::<u64>(),
+        );
+        let delete_orphans = loader.take(
+            loader
+                .var("asset_gc.delete_orphans", "ASSET_GC_DELETE_ORPHANS")
+                .with_default_value("false")
+                .parse::<bool>(),
+        );
+        Some(Self {
+            interval: interval?,
+            grace_period: grace_period?,
+            delete_orphans: delete_orphans?,
+        })
+    }
+}
+
+/// Hides the value, but keeps an unset secret recognizable as unset.
+fn redact<S: Serializer>(value: &str, serializer: S) -> Result<S::Ok, S::Error> {
+    match value.is_empty() {
+        true => serializer.serialize_str(""),
+        false => serializer.serialize_str(REDACTED),
+    }
+}
+
+/// Keeps the url readable but drops any password in it.
+fn redact_url<S: Serializer>(url: &str, serializer: S) -> Result<S::Ok, S::Error> {
+    match reqwest::Url::parse(url) {
+        Ok(mut parsed) if parsed.password().is_some() => {
+            parsed.set_password(Some(REDACTED)).ok();
+            serializer.serialize_str(parsed.as_str())
+        }
+        _ => serializer.serialize_str(url),
+    }
+}
+
+pub(crate) fn seconds<S: Serializer>(
+    duration: &Duration,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    serializer.serialize_u64(duration.as_secs())
+}
+
+pub(crate) fn milliseconds<S: Serializer>(
+    duration: &Duration,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    serializer.serialize_u64(duration.as_millis() as u64)
+}
@@ -0,0 +1,53 @@
+use diesel::result::DatabaseErrorKind;
+
+#[derive(Debug)]
+pub enum DbError {
+    Unavailable(r2d2::Error),
+    NotFound,
+    Conflict(String),
+    Query(diesel::result::Error),
+}
+
+impl PartialEq for DbError {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Self::Unavailable(a), Self::Unavailable(b)) => a.to_string() == b.to_string(),
+            (Self::NotFound, Self::NotFound) => true,
+            (Self::Conflict(a), Self::Conflict(b)) => a == b,
+            (Self::Query(a), Self::Query(b)) => a == b,
+            _ => false,
+        }
+    }
+}
+
+impl std::fmt::Display for DbError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Unavailable(error) => write!(f, "database unavailable: {error}"),
+            Self::NotFound => f.write_str("record not found"),
+            Self::Conflict(message) => write!(f, "conflicting record: {message}"),
+            Self::Query(error) => write!(f, "query failed: {error}"),
+        }
+    }
+}
+
+impl std::error::Error for DbError {}
+
+impl From<r2d2::Error> for DbError {
+    fn from(error: r2d2::Error) -> Self {
+        Self::Unavailable(error)
+    }
+}
+
+impl From<diesel::result::Error> for DbError {
+    fn from(error: diesel::result::Error) -> Self {
+        match error {
+            diesel::result::Error::NotFound => Self::NotFound,
+            diesel::result::Error::DatabaseError(
+                DatabaseErrorKind::UniqueViolation | DatabaseErrorKind::ForeignKeyViolation,
+                info,
+            ) => Self::Conflict(info.message().to_string()),
+            error => Self::Query(error),
+        }
+    }
+}
@@ -0,0 +1,889 @@
+use std::{
+    collections::HashMap,
+    sync::{Mutex, MutexGuard, PoisonError},
+};
+
+use crate::models::{
+    ApiToken, Asset, AssetPage, AssetSearchQuery, ChannelAdmin, ChannelRole, EventTrigger, Folder,
+    NewApiToken, NewEventTrigger, NewSubmission, OverlayToken, StoredChannelState,
+    StoredEventSubSubscription, StoredSession, StoredUserTokens, Submission, Tag, User,
+    UserFacingAsset, UserSettings, ValidatedAssetMetadata,
+};
+
+use super::{
+    AdminRepository, AssetRepository, ChannelStateRepository, DbError, SessionRepository,
+    SettingsRepository, SubmissionRepository, TokenRepository, TriggerRepository, UserRepository,
+};
+
+#[derive(Default)]
+struct Tables {
+    last_id: i32,
+    users: Vec<User>,
+    user_settings: Vec<UserSettings>,
+    assets: Vec<Asset>,
+    folders: Vec<Folder>,
+    tags: Vec<Tag>,
+    asset_tags: Vec<(String, i32)>,
+    channel_admins: Vec<ChannelAdmin>,
+    sessions: Vec<StoredSession>,
+    user_tokens: Vec<StoredUserTokens>,
+    overlay_tokens: Vec<OverlayToken>,
+    api_tokens: Vec<ApiToken>,
+    event_triggers: Vec<EventTrigger>,
+    eventsub_subscriptions: Vec<StoredEventSubSubscription>,
+    submissions: Vec<Submission>,
+    channel_states: Vec<StoredChannelState>,
+}
+
+impl Tables {
+    fn next_id(&mut self) -> i32 {
+        self.last_id += 1;
+        self.last_id
+    }
+
+    fn asset_metadata(&self, asset: &Asset) -> ValidatedAssetMetadata {
+        let folder = asset.folder_id.and_then(|id| {
+            self.folders
+                .iter()
+                .find(|folder| folder.id == id)
+                .map(|folder| folder.name.clone())
+        });
+        let mut tags: Vec<String> = self
+            .asset_tags
+            .iter()
+            .filter(|(local_filename, _)| *local_filename == asset.local_filename)
+            .filter_map(|(_, tag_id)| self.tags.iter().find(|tag| tag.id == *tag_id))
+            .map(|tag| tag.name.clone())
+            .collect();
+        tags.sort();
+        ValidatedAssetMetadata { folder, tags }
+    }
+
+    fn matches_search(&self, asset: &Asset, query: &AssetSearchQuery) -> bool {
+        let contains =
+            |haystack: &str, needle: &str| haystack.to_lowercase().contains(&needle.to_lowercase());
+        let starts_with = |haystack: &str, needle: &str| {
+            haystack.to_lowercase().starts_with(&needle.to_lowercase())
+        };
+        let metadata = self.asset_metadata(asset);
+        query
+            .name
+            .as_ref()
+            .is_none_or(|name| contains(&asset.original_filename, name))
+            && query
+                .content_type
+                .as_ref()
+                .is_none_or(|content_type| starts_with(&asset.content_type, content_type))
+            && query
+                .uploaded_after
+                .is_none_or(|uploaded_after| asset.uploaded_at >= uploaded_after)
+            && query
+                .uploaded_before
+                .is_none_or(|uploaded_before| asset.uploaded_at < uploaded_before)
+            && query
+                .folder
+                .as_ref()
+                .is_none_or(|folder| metadata.folder.as_ref() == Some(folder))
+            && query
+                .tag
+                .as_ref()
+                .is_none_or(|tag| metadata.tags.contains(tag))
+    }
+}
+
+/// Keeps every table in memory. Meant for handler tests that don't need sqlite.
+#[derive(Default)]
+pub struct InMemoryDbService {
+    tables: Mutex<Tables>,
+}
+
+impl InMemoryDbService {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn tables(&self) -> MutexGuard<'_, Tables> {
+        self.tables.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+fn conflict(table: &str) -> DbError {
+    DbError::Conflict(format!("UNIQUE constraint failed: {table}"))
+}
+
+impl UserRepository for InMemoryDbService {
+    fn get_user(&self, username: &str) -> Result<Option<User>, DbError> {
+        let tables = self.tables();
+        Ok(tables
+            .users
+            .iter()
+            .find(|user| user.username == username)
+            .cloned())
+    }
+
+    fn create_user(&self, user: &User) -> Result<User, DbError> {
+        let mut tables = self.tables();
+        if tables.users.iter().any(|u| u.username == user.username) {
+            return Err(conflict("users.username"));
+        }
+        tables.users.push(user.clone());
+        Ok(user.clone())
+    }
+}
+
+impl SettingsRepository for InMemoryDbService {
+    fn get_user_settings(&self, user: &User) -> Result<Option<UserSettings>, DbError> {
+        let tables = self.tables();
+        Ok(tables
+            .user_settings
+            .iter()
+            .find(|settings| settings.username == user.username)
+            .cloned())
+    }
+
+    fn update_user_settings(&self, settings: &UserSettings) -> Result<UserSettings, DbError> {
+        let mut tables = self.tables();
+        let existing = tables
+            .user_settings
+            .iter_mut()
+            .find(|existing| existing.username == settings.username)
+            .ok_or(DbError::NotFound)?;
+        *existing = settings.clone();
+        Ok(settings.clone())
+    }
+
+    fn create_user_settings(&self, settings: &UserSettings) -> Result<UserSettings, DbError> {
+        let mut tables = self.tables();
+        if tables
+            .user_settings
+            .iter()
+            .any(|existing| existing.username == settings.username)
+        {
+            return Err(conflict("user_settings.username"));
+        }
+        tables.user_settings.push(settings.clone());
+        Ok(settings.clone())
+    }
+
+    fn get_chat_channels(&self) -> Result<Vec<String>, DbError> {
+        let tables = self.tables();
+        Ok(tables
+            .user_settings
+            .iter()
+            .filter(|settings| settings.chat_commands)
+            .map(|settings| settings.username.clone())
+            .collect())
+    }
+
+    fn get_moderator_sync_users(&self) -> Result<Vec<User>, DbError> {
+        let tables = self.tables();
+        Ok(tables
+            .users
+            .iter()
+            .filter(|user| {
+                tables
+                    .user_settings
+                    .iter()
+                    .any(|settings| settings.username == user.username && settings.sync_moderators)
+            })
+            .cloned()
+            .collect())
+    }
+}
+
+impl AssetRepository for InMemoryDbService {
+    fn get_asset(&self, filename: &str) -> Result<Option<Asset>, DbError> {
+        let tables = self.tables();
+        Ok(tables
+            .assets
+            .iter()
+            .find(|asset| asset.local_filename == filename)
+            .cloned())
+    }
+
+    fn get_asset_by_checksum(&self, checksum: &str) -> Result<Option<Asset>, DbError> {
+        let tables = self.tables();
+        Ok(tables
+            .assets
+            .iter()
+            .find(|asset| asset.checksum == checksum)
+            .cloned())
+    }
+
+    fn create_asset(&self, asset: &Asset) -> Result<Asset, DbError> {
+        let mut tables = self.tables();
+        if tables
+            .assets
+            .iter()
+            .any(|existing| existing.local_filename == asset.local_filename)
+        {
+            return Err(conflict("assets.local_filename"));
+        }
+        tables.assets.push(asset.clone());
+        Ok(asset.clone())
+    }
+
+    fn get_all_assets(&self) -> Result<Vec<Asset>, DbError> {
+        Ok(self.tables().assets.clone())
+    }
+
+    fn get_broadcaster_assets(&self, broadcaster: &User) -> Result<Vec<Asset>, DbError> {
+        let tables = self.tables();
+        Ok(tables
+            .assets
+            .iter()
+            .filter(|asset| asset.username == broadcaster.username)
+            .cloned()
+            .collect())
+    }
+
+    fn search_assets(
+        &self,
+        broadcaster: &User,
+        query: &AssetSearchQuery,
+    ) -> Result<AssetPage, DbError> {
+        let tables = self.tables();
+        let mut matching: Vec<&Asset> = tables
+            .assets
+            .iter()
+            .filter(|asset| asset.username == broadcaster.username)
+            .filter(|asset| tables.matches_search(asset, query))
+            .collect();
+        matching.sort_by(|a, b| {
+            b.uploaded_at
+                .cmp(&a.uploaded_at)
+                .then_with(|| a.local_filename.cmp(&b.local_filename))
+        });
+        let assets = matching
+            .iter()
+            .skip(query.offset() as usize)
+            .take(query.per_page() as usize)
+            .map(|asset| {
+                let metadata = tables.asset_metadata(asset);
+                UserFacingAsset::from((*asset).clone())
+                    .with_folder(metadata.folder)
+                    .with_tags(metadata.tags)
+            })
+            .collect();
+        Ok(AssetPage {
+            assets,
+            page: query.page(),
+            per_page: query.per_page(),
+            total: matching.len() as i64,
+        })
+    }
+
+    fn get_assets_metadata(
+        &self,
+        assets: &[Asset],
+    ) -> Result<HashMap<String, ValidatedAssetMetadata>, DbError> {
+        let tables = self.tables();
+        Ok(assets
+            .iter()
+            .map(|asset| (asset.local_filename.clone(), tables.asset_metadata(asset)))
+            .collect())
+    }
+
+    fn get_folders(&self, broadcaster: &User) -> Result<Vec<Folder>, DbError> {
+        let tables = self.tables();
+        let mut folders: Vec<Folder> = tables
+            .folders
+            .iter()
+            .filter(|folder| folder.username == broadcaster.username)
+            .cloned()
+            .collect();
+        folders.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(folders)
+    }
+
+    fn get_tags(&self, broadcaster: &User) -> Result<Vec<Tag>, DbError> {
+        let tables = self.tables();
+        let mut tags: Vec<Tag> = tables
+            .tags
+            .iter()
+            .filter(|tag| tag.username == broadcaster.username)
+            .cloned()
+            .collect();
+        tags.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(tags)
+    }
+
+    fn set_asset_metadata(
+        &self,
+        asset: &Asset,
+        metadata: &ValidatedAssetMetadata,
+    ) -> Result<(), DbError> {
+        let mut tables = self.tables();
+        let folder_id = match &metadata.folder {
+            Some(name) => {
+                let existing = tables
+                    .folders
+                    .iter()
+                    .find(|folder| folder.username == asset.username && folder.name == *name)
+                    .map(|folder| folder.id);
+                Some(match existing {
+                    Some(id) => id,
+                    None => {
+                        let id = tables.next_id();
+                        tables.folders.push(Folder {
+                            id,
+                            username: asset.username.clone(),
+                            name: name.clone(),
+                        });
+                        id
+                    }
+                })
+            }
+            None => None,
+        };
+        if let Some(stored) = tables
+            .assets
+            .iter_mut()
+            .find(|stored| stored.local_filename == asset.local_filename)
+        {
+            stored.folder_id = folder_id;
+        }
+
+        tables
+            .asset_tags
+            .retain(|(local_filename, _)| *local_filename != asset.local_filename);
+        for name in &metadata.tags {
+            let existing = tables
+                .tags
+                .iter()
+                .find(|tag| tag.username == asset.username && tag.name == *name)
+                .map(|tag| tag.id);
+            let tag_id = match existing {
+                Some(id) => id,
+                None => {
+                    let id = tables.next_id();
+                    tables.tags.push(Tag {
+                        id,
+                        username: asset.username.clone(),
+                        name: name.clone(),
+                    });
+                    id
+                }
+            };
+            tables
+                .asset_tags
+                .push((asset.local_filename.clone(), tag_id));
+        }
+        Ok(())
+    }
+}
+
+impl AdminRepository for InMemoryDbService {
+    fn create_channel_admin(&self, channel_admin: &ChannelAdmin) -> Result<ChannelAdmin, DbError> {
+        let mut tables = self.tables();
+        if tables.channel_admins.iter().any(|existing| {
+            existing.username == channel_admin.username
+                && existing.broadcaster_username == channel_admin.broadcaster_username
+        }) {
+            return Err(conflict("channel_admins.username"));
+        }
+        tables.channel_admins.push(channel_admin.clone());
+        Ok(channel_admin.clone())
+    }
+
+    fn get_channel_admin(
+        &self,
+        username: &str,
+        broadcaster: &User,
+    ) -> Result<Option<ChannelAdmin>, DbError> {
+        let tables = self.tables();
+        Ok(tables
+            .channel_admins
+            .iter()
+            .find(|channel_admin| {
+                channel_admin.username == username
+                    && channel_admin.broadcaster_username == broadcaster.username
+            })
+            .cloned())
+    }
+
+    fn get_channel_admins(&self, broadcaster: &User) -> Result<Vec<ChannelAdmin>, DbError> {
+        let tables = self.tables();
+        Ok(tables
+            .channel_admins
+            .iter()
+            .filter(|channel_admin| channel_admin.broadcaster_username == broadcaster.username)
+            .cloned()
+            .collect())
+    }
+
+    fn set_channel_admin_role(
+        &self,
+        username: &str,
+        broadcaster: &User,
+        role: ChannelRole,
+    ) -> Result<Option<ChannelAdmin>, DbError> {
+        let mut tables = self.tables();
+        Ok(tables
+            .channel_admins
+            .iter_mut()
+            .find(|channel_admin| {
+                channel_admin.username == username
+                    && channel_admin.broadcaster_username == broadcaster.username
+            })
+            .map(|channel_admin| {
+                channel_admin.role = role.as_str().to_string();
+                channel_admin.clone()
+            }))
+    }
+
+    fn delete_channel_admin(&self, username: &str, broadcaster: &User) -> Result<usize, DbError> {
+        let mut tables = self.tables();
+        let before = tables.channel_admins.len();
+        tables.channel_admins.retain(|channel_admin| {
+            channel_admin.username != username
+                || channel_admin.broadcaster_username != broadcaster.username
+        });
+        Ok(before - tables.channel_admins.len())
+    }
+
+    fn replace_synced_moderators(
+        &self,
+        broadcaster: &User,
+        moderators: &[String],
+    ) -> Result<(usize, usize), DbError> {
+        let mut tables = self.tables();
+        let before = tables.channel_admins.len();
+        tables.channel_admins.retain(|channel_admin| {
+            channel_admin.broadcaster_username != broadcaster.username
+                || channel_admin.source != ChannelAdmin::TWITCH_MODERATOR
+                || moderators.contains(&channel_admin.username)
+        });
+        let removed = before - tables.channel_admins.len();
+        let mut added = 0;
+        for moderator in moderators {
+            let exists = tables.channel_admins.iter().any(|channel_admin| {
+                channel_admin.username == *moderator
+                    && channel_admin.broadcaster_username == broadcaster.username
+            });
+            if !exists {
+                tables.channel_admins.push(ChannelAdmin::for_login(
+                    moderator,
+                    broadcaster,
+                    ChannelAdmin::TWITCH_MODERATOR,
+                ));
+                added += 1;
+            }
+        }
+        Ok((added, removed))
+    }
+}
+
+impl SessionRepository for InMemoryDbService {
+    fn get_session(&self, id: &str, now: i64) -> Result<Option<StoredSession>, DbError> {
+        let tables = self.tables();
+        Ok(tables
+            .sessions
+            .iter()
+            .find(|session| session.id == id && session.expiry_date > now)
+            .cloned())
+    }
+
+    fn create_session(&self, session: &StoredSession) -> Result<StoredSession, DbError> {
+        let mut tables = self.tables();
+        if tables
+            .sessions
+            .iter()
+            .any(|existing| existing.id == session.id)
+        {
+            return Err(conflict("sessions.id"));
+        }
+        tables.sessions.push(session.clone());
+        Ok(session.clone())
+    }
+
+    fn save_session(&self, session: &StoredSession) -> Result<(), DbError> {
+        let mut tables = self.tables();
+        tables.sessions.retain(|existing| existing.id != session.id);
+        tables.sessions.push(session.clone());
+        Ok(())
+    }
+
+    fn delete_session(&self, id: &str) -> Result<(), DbError> {
+        self.tables().sessions.retain(|session| session.id != id);
+        Ok(())
+    }
+
+    fn delete_expired_sessions(&self, now: i64) -> Result<usize, DbError> {
+        let mut tables = self.tables();
+        let before = tables.sessions.len();
+        tables.sessions.retain(|session| session.expiry_date > now);
+        Ok(before - tables.sessions.len())
+    }
+}
+
+impl TokenRepository for InMemoryDbService {
+    fn get_user_tokens(&self, username: &str) -> Result<Option<StoredUserTokens>, DbError> {
+        let tables = self.tables();
+        Ok(tables
+            .user_tokens
+            .iter()
+            .find(|tokens| tokens.username == username)
+            .cloned())
+    }
+
+    fn save_user_tokens(&self, tokens: &StoredUserTokens) -> Result<(), DbError> {
+        let mut tables = self.tables();
+        tables
+            .user_tokens
+            .retain(|existing| existing.username != tokens.username);
+        tables.user_tokens.push(tokens.clone());
+        Ok(())
+    }
+
+    fn get_expiring_user_tokens(&self, before: i64) -> Result<Vec<StoredUserTokens>, DbError> {
+        let tables = self.tables();
+        Ok(tables
+            .user_tokens
+            .iter()
+            .filter(|tokens| tokens.expires_at <= before && tokens.refresh_token.is_some())
+            .cloned()
+            .collect())
+    }
+
+    fn delete_user_tokens(&self, username: &str) -> Result<(), DbError> {
+        self.tables()
+            .user_tokens
+            .retain(|tokens| tokens.username != username);
+        Ok(())
+    }
+
+    fn get_overlay_token(&self, owner: &User) -> Result<Option<OverlayToken>, DbError> {
+        let tables = self.tables();
+        Ok(tables
+            .overlay_tokens
+            .iter()
+            .find(|token| token.username == owner.username)
+            .cloned())
+    }
+
+    fn save_overlay_token(&self, token: &OverlayToken) -> Result<(), DbError> {
+        let mut tables = self.tables();
+        tables
+            .overlay_tokens
+            .retain(|existing| existing.username != token.username);
+        tables.overlay_tokens.push(token.clone());
+        Ok(())
+    }
+
+    fn delete_overlay_token(&self, owner: &User) -> Result<usize, DbError> {
+        let mut tables = self.tables();
+        let before = tables.overlay_tokens.len();
+        tables
+            .overlay_tokens
+            .retain(|token| token.username != owner.username);
+        Ok(before - tables.overlay_tokens.len())
+    }
+
+    fn create_api_token(&self, token: &NewApiToken) -> Result<ApiToken, DbError> {
+        let mut tables = self.tables();
+        if tables
+            .api_tokens
+            .iter()
+            .any(|existing| existing.token_hash == token.token_hash)
+        {
+            return Err(conflict("api_tokens.token_hash"));
+        }
+        let created = ApiToken {
+            id: tables.next_id(),
+            username: token.username.clone(),
+            name: token.name.clone(),
+            token_hash: token.token_hash.clone(),
+            scopes: token.scopes.clone(),
+            created_at: token.created_at,
+            expires_at: token.expires_at,
+            last_used_at: None,
+        };
+        tables.api_tokens.push(created.clone());
+        Ok(created)
+    }
+
+    fn get_api_tokens(&self, owner: &User) -> Result<Vec<ApiToken>, DbError> {
+        let tables = self.tables();
+        Ok(tables
+            .api_tokens
+            .iter()
+            .filter(|token| token.username == owner.username)
+            .cloned()
+            .collect())
+    }
+
+    fn get_api_token_by_hash(&self, token_hash: &str) -> Result<Option<ApiToken>, DbError> {
+        let tables = self.tables();
+        Ok(tables
+            .api_tokens
+            .iter()
+            .find(|token| token.token_hash == token_hash)
+            .cloned())
+    }
+
+    fn touch_api_token(&self, token: &ApiToken, used_at: i64) -> Result<(), DbError> {
+        let mut tables = self.tables();
+        if let Some(stored) = tables
+            .api_tokens
+            .iter_mut()
+            .find(|stored| stored.id == token.id)
+        {
+            stored.last_used_at = Some(used_at);
+        }
+        Ok(())
+    }
+
+    fn delete_api_token(&self, owner: &User, id: i32) -> Result<usize, DbError> {
+        let mut tables = self.tables();
+        let before = tables.api_tokens.len();
+        tables
+            .api_tokens
+            .retain(|token| token.id != id || token.username != owner.username);
+        Ok(before - tables.api_tokens.len())
+    }
+}
+
+impl TriggerRepository for InMemoryDbService {
+    fn get_event_triggers(&self, owner: &User) -> Result<Vec<EventTrigger>, DbError> {
+        let tables = self.tables();
+        Ok(tables
+            .event_triggers
+            .iter()
+            .filter(|trigger| trigger.username == owner.username)
+            .cloned()
+            .collect())
+    }
+
+    fn get_matching_event_triggers(
+        &self,
+        username: &str,
+        event_type: &str,
+        reward_id: Option<&str>,
+    ) -> Result<Vec<EventTrigger>, DbError> {
+        let tables = self.tables();
+        Ok(tables
+            .event_triggers
+            .iter()
+            .filter(|trigger| trigger.username == username && trigger.event_type == event_type)
+            .filter(|trigger| match (&trigger.reward_id, reward_id) {
+                (None, _) => true,
+                (Some(trigger_reward), Some(reward_id)) => trigger_reward == reward_id,
+                (Some(_), None) => false,
+            })
+            .cloned()
+            .collect())
+    }
+
+    fn create_event_trigger(&self, trigger: &NewEventTrigger) -> Result<EventTrigger, DbError> {
+        let mut tables = self.tables();
+        let created = EventTrigger {
+            id: tables.next_id(),
+            username: trigger.username.clone(),
+            event_type: trigger.event_type.clone(),
+            reward_id: trigger.reward_id.clone(),
+            asset_filename: trigger.asset_filename.clone(),
+            x: trigger.x,
+            y: trigger.y,
+            w: trigger.w,
+            h: trigger.h,
+            duration_seconds: trigger.duration_seconds,
+        };
+        tables.event_triggers.push(created.clone());
+        Ok(created)
+    }
+
+    fn delete_event_trigger(&self, owner: &User, id: i32) -> Result<Option<EventTrigger>, DbError> {
+        let mut tables = self.tables();
+        let position = tables
+            .event_triggers
+            .iter()
+            .position(|trigger| trigger.id == id && trigger.username == owner.username);
+        Ok(position.map(|position| tables.event_triggers.remove(position)))
+    }
+
+    fn get_eventsub_subscription(
+        &self,
+        id: &str,
+    ) -> Result<Option<StoredEventSubSubscription>, DbError> {
+        let tables = self.tables();
+        Ok(tables
+            .eventsub_subscriptions
+            .iter()
+            .find(|subscription| subscription.id == id)
+            .cloned())
+    }
+
+    fn get_eventsub_subscription_for(
+        &self,
+        username: &str,
+        event_type: &str,
+    ) -> Result<Option<StoredEventSubSubscription>, DbError> {
+        let tables = self.tables();
+        Ok(tables
+            .eventsub_subscriptions
+            .iter()
+            .find(|subscription| {
+                subscription.username == username && subscription.event_type == event_type
+            })
+            .cloned())
+    }
+
+    fn save_eventsub_subscription(
+        &self,
+        subscription: &StoredEventSubSubscription,
+    ) -> Result<(), DbError> {
+        let mut tables = self.tables();
+        tables
+            .eventsub_subscriptions
+            .retain(|existing| existing.id != subscription.id);
+        tables.eventsub_subscriptions.push(subscription.clone());
+        Ok(())
+    }
+
+    fn delete_eventsub_subscription(&self, id: &str) -> Result<(), DbError> {
+        self.tables()
+            .eventsub_subscriptions
+            .retain(|subscription| subscription.id != id);
+        Ok(())
+    }
+}
+
+impl SubmissionRepository for InMemoryDbService {
+    fn create_submission(&self, submission: &NewSubmission) -> Result<Submission, DbError> {
+        let mut tables = self.tables();
+        let created = Submission {
+            id: tables.next_id(),
+            broadcaster_username: submission.broadcaster_username.clone(),
+            submitter: submission.submitter.clone(),
+            local_filename: submission.local_filename.clone(),
+            original_filename: submission.original_filename.clone(),
+            checksum: submission.checksum.clone(),
+            content_type: submission.content_type.clone(),
+            status: submission.status.clone(),
+            submitted_at: submission.submitted_at,
+            reviewed_by: None,
+            reviewed_at: None,
+        };
+        tables.submissions.push(created.clone());
+        Ok(created)
+    }
+
+    fn get_submission(&self, broadcaster: &User, id: i32) -> Result<Option<Submission>, DbError> {
+        let tables = self.tables();
+        Ok(tables
+            .submissions
+            .iter()
+            .find(|submission| {
+                submission.id == id && submission.broadcaster_username == broadcaster.username
+            })
+            .cloned())
+    }
+
+    fn get_submissions(
+        &self,
+        broadcaster: &User,
+        status: Option<&str>,
+    ) -> Result<Vec<Submission>, DbError> {
+        let tables = self.tables();
+        Ok(tables
+            .submissions
+            .iter()
+            .filter(|submission| submission.broadcaster_username == broadcaster.username)
+            .filter(|submission| status.is_none_or(|status| submission.status == status))
+            .cloned()
+            .collect())
+    }
+
+    fn get_all_pending_submissions(&self) -> Result<Vec<Submission>, DbError> {
+        let tables = self.tables();
+        Ok(tables
+            .submissions
+            .iter()
+            .filter(|submission| submission.is_pending())
+            .cloned()
+            .collect())
+    }
+
+    fn count_pending_submissions(
+        &self,
+        broadcaster: &User,
+        submitter: &str,
+    ) -> Result<i64, DbError> {
+        let tables = self.tables();
+        Ok(tables
+            .submissions
+            .iter()
+            .filter(|submission| {
+                submission.broadcaster_username == broadcaster.username
+                    && submission.submitter == submitter
+                    && submission.is_pending()
+            })
+            .count() as i64)
+    }
+
+    fn review_submission(
+        &self,
+        submission: &Submission,
+        reviewer: &str,
+        status: &str,
+    ) -> Result<Option<Submission>, DbError> {
+        let mut tables = self.tables();
+        let Some(position) = tables
+            .submissions
+            .iter()
+            .position(|stored| stored.id == submission.id && stored.is_pending())
+        else {
+            return Ok(None);
+        };
+        let mut reviewed = tables.submissions[position].clone();
+        reviewed.status = status.to_string();
+        reviewed.reviewed_by = Some(reviewer.to_string());
+        reviewed.reviewed_at = Some(time::OffsetDateTime::now_utc().unix_timestamp());
+        if status == Submission::APPROVED {
+            let asset = reviewed.as_asset();
+            if tables
+                .assets
+                .iter()
+                .any(|existing| existing.local_filename == asset.local_filename)
+            {
+                return Err(conflict("assets.local_filename"));
+            }
+            match tables
+                .assets
+                .iter()
+                .find(|existing| existing.checksum == asset.checksum)
+            {
+                None => tables.assets.push(asset),
+                Some(existing) if existing.username == asset.username => {}
+                Some(_) => return Err(conflict("assets.checksum")),
+            }
+        }
+        tables.submissions[position] = reviewed.clone();
+        Ok(Some(reviewed))
+    }
+}
+
+impl ChannelStateRepository for InMemoryDbService {
+    fn get_channel_states(&self) -> Result<Vec<StoredChannelState>, DbError> {
+        Ok(self.tables().channel_states.clone())
+    }
+
+    fn save_channel_state(&self, state: &StoredChannelState) -> Result<(), DbError> {
+        let mut tables = self.tables();
+        tables
+            .channel_states
+            .retain(|existing| existing.username != state.username);
+        tables.channel_states.push(state.clone());
+        Ok(())
+    }
+
+    fn delete_channel_state(&self, username: &str) -> Result<usize, DbError> {
+        let mut tables = self.tables();
+        let before = tables.channel_states.len();
+        tables
+            .channel_states
+            .retain(|state| state.username != username);
+        Ok(before - tables.channel_states.len())
+    }
+}
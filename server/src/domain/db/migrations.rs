@@ -0,0 +1,126 @@
+use diesel::backend::Backend;
+use diesel::migration::MigrationSource;
+use diesel_migrations::{embed_migrations, EmbeddedMigrations, MigrationHarness};
+
+use super::{ConnectionPool, SqlDbService};
+
+pub const SQLITE_MIGRATIONS: EmbeddedMigrations = embed_migrations!("migrations/sqlite");
+pub const POSTGRES_MIGRATIONS: EmbeddedMigrations = embed_migrations!("migrations/postgres");
+
+#[derive(Debug)]
+pub enum MigrationError {
+    Unavailable(r2d2::Error),
+    Failed(Box<dyn std::error::Error + Send + Sync>),
+    UnknownVersions(Vec<String>),
+    Pending(Vec<String>),
+}
+
+impl std::fmt::Display for MigrationError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Unavailable(error) => write!(f, "database unavailable: {error}"),
+            Self::Failed(error) => write!(f, "migration failed: {error}"),
+            Self::UnknownVersions(versions) => write!(
+                f,
+                "database has migrations this build does not know: {}",
+                versions.join(", ")
+            ),
+            Self::Pending(versions) => write!(
+                f,
+                "database has pending migrations: {}",
+                versions.join(", ")
+            ),
+        }
+    }
+}
+
+impl std::error::Error for MigrationError {}
+
+#[derive(Debug, Default, PartialEq)]
+pub struct SchemaStatus {
+    pub pending: Vec<String>,
+    pub unknown: Vec<String>,
+}
+
+impl SqlDbService {
+    pub fn schema_status(&self) -> Result<SchemaStatus, MigrationError> {
+        match &self.pool {
+            ConnectionPool::Sqlite(pool) => schema_status(
+                &mut pool.get().map_err(MigrationError::Unavailable)?,
+                &SQLITE_MIGRATIONS,
+            ),
+            ConnectionPool::Postgres(pool) => schema_status(
+                &mut pool.get().map_err(MigrationError::Unavailable)?,
+                &POSTGRES_MIGRATIONS,
+            ),
+        }
+    }
+
+    /// Applies every pending migration. Refuses to touch a schema written by
+    /// a newer build.
+    pub fn migrate(&self) -> Result<Vec<String>, MigrationError> {
+        let status = self.schema_status()?;
+        if !status.unknown.is_empty() {
+            return Err(MigrationError::UnknownVersions(status.unknown));
+        }
+        let applied = match &self.pool {
+            ConnectionPool::Sqlite(pool) => pool
+                .get()
+                .map_err(MigrationError::Unavailable)?
+                .run_pending_migrations(SQLITE_MIGRATIONS)
+                .map_err(MigrationError::Failed)?
+                .iter()
+                .map(ToString::to_string)
+                .collect(),
+            ConnectionPool::Postgres(pool) => pool
+                .get()
+                .map_err(MigrationError::Unavailable)?
+                .run_pending_migrations(POSTGRES_MIGRATIONS)
+                .map_err(MigrationError::Failed)?
+                .iter()
+                .map(ToString::to_string)
+                .collect(),
+        };
+        Ok(applied)
+    }
+
+    /// Succeeds only when the schema matches this build exactly.
+    pub fn check_schema(&self) -> Result<(), MigrationError> {
+        let status = self.schema_status()?;
+        if !status.unknown.is_empty() {
+            return Err(MigrationError::UnknownVersions(status.unknown));
+        }
+        if !status.pending.is_empty() {
+            return Err(MigrationError::Pending(status.pending));
+        }
+        Ok(())
+    }
+}
+
+fn schema_status<DB: Backend>(
+    conn: &mut impl MigrationHarness<DB>,
+    migrations: &EmbeddedMigrations,
+) -> Result<SchemaStatus, MigrationError> {
+    let known: Vec<String> = MigrationSource::<DB>::migrations(migrations)
+        .map_err(MigrationError::Failed)?
+        .iter()
+        .map(|migration| migration.name().version().to_string())
+        .collect();
+    let applied: Vec<String> = conn
+        .applied_migrations()
+        .map_err(MigrationError::Failed)?
+        .iter()
+        .map(ToString::to_string)
+        .collect();
+    Ok(SchemaStatus {
+        pending: known
+            .iter()
+            .filter(|version| !applied.contains(version))
+            .cloned()
+            .collect(),
+        unknown: applied
+            .into_iter()
+            .filter(|version| !known.contains(version))
+            .collect(),
+    })
+}
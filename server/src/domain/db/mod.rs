@@ -1,3 +1,24 @@
-pub mod sqlite;
+pub mod error;
+pub mod memory;
+pub mod migrations;
+pub mod repository;
+pub mod sql;
 
-pub use sqlite::SqliteDbService;
+pub use error::DbError;
+pub use memory::InMemoryDbService;
+pub use migrations::MigrationError;
+pub use migrations::SchemaStatus;
+pub use migrations::POSTGRES_MIGRATIONS;
+pub use migrations::SQLITE_MIGRATIONS;
+pub use repository::AdminRepository;
+pub use repository::AssetRepository;
+pub use repository::ChannelStateRepository;
+pub use repository::Database;
+pub use repository::SessionRepository;
+pub use repository::SettingsRepository;
+pub use repository::SubmissionRepository;
+pub use repository::TokenRepository;
+pub use repository::TriggerRepository;
+pub use repository::UserRepository;
+pub use sql::ConnectionPool;
+pub use sql::SqlDbService;
@@ -0,0 +1,176 @@
+use std::collections::HashMap;
+
+use crate::models::{
+    ApiToken, Asset, AssetPage, AssetSearchQuery, ChannelAdmin, ChannelRole, EventTrigger, Folder,
+    NewApiToken, NewEventTrigger, NewSubmission, OverlayToken, StoredChannelState,
+    StoredEventSubSubscription, StoredSession, StoredUserTokens, Submission, Tag, User,
+    UserSettings, ValidatedAssetMetadata,
+};
+
+use super::DbError;
+
+pub trait UserRepository {
+    fn get_user(&self, username: &str) -> Result<Option<User>, DbError>;
+    fn create_user(&self, user: &User) -> Result<User, DbError>;
+}
+
+pub trait SettingsRepository {
+    fn get_user_settings(&self, user: &User) -> Result<Option<UserSettings>, DbError>;
+    fn update_user_settings(&self, settings: &UserSettings) -> Result<UserSettings, DbError>;
+    fn create_user_settings(&self, settings: &UserSettings) -> Result<UserSettings, DbError>;
+    fn get_chat_channels(&self) -> Result<Vec<String>, DbError>;
+    fn get_moderator_sync_users(&self) -> Result<Vec<User>, DbError>;
+}
+
+pub trait AssetRepository {
+    fn get_asset(&self, filename: &str) -> Result<Option<Asset>, DbError>;
+    fn get_asset_by_checksum(&self, checksum: &str) -> Result<Option<Asset>, DbError>;
+    fn create_asset(&self, asset: &Asset) -> Result<Asset, DbError>;
+    fn get_all_assets(&self) -> Result<Vec<Asset>, DbError>;
+    fn get_broadcaster_assets(&self, broadcaster: &User) -> Result<Vec<Asset>, DbError>;
+    fn search_assets(
+        &self,
+        broadcaster: &User,
+        query: &AssetSearchQuery,
+    ) -> Result<AssetPage, DbError>;
+    fn get_assets_metadata(
+        &self,
+        assets: &[Asset],
+    ) -> Result<HashMap<String, ValidatedAssetMetadata>, DbError>;
+    fn get_folders(&self, broadcaster: &User) -> Result<Vec<Folder>, DbError>;
+    fn get_tags(&self, broadcaster: &User) -> Result<Vec<Tag>, DbError>;
+    fn set_asset_metadata(
+        &self,
+        asset: &Asset,
+        metadata: &ValidatedAssetMetadata,
+    ) -> Result<(), DbError>;
+}
+
+pub trait AdminRepository {
+    fn create_channel_admin(&self, channel_admin: &ChannelAdmin) -> Result<ChannelAdmin, DbError>;
+    fn get_channel_admin(
+        &self,
+        username: &str,
+        broadcaster: &User,
+    ) -> Result<Option<ChannelAdmin>, DbError>;
+    fn get_channel_admins(&self, broadcaster: &User) -> Result<Vec<ChannelAdmin>, DbError>;
+    fn set_channel_admin_role(
+        &self,
+        username: &str,
+        broadcaster: &User,
+        role: ChannelRole,
+    ) -> Result<Option<ChannelAdmin>, DbError>;
+    fn delete_channel_admin(&self, username: &str, broadcaster: &User) -> Result<usize, DbError>;
+    fn replace_synced_moderators(
+        &self,
+        broadcaster: &User,
+        moderators: &[String],
+    ) -> Result<(usize, usize), DbError>;
+}
+
+pub trait SessionRepository {
+    fn get_session(&self, id: &str, now: i64) -> Result<Option<StoredSession>, DbError>;
+    fn create_session(&self, session: &StoredSession) -> Result<StoredSession, DbError>;
+    fn save_session(&self, session: &StoredSession) -> Result<(), DbError>;
+    fn delete_session(&self, id: &str) -> Result<(), DbError>;
+    fn delete_expired_sessions(&self, now: i64) -> Result<usize, DbError>;
+}
+
+pub trait TokenRepository {
+    fn get_user_tokens(&self, username: &str) -> Result<Option<StoredUserTokens>, DbError>;
+    fn save_user_tokens(&self, tokens: &StoredUserTokens) -> Result<(), DbError>;
+    fn get_expiring_user_tokens(&self, before: i64) -> Result<Vec<StoredUserTokens>, DbError>;
+    fn delete_user_tokens(&self, username: &str) -> Result<(), DbError>;
+    fn get_overlay_token(&self, owner: &User) -> Result<Option<OverlayToken>, DbError>;
+    fn save_overlay_token(&self, token: &OverlayToken) -> Result<(), DbError>;
+    fn delete_overlay_token(&self, owner: &User) -> Result<usize, DbError>;
+    fn create_api_token(&self, token: &NewApiToken) -> Result<ApiToken, DbError>;
+    fn get_api_tokens(&self, owner: &User) -> Result<Vec<ApiToken>, DbError>;
+    fn get_api_token_by_hash(&self, token_hash: &str) -> Result<Option<ApiToken>, DbError>;
+    fn touch_api_token(&self, token: &ApiToken, used_at: i64) -> Result<(), DbError>;
+    fn delete_api_token(&self, owner: &User, id: i32) -> Result<usize, DbError>;
+}
+
+pub trait TriggerRepository {
+    fn get_event_triggers(&self, owner: &User) -> Result<Vec<EventTrigger>, DbError>;
+    fn get_matching_event_triggers(
+        &self,
+        username: &str,
+        event_type: &str,
+        reward_id: Option<&str>,
+    ) -> Result<Vec<EventTrigger>, DbError>;
+    fn create_event_trigger(&self, trigger: &NewEventTrigger) -> Result<EventTrigger, DbError>;
+    fn delete_event_trigger(&self, owner: &User, id: i32) -> Result<Option<EventTrigger>, DbError>;
+    fn get_eventsub_subscription(
+        &self,
+        id: &str,
+    ) -> Result<Option<StoredEventSubSubscription>, DbError>;
+    fn get_eventsub_subscription_for(
+        &self,
+        username: &str,
+        event_type: &str,
+    ) -> Result<Option<StoredEventSubSubscription>, DbError>;
+    fn save_eventsub_subscription(
+        &self,
+        subscription: &StoredEventSubSubscription,
+    ) -> Result<(), DbError>;
+    fn delete_eventsub_subscription(&self, id: &str) -> Result<(), DbError>;
+}
+
+pub trait SubmissionRepository {
+    fn create_submission(&self, submission: &NewSubmission) -> Result<Submission, DbError>;
+    fn get_submission(&self, broadcaster: &User, id: i32) -> Result<Option<Submission>, DbError>;
+    fn get_submissions(
+        &self,
+        broadcaster: &User,
+        status: Option<&str>,
+    ) -> Result<Vec<Submission>, DbError>;
+    fn get_all_pending_submissions(&self) -> Result<Vec<Submission>, DbError>;
+    fn count_pending_submissions(
+        &self,
+        broadcaster: &User,
+        submitter: &str,
+    ) -> Result<i64, DbError>;
+    fn review_submission(
+        &self,
+        submission: &Submission,
+        reviewer: &str,
+        status: &str,
+    ) -> Result<Option<Submission>, DbError>;
+}
+
+pub trait ChannelStateRepository {
+    fn get_channel_states(&self) -> Result<Vec<StoredChannelState>, DbError>;
+    fn save_channel_state(&self, state: &StoredChannelState) -> Result<(), DbError>;
+    fn delete_channel_state(&self, username: &str) -> Result<usize, DbError>;
+}
+
+pub trait Database:
+    UserRepository
+    + SettingsRepository
+    + AssetRepository
+    + AdminRepository
+    + SessionRepository
+    + TokenRepository
+    + TriggerRepository
+    + SubmissionRepository
+    + ChannelStateRepository
+    + Send
+    + Sync
+{
+}
+
+impl<T> Database for T where
+    T: UserRepository
+        + SettingsRepository
+        + AssetRepository
+        + AdminRepository
+        + SessionRepository
+        + TokenRepository
+        + TriggerRepository
+        + SubmissionRepository
+        + ChannelStateRepository
+        + Send
+        + Sync
+{
+}
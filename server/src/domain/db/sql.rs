@@ -0,0 +1,1145 @@
+use std::collections::HashMap;
+use std::time::Duration;
+
+use diesel::connection::SimpleConnection;
+use diesel::r2d2::{CustomizeConnection, R2D2Connection};
+use diesel::{PgConnection, SqliteConnection};
+use tokio::runtime::{Handle, RuntimeFlavor};
+
+use crate::models::{
+    ApiToken, Asset, AssetPage, AssetSearchQuery, AssetTag, ChannelAdmin, ChannelRole,
+    EventTrigger, Folder, NewApiToken, NewEventTrigger, NewFolder, NewSubmission, NewTag,
+    OverlayToken, StoredChannelState, StoredEventSubSubscription, StoredSession, StoredUserTokens,
+    Submission, Tag, User, UserFacingAsset, UserSettings, ValidatedAssetMetadata,
+};
+use diesel::prelude::*;
+use diesel::r2d2::{ConnectionManager, Pool};
+
+use super::{
+    AdminRepository, AssetRepository, ChannelStateRepository, DbError, SessionRepository,
+    SettingsRepository, SubmissionRepository, TokenRepository, TriggerRepository, UserRepository,
+};
+
+/// Runs `$body` on a pooled connection of whichever backend the service was
+/// opened with. The body is expanded once per backend, so every query is
+/// checked against a concrete connection type.
+macro_rules! with_connection {
+    ($service:expr, |$conn:ident| $body:expr) => {
+        match &$service.pool {
+            ConnectionPool::Sqlite(pool) => SqlDbService::run_query(pool, |$conn| $body),
+            ConnectionPool::Postgres(pool) => SqlDbService::run_query(pool, |$conn| $body),
+        }
+    };
+}
+
+#[derive(Debug)]
+struct ConnectionPragmas {
+    busy_timeout: Duration,
+}
+
+impl CustomizeConnection<SqliteConnection, diesel::r2d2::Error> for ConnectionPragmas {
+    fn on_acquire(&self, conn: &mut SqliteConnection) -> Result<(), diesel::r2d2::Error> {
+        conn.batch_execute(&format!(
+            "PRAGMA journal_mode = WAL; PRAGMA synchronous = NORMAL; PRAGMA busy_timeout = {};",
+            self.busy_timeout.as_millis()
+        ))
+        .map_err(diesel::r2d2::Error::QueryError)
+    }
+}
+
+pub enum ConnectionPool {
+    Sqlite(Pool<ConnectionManager<SqliteConnection>>),
+    Postgres(Pool<ConnectionManager<PgConnection>>),
+}
+
+pub struct SqlDbService {
+    pub pool: ConnectionPool,
+}
+
+impl SqlDbService {
+    const BUSY_TIMEOUT: Duration = Duration::from_secs(5);
+    const MAX_CONNECTIONS: u32 = 16;
+
+    /// `postgres://` and `postgresql://` URLs open a PostgreSQL pool, anything
+    /// else is treated as a SQLite path.
+    pub fn new(database_url: &str) -> Result<Self, r2d2::Error> {
+        let pool = if Self::is_postgres_url(database_url) {
+            Pool::builder()
+                .max_size(Self::MAX_CONNECTIONS)
+                .build(ConnectionManager::<PgConnection>::new(database_url))
+                .map(ConnectionPool::Postgres)
+        } else {
+            // every connection to ":memory:" opens its own empty database
+            let max_size = if database_url == ":memory:" {
+                1
+            } else {
+                Self::MAX_CONNECTIONS
+            };
+            Pool::builder()
+                .max_size(max_size)
+                .connection_customizer(Box::new(ConnectionPragmas {
+                    busy_timeout: Self::BUSY_TIMEOUT,
+                }))
+                .build(ConnectionManager::<SqliteConnection>::new(database_url))
+                .map(ConnectionPool::Sqlite)
+        }
+        .inspect_err(|error| tracing::error!(?error, "db connection failed"))?;
+        Ok(Self { pool })
+    }
+
+    pub fn is_postgres_url(database_url: &str) -> bool {
+        database_url.starts_with("postgres://") || database_url.starts_with("postgresql://")
+    }
+
+    /// Runs `query` on a pooled connection, off the async worker when the
+    /// runtime allows it.
+    fn run_query<C, T>(
+        pool: &Pool<ConnectionManager<C>>,
+        query: impl FnOnce(&mut C) -> Result<T, DbError>,
+    ) -> Result<T, DbError>
+    where
+        C: R2D2Connection + 'static,
+    {
+        let run = || {
+            let mut conn = pool
+                .get()
+                .inspect_err(|error| tracing::error!(?error, "unable to get db connection"))?;
+            query(&mut conn)
+        };
+        match Handle::try_current() {
+            Ok(handle) if handle.runtime_flavor() == RuntimeFlavor::MultiThread => {
+                tokio::task::block_in_place(run)
+            }
+            _ => run(),
+        }
+    }
+}
+
+/// Asset queries that are shared by several repository methods. Boxed queries
+/// are tied to one backend, so these are implemented per connection type.
+trait AssetQueries: Connection {
+    fn filtered_assets<'a>(
+        &self,
+        broadcaster: &'a User,
+        query: &'a AssetSearchQuery,
+    ) -> crate::models::schema::assets::BoxedQuery<'a, Self::Backend>;
+
+    fn load_asset_metadata(
+        &mut self,
+        assets: &[Asset],
+    ) -> Result<HashMap<String, ValidatedAssetMetadata>, diesel::result::Error>;
+}
+
+/// `$like` is the case-insensitive pattern match of the backend: SQLite's
+/// `LIKE` already ignores ASCII case, PostgreSQL needs `ILIKE`.
+macro_rules! impl_asset_queries {
+    ($connection:ty, $like:ident) => {
+        impl AssetQueries for $connection {
+            fn filtered_assets<'a>(
+                &self,
+                broadcaster: &'a User,
+                query: &'a AssetSearchQuery,
+            ) -> crate::models::schema::assets::BoxedQuery<'a, Self::Backend> {
+                use crate::models::schema::{asset_tags, assets, folders, tags};
+
+                let mut filtered = assets::table
+                    .filter(assets::username.eq(&broadcaster.username))
+                    .into_boxed();
+                if let Some(pattern) = query.name_pattern() {
+                    filtered =
+                        filtered.filter(assets::original_filename.$like(pattern).escape('\\'));
+                }
+                if let Some(pattern) = query.content_type_pattern() {
+                    filtered = filtered.filter(assets::content_type.$like(pattern).escape('\\'));
+                }
+                if let Some(uploaded_after) = query.uploaded_after {
+                    filtered = filtered.filter(assets::uploaded_at.ge(uploaded_after));
+                }
+                if let Some(uploaded_before) = query.uploaded_before {
+                    filtered = filtered.filter(assets::uploaded_at.lt(uploaded_before));
+                }
+                if let Some(folder) = &query.folder {
+                    filtered = filtered.filter(
+                        assets::folder_id.eq_any(
+                            folders::table
+                                .filter(folders::username.eq(&broadcaster.username))
+                                .filter(folders::name.eq(folder))
+                                .select(folders::id.nullable()),
+                        ),
+                    );
+                }
+                if let Some(tag) = &query.tag {
+                    filtered = filtered.filter(
+                        assets::local_filename.eq_any(
+                            asset_tags::table
+                                .inner_join(tags::table)
+                                .filter(tags::username.eq(&broadcaster.username))
+                                .filter(tags::name.eq(tag))
+                                .select(asset_tags::local_filename),
+                        ),
+                    );
+                }
+                filtered
+            }
+
+            fn load_asset_metadata(
+                &mut self,
+                assets: &[Asset],
+            ) -> Result<HashMap<String, ValidatedAssetMetadata>, diesel::result::Error> {
+                use crate::models::schema::{asset_tags, folders, tags};
+
+                let filenames: Vec<&str> = assets
+                    .iter()
+                    .map(|asset| asset.local_filename.as_str())
+                    .collect();
+                let folder_ids: Vec<i32> = assets.iter().filter_map(|a| a.folder_id).collect();
+                let folder_names: HashMap<i32, String> = folders::table
+                    .filter(folders::id.eq_any(&folder_ids))
+                    .select((folders::id, folders::name))
+                    .load::<(i32, String)>(self)
+                    .inspect_err(|error| tracing::error!(?error, "get asset folders"))?
+                    .into_iter()
+                    .collect();
+                let mut metadata: HashMap<String, ValidatedAssetMetadata> = assets
+                    .iter()
+                    .map(|asset| {
+                        let folder = asset
+                            .folder_id
+                            .and_then(|id| folder_names.get(&id).cloned());
+                        let asset_metadata = ValidatedAssetMetadata {
+                            folder,
+                            tags: vec![],
+                        };
+                        (asset.local_filename.clone(), asset_metadata)
+                    })
+                    .collect();
+                for (local_filename, tag_name) in asset_tags::table
+                    .inner_join(tags::table)
+                    .filter(asset_tags::local_filename.eq_any(&filenames))
+                    .order(tags::name.asc())
+                    .select((asset_tags::local_filename, tags::name))
+                    .load::<(String, String)>(self)
+                    .inspect_err(|error| tracing::error!(?error, "get asset tags"))?
+                {
+                    if let Some(asset_metadata) = metadata.get_mut(&local_filename) {
+                        asset_metadata.tags.push(tag_name);
+                    }
+                }
+                Ok(metadata)
+            }
+        }
+    };
+}
+
+impl_asset_queries!(SqliteConnection, like);
+impl_asset_queries!(PgConnection, ilike);
+
+impl UserRepository for SqlDbService {
+    fn get_user(&self, username: &str) -> Result<Option<User>, DbError> {
+        with_connection!(self, |conn| {
+            let user = crate::models::schema::users::dsl::users
+                .filter(crate::models::schema::users::dsl::username.eq(username))
+                .first::<User>(conn)
+                .optional()
+                .inspect_err(|error| tracing::error!(?error, "get user"))?;
+            Ok(user)
+        })
+    }
+
+    fn create_user(&self, user: &User) -> Result<User, DbError> {
+        with_connection!(self, |conn| {
+            let user = diesel::insert_into(crate::models::schema::users::dsl::users)
+                .values(user)
+                .get_result::<User>(conn)
+                .inspect_err(|error| tracing::error!(?error, "create user"))?;
+
+            Ok(user)
+        })
+    }
+}
+
+impl SettingsRepository for SqlDbService {
+    fn get_user_settings(&self, user: &User) -> Result<Option<UserSettings>, DbError> {
+        with_connection!(self, |conn| {
+            let settings = crate::models::schema::user_settings::dsl::user_settings
+                .filter(crate::models::schema::user_settings::dsl::username.eq(&user.username))
+                .first::<UserSettings>(conn)
+                .optional()
+                .inspect_err(|error| tracing::error!(?error, "get user settings"))?;
+            Ok(settings)
+        })
+    }
+
+    fn update_user_settings(&self, settings: &UserSettings) -> Result<UserSettings, DbError> {
+        with_connection!(self, |conn| {
+            let new_settings = diesel::update(
+                crate::models::schema::user_settings::dsl::user_settings
+                    .find(settings.username.clone()),
+            )
+            .set(settings)
+            .get_result::<UserSettings>(conn)
+            .inspect_err(|error| tracing::error!(?error, "update user settings"))?;
+            Ok(new_settings)
+        })
+    }
+
+    fn create_user_settings(&self, settings: &UserSettings) -> Result<UserSettings, DbError> {
+        with_connection!(self, |conn| {
+            let new_settings =
+                diesel::insert_into(crate::models::schema::user_settings::dsl::user_settings)
+                    .values(settings)
+                    .get_result::<UserSettings>(conn)
+                    .inspect_err(|error| tracing::error!(?error, "create user settings"))?;
+            Ok(new_settings)
+        })
+    }
+
+    fn get_chat_channels(&self) -> Result<Vec<String>, DbError> {
+        with_connection!(self, |conn| {
+            let channels = crate::models::schema::user_settings::dsl::user_settings
+                .filter(crate::models::schema::user_settings::dsl::chat_commands.eq(true))
+                .select(crate::models::schema::user_settings::dsl::username)
+                .load::<String>(conn)
+                .inspect_err(|error| tracing::error!(?error, "get chat channels"))?;
+            Ok(channels)
+        })
+    }
+
+    fn get_moderator_sync_users(&self) -> Result<Vec<User>, DbError> {
+        with_connection!(self, |conn| {
+            let users = crate::models::schema::users::dsl::users
+                .inner_join(crate::models::schema::user_settings::dsl::user_settings)
+                .filter(crate::models::schema::user_settings::dsl::sync_moderators.eq(true))
+                .select(User::as_select())
+                .load(conn)
+                .inspect_err(|error| tracing::error!(?error, "get moderator sync users"))?;
+            Ok(users)
+        })
+    }
+}
+
+impl AssetRepository for SqlDbService {
+    fn get_asset(&self, filename: &str) -> Result<Option<Asset>, DbError> {
+        tracing::debug!(?filename, "looking for asset");
+        with_connection!(self, |conn| {
+            let asset = crate::models::schema::assets::dsl::assets
+                .filter(crate::models::schema::assets::dsl::local_filename.eq(filename))
+                .first::<Asset>(conn)
+                .optional()
+                .inspect_err(|error| tracing::error!(?error, "get asset"))?;
+            Ok(asset)
+        })
+    }
+
+    fn get_asset_by_checksum(&self, checksum: &str) -> Result<Option<Asset>, DbError> {
+        with_connection!(self, |conn| {
+            let asset = crate::models::schema::assets::dsl::assets
+                .filter(crate::models::schema::assets::dsl::checksum.eq(checksum))
+                .first::<Asset>(conn)
+                .optional()
+                .inspect_err(|error| tracing::error!(?error, "get asset by checksum"))?;
+            Ok(asset)
+        })
+    }
+
+    fn create_asset(&self, asset: &Asset) -> Result<Asset, DbError> {
+        with_connection!(self, |conn| {
+            let new_asset = diesel::insert_into(crate::models::schema::assets::dsl::assets)
+                .values(asset)
+                .get_result::<Asset>(conn)
+                .inspect_err(|error| tracing::error!(?error, "create asset"))?;
+            Ok(new_asset)
+        })
+    }
+
+    fn get_all_assets(&self) -> Result<Vec<Asset>, DbError> {
+        with_connection!(self, |conn| {
+            let assets = crate::models::schema::assets::dsl::assets
+                .select(Asset::as_select())
+                .load(conn)
+                .inspect_err(|error| tracing::error!(?error, "get all assets"))?;
+            Ok(assets)
+        })
+    }
+
+    fn get_broadcaster_assets(&self, broadcaster: &User) -> Result<Vec<Asset>, DbError> {
+        with_connection!(self, |conn| {
+            let broadcaster_assets = crate::models::schema::assets::dsl::assets
+                .filter(crate::models::schema::assets::dsl::username.eq(&broadcaster.username))
+                .select(Asset::as_select())
+                .load(conn)
+                .inspect_err(|error| tracing::error!(?error, "get broadcaster assets"))?;
+            Ok(broadcaster_assets)
+        })
+    }
+
+    fn search_assets(
+        &self,
+        broadcaster: &User,
+        query: &AssetSearchQuery,
+    ) -> Result<AssetPage, DbError> {
+        use crate::models::schema::assets;
+
+        with_connection!(self, |conn| {
+            let total = conn
+                .filtered_assets(broadcaster, query)
+                .count()
+                .get_result::<i64>(conn)
+                .inspect_err(|error| tracing::error!(?error, "count broadcaster assets"))?;
+            let page_assets = conn
+                .filtered_assets(broadcaster, query)
+                .order((assets::uploaded_at.desc(), assets::local_filename.asc()))
+                .limit(i64::from(query.per_page()))
+                .offset(query.offset())
+                .select(Asset::as_select())
+                .load(conn)
+                .inspect_err(|error| tracing::error!(?error, "search broadcaster assets"))?;
+
+            let mut metadata = conn.load_asset_metadata(&page_assets)?;
+            let assets = page_assets
+                .into_iter()
+                .map(|asset| {
+                    let asset_metadata = metadata.remove(&asset.local_filename).unwrap_or_default();
+                    UserFacingAsset::from(asset)
+                        .with_folder(asset_metadata.folder)
+                        .with_tags(asset_metadata.tags)
+                })
+                .collect();
+            Ok(AssetPage {
+                assets,
+                page: query.page(),
+                per_page: query.per_page(),
+                total,
+            })
+        })
+    }
+
+    fn get_assets_metadata(
+        &self,
+        assets: &[Asset],
+    ) -> Result<HashMap<String, ValidatedAssetMetadata>, DbError> {
+        with_connection!(self, |conn| Ok(conn.load_asset_metadata(assets)?))
+    }
+
+    fn get_folders(&self, broadcaster: &User) -> Result<Vec<Folder>, DbError> {
+        with_connection!(self, |conn| {
+            let folders = crate::models::schema::folders::dsl::folders
+                .filter(crate::models::schema::folders::dsl::username.eq(&broadcaster.username))
+                .order(crate::models::schema::folders::dsl::name.asc())
+                .select(Folder::as_select())
+                .load(conn)
+                .inspect_err(|error| tracing::error!(?error, "get folders"))?;
+            Ok(folders)
+        })
+    }
+
+    fn get_tags(&self, broadcaster: &User) -> Result<Vec<Tag>, DbError> {
+        with_connection!(self, |conn| {
+            let tags = crate::models::schema::tags::dsl::tags
+                .filter(crate::models::schema::tags::dsl::username.eq(&broadcaster.username))
+                .order(crate::models::schema::tags::dsl::name.asc())
+                .select(Tag::as_select())
+                .load(conn)
+                .inspect_err(|error| tracing::error!(?error, "get tags"))?;
+            Ok(tags)
+        })
+    }
+
+    fn set_asset_metadata(
+        &self,
+        asset: &Asset,
+        metadata: &ValidatedAssetMetadata,
+    ) -> Result<(), DbError> {
+        use crate::models::schema::{asset_tags, assets, folders, tags};
+
+        with_connection!(self, |conn| {
+            let owner = User::new(&asset.username);
+            conn.transaction::<_, diesel::result::Error, _>(|conn| {
+                let folder_id = match &metadata.folder {
+                    Some(name) => {
+                        diesel::insert_into(folders::table)
+                            .values(&NewFolder::new(&owner, name))
+                            .on_conflict_do_nothing()
+                            .execute(conn)?;
+                        Some(
+                            folders::table
+                                .filter(folders::username.eq(&owner.username))
+                                .filter(folders::name.eq(name))
+                                .select(folders::id)
+                                .first::<i32>(conn)?,
+                        )
+                    }
+                    None => None,
+                };
+                diesel::update(assets::table.find(&asset.local_filename))
+                    .set(assets::folder_id.eq(folder_id))
+                    .execute(conn)?;
+
+                diesel::delete(
+                    asset_tags::table.filter(asset_tags::local_filename.eq(&asset.local_filename)),
+                )
+                .execute(conn)?;
+                for name in &metadata.tags {
+                    diesel::insert_into(tags::table)
+                        .values(&NewTag::new(&owner, name))
+                        .on_conflict_do_nothing()
+                        .execute(conn)?;
+                    let tag_id = tags::table
+                        .filter(tags::username.eq(&owner.username))
+                        .filter(tags::name.eq(name))
+                        .select(tags::id)
+                        .first::<i32>(conn)?;
+                    diesel::insert_into(asset_tags::table)
+                        .values(&AssetTag {
+                            local_filename: asset.local_filename.clone(),
+                            tag_id,
+                        })
+                        .execute(conn)?;
+                }
+                Ok(())
+            })
+            .inspect_err(|error| tracing::error!(?error, "set asset metadata"))?;
+            Ok(())
+        })
+    }
+}
+
+impl AdminRepository for SqlDbService {
+    fn create_channel_admin(&self, channel_admin: &ChannelAdmin) -> Result<ChannelAdmin, DbError> {
+        with_connection!(self, |conn| {
+            let new_channel_admin =
+                diesel::insert_into(crate::models::schema::channel_admins::dsl::channel_admins)
+                    .values(channel_admin)
+                    .get_result::<ChannelAdmin>(conn)
+                    .inspect_err(|error| tracing::error!(?error, "create channel admin"))?;
+            Ok(new_channel_admin)
+        })
+    }
+
+    fn get_channel_admin(
+        &self,
+        username: &str,
+        broadcaster: &User,
+    ) -> Result<Option<ChannelAdmin>, DbError> {
+        with_connection!(self, |conn| {
+            let channel_admin = crate::models::schema::channel_admins::dsl::channel_admins
+                .filter(crate::models::schema::channel_admins::dsl::username.eq(username))
+                .filter(
+                    crate::models::schema::channel_admins::dsl::broadcaster_username
+                        .eq(&broadcaster.username),
+                )
+                .first::<ChannelAdmin>(conn)
+                .optional()
+                .inspect_err(|error| tracing::error!(?error, "get channel admin"))?;
+            Ok(channel_admin)
+        })
+    }
+
+    fn get_channel_admins(&self, broadcaster: &User) -> Result<Vec<ChannelAdmin>, DbError> {
+        with_connection!(self, |conn| {
+            let channel_admins = crate::models::schema::channel_admins::dsl::channel_admins
+                .filter(
+                    crate::models::schema::channel_admins::dsl::broadcaster_username
+                        .eq(&broadcaster.username),
+                )
+                .select(ChannelAdmin::as_select())
+                .load(conn)
+                .inspect_err(|error| tracing::error!(?error, "get channel admins"))?;
+            Ok(channel_admins)
+        })
+    }
+
+    fn set_channel_admin_role(
+        &self,
+        username: &str,
+        broadcaster: &User,
+        role: ChannelRole,
+    ) -> Result<Option<ChannelAdmin>, DbError> {
+        with_connection!(self, |conn| {
+            let channel_admin = diesel::update(
+                crate::models::schema::channel_admins::dsl::channel_admins
+                    .filter(crate::models::schema::channel_admins::dsl::username.eq(username))
+                    .filter(
+                        crate::models::schema::channel_admins::dsl::broadcaster_username
+                            .eq(&broadcaster.username),
+                    ),
+            )
+            .set(crate::models::schema::channel_admins::dsl::role.eq(role.as_str()))
+            .get_result::<ChannelAdmin>(conn)
+            .optional()
+            .inspect_err(|error| tracing::error!(?error, "set channel admin role"))?;
+            Ok(channel_admin)
+        })
+    }
+
+    fn delete_channel_admin(&self, username: &str, broadcaster: &User) -> Result<usize, DbError> {
+        with_connection!(self, |conn| {
+            let deleted = diesel::delete(
+                crate::models::schema::channel_admins::dsl::channel_admins
+                    .filter(crate::models::schema::channel_admins::dsl::username.eq(username))
+                    .filter(
+                        crate::models::schema::channel_admins::dsl::broadcaster_username
+                            .eq(&broadcaster.username),
+                    ),
+            )
+            .execute(conn)
+            .inspect_err(|error| tracing::error!(?error, "delete channel admin"))?;
+            Ok(deleted)
+        })
+    }
+
+    fn replace_synced_moderators(
+        &self,
+        broadcaster: &User,
+        moderators: &[String],
+    ) -> Result<(usize, usize), DbError> {
+        with_connection!(self, |conn| {
+            let changes = conn
+                .transaction::<_, diesel::result::Error, _>(|conn| {
+                    let removed = diesel::delete(
+                        crate::models::schema::channel_admins::dsl::channel_admins
+                            .filter(
+                                crate::models::schema::channel_admins::dsl::broadcaster_username
+                                    .eq(&broadcaster.username),
+                            )
+                            .filter(
+                                crate::models::schema::channel_admins::dsl::source
+                                    .eq(ChannelAdmin::TWITCH_MODERATOR),
+                            )
+                            .filter(
+                                crate::models::schema::channel_admins::dsl::username
+                                    .ne_all(moderators),
+                            ),
+                    )
+                    .execute(conn)?;
+                    let mut added = 0;
+                    for moderator in moderators {
+                        added += diesel::insert_into(
+                            crate::models::schema::channel_admins::dsl::channel_admins,
+                        )
+                        .values(ChannelAdmin::for_login(
+                            moderator,
+                            broadcaster,
+                            ChannelAdmin::TWITCH_MODERATOR,
+                        ))
+                        .on_conflict_do_nothing()
+                        .execute(conn)?;
+                    }
+                    Ok((added, removed))
+                })
+                .inspect_err(|error| tracing::error!(?error, "replace synced moderators"))?;
+            Ok(changes)
+        })
+    }
+}
+
+impl SessionRepository for SqlDbService {
+    fn get_session(&self, id: &str, now: i64) -> Result<Option<StoredSession>, DbError> {
+        with_connection!(self, |conn| {
+            let session = crate::models::schema::sessions::dsl::sessions
+                .filter(crate::models::schema::sessions::dsl::id.eq(id))
+                .filter(crate::models::schema::sessions::dsl::expiry_date.gt(now))
+                .first::<StoredSession>(conn)
+                .optional()
+                .inspect_err(|error| tracing::error!(?error, "get session"))?;
+            Ok(session)
+        })
+    }
+
+    fn create_session(&self, session: &StoredSession) -> Result<StoredSession, DbError> {
+        with_connection!(self, |conn| {
+            let new_session = diesel::insert_into(crate::models::schema::sessions::dsl::sessions)
+                .values(session)
+                .get_result::<StoredSession>(conn)
+                .inspect_err(|error| tracing::error!(?error, "create session"))?;
+            Ok(new_session)
+        })
+    }
+
+    fn save_session(&self, session: &StoredSession) -> Result<(), DbError> {
+        with_connection!(self, |conn| {
+            diesel::insert_into(crate::models::schema::sessions::dsl::sessions)
+                .values(session)
+                .on_conflict(crate::models::schema::sessions::dsl::id)
+                .do_update()
+                .set(session)
+                .execute(conn)
+                .inspect_err(|error| tracing::error!(?error, "save session"))?;
+            Ok(())
+        })
+    }
+
+    fn delete_session(&self, id: &str) -> Result<(), DbError> {
+        with_connection!(self, |conn| {
+            diesel::delete(crate::models::schema::sessions::dsl::sessions.find(id))
+                .execute(conn)
+                .inspect_err(|error| tracing::error!(?error, "delete session"))?;
+            Ok(())
+        })
+    }
+
+    fn delete_expired_sessions(&self, now: i64) -> Result<usize, DbError> {
+        with_connection!(self, |conn| {
+            let deleted = diesel::delete(
+                crate::models::schema::sessions::dsl::sessions
+                    .filter(crate::models::schema::sessions::dsl::expiry_date.le(now)),
+            )
+            .execute(conn)
+            .inspect_err(|error| tracing::error!(?error, "delete expired sessions"))?;
+            Ok(deleted)
+        })
+    }
+}
+
+impl TokenRepository for SqlDbService {
+    fn get_user_tokens(&self, username: &str) -> Result<Option<StoredUserTokens>, DbError> {
+        with_connection!(self, |conn| {
+            let tokens = crate::models::schema::user_tokens::dsl::user_tokens
+                .find(username)
+                .first::<StoredUserTokens>(conn)
+                .optional()
+                .inspect_err(|error| tracing::error!(?error, "get user tokens"))?;
+            Ok(tokens)
+        })
+    }
+
+    fn save_user_tokens(&self, tokens: &StoredUserTokens) -> Result<(), DbError> {
+        with_connection!(self, |conn| {
+            diesel::insert_into(crate::models::schema::user_tokens::dsl::user_tokens)
+                .values(tokens)
+                .on_conflict(crate::models::schema::user_tokens::dsl::username)
+                .do_update()
+                .set(tokens)
+                .execute(conn)
+                .inspect_err(|error| tracing::error!(?error, "save user tokens"))?;
+            Ok(())
+        })
+    }
+
+    fn get_expiring_user_tokens(&self, before: i64) -> Result<Vec<StoredUserTokens>, DbError> {
+        with_connection!(self, |conn| {
+            let tokens = crate::models::schema::user_tokens::dsl::user_tokens
+                .filter(crate::models::schema::user_tokens::dsl::expires_at.le(before))
+                .filter(crate::models::schema::user_tokens::dsl::refresh_token.is_not_null())
+                .load::<StoredUserTokens>(conn)
+                .inspect_err(|error| tracing::error!(?error, "get expiring user tokens"))?;
+            Ok(tokens)
+        })
+    }
+
+    fn delete_user_tokens(&self, username: &str) -> Result<(), DbError> {
+        with_connection!(self, |conn| {
+            diesel::delete(crate::models::schema::user_tokens::dsl::user_tokens.find(username))
+                .execute(conn)
+                .inspect_err(|error| tracing::error!(?error, "delete user tokens"))?;
+            Ok(())
+        })
+    }
+
+    fn get_overlay_token(&self, owner: &User) -> Result<Option<OverlayToken>, DbError> {
+        with_connection!(self, |conn| {
+            let token = crate::models::schema::overlay_tokens::dsl::overlay_tokens
+                .find(&owner.username)
+                .first::<OverlayToken>(conn)
+                .optional()
+                .inspect_err(|error| tracing::error!(?error, "get overlay token"))?;
+            Ok(token)
+        })
+    }
+
+    fn save_overlay_token(&self, token: &OverlayToken) -> Result<(), DbError> {
+        with_connection!(self, |conn| {
+            diesel::insert_into(crate::models::schema::overlay_tokens::dsl::overlay_tokens)
+                .values(token)
+                .on_conflict(crate::models::schema::overlay_tokens::dsl::username)
+                .do_update()
+                .set(token)
+                .execute(conn)
+                .inspect_err(|error| tracing::error!(?error, "save overlay token"))?;
+            Ok(())
+        })
+    }
+
+    fn delete_overlay_token(&self, owner: &User) -> Result<usize, DbError> {
+        with_connection!(self, |conn| {
+            let deleted = diesel::delete(
+                crate::models::schema::overlay_tokens::dsl::overlay_tokens.find(&owner.username),
+            )
+            .execute(conn)
+            .inspect_err(|error| tracing::error!(?error, "delete overlay token"))?;
+            Ok(deleted)
+        })
+    }
+
+    fn create_api_token(&self, token: &NewApiToken) -> Result<ApiToken, DbError> {
+        with_connection!(self, |conn| {
+            let created = diesel::insert_into(crate::models::schema::api_tokens::dsl::api_tokens)
+                .values(token)
+                .get_result::<ApiToken>(conn)
+                .inspect_err(|error| tracing::error!(?error, "create api token"))?;
+            Ok(created)
+        })
+    }
+
+    fn get_api_tokens(&self, owner: &User) -> Result<Vec<ApiToken>, DbError> {
+        with_connection!(self, |conn| {
+            let tokens = crate::models::schema::api_tokens::dsl::api_tokens
+                .filter(crate::models::schema::api_tokens::dsl::username.eq(&owner.username))
+                .order(crate::models::schema::api_tokens::dsl::id.asc())
+                .select(ApiToken::as_select())
+                .load(conn)
+                .inspect_err(|error| tracing::error!(?error, "get api tokens"))?;
+            Ok(tokens)
+        })
+    }
+
+    fn get_api_token_by_hash(&self, token_hash: &str) -> Result<Option<ApiToken>, DbError> {
+        with_connection!(self, |conn| {
+            let token = crate::models::schema::api_tokens::dsl::api_tokens
+                .filter(crate::models::schema::api_tokens::dsl::token_hash.eq(token_hash))
+                .select(ApiToken::as_select())
+                .first(conn)
+                .optional()
+                .inspect_err(|error| tracing::error!(?error, "get api token"))?;
+            Ok(token)
+        })
+    }
+
+    fn touch_api_token(&self, token: &ApiToken, used_at: i64) -> Result<(), DbError> {
+        with_connection!(self, |conn| {
+            diesel::update(crate::models::schema::api_tokens::dsl::api_tokens.find(token.id))
+                .set(crate::models::schema::api_tokens::dsl::last_used_at.eq(used_at))
+                .execute(conn)
+                .inspect_err(|error| tracing::error!(?error, "touch api token"))?;
+            Ok(())
+        })
+    }
+
+    fn delete_api_token(&self, owner: &User, id: i32) -> Result<usize, DbError> {
+        with_connection!(self, |conn| {
+            let deleted = diesel::delete(
+                crate::models::schema::api_tokens::dsl::api_tokens
+                    .filter(crate::models::schema::api_tokens::dsl::id.eq(id))
+                    .filter(crate::models::schema::api_tokens::dsl::username.eq(&owner.username)),
+            )
+            .execute(conn)
+            .inspect_err(|error| tracing::error!(?error, "delete api token"))?;
+            Ok(deleted)
+        })
+    }
+}
+
+impl TriggerRepository for SqlDbService {
+    fn get_event_triggers(&self, owner: &User) -> Result<Vec<EventTrigger>, DbError> {
+        with_connection!(self, |conn| {
+            let triggers = crate::models::schema::event_triggers::dsl::event_triggers
+                .filter(crate::models::schema::event_triggers::dsl::username.eq(&owner.username))
+                .order(crate::models::schema::event_triggers::dsl::id.asc())
+                .select(EventTrigger::as_select())
+                .load(conn)
+                .inspect_err(|error| tracing::error!(?error, "get event triggers"))?;
+            Ok(triggers)
+        })
+    }
+
+    fn get_matching_event_triggers(
+        &self,
+        username: &str,
+        event_type: &str,
+        reward_id: Option<&str>,
+    ) -> Result<Vec<EventTrigger>, DbError> {
+        with_connection!(self, |conn| {
+            let mut query = crate::models::schema::event_triggers::dsl::event_triggers
+                .filter(crate::models::schema::event_triggers::dsl::username.eq(username))
+                .filter(crate::models::schema::event_triggers::dsl::event_type.eq(event_type))
+                .into_boxed();
+            query = match reward_id {
+                Some(reward_id) => query.filter(
+                    crate::models::schema::event_triggers::dsl::reward_id
+                        .is_null()
+                        .or(crate::models::schema::event_triggers::dsl::reward_id.eq(reward_id)),
+                ),
+                None => {
+                    query.filter(crate::models::schema::event_triggers::dsl::reward_id.is_null())
+                }
+            };
+            let triggers = query
+                .select(EventTrigger::as_select())
+                .load(conn)
+                .inspect_err(|error| tracing::error!(?error, "get matching event triggers"))?;
+            Ok(triggers)
+        })
+    }
+
+    fn create_event_trigger(&self, trigger: &NewEventTrigger) -> Result<EventTrigger, DbError> {
+        with_connection!(self, |conn| {
+            let new_trigger =
+                diesel::insert_into(crate::models::schema::event_triggers::dsl::event_triggers)
+                    .values(trigger)
+                    .returning(EventTrigger::as_returning())
+                    .get_result(conn)
+                    .inspect_err(|error| tracing::error!(?error, "create event trigger"))?;
+            Ok(new_trigger)
+        })
+    }
+
+    fn delete_event_trigger(&self, owner: &User, id: i32) -> Result<Option<EventTrigger>, DbError> {
+        with_connection!(self, |conn| {
+            let deleted = diesel::delete(
+                crate::models::schema::event_triggers::dsl::event_triggers
+                    .filter(crate::models::schema::event_triggers::dsl::id.eq(id))
+                    .filter(
+                        crate::models::schema::event_triggers::dsl::username.eq(&owner.username),
+                    ),
+            )
+            .returning(EventTrigger::as_returning())
+            .get_result(conn)
+            .optional()
+            .inspect_err(|error| tracing::error!(?error, "delete event trigger"))?;
+            Ok(deleted)
+        })
+    }
+
+    fn get_eventsub_subscription(
+        &self,
+        id: &str,
+    ) -> Result<Option<StoredEventSubSubscription>, DbError> {
+        with_connection!(self, |conn| {
+            let subscription =
+                crate::models::schema::eventsub_subscriptions::dsl::eventsub_subscriptions
+                    .find(id)
+                    .first::<StoredEventSubSubscription>(conn)
+                    .optional()
+                    .inspect_err(|error| tracing::error!(?error, "get eventsub subscription"))?;
+            Ok(subscription)
+        })
+    }
+
+    fn get_eventsub_subscription_for(
+        &self,
+        username: &str,
+        event_type: &str,
+    ) -> Result<Option<StoredEventSubSubscription>, DbError> {
+        with_connection!(self, |conn| {
+            let subscription =
+                crate::models::schema::eventsub_subscriptions::dsl::eventsub_subscriptions
+                    .filter(
+                        crate::models::schema::eventsub_subscriptions::dsl::username.eq(username),
+                    )
+                    .filter(
+                        crate::models::schema::eventsub_subscriptions::dsl::event_type
+                            .eq(event_type),
+                    )
+                    .first::<StoredEventSubSubscription>(conn)
+                    .optional()
+                    .inspect_err(|error| tracing::error!(?error, "get eventsub subscription"))?;
+            Ok(subscription)
+        })
+    }
+
+    fn save_eventsub_subscription(
+        &self,
+        subscription: &StoredEventSubSubscription,
+    ) -> Result<(), DbError> {
+        with_connection!(self, |conn| {
+            diesel::insert_into(
+                crate::models::schema::eventsub_subscriptions::dsl::eventsub_subscriptions,
+            )
+            .values(subscription)
+            .on_conflict(crate::models::schema::eventsub_subscriptions::dsl::id)
+            .do_update()
+            .set(subscription)
+            .execute(conn)
+            .inspect_err(|error| tracing::error!(?error, "save eventsub subscription"))?;
+            Ok(())
+        })
+    }
+
+    fn delete_eventsub_subscription(&self, id: &str) -> Result<(), DbError> {
+        with_connection!(self, |conn| {
+            diesel::delete(
+                crate::models::schema::eventsub_subscriptions::dsl::eventsub_subscriptions.find(id),
+            )
+            .execute(conn)
+            .inspect_err(|error| tracing::error!(?error, "delete eventsub subscription"))?;
+            Ok(())
+        })
+    }
+}
+
+impl SubmissionRepository for SqlDbService {
+    fn create_submission(&self, submission: &NewSubmission) -> Result<Submission, DbError> {
+        with_connection!(self, |conn| {
+            let submission =
+                diesel::insert_into(crate::models::schema::submissions::dsl::submissions)
+                    .values(submission)
+                    .get_result::<Submission>(conn)
+                    .inspect_err(|error| tracing::error!(?error, "create submission"))?;
+            Ok(submission)
+        })
+    }
+
+    fn get_submission(&self, broadcaster: &User, id: i32) -> Result<Option<Submission>, DbError> {
+        with_connection!(self, |conn| {
+            let submission = crate::models::schema::submissions::dsl::submissions
+                .find(id)
+                .filter(
+                    crate::models::schema::submissions::dsl::broadcaster_username
+                        .eq(&broadcaster.username),
+                )
+                .first::<Submission>(conn)
+                .optional()
+                .inspect_err(|error| tracing::error!(?error, "get submission"))?;
+            Ok(submission)
+        })
+    }
+
+    fn get_submissions(
+        &self,
+        broadcaster: &User,
+        status: Option<&str>,
+    ) -> Result<Vec<Submission>, DbError> {
+        with_connection!(self, |conn| {
+            let mut query = crate::models::schema::submissions::dsl::submissions
+                .filter(
+                    crate::models::schema::submissions::dsl::broadcaster_username
+                        .eq(&broadcaster.username),
+                )
+                .into_boxed();
+            if let Some(status) = status {
+                query = query.filter(crate::models::schema::submissions::dsl::status.eq(status));
+            }
+            let submissions = query
+                .order(crate::models::schema::submissions::dsl::id.asc())
+                .select(Submission::as_select())
+                .load(conn)
+                .inspect_err(|error| tracing::error!(?error, "get submissions"))?;
+            Ok(submissions)
+        })
+    }
+
+    fn get_all_pending_submissions(&self) -> Result<Vec<Submission>, DbError> {
+        with_connection!(self, |conn| {
+            let submissions = crate::models::schema::submissions::dsl::submissions
+                .filter(crate::models::schema::submissions::dsl::status.eq(Submission::PENDING))
+                .select(Submission::as_select())
+                .load(conn)
+                .inspect_err(|error| tracing::error!(?error, "get all pending submissions"))?;
+            Ok(submissions)
+        })
+    }
+
+    fn count_pending_submissions(
+        &self,
+        broadcaster: &User,
+        submitter: &str,
+    ) -> Result<i64, DbError> {
+        with_connection!(self, |conn| {
+            let count = crate::models::schema::submissions::dsl::submissions
+                .filter(
+                    crate::models::schema::submissions::dsl::broadcaster_username
+                        .eq(&broadcaster.username),
+                )
+                .filter(crate::models::schema::submissions::dsl::submitter.eq(submitter))
+                .filter(crate::models::schema::submissions::dsl::status.eq(Submission::PENDING))
+                .count()
+                .get_result::<i64>(conn)
+                .inspect_err(|error| tracing::error!(?error, "count pending submissions"))?;
+            Ok(count)
+        })
+    }
+
+    fn review_submission(
+        &self,
+        submission: &Submission,
+        reviewer: &str,
+        status: &str,
+    ) -> Result<Option<Submission>, DbError> {
+        with_connection!(self, |conn| {
+            let reviewed = conn
+                .transaction::<_, DbError, _>(|conn| {
+                    let Some(reviewed) = diesel::update(
+                        crate::models::schema::submissions::dsl::submissions
+                            .find(submission.id)
+                            .filter(
+                                crate::models::schema::submissions::dsl::status
+                                    .eq(Submission::PENDING),
+                            ),
+                    )
+                    .set((
+                        crate::models::schema::submissions::dsl::status.eq(status),
+                        crate::models::schema::submissions::dsl::reviewed_by.eq(reviewer),
+                        crate::models::schema::submissions::dsl::reviewed_at
+                            .eq(time::OffsetDateTime::now_utc().unix_timestamp()),
+                    ))
+                    .get_result::<Submission>(conn)
+                    .optional()?
+                    else {
+                        return Ok(None);
+                    };
+                    if status == Submission::APPROVED {
+                        let existing = crate::models::schema::assets::dsl::assets
+                            .filter(
+                                crate::models::schema::assets::dsl::checksum.eq(&reviewed.checksum),
+                            )
+                            .first::<Asset>(conn)
+                            .optional()?;
+                        match existing {
+                            None => {
+                                diesel::insert_into(crate::models::schema::assets::dsl::assets)
+                                    .values(reviewed.as_asset())
+                                    .execute(conn)?;
+                            }
+                            // the broadcaster already has this image, so the
+                            // submission resolves to the existing asset
+                            Some(existing)
+                                if existing.username == reviewed.broadcaster_username => {}
+                            Some(_) => {
+                                return Err(DbError::Conflict(
+                                    "UNIQUE constraint failed: assets.checksum".to_string(),
+                                ))
+                            }
+                        }
+                    }
+                    Ok(Some(reviewed))
+                })
+                .inspect_err(|error| tracing::error!(?error, "review submission"))?;
+            Ok(reviewed)
+        })
+    }
+}
+
+impl ChannelStateRepository for SqlDbService {
+    fn get_channel_states(&self) -> Result<Vec<StoredChannelState>, DbError> {
+        with_connection!(self, |conn| {
+            let states = crate::models::schema::channel_states::dsl::channel_states
+                .load::<StoredChannelState>(conn)
+                .inspect_err(|error| tracing::error!(?error, "get channel states"))?;
+            Ok(states)
+        })
+    }
+
+    fn save_channel_state(&self, state: &StoredChannelState) -> Result<(), DbError> {
+        with_connection!(self, |conn| {
+            diesel::insert_into(crate::models::schema::channel_states::dsl::channel_states)
+                .values(state)
+                .on_conflict(crate::models::schema::channel_states::dsl::username)
+                .do_update()
+                .set(state)
+                .execute(conn)
+                .inspect_err(|error| tracing::error!(?error, "save channel state"))?;
+            Ok(())
+        })
+    }
+
+    fn delete_channel_state(&self, username: &str) -> Result<usize, DbError> {
+        with_connection!(self, |conn| {
+            let deleted = diesel::delete(
+                crate::models::schema::channel_states::dsl::channel_states.find(username),
+            )
+            .execute(conn)
+            .inspect_err(|error| tracing::error!(?error, "delete channel state"))?;
+            Ok(deleted)
+        })
+    }
+}
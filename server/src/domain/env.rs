@@ -1,28 +1,78 @@
 use std::{ffi::OsStr, path::PathBuf};
 
-pub struct EnvVar<Inner>(pub Option<Inner>);
+use super::config::ConfigError;
+
+/// A setting read from the environment. Checks that fail keep the reason
+/// instead of the value, so every unusable setting can be reported at once.
+pub struct EnvVar<Inner> {
+    pub key: String,
+    value: Result<Inner, String>,
+}
 
 impl<Inner> EnvVar<Inner> {
     pub fn unwrap(self) -> Inner {
-        self.0.unwrap()
+        match self.value {
+            Ok(value) => value,
+            Err(reason) => panic!("{}: {reason}", self.key),
+        }
+    }
+
+    pub fn value(&self) -> Option<&Inner> {
+        self.value.as_ref().ok()
+    }
+
+    pub fn into_result(self) -> Result<Inner, ConfigError> {
+        self.value.map_err(|reason| ConfigError {
+            key: self.key,
+            reason,
+        })
+    }
+
+    pub fn ensure(self, check: impl FnOnce(&Inner) -> Result<(), String>) -> Self {
+        match self.value {
+            Ok(value) => {
+                let value = check(&value).map(|()| value);
+                Self { value, ..self }
+            }
+            Err(_) => self,
+        }
     }
 }
 
 impl EnvVar<String> {
     pub fn new(key: &str) -> EnvVar<String> {
-        match std::env::var(key) {
-            Ok(value) => EnvVar(Some(value)),
-            Err(error) => {
-                tracing::error!(?error, ?key, "unable to read env var");
-                EnvVar(None)
-            }
+        Self::from_value(key, std::env::var(key).ok())
+    }
+
+    pub fn from_value(key: &str, value: Option<String>) -> EnvVar<String> {
+        EnvVar {
+            key: key.to_string(),
+            value: value.ok_or_else(|| "not set".to_string()),
+        }
+    }
+
+    /// Falls back to `value` from the config file, found under `setting`.
+    pub fn or_setting(self, setting: &str, value: Option<String>) -> EnvVar<String> {
+        match (&self.value, value) {
+            (Ok(_), _) => self,
+            (Err(_), Some(value)) => EnvVar {
+                key: setting.to_string(),
+                value: Ok(value),
+            },
+            (Err(reason), None) => EnvVar {
+                key: format!("{} / {setting}", self.key),
+                value: Err(reason.clone()),
+            },
         }
     }
 
     pub fn with_default_value(self, default: impl Into<String>) -> EnvVar<String> {
-        match self.0 {
-            Some(_) => self,
-            None => EnvVar(Some(default.into())),
+        match self.value {
+            Ok(_) => self,
+            Err(_) => EnvVar {
+                value: Ok(default.into()),
+                ..self
+            },
         }
     }
 }
@@ -35,35 +85,46 @@ where
     where
         Inner: AsRef<str>,
     {
-        if self.0.as_ref().unwrap().as_ref().is_empty() {
-            tracing::error!("env var was empty");
-            return EnvVar(None);
-        }
-        self
+        self.ensure(|value| match value.as_ref().is_empty() {
+            true => Err("is empty".to_string()),
+            false => Ok(()),
+        })
+    }
+
+    pub fn ensure_positive(self) -> Self
+    where
+        Inner: Default + PartialOrd,
+    {
+        self.ensure(|value| match *value > Inner::default() {
+            true => Ok(()),
+            false => Err("must be greater than 0".to_string()),
+        })
     }
 
     pub fn ensure_file(self) -> Self
     where
         Inner: AsRef<OsStr>,
     {
-        let path = PathBuf::from(self.0.as_ref().expect("No value in EnvVar"));
-        if !path.is_file() {
-            tracing::error!(?path, "file not found");
-            return EnvVar(None);
-        }
-        self
+        self.ensure(|value| {
+            let path = PathBuf::from(value);
+            match path.is_file() {
+                true => Ok(()),
+                false => Err(format!("file {path:?} not found")),
+            }
+        })
     }
 
     pub fn ensure_directory(self) -> Self
     where
         Inner: AsRef<OsStr>,
     {
-        let path = PathBuf::from(OsStr::new(self.0.as_ref().expect("No value in EnvVar")));
-        if !path.is_dir() {
-            tracing::error!(?path, "directory not found");
-            return EnvVar(None);
-        }
-        self
+        self.ensure(|value| {
+            let path = PathBuf::from(value);
+            match path.is_dir() {
+                true => Ok(()),
+                false => Err(format!("directory {path:?} not found")),
+            }
+        })
     }
 }
 
@@ -75,9 +136,12 @@ where
     where
         T: std::str::FromStr<Err: std::fmt::Debug> + std::fmt::Debug,
     {
-        match self.parse::<T>().0 {
-            Some(_) => self,
-            None => EnvVar(None),
+        match self.parse::<T>().value {
+            Ok(_) => self,
+            Err(reason) => EnvVar {
+                value: Err(reason),
+                ..self
+            },
         }
     }
 
@@ -85,17 +149,19 @@ where
     where
         T: std::str::FromStr<Err: std::fmt::Debug> + std::fmt::Debug,
     {
-        match self.0.as_ref().unwrap().as_ref().parse() {
-            Ok(parsed) => EnvVar(Some(parsed)),
-            Err(error) => {
-                tracing::error!(
-                    ?error,
-                    value = ?self.0,
-                    target = std::any::type_name::<T>(),
-                    "couldn't parse env variable"
-                );
-                EnvVar(None)
-            }
+        let value = match &self.value {
+            Ok(value) => value.as_ref().parse().map_err(|error| {
+                format!(
+                    "couldn't parse {:?} as {}: {error:?}",
+                    value.as_ref(),
+                    std::any::type_name::<T>()
+                )
+            }),
+            Err(reason) => Err(reason.clone()),
+        };
+        EnvVar {
+            key: self.key.clone(),
+            value,
         }
     }
 }
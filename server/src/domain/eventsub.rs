@@ -0,0 +1,325 @@
+use std::{collections::HashMap, sync::Arc, time::Duration};
+
+use axum::http::{HeaderMap, StatusCode};
+use time::{format_description::well_known::Rfc3339, OffsetDateTime};
+use tokio::sync::Mutex;
+
+use crate::{
+    models::{EventTrigger, StoredEventSubSubscription},
+    twitch::{
+        eventsub::{
+            verify_eventsub_signature, EVENTSUB_MESSAGE_ID, EVENTSUB_MESSAGE_SIGNATURE,
+            EVENTSUB_MESSAGE_TIMESTAMP, EVENTSUB_MESSAGE_TYPE,
+        },
+        EventSubClient, EventSubClientError, EventSubMessageType, EventSubPayload,
+        EventSubSubscriptionRequest, EventSubTransport,
+    },
+};
+
+use super::{
+    db::{Database, DbError},
+    message::ImgfloatAsset,
+    token_store::TokenStoreError,
+    ApiError, ChannelController, TwitchTokenStore,
+};
+
+#[derive(Debug)]
+pub enum EventSubError {
+    MissingHeader(&'static str),
+    InvalidSignature,
+    StaleMessage,
+    DuplicateMessage,
+    InvalidPayload(String),
+    UnsupportedEvent(String),
+    UnknownBroadcaster,
+    Database(DbError),
+    Client(EventSubClientError),
+    TokenStore(TokenStoreError),
+}
+
+impl From<EventSubError> for ApiError {
+    fn from(error: EventSubError) -> ApiError {
+        match error {
+            EventSubError::InvalidSignature => {
+                ApiError::forbidden("eventsub signature does not match")
+            }
+            EventSubError::StaleMessage => ApiError::forbidden("eventsub message is too old"),
+            EventSubError::MissingHeader(name) => {
+                ApiError::bad_request("missing_header", format!("missing {name} header"))
+            }
+            EventSubError::DuplicateMessage => {
+                ApiError::conflict("duplicate_message", "eventsub message was already handled")
+            }
+            EventSubError::InvalidPayload(message) => {
+                ApiError::bad_request("invalid_payload", message)
+            }
+            EventSubError::UnsupportedEvent(event) => {
+                ApiError::bad_request("unsupported_event", format!("{event} is not supported"))
+            }
+            EventSubError::UnknownBroadcaster => {
+                ApiError::not_found("unknown_channel", "no such channel")
+            }
+            EventSubError::Database(error) => error.into(),
+            EventSubError::Client(_) => ApiError::upstream("twitch eventsub request failed"),
+            EventSubError::TokenStore(_) => ApiError::new(
+                StatusCode::SERVICE_UNAVAILABLE,
+                "token_unavailable",
+                "no usable twitch token for this channel",
+            ),
+        }
+    }
+}
+
+pub struct EventSubService {
+    database: Arc<dyn Database>,
+    controller: Arc<ChannelController>,
+    token_store: Arc<TwitchTokenStore>,
+    client: Box<dyn EventSubClient>,
+    secret: String,
+    callback_url: String,
+    seen_messages: Mutex<HashMap<String, OffsetDateTime>>,
+}
+
+impl EventSubService {
+    pub const MAX_MESSAGE_AGE: Duration = Duration::from_secs(600);
+
+    pub fn new(
+        database: Arc<dyn Database>,
+        controller: Arc<ChannelController>,
+        token_store: Arc<TwitchTokenStore>,
+        client: Box<dyn EventSubClient>,
+        secret: impl Into<String>,
+        callback_url: impl Into<String>,
+    ) -> Self {
+        Self {
+            database,
+            controller,
+            token_store,
+            client,
+            secret: secret.into(),
+            callback_url: callback_url.into(),
+            seen_messages: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub async fn verify(
+        &self,
+        headers: &HeaderMap,
+        body: &[u8],
+    ) -> Result<(EventSubMessageType, EventSubPayload), EventSubError> {
+        let message_id = Self::header(headers, EVENTSUB_MESSAGE_ID)?;
+        let timestamp = Self::header(headers, EVENTSUB_MESSAGE_TIMESTAMP)?;
+        let signature = Self::header(headers, EVENTSUB_MESSAGE_SIGNATURE)?;
+        let message_type = Self::header(headers, EVENTSUB_MESSAGE_TYPE)?
+            .parse::<EventSubMessageType>()
+            .map_err(EventSubError::InvalidPayload)?;
+
+        if !verify_eventsub_signature(&self.secret, message_id, timestamp, body, signature) {
+            tracing::warn!(?message_id, "invalid eventsub signature");
+            return Err(EventSubError::InvalidSignature);
+        }
+        let sent_at = OffsetDateTime::parse(timestamp, &Rfc3339)
+            .map_err(|error| EventSubError::InvalidPayload(error.to_string()))?;
+        let now = OffsetDateTime::now_utc();
+        if (now - sent_at).unsigned_abs() > Self::MAX_MESSAGE_AGE {
+            tracing::warn!(?message_id, ?timestamp, "stale eventsub message");
+            return Err(EventSubError::StaleMessage);
+        }
+
+        let mut seen_messages = self.seen_messages.lock().await;
+        seen_messages.retain(|_, seen_at| (now - *seen_at).unsigned_abs() <= Self::MAX_MESSAGE_AGE);
+        if seen_messages.contains_key(message_id) {
+            tracing::debug!(?message_id, "duplicate eventsub message");
+            return Err(EventSubError::DuplicateMessage);
+        }
+        let payload = serde_json::from_slice::<EventSubPayload>(body)
+            .inspect_err(|error| tracing::error!(?error, "invalid eventsub payload"))
+            .map_err(|error| EventSubError::InvalidPayload(error.to_string()))?;
+        seen_messages.insert(message_id.to_string(), sent_at);
+        Ok((message_type, payload))
+    }
+
+    pub async fn confirm(&self, payload: &EventSubPayload) -> Result<String, EventSubError> {
+        let challenge = payload
+            .challenge
+            .clone()
+            .ok_or(EventSubError::InvalidPayload(
+                "missing challenge".to_string(),
+            ))?;
+        let database = &self.database;
+        if let Some(mut subscription) = database
+            .get_eventsub_subscription(&payload.subscription.id)
+            .map_err(EventSubError::Database)?
+        {
+            subscription.status = "enabled".to_string();
+            database
+                .save_eventsub_subscription(&subscription)
+                .map_err(EventSubError::Database)?;
+        }
+        Ok(challenge)
+    }
+
+    pub async fn revoke(&self, payload: &EventSubPayload) -> Result<(), EventSubError> {
+        tracing::warn!(subscription = ?payload.subscription, "eventsub subscription revoked");
+        self.database
+            .delete_eventsub_subscription(&payload.subscription.id)
+            .map_err(EventSubError::Database)
+    }
+
+    pub async fn notify(&self, payload: &EventSubPayload) -> Result<usize, EventSubError> {
+        let subscription = self
+            .database
+            .get_eventsub_subscription(&payload.subscription.id)
+            .map_err(EventSubError::Database)?
+            .ok_or(EventSubError::UnknownBroadcaster)?;
+        let reward_id = payload
+            .event
+            .as_ref()
+            .and_then(|event| event.pointer("/reward/id"))
+            .and_then(|reward_id| reward_id.as_str());
+        let triggers = self
+            .database
+            .get_matching_event_triggers(
+                &subscription.username,
+                &payload.subscription.r#type,
+                reward_id,
+            )
+            .map_err(EventSubError::Database)?;
+        tracing::debug!(
+            username = ?subscription.username,
+            event_type = ?payload.subscription.r#type,
+            trigger_count = triggers.len(),
+            "eventsub notification"
+        );
+        for trigger in &triggers {
+            self.show_trigger(trigger).await;
+        }
+        Ok(triggers.len())
+    }
+
+    pub async fn subscribe(&self, username: &str, event_type: &str) -> Result<(), EventSubError> {
+        if self
+            .database
+            .get_eventsub_subscription_for(username, event_type)
+            .map_err(EventSubError::Database)?
+            .is_some()
+        {
+            return Ok(());
+        }
+        let broadcaster_id = self
+            .token_store
+            .user_id(username)
+            .await
+            .map_err(EventSubError::TokenStore)?;
+        let (version, condition) = match event_type {
+            "channel.follow" => (
+                "2",
+                serde_json::json!({
+                    "broadcaster_user_id": broadcaster_id,
+                    "moderator_user_id": broadcaster_id,
+                }),
+            ),
+            "channel.raid" => (
+                "1",
+                serde_json::json!({ "to_broadcaster_user_id": broadcaster_id }),
+            ),
+            "channel.subscribe"
+            | "channel.cheer"
+            | "channel.channel_points_custom_reward_redemption.add" => (
+                "1",
+                serde_json::json!({ "broadcaster_user_id": broadcaster_id }),
+            ),
+            other => return Err(EventSubError::UnsupportedEvent(other.to_string())),
+        };
+        let request = EventSubSubscriptionRequest {
+            r#type: event_type.to_string(),
+            version: version.to_string(),
+            condition,
+            transport: EventSubTransport {
+                method: "webhook".to_string(),
+                callback: self.callback_url.clone(),
+                secret: Some(self.secret.clone()),
+            },
+        };
+        let subscription = self
+            .client
+            .create_subscription(&request)
+            .await
+            .map_err(EventSubError::Client)?;
+        tracing::info!(?username, ?subscription, "created eventsub subscription");
+        self.database
+            .save_eventsub_subscription(&StoredEventSubSubscription {
+                id: subscription.id,
+                username: username.to_string(),
+                event_type: event_type.to_string(),
+                status: subscription.status,
+            })
+            .map_err(EventSubError::Database)
+    }
+
+    pub async fn unsubscribe_unused(
+        &self,
+        username: &str,
+        event_type: &str,
+    ) -> Result<(), EventSubError> {
+        let database = &self.database;
+        let Some(subscription) = database
+            .get_eventsub_subscription_for(username, event_type)
+            .map_err(EventSubError::Database)?
+        else {
+            return Ok(());
+        };
+        let user = database
+            .get_user(username)
+            .map_err(EventSubError::Database)?
+            .ok_or(EventSubError::UnknownBroadcaster)?;
+        let in_use = database
+            .get_event_triggers(&user)
+            .map_err(EventSubError::Database)?
+            .iter()
+            .any(|trigger| trigger.event_type == event_type);
+        if in_use {
+            return Ok(());
+        }
+        self.client
+            .delete_subscription(&subscription.id)
+            .await
+            .map_err(EventSubError::Client)?;
+        database
+            .delete_eventsub_subscription(&subscription.id)
+            .map_err(EventSubError::Database)
+    }
+
+    async fn show_trigger(&self, trigger: &EventTrigger) {
+        let asset = ImgfloatAsset {
+            id: uuid::Uuid::new_v4().to_string(),
+            x: trigger.x,
+            y: trigger.y,
+            w: trigger.w,
+            h: trigger.h,
+            theta: 0.0,
+            url: format!(
+                "/api/assets/{}/{}",
+                trigger.username, trigger.asset_filename
+            ),
+        };
+        let asset_id = asset.id.clone();
+        self.controller.show_asset(&trigger.username, asset).await;
+        if trigger.duration_seconds > 0 {
+            let controller = Arc::clone(&self.controller);
+            let username = trigger.username.clone();
+            let duration = Duration::from_secs(trigger.duration_seconds as u64);
+            tokio::spawn(async move {
+                tokio::time::sleep(duration).await;
+                controller.hide_asset(&username, &asset_id).await;
+            });
+        }
+    }
+
+    fn header<'a>(headers: &'a HeaderMap, name: &'static str) -> Result<&'a str, EventSubError> {
+        headers
+            .get(name)
+            .and_then(|value| value.to_str().ok())
+            .ok_or(EventSubError::MissingHeader(name))
+    }
+}
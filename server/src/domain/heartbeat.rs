@@ -0,0 +1,77 @@
+use std::time::Duration;
+
+use axum::extract::ws::Message;
+use futures::{Sink, SinkExt};
+use tokio::time::{Instant, Interval, MissedTickBehavior};
+
+#[derive(Clone, Copy, Debug, serde::Serialize)]
+pub struct HeartbeatConfig {
+    /// How often an idle socket is pinged.
+    #[serde(serialize_with = "super::config::seconds")]
+    pub interval: Duration,
+    /// How long a socket may stay silent, or take to accept a message,
+    /// before it is dropped.
+    #[serde(serialize_with = "super::config::seconds")]
+    pub timeout: Duration,
+}
+
+impl Default for HeartbeatConfig {
+    fn default() -> Self {
+        Self {
+            interval: Duration::from_secs(15),
+            timeout: Duration::from_secs(45),
+        }
+    }
+}
+
+/// Keeps track of when a socket's peer was last heard from.
+pub struct Heartbeat {
+    config: HeartbeatConfig,
+    ticks: Interval,
+    last_seen: Instant,
+}
+
+impl Heartbeat {
+    pub fn new(config: HeartbeatConfig) -> Self {
+        let mut ticks = tokio::time::interval_at(Instant::now() + config.interval, config.interval);
+        ticks.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        Self {
+            config,
+            ticks,
+            last_seen: Instant::now(),
+        }
+    }
+
+    pub fn seen(&mut self) {
+        self.last_seen = Instant::now();
+    }
+
+    /// Waits for the next ping to send, or returns `None` once the peer has
+    /// been silent for longer than the timeout.
+    pub async fn next_ping(&mut self) -> Option<Message> {
+        self.ticks.tick().await;
+        if self.last_seen.elapsed() >= self.config.timeout {
+            return None;
+        }
+        Some(Message::Ping(vec![]))
+    }
+
+    /// Returns false when the message could not be sent in time.
+    pub async fn send<S>(&self, socket: &mut S, message: Message) -> bool
+    where
+        S: Sink<Message> + Unpin,
+        S::Error: std::fmt::Debug,
+    {
+        match tokio::time::timeout(self.config.timeout, socket.send(message)).await {
+            Ok(Ok(())) => true,
+            Ok(Err(error)) => {
+                tracing::debug!(?error, "unable to send socket message");
+                false
+            }
+            Err(_) => {
+                tracing::warn!(timeout = ?self.config.timeout, "socket send timed out");
+                false
+            }
+        }
+    }
+}
@@ -1,13 +1,14 @@
 use axum::{
     body::Body,
-    http::{Response, StatusCode},
+    http::{header, Response, StatusCode},
     response::IntoResponse,
-    Json,
 };
 use serde::Serialize;
 
+use super::ApiError;
+
 pub struct JsonResponse<T> {
-    body: Json<T>,
+    body: T,
     status_code: StatusCode,
 }
 
@@ -17,7 +18,7 @@ where
 {
     pub fn new(body: T) -> Self {
         Self {
-            body: Json(body),
+            body,
             status_code: StatusCode::OK,
         }
     }
@@ -35,6 +36,16 @@ where
     T: Serialize,
 {
     fn into_response(self) -> Response<Body> {
-        (self.status_code, self.body.into_response()).into_response()
+        match serde_json::to_vec(&self.body) {
+            Ok(body) => (
+                self.status_code,
+                [(header::CONTENT_TYPE, "application/json")],
+                body,
+            )
+                .into_response(),
+            Err(error) => {
+                ApiError::internal(format!("unable to serialize response: {error}")).into_response()
+            }
+        }
     }
 }
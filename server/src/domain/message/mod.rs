@@ -1,5 +1,9 @@
+pub mod notification;
+pub mod server;
 pub mod state;
 
+pub use notification::ImgfloatWriterNotification;
+pub use server::ImgfloatServerMessage;
 pub use state::ImgfloatAsset;
 pub use state::ImgfloatAssetStateMessage;
 pub use state::ImgfloatState;
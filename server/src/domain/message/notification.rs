@@ -0,0 +1,7 @@
+use crate::models::Submission;
+
+#[derive(Debug, serde::Deserialize, serde::Serialize, Clone, utoipa::ToSchema)]
+pub enum ImgfloatWriterNotification {
+    SubmissionReceived(Submission),
+    SubmissionReviewed(Submission),
+}
@@ -0,0 +1,7 @@
+/// Sent to readers and writers by the server itself rather than a channel.
+#[derive(Debug, serde::Deserialize, serde::Serialize, Clone, utoipa::ToSchema)]
+pub enum ImgfloatServerMessage {
+    /// The server is going away; reconnecting after the delay reaches its
+    /// replacement.
+    Restarting { reconnect_in_ms: u64 },
+}
@@ -1,11 +1,23 @@
-#[derive(Debug, serde::Deserialize, serde::Serialize, Clone)]
+use crate::models::Capability;
+
+#[derive(Debug, serde::Deserialize, serde::Serialize, Clone, utoipa::ToSchema)]
 pub enum ImgfloatAssetStateMessage {
     New(ImgfloatState),
     Update(ImgfloatAsset),
     Delete(String),
 }
 
-#[derive(Debug, serde::Deserialize, serde::Serialize, Clone)]
+impl ImgfloatAssetStateMessage {
+    pub fn required_capability(&self) -> Capability {
+        match self {
+            Self::New(_) => Capability::ManageScenes,
+            Self::Update(_) => Capability::PlaceAssets,
+            Self::Delete(_) => Capability::DeleteAssets,
+        }
+    }
+}
+
+#[derive(Debug, serde::Deserialize, serde::Serialize, Clone, utoipa::ToSchema)]
 pub struct ImgfloatAsset {
     pub id: String,
     pub x: f32,
@@ -16,7 +28,11 @@ pub struct ImgfloatAsset {
     pub url: String,
 }
 
-#[derive(Debug, serde::Deserialize, serde::Serialize, Clone)]
+impl ImgfloatAsset {
+    pub const DEFAULT_SIZE: f32 = 320.0;
+}
+
+#[derive(Debug, serde::Deserialize, serde::Serialize, Clone, utoipa::ToSchema)]
 pub struct ImgfloatState {
     pub assets: Vec<ImgfloatAsset>,
 }
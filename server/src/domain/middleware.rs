@@ -1,4 +1,15 @@
-use axum::{extract::Request, middleware::Next, response::Response};
+use std::sync::Arc;
+
+use axum::{
+    extract::{Request, State},
+    http::{header, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+
+use crate::models::{secret, ApiToken};
+
+use super::{db::Database, ApiError};
 
 pub async fn log_requests(request: Request, next: Next) -> Response {
     let uri = request.uri().clone();
@@ -11,3 +22,49 @@ pub async fn log_requests(request: Request, next: Next) -> Response {
     }
     response
 }
+
+pub async fn authenticate_api_token(
+    State(database): State<Arc<dyn Database>>,
+    mut request: Request,
+    next: Next,
+) -> Response {
+    let bearer = request
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .map(str::trim)
+        .filter(|token| token.starts_with(ApiToken::PREFIX));
+    let Some(bearer) = bearer else {
+        return next.run(request).await;
+    };
+    let now = time::OffsetDateTime::now_utc().unix_timestamp();
+    let api_token = database.get_api_token_by_hash(&secret::hash(bearer));
+    match api_token {
+        Err(error) => ApiError::from(error).into_response(),
+        Ok(Some(api_token)) if !api_token.is_expired(now) => {
+            let _ = database.touch_api_token(&api_token, now);
+            tracing::debug!(username = ?api_token.username, id = ?api_token.id, "api token");
+            request.extensions_mut().insert(api_token);
+            next.run(request).await
+        }
+        Ok(Some(api_token)) => {
+            tracing::warn!(username = ?api_token.username, id = ?api_token.id, "expired api token");
+            ApiError::new(
+                StatusCode::UNAUTHORIZED,
+                "expired_api_token",
+                "api token has expired",
+            )
+            .into_response()
+        }
+        Ok(None) => {
+            tracing::warn!("unknown api token");
+            ApiError::new(
+                StatusCode::UNAUTHORIZED,
+                "invalid_api_token",
+                "api token is not recognised",
+            )
+            .into_response()
+        }
+    }
+}
@@ -1,17 +1,66 @@
+pub mod api_error;
+pub mod asset_archive;
+pub mod asset_storage;
+pub mod backplane;
 pub mod channel_controller;
+pub mod chat;
+pub mod cli;
+pub mod config;
 pub mod db;
 pub mod env;
+pub mod eventsub;
+pub mod extract;
+pub mod heartbeat;
 pub mod json_response;
 pub mod message;
 pub mod middleware;
+pub mod moderator_sync;
 pub mod percentage;
+pub mod permission;
+pub mod rate_limit;
 pub mod session;
+pub mod session_store;
+pub mod shutdown;
 pub mod state;
+pub mod storage_check;
+pub mod submission_queue;
+pub mod token_cipher;
+pub mod token_store;
 
+pub use api_error::ApiError;
+pub use api_error::ApiErrorBody;
+pub use asset_archive::AssetArchive;
+pub use asset_archive::AssetArchiveError;
+pub use asset_storage::AssetStorage;
+pub use asset_storage::FilesystemAssetStorage;
 pub use channel_controller::ChannelController;
+pub use chat::ChatBot;
+pub use chat::ChatConfig;
+pub use config::Config;
+pub use config::ConfigError;
+pub use config::ConfigErrors;
+pub use config::ConfigSource;
 pub use env::EnvVar;
+pub use eventsub::EventSubError;
+pub use eventsub::EventSubService;
+pub use heartbeat::HeartbeatConfig;
 pub use json_response::JsonResponse;
+pub use moderator_sync::ModeratorSync;
 pub use percentage::Percentage;
+pub use permission::PermissionError;
+pub use permission::PermissionService;
+pub use rate_limit::RateLimit;
+pub use rate_limit::RateLimitConfig;
+pub use rate_limit::RateLimiter;
 pub use session::UserSession;
+pub use session_store::AppSessionStore;
+pub use session_store::DatabaseSessionStore;
+pub use shutdown::ShutdownConfig;
 pub use state::AppState;
 pub use state::AssetDirectory;
+pub use storage_check::StorageConsistencyChecker;
+pub use storage_check::StorageConsistencyReport;
+pub use submission_queue::SubmissionError;
+pub use submission_queue::SubmissionQueue;
+pub use token_cipher::TokenCipher;
+pub use token_store::TwitchTokenStore;
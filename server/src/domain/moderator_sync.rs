@@ -0,0 +1,142 @@
+use std::{sync::Arc, time::Duration};
+
+use crate::models::User;
+
+use super::{
+    db::{Database, DbError},
+    token_store::TokenStoreError,
+    ApiError, TwitchTokenStore,
+};
+
+#[derive(Debug)]
+pub enum ModeratorSyncError {
+    TokenStore(TokenStoreError),
+    UnexpectedResponse,
+    Database(DbError),
+}
+
+impl From<ModeratorSyncError> for ApiError {
+    fn from(error: ModeratorSyncError) -> ApiError {
+        match error {
+            ModeratorSyncError::Database(error)
+            | ModeratorSyncError::TokenStore(TokenStoreError::Database(error)) => error.into(),
+            _ => ApiError::upstream("unable to fetch moderators from twitch"),
+        }
+    }
+}
+
+#[derive(Debug, Default, PartialEq, serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
+pub struct ModeratorSyncSummary {
+    pub moderators: usize,
+    pub added: usize,
+    pub removed: usize,
+}
+
+pub struct ModeratorSync {
+    database: Arc<dyn Database>,
+    token_store: Arc<TwitchTokenStore>,
+}
+
+impl ModeratorSync {
+    const MAX_PAGES: usize = 50;
+
+    pub fn new(database: Arc<dyn Database>, token_store: Arc<TwitchTokenStore>) -> Self {
+        Self {
+            database,
+            token_store,
+        }
+    }
+
+    pub async fn sync(
+        &self,
+        broadcaster: &User,
+    ) -> Result<ModeratorSyncSummary, ModeratorSyncError> {
+        let moderators = self.fetch_moderators(broadcaster).await?;
+        let (added, removed) = self
+            .database
+            .replace_synced_moderators(broadcaster, &moderators)
+            .map_err(ModeratorSyncError::Database)?;
+        let summary = ModeratorSyncSummary {
+            moderators: moderators.len(),
+            added,
+            removed,
+        };
+        tracing::debug!(?broadcaster, ?summary, "synced twitch moderators");
+        Ok(summary)
+    }
+
+    pub async fn sync_all(&self) -> Result<usize, ModeratorSyncError> {
+        let broadcasters = self
+            .database
+            .get_moderator_sync_users()
+            .map_err(ModeratorSyncError::Database)?;
+        let mut synced = 0;
+        for broadcaster in broadcasters {
+            match self.sync(&broadcaster).await {
+                Ok(_) => synced += 1,
+                Err(error) => tracing::warn!(?broadcaster, ?error, "unable to sync moderators"),
+            }
+        }
+        Ok(synced)
+    }
+
+    pub async fn run_periodically(self, period: Duration) {
+        let mut interval = tokio::time::interval(period);
+        loop {
+            interval.tick().await;
+            match self.sync_all().await {
+                Ok(synced) => tracing::debug!(?synced, "synced moderators"),
+                Err(error) => tracing::error!(?error, "unable to sync moderators"),
+            }
+        }
+    }
+
+    async fn fetch_moderators(
+        &self,
+        broadcaster: &User,
+    ) -> Result<Vec<String>, ModeratorSyncError> {
+        let broadcaster_id = self
+            .token_store
+            .user_id(&broadcaster.username)
+            .await
+            .map_err(ModeratorSyncError::TokenStore)?;
+        let mut moderators = Vec::new();
+        let mut cursor: Option<String> = None;
+        for _ in 0..Self::MAX_PAGES {
+            let path_and_query = {
+                let mut query = form_urlencoded::Serializer::new(String::new());
+                query
+                    .append_pair("broadcaster_id", &broadcaster_id)
+                    .append_pair("first", "100");
+                if let Some(cursor) = &cursor {
+                    query.append_pair("after", cursor);
+                }
+                format!("moderation/moderators?{}", query.finish())
+            };
+            let response = self
+                .token_store
+                .helix_get(&broadcaster.username, &path_and_query)
+                .await
+                .map_err(ModeratorSyncError::TokenStore)?;
+            let page = response
+                .get("data")
+                .and_then(|data| data.as_array())
+                .ok_or(ModeratorSyncError::UnexpectedResponse)?;
+            moderators.extend(
+                page.iter()
+                    .filter_map(|moderator| moderator.get("user_login"))
+                    .filter_map(|login| login.as_str())
+                    .map(str::to_lowercase),
+            );
+            cursor = response
+                .pointer("/pagination/cursor")
+                .and_then(|cursor| cursor.as_str())
+                .filter(|cursor| !cursor.is_empty())
+                .map(str::to_string);
+            if cursor.is_none() {
+                break;
+            }
+        }
+        Ok(moderators)
+    }
+}
@@ -0,0 +1,156 @@
+use std::sync::Arc;
+
+use crate::models::{Capability, ChannelRole, User};
+
+use super::{
+    db::{Database, DbError},
+    ApiError, UserSession,
+};
+
+#[derive(Debug, PartialEq)]
+pub enum PermissionError {
+    Unauthenticated,
+    UnknownBroadcaster,
+    Forbidden,
+    Database(DbError),
+}
+
+impl From<PermissionError> for ApiError {
+    fn from(error: PermissionError) -> ApiError {
+        match error {
+            PermissionError::Unauthenticated => ApiError::unauthorized(),
+            PermissionError::UnknownBroadcaster => {
+                ApiError::not_found("unknown_channel", "no such channel")
+            }
+            PermissionError::Forbidden => {
+                ApiError::forbidden("missing permission for this channel")
+            }
+            PermissionError::Database(error) => error.into(),
+        }
+    }
+}
+
+pub struct PermissionService {
+    database: Arc<dyn Database>,
+}
+
+impl PermissionService {
+    /// What a logged-in viewer who isn't one of the channel's admins may do.
+    pub const VIEWER_ROLE: ChannelRole = ChannelRole::ViewerSubmitter;
+
+    pub fn new(database: Arc<dyn Database>) -> Self {
+        Self { database }
+    }
+
+    pub async fn role(&self, login: &str, broadcaster: &User) -> Option<ChannelRole> {
+        self.try_role(login, broadcaster)
+            .await
+            .unwrap_or_else(|error| {
+                tracing::error!(?error, "failed to load channel role");
+                None
+            })
+    }
+
+    async fn try_role(
+        &self,
+        login: &str,
+        broadcaster: &User,
+    ) -> Result<Option<ChannelRole>, PermissionError> {
+        if login == broadcaster.username {
+            return Ok(Some(ChannelRole::Broadcaster));
+        }
+        Ok(self
+            .database
+            .get_channel_admin(login, broadcaster)
+            .map_err(PermissionError::Database)?
+            .map(|channel_admin| channel_admin.role()))
+    }
+
+    pub async fn session_role(
+        &self,
+        session: &UserSession,
+        username: &str,
+    ) -> Result<(User, ChannelRole), PermissionError> {
+        self.resolve_role(session, username, None).await
+    }
+
+    async fn resolve_role(
+        &self,
+        session: &UserSession,
+        username: &str,
+        viewer_role: Option<ChannelRole>,
+    ) -> Result<(User, ChannelRole), PermissionError> {
+        let session_user = session
+            .user
+            .as_ref()
+            .ok_or(PermissionError::Unauthenticated)?;
+        let broadcaster = self
+            .database
+            .get_user(username)
+            .map_err(PermissionError::Database)?
+            .ok_or(PermissionError::UnknownBroadcaster)?;
+        let role = self
+            .try_role(&session_user.login, &broadcaster)
+            .await?
+            .or(viewer_role)
+            .ok_or_else(|| {
+                tracing::warn!(?broadcaster, user = ?session_user.login, "not a channel admin");
+                PermissionError::Forbidden
+            })?;
+        Ok((broadcaster, role))
+    }
+
+    pub async fn authorize_reader(
+        &self,
+        session: &UserSession,
+        username: &str,
+        token: Option<&str>,
+    ) -> Result<User, PermissionError> {
+        if let Some(token) = token {
+            let database = &self.database;
+            let broadcaster = database
+                .get_user(username)
+                .map_err(PermissionError::Database)?
+                .ok_or(PermissionError::UnknownBroadcaster)?;
+            let overlay_token = database
+                .get_overlay_token(&broadcaster)
+                .map_err(PermissionError::Database)?;
+            return match overlay_token {
+                Some(overlay_token) if overlay_token.matches(token) => Ok(broadcaster),
+                _ => {
+                    tracing::warn!(?broadcaster, "invalid overlay token");
+                    Err(PermissionError::Unauthenticated)
+                }
+            };
+        }
+        self.authorize(session, username, Capability::PlaceAssets)
+            .await
+    }
+
+    /// Checks `capability` against the session's role on the channel, where
+    /// viewers without one get [`Self::VIEWER_ROLE`], and against the scope of
+    /// its api token.
+    pub async fn authorize(
+        &self,
+        session: &UserSession,
+        username: &str,
+        capability: Capability,
+    ) -> Result<User, PermissionError> {
+        let (broadcaster, role) = self
+            .resolve_role(session, username, Some(Self::VIEWER_ROLE))
+            .await?;
+        if !role.allows(capability) {
+            tracing::warn!(?broadcaster, ?role, ?capability, "capability denied");
+            return Err(PermissionError::Forbidden);
+        }
+        if !session.allows(capability) {
+            tracing::warn!(
+                ?broadcaster,
+                ?capability,
+                "capability outside api token scope"
+            );
+            return Err(PermissionError::Forbidden);
+        }
+        Ok(broadcaster)
+    }
+}
@@ -0,0 +1,205 @@
+use std::{
+    collections::HashMap,
+    net::SocketAddr,
+    str::FromStr,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use axum::{
+    extract::{ConnectInfo, Request, State},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use tokio::sync::Mutex;
+
+use super::{ApiError, UserSession};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RateLimit {
+    pub burst: u32,
+    pub period: Duration,
+}
+
+impl RateLimit {
+    pub fn new(burst: u32, period: Duration) -> Self {
+        Self { burst, period }
+    }
+
+    fn refill_per_second(&self) -> f64 {
+        self.burst as f64 / self.period.as_secs_f64()
+    }
+}
+
+impl std::fmt::Display for RateLimit {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}/{}", self.burst, self.period.as_secs())
+    }
+}
+
+impl serde::Serialize for RateLimit {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+#[derive(Debug)]
+pub struct InvalidRateLimit(pub String);
+
+impl FromStr for RateLimit {
+    type Err = InvalidRateLimit;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let (burst, seconds) = value
+            .split_once('/')
+            .ok_or_else(|| InvalidRateLimit(value.to_string()))?;
+        let burst = burst
+            .trim()
+            .parse::<u32>()
+            .map_err(|_| InvalidRateLimit(value.to_string()))?;
+        let seconds = seconds
+            .trim()
+            .parse::<u64>()
+            .map_err(|_| InvalidRateLimit(value.to_string()))?;
+        if burst == 0 || seconds == 0 {
+            return Err(InvalidRateLimit(value.to_string()));
+        }
+        Ok(Self::new(burst, Duration::from_secs(seconds)))
+    }
+}
+
+#[derive(Clone, Copy, Debug, serde::Serialize)]
+pub struct RateLimitConfig {
+    pub uploads: RateLimit,
+    pub sockets: RateLimit,
+    pub writer_messages: RateLimit,
+}
+
+impl Default for RateLimitConfig {
+    fn default() -> Self {
+        Self {
+            uploads: RateLimit::new(20, Duration::from_secs(60)),
+            sockets: RateLimit::new(30, Duration::from_secs(60)),
+            writer_messages: RateLimit::new(120, Duration::from_secs(2)),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct TokenBucket {
+    limit: RateLimit,
+    tokens: f64,
+    updated_at: Instant,
+}
+
+impl TokenBucket {
+    pub fn new(limit: RateLimit, now: Instant) -> Self {
+        Self {
+            limit,
+            tokens: limit.burst as f64,
+            updated_at: now,
+        }
+    }
+
+    fn refill(&mut self, now: Instant) {
+        let elapsed = now.saturating_duration_since(self.updated_at);
+        self.tokens = (self.tokens + elapsed.as_secs_f64() * self.limit.refill_per_second())
+            .min(self.limit.burst as f64);
+        self.updated_at = now;
+    }
+
+    pub fn try_take(&mut self, now: Instant) -> Result<(), Duration> {
+        self.refill(now);
+        if self.tokens >= 1.0 {
+            self.tokens -= 1.0;
+            Ok(())
+        } else {
+            let missing = 1.0 - self.tokens;
+            Err(Duration::from_secs_f64(
+                missing / self.limit.refill_per_second(),
+            ))
+        }
+    }
+
+    fn is_full(&mut self, now: Instant) -> bool {
+        self.refill(now);
+        self.tokens >= self.limit.burst as f64
+    }
+}
+
+pub struct RateLimiter {
+    name: &'static str,
+    limit: RateLimit,
+    buckets: Mutex<HashMap<String, TokenBucket>>,
+}
+
+impl RateLimiter {
+    pub fn new(name: &'static str, limit: RateLimit) -> Self {
+        Self {
+            name,
+            limit,
+            buckets: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub async fn check(&self, key: &str) -> Result<(), Duration> {
+        self.check_at(key, Instant::now()).await
+    }
+
+    pub async fn check_at(&self, key: &str, now: Instant) -> Result<(), Duration> {
+        self.buckets
+            .lock()
+            .await
+            .entry(key.to_string())
+            .or_insert_with(|| TokenBucket::new(self.limit, now))
+            .try_take(now)
+            .inspect_err(|retry_after| {
+                tracing::warn!(limiter = self.name, ?key, ?retry_after, "rate limited")
+            })
+    }
+
+    pub async fn prune(&self) {
+        let now = Instant::now();
+        let mut buckets = self.buckets.lock().await;
+        buckets.retain(|_, bucket| !bucket.is_full(now));
+        tracing::debug!(
+            limiter = self.name,
+            remaining = buckets.len(),
+            "pruned buckets"
+        );
+    }
+
+    pub async fn run_cleanup(self: Arc<Self>, interval: Duration) {
+        let mut interval = tokio::time::interval(interval);
+        loop {
+            interval.tick().await;
+            self.prune().await;
+        }
+    }
+}
+
+fn rate_limit_key(session: Option<&UserSession>, request: &Request) -> String {
+    if let Some(user) = session.and_then(UserSession::user) {
+        return format!("user:{}", user.login);
+    }
+    match request.extensions().get::<ConnectInfo<SocketAddr>>() {
+        Some(ConnectInfo(address)) => format!("ip:{}", address.ip()),
+        None => "anonymous".to_string(),
+    }
+}
+
+pub async fn rate_limit(
+    State(limiter): State<Arc<RateLimiter>>,
+    session: Option<UserSession>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let key = rate_limit_key(session.as_ref(), &request);
+    match limiter.check(&key).await {
+        Ok(()) => next.run(request).await,
+        Err(retry_after) => {
+            let retry_after_secs = retry_after.as_secs_f64().ceil().max(1.0) as u64;
+            ApiError::rate_limited(retry_after_secs).into_response()
+        }
+    }
+}
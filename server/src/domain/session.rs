@@ -1,18 +1,21 @@
 use std::sync::Arc;
 
-use axum::{
-    extract::FromRequestParts,
-    http::{request::Parts, StatusCode},
-};
+use axum::{extract::FromRequestParts, http::request::Parts};
 use tower_sessions::Session;
 
-use crate::twitch::{AuthCallbackSuccessQuery, TwitchAuthenticator, TwitchUser};
+use crate::{
+    models::{ApiToken, Capability},
+    twitch::{AuthCallbackSuccessQuery, TwitchAuthenticator, TwitchUser, TwitchUserTokens},
+};
+
+use super::ApiError;
 
 #[derive(Debug)]
 pub enum UserSessionError {
     TokenRequestFailed,
     UserInfoRequestFailed,
     NoSuchUser,
+    StateMismatch,
     SessionUnavailable(tower_sessions::session::Error),
 }
 
@@ -20,16 +23,46 @@ pub enum UserSessionError {
 pub struct UserSession {
     pub session: Session,
     pub user: Option<TwitchUser>,
+    pub api_token: Option<ApiToken>,
 }
 
 impl UserSession {
     const SESSION_USER_KEY: &'static str = "session.user";
+    const SESSION_OAUTH_STATE_KEY: &'static str = "session.oauth_state";
+
+    pub async fn begin_login(session: &Session) -> Result<String, UserSessionError> {
+        let state = uuid::Uuid::new_v4().simple().to_string();
+        session
+            .insert(Self::SESSION_OAUTH_STATE_KEY, &state)
+            .await
+            .inspect_err(|error| tracing::error!(?error, "session insert error"))
+            .map_err(UserSessionError::SessionUnavailable)?;
+        Ok(state)
+    }
+
+    pub async fn verify_login_state(
+        session: &Session,
+        state: Option<&str>,
+    ) -> Result<(), UserSessionError> {
+        let expected_state: Option<String> = session
+            .remove(Self::SESSION_OAUTH_STATE_KEY)
+            .await
+            .inspect_err(|error| tracing::error!(?error, "session remove error"))
+            .map_err(UserSessionError::SessionUnavailable)?;
+        match (expected_state, state) {
+            (Some(expected_state), Some(state)) if expected_state == state => Ok(()),
+            (expected_state, state) => {
+                tracing::warn!(?expected_state, ?state, "oauth state mismatch");
+                Err(UserSessionError::StateMismatch)
+            }
+        }
+    }
 
     pub async fn update(
         query: &AuthCallbackSuccessQuery,
         session: &Session,
         authenticator: Arc<Box<dyn TwitchAuthenticator>>,
-    ) -> Result<String, UserSessionError> {
+    ) -> Result<(String, TwitchUserTokens), UserSessionError> {
         let tokens = authenticator
             .get_tokens(&query.code)
             .await
@@ -44,13 +77,20 @@ impl UserSession {
         tracing::debug!(username = &user.login, "logged user in");
         let user_login = user.login.clone();
 
+        // a fresh id keeps a session planted before login from becoming
+        // authenticated
+        session
+            .cycle_id()
+            .await
+            .inspect_err(|error| tracing::error!(?error, "session cycle error"))
+            .map_err(UserSessionError::SessionUnavailable)?;
         session
             .insert(Self::SESSION_USER_KEY, &user)
             .await
             .inspect_err(|error| tracing::error!(?error, "session insert error"))
             .map_err(UserSessionError::SessionUnavailable)?;
 
-        Ok(user_login)
+        Ok((user_login, tokens))
     }
 
     async fn get_user_from_session(session: &Session) -> Option<TwitchUser> {
@@ -64,6 +104,26 @@ impl UserSession {
     pub fn user(&self) -> Option<&TwitchUser> {
         self.user.as_ref()
     }
+
+    pub fn allows(&self, capability: Capability) -> bool {
+        self.api_token
+            .as_ref()
+            .is_none_or(|api_token| api_token.allows(capability))
+    }
+
+    fn api_token_user(api_token: &ApiToken) -> TwitchUser {
+        TwitchUser {
+            id: String::new(),
+            login: api_token.username.clone(),
+            display_name: api_token.username.clone(),
+            r#type: String::new(),
+            broadcaster_type: String::new(),
+            description: String::new(),
+            profile_image_url: String::new(),
+            offline_image_url: String::new(),
+            created_at: String::new(),
+        }
+    }
 }
 
 #[async_trait::async_trait]
@@ -71,12 +131,25 @@ impl<S> FromRequestParts<S> for UserSession
 where
     S: Send + Sync,
 {
-    type Rejection = (StatusCode, &'static str);
+    type Rejection = ApiError;
 
     async fn from_request_parts(req: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
-        let session = Session::from_request_parts(req, _state).await?;
+        let session = Session::from_request_parts(req, _state)
+            .await
+            .map_err(|(_, message)| ApiError::internal(message))?;
+        if let Some(api_token) = req.extensions.get::<ApiToken>().cloned() {
+            return Ok(Self {
+                session,
+                user: Some(Self::api_token_user(&api_token)),
+                api_token: Some(api_token),
+            });
+        }
         let user = Self::get_user_from_session(&session).await;
 
-        Ok(Self { session, user })
+        Ok(Self {
+            session,
+            user,
+            api_token: None,
+        })
     }
 }
@@ -0,0 +1,172 @@
+use std::{str::FromStr, sync::Arc, time::Duration};
+
+use time::OffsetDateTime;
+use tower_sessions::{
+    session::{Id, Record},
+    session_store, ExpiredDeletion, MemoryStore, SessionStore,
+};
+
+use crate::models::StoredSession;
+
+use super::db::Database;
+
+#[derive(Clone)]
+pub struct DatabaseSessionStore {
+    database: Arc<dyn Database>,
+}
+
+impl std::fmt::Debug for DatabaseSessionStore {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("DatabaseSessionStore")
+    }
+}
+
+impl DatabaseSessionStore {
+    pub fn new(database: Arc<dyn Database>) -> Self {
+        Self { database }
+    }
+
+    fn to_stored_session(record: &Record) -> session_store::Result<StoredSession> {
+        let data = serde_json::to_string(&record.data)
+            .map_err(|error| session_store::Error::Encode(error.to_string()))?;
+        Ok(StoredSession {
+            id: record.id.to_string(),
+            data,
+            expiry_date: record.expiry_date.unix_timestamp(),
+        })
+    }
+
+    fn to_record(stored_session: StoredSession) -> session_store::Result<Record> {
+        let id = Id::from_str(&stored_session.id)
+            .map_err(|error| session_store::Error::Decode(error.to_string()))?;
+        let data = serde_json::from_str(&stored_session.data)
+            .map_err(|error| session_store::Error::Decode(error.to_string()))?;
+        let expiry_date = OffsetDateTime::from_unix_timestamp(stored_session.expiry_date)
+            .map_err(|error| session_store::Error::Decode(error.to_string()))?;
+        Ok(Record {
+            id,
+            data,
+            expiry_date,
+        })
+    }
+
+    pub async fn run_expiry_cleanup(self, period: Duration) {
+        let mut interval = tokio::time::interval(period);
+        loop {
+            interval.tick().await;
+            if let Err(error) = self.delete_expired().await {
+                tracing::error!(?error, "unable to delete expired sessions");
+            }
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl SessionStore for DatabaseSessionStore {
+    async fn create(&self, record: &mut Record) -> session_store::Result<()> {
+        let database = &self.database;
+        loop {
+            let stored_session = Self::to_stored_session(record)?;
+            let now = OffsetDateTime::now_utc().unix_timestamp();
+            let existing = database
+                .get_session(&stored_session.id, now)
+                .map_err(|error| session_store::Error::Backend(error.to_string()))?;
+            if existing.is_some() {
+                tracing::warn!("session id collision");
+                record.id = Id::default();
+                continue;
+            }
+            return database
+                .create_session(&stored_session)
+                .map(|_| ())
+                .map_err(|error| session_store::Error::Backend(error.to_string()));
+        }
+    }
+
+    async fn save(&self, record: &Record) -> session_store::Result<()> {
+        let stored_session = Self::to_stored_session(record)?;
+        self.database
+            .save_session(&stored_session)
+            .map_err(|error| session_store::Error::Backend(error.to_string()))
+    }
+
+    async fn load(&self, session_id: &Id) -> session_store::Result<Option<Record>> {
+        let now = OffsetDateTime::now_utc().unix_timestamp();
+        self.database
+            .get_session(&session_id.to_string(), now)
+            .map_err(|error| session_store::Error::Backend(error.to_string()))?
+            .map(Self::to_record)
+            .transpose()
+    }
+
+    async fn delete(&self, session_id: &Id) -> session_store::Result<()> {
+        self.database
+            .delete_session(&session_id.to_string())
+            .map_err(|error| session_store::Error::Backend(error.to_string()))
+    }
+}
+
+#[async_trait::async_trait]
+impl ExpiredDeletion for DatabaseSessionStore {
+    async fn delete_expired(&self) -> session_store::Result<()> {
+        let now = OffsetDateTime::now_utc().unix_timestamp();
+        let deleted = self
+            .database
+            .delete_expired_sessions(now)
+            .map_err(|error| session_store::Error::Backend(error.to_string()))?;
+        tracing::debug!(?deleted, "deleted expired sessions");
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum AppSessionStore {
+    Memory(MemoryStore),
+    Database(DatabaseSessionStore),
+}
+
+impl AppSessionStore {
+    pub const KINDS: &'static [&'static str] = &["database", "memory"];
+
+    pub fn from_config(kind: &str, database: Arc<dyn Database>) -> Option<Self> {
+        match kind {
+            "memory" => Some(Self::Memory(MemoryStore::default())),
+            "database" => Some(Self::Database(DatabaseSessionStore::new(database))),
+            _ => {
+                tracing::error!(?kind, "unknown session store");
+                None
+            }
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl SessionStore for AppSessionStore {
+    async fn create(&self, record: &mut Record) -> session_store::Result<()> {
+        match self {
+            Self::Memory(store) => store.create(record).await,
+            Self::Database(store) => store.create(record).await,
+        }
+    }
+
+    async fn save(&self, record: &Record) -> session_store::Result<()> {
+        match self {
+            Self::Memory(store) => store.save(record).await,
+            Self::Database(store) => store.save(record).await,
+        }
+    }
+
+    async fn load(&self, session_id: &Id) -> session_store::Result<Option<Record>> {
+        match self {
+            Self::Memory(store) => store.load(session_id).await,
+            Self::Database(store) => store.load(session_id).await,
+        }
+    }
+
+    async fn delete(&self, session_id: &Id) -> session_store::Result<()> {
+        match self {
+            Self::Memory(store) => store.delete(session_id).await,
+            Self::Database(store) => store.delete(session_id).await,
+        }
+    }
+}
@@ -0,0 +1,51 @@
+use std::time::Duration;
+
+#[derive(Clone, Copy, Debug, serde::Serialize)]
+pub struct ShutdownConfig {
+    /// How long connections get to drain before the server exits anyway.
+    #[serde(rename = "timeout_ms", serialize_with = "super::config::milliseconds")]
+    pub timeout: Duration,
+    /// How long readers and writers are told to wait before reconnecting.
+    #[serde(
+        rename = "reconnect_delay_ms",
+        serialize_with = "super::config::milliseconds"
+    )]
+    pub reconnect_delay: Duration,
+}
+
+impl Default for ShutdownConfig {
+    fn default() -> Self {
+        Self {
+            timeout: Duration::from_secs(10),
+            reconnect_delay: Duration::from_secs(2),
+        }
+    }
+}
+
+/// Resolves on SIGTERM or ctrl-c.
+pub async fn signal() {
+    let ctrl_c = async {
+        if let Err(error) = tokio::signal::ctrl_c().await {
+            tracing::error!(?error, "unable to listen for ctrl-c");
+            std::future::pending::<()>().await;
+        }
+    };
+    #[cfg(unix)]
+    let terminate = async {
+        match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate()) {
+            Ok(mut terminate) => {
+                terminate.recv().await;
+            }
+            Err(error) => {
+                tracing::error!(?error, "unable to listen for SIGTERM");
+                std::future::pending::<()>().await;
+            }
+        }
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+    tokio::select! {
+        () = ctrl_c => tracing::info!("received ctrl-c"),
+        () = terminate => tracing::info!("received SIGTERM"),
+    }
+}
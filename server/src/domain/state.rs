@@ -1,11 +1,13 @@
 use std::sync::Arc;
 
 use axum::extract::FromRef;
-use tokio::sync::RwLock;
 
 use crate::twitch::TwitchAuthenticator;
 
-use super::{db::SqliteDbService, ChannelController};
+use super::{
+    db::Database, ChannelController, EventSubService, PermissionService, SubmissionQueue,
+    TwitchTokenStore,
+};
 
 #[derive(Clone)]
 pub struct AssetDirectory(pub String);
@@ -14,7 +16,11 @@ pub struct AssetDirectory(pub String);
 pub struct AppState {
     controller: Arc<ChannelController>,
     twitch_authenticator: Arc<Box<dyn TwitchAuthenticator>>,
-    database: Arc<RwLock<SqliteDbService>>,
+    database: Arc<dyn Database>,
+    token_store: Arc<TwitchTokenStore>,
+    eventsub: Option<Arc<EventSubService>>,
+    permissions: Arc<PermissionService>,
+    submissions: Arc<SubmissionQueue>,
     asset_dir: AssetDirectory,
 }
 
@@ -22,13 +28,23 @@ impl AppState {
     pub fn new(
         controller: Arc<ChannelController>,
         twitch_authenticator: Arc<Box<dyn TwitchAuthenticator>>,
-        database: Arc<RwLock<SqliteDbService>>,
+        database: Arc<dyn Database>,
+        token_store: Arc<TwitchTokenStore>,
+        eventsub: Option<Arc<EventSubService>>,
         asset_dir: String,
     ) -> Self {
         Self {
+            submissions: Arc::new(SubmissionQueue::new(
+                Arc::clone(&database),
+                Arc::clone(&controller),
+                asset_dir.clone(),
+            )),
             controller,
             twitch_authenticator,
+            permissions: Arc::new(PermissionService::new(Arc::clone(&database))),
             database,
+            token_store,
+            eventsub,
             asset_dir: AssetDirectory(asset_dir),
         }
     }
@@ -46,12 +62,36 @@ impl FromRef<AppState> for Arc<Box<dyn TwitchAuthenticator>> {
     }
 }
 
-impl FromRef<AppState> for Arc<RwLock<SqliteDbService>> {
-    fn from_ref(app_state: &AppState) -> Arc<RwLock<SqliteDbService>> {
+impl FromRef<AppState> for Arc<dyn Database> {
+    fn from_ref(app_state: &AppState) -> Arc<dyn Database> {
         Arc::clone(&app_state.database)
     }
 }
 
+impl FromRef<AppState> for Arc<TwitchTokenStore> {
+    fn from_ref(app_state: &AppState) -> Arc<TwitchTokenStore> {
+        Arc::clone(&app_state.token_store)
+    }
+}
+
+impl FromRef<AppState> for Option<Arc<EventSubService>> {
+    fn from_ref(app_state: &AppState) -> Option<Arc<EventSubService>> {
+        app_state.eventsub.clone()
+    }
+}
+
+impl FromRef<AppState> for Arc<PermissionService> {
+    fn from_ref(app_state: &AppState) -> Arc<PermissionService> {
+        Arc::clone(&app_state.permissions)
+    }
+}
+
+impl FromRef<AppState> for Arc<SubmissionQueue> {
+    fn from_ref(app_state: &AppState) -> Arc<SubmissionQueue> {
+        Arc::clone(&app_state.submissions)
+    }
+}
+
 impl FromRef<AppState> for AssetDirectory {
     fn from_ref(app_state: &AppState) -> AssetDirectory {
         app_state.asset_dir.clone()
@@ -0,0 +1,145 @@
+use std::{
+    collections::HashSet,
+    sync::Arc,
+    time::{Duration, SystemTime},
+};
+
+use sha2::{Digest, Sha256};
+
+use crate::models::{Asset, Submission};
+
+use super::{asset_storage::AssetStorage, db::Database};
+
+#[derive(Debug, Default, PartialEq, serde::Serialize)]
+pub struct StorageConsistencyReport {
+    pub orphan_files: Vec<String>,
+    pub missing_files: Vec<String>,
+    pub checksum_mismatches: Vec<String>,
+    pub deleted_orphans: Vec<String>,
+}
+
+impl StorageConsistencyReport {
+    pub fn is_consistent(&self) -> bool {
+        self.orphan_files.is_empty()
+            && self.missing_files.is_empty()
+            && self.checksum_mismatches.is_empty()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct StorageConsistencyChecker {
+    pub grace_period: Duration,
+    pub delete_orphans: bool,
+}
+
+impl StorageConsistencyChecker {
+    pub fn new(grace_period: Duration) -> Self {
+        Self {
+            grace_period,
+            delete_orphans: false,
+        }
+    }
+
+    pub fn with_orphan_deletion(mut self, delete_orphans: bool) -> Self {
+        self.delete_orphans = delete_orphans;
+        self
+    }
+
+    pub fn check(
+        &self,
+        assets: &[Asset],
+        storage: &dyn AssetStorage,
+    ) -> std::io::Result<StorageConsistencyReport> {
+        let mut report = StorageConsistencyReport::default();
+        let stored_assets = storage.list()?;
+        let stored_filenames: HashSet<&str> = stored_assets
+            .iter()
+            .map(|stored| stored.filename.as_str())
+            .collect();
+        let known_filenames: HashSet<&str> = assets
+            .iter()
+            .map(|asset| asset.local_filename.as_str())
+            .collect();
+
+        for asset in assets {
+            if !stored_filenames.contains(asset.local_filename.as_str()) {
+                tracing::warn!(?asset, "asset file missing from storage");
+                report.missing_files.push(asset.local_filename.clone());
+                continue;
+            }
+            let data = storage.read(&asset.local_filename)?;
+            let checksum = format!("{:x}", Sha256::digest(&data));
+            if checksum != asset.checksum {
+                tracing::warn!(?asset, ?checksum, "asset checksum mismatch");
+                report
+                    .checksum_mismatches
+                    .push(asset.local_filename.clone());
+            }
+        }
+
+        let now = SystemTime::now();
+        for stored in &stored_assets {
+            if known_filenames.contains(stored.filename.as_str()) {
+                continue;
+            }
+            tracing::warn!(?stored, "orphaned file in storage");
+            report.orphan_files.push(stored.filename.clone());
+            let age = now.duration_since(stored.modified).unwrap_or_default();
+            if self.delete_orphans && age >= self.grace_period {
+                match storage.delete(&stored.filename) {
+                    Ok(()) => {
+                        tracing::info!(?stored, ?age, "deleted orphaned file");
+                        report.deleted_orphans.push(stored.filename.clone());
+                    }
+                    Err(error) => tracing::error!(?error, ?stored, "unable to delete orphan"),
+                }
+            }
+        }
+
+        report.orphan_files.sort();
+        report.missing_files.sort();
+        report.checksum_mismatches.sort();
+        report.deleted_orphans.sort();
+        Ok(report)
+    }
+
+    pub async fn check_database(
+        &self,
+        database: &dyn Database,
+        storage: Arc<dyn AssetStorage>,
+    ) -> Result<StorageConsistencyReport, Box<dyn std::error::Error + Send + Sync>> {
+        let mut assets = database
+            .get_all_assets()
+            .map_err(|error| error.to_string())?;
+        let pending_submissions = database
+            .get_all_pending_submissions()
+            .map_err(|error| error.to_string())?;
+        assets.extend(pending_submissions.iter().map(Submission::as_asset));
+        let checker = self.clone();
+        let report =
+            tokio::task::spawn_blocking(move || checker.check(&assets, storage.as_ref())).await??;
+        Ok(report)
+    }
+
+    pub async fn run_periodically(
+        self,
+        database: Arc<dyn Database>,
+        storage: Arc<dyn AssetStorage>,
+        interval: Duration,
+    ) {
+        let mut ticker = tokio::time::interval(interval);
+        loop {
+            ticker.tick().await;
+            match self
+                .check_database(database.as_ref(), Arc::clone(&storage))
+                .await
+            {
+                Ok(report) if report.is_consistent() => {
+                    tracing::debug!("asset storage is consistent")
+                }
+                Ok(report) => tracing::warn!(?report, "asset storage inconsistencies found"),
+                Err(error) => tracing::error!(?error, "asset storage check failed"),
+            }
+        }
+    }
+}
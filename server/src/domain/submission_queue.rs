@@ -0,0 +1,209 @@
+use std::sync::Arc;
+
+use axum::http::StatusCode;
+
+use crate::models::{NewSubmission, Submission, SubmissionDecision, UnownedAsset, User};
+
+use super::{
+    db::{Database, DbError},
+    message::{ImgfloatAsset, ImgfloatWriterNotification},
+    ApiError, ChannelController,
+};
+
+#[derive(Debug)]
+pub enum SubmissionError {
+    UnsupportedContentType(String),
+    TooManyPending,
+    AlreadyReviewed,
+    DuplicateAsset,
+    Storage(String),
+    Database(DbError),
+}
+
+impl From<SubmissionError> for ApiError {
+    fn from(error: SubmissionError) -> ApiError {
+        match error {
+            SubmissionError::UnsupportedContentType(content_type) => ApiError::new(
+                StatusCode::UNSUPPORTED_MEDIA_TYPE,
+                "unsupported_media_type",
+                format!("{content_type} cannot be submitted"),
+            ),
+            SubmissionError::TooManyPending => ApiError::new(
+                StatusCode::TOO_MANY_REQUESTS,
+                "too_many_pending",
+                "too many submissions are waiting for review",
+            ),
+            SubmissionError::AlreadyReviewed => {
+                ApiError::conflict("already_reviewed", "submission was already reviewed")
+            }
+            SubmissionError::DuplicateAsset => ApiError::conflict(
+                "duplicate_asset",
+                "this image is already in another channel's library",
+            ),
+            SubmissionError::Storage(message) => ApiError::storage(message),
+            SubmissionError::Database(error) => error.into(),
+        }
+    }
+}
+
+pub struct SubmissionQueue {
+    database: Arc<dyn Database>,
+    controller: Arc<ChannelController>,
+    asset_dir: String,
+}
+
+impl SubmissionQueue {
+    pub const MAX_PENDING_PER_SUBMITTER: i64 = 5;
+
+    pub fn new(
+        database: Arc<dyn Database>,
+        controller: Arc<ChannelController>,
+        asset_dir: impl Into<String>,
+    ) -> Self {
+        Self {
+            database,
+            controller,
+            asset_dir: asset_dir.into(),
+        }
+    }
+
+    pub async fn submit(
+        &self,
+        broadcaster: &User,
+        submitter: &str,
+        field: axum::extract::multipart::Field<'_>,
+    ) -> Result<Submission, SubmissionError> {
+        let content_type = field.content_type().map(str::to_string).unwrap_or_default();
+        if !UnownedAsset::is_supported_content_type(&content_type) {
+            tracing::warn!(
+                ?broadcaster,
+                ?submitter,
+                ?content_type,
+                "unsupported submission"
+            );
+            return Err(SubmissionError::UnsupportedContentType(content_type));
+        }
+        let pending = self
+            .database
+            .count_pending_submissions(broadcaster, submitter)
+            .map_err(SubmissionError::Database)?;
+        if pending >= Self::MAX_PENDING_PER_SUBMITTER {
+            tracing::warn!(
+                ?broadcaster,
+                ?submitter,
+                ?pending,
+                "too many pending submissions"
+            );
+            return Err(SubmissionError::TooManyPending);
+        }
+
+        let asset = UnownedAsset::from_mutlipart(field, self.asset_dir.clone())
+            .await
+            .map_err(|error| SubmissionError::Storage(error.to_string()))?;
+        let local_filename = asset.local_filename.clone();
+        let created =
+            self.database
+                .create_submission(&NewSubmission::new(asset, broadcaster, submitter));
+        let submission = match created {
+            Ok(submission) => submission,
+            Err(error) => {
+                self.remove_file(&local_filename).await;
+                return Err(SubmissionError::Database(error));
+            }
+        };
+        tracing::info!(?submission, "new submission");
+        self.controller
+            .notify_writers(
+                &broadcaster.username,
+                ImgfloatWriterNotification::SubmissionReceived(submission.clone()),
+            )
+            .await;
+        Ok(submission)
+    }
+
+    pub async fn review(
+        &self,
+        broadcaster: &User,
+        submission: &Submission,
+        reviewer: &str,
+        decision: SubmissionDecision,
+    ) -> Result<Submission, SubmissionError> {
+        let status = match decision {
+            SubmissionDecision::Approve | SubmissionDecision::ApproveAndShow => {
+                Submission::APPROVED
+            }
+            SubmissionDecision::Reject => Submission::REJECTED,
+        };
+        let reviewed = self
+            .database
+            .review_submission(submission, reviewer, status)
+            .map_err(|error| match error {
+                DbError::Conflict(_) => SubmissionError::DuplicateAsset,
+                error => SubmissionError::Database(error),
+            })?
+            .ok_or(SubmissionError::AlreadyReviewed)?;
+        tracing::info!(?reviewed, ?decision, "reviewed submission");
+
+        let local_filename = match decision {
+            SubmissionDecision::Reject => reviewed.local_filename.clone(),
+            _ => self.approved_filename(&reviewed).await?,
+        };
+        match decision {
+            SubmissionDecision::Reject => self.remove_file(&reviewed.local_filename).await,
+            SubmissionDecision::ApproveAndShow => {
+                let shown = ImgfloatAsset {
+                    id: uuid::Uuid::new_v4().to_string(),
+                    x: 0.0,
+                    y: 0.0,
+                    w: ImgfloatAsset::DEFAULT_SIZE,
+                    h: ImgfloatAsset::DEFAULT_SIZE,
+                    theta: 0.0,
+                    url: format!("/api/assets/{}/{}", broadcaster.username, local_filename),
+                };
+                self.controller
+                    .show_asset(&broadcaster.username, shown)
+                    .await;
+            }
+            SubmissionDecision::Approve => {}
+        }
+        self.controller
+            .notify_writers(
+                &broadcaster.username,
+                ImgfloatWriterNotification::SubmissionReviewed(reviewed.clone()),
+            )
+            .await;
+        Ok(reviewed)
+    }
+
+    /// The asset an approved submission ended up as. An image the broadcaster
+    /// already had is linked to instead of stored twice.
+    async fn approved_filename(&self, reviewed: &Submission) -> Result<String, SubmissionError> {
+        let asset = self
+            .database
+            .get_asset_by_checksum(&reviewed.checksum)
+            .map_err(SubmissionError::Database)?;
+        match asset {
+            Some(asset) if asset.local_filename != reviewed.local_filename => {
+                tracing::info!(?reviewed, ?asset, "submission duplicates an existing asset");
+                self.remove_file(&reviewed.local_filename).await;
+                Ok(asset.local_filename)
+            }
+            _ => Ok(reviewed.local_filename.clone()),
+        }
+    }
+
+    pub async fn read_file(&self, submission: &Submission) -> Result<Vec<u8>, SubmissionError> {
+        let path = format!("{}/{}", self.asset_dir, submission.local_filename);
+        tokio::fs::read(&path)
+            .await
+            .inspect_err(|error| tracing::error!(?error, ?path, "unable to read submission"))
+            .map_err(|error| SubmissionError::Storage(error.to_string()))
+    }
+
+    async fn remove_file(&self, local_filename: &str) {
+        let path = format!("{}/{}", self.asset_dir, local_filename);
+        let _ = tokio::fs::remove_file(&path)
+            .await
+            .inspect_err(|error| tracing::error!(?error, ?path, "unable to clean up submission"));
+    }
+}
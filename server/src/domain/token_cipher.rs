@@ -0,0 +1,74 @@
+use base64::{engine::general_purpose::STANDARD, Engine};
+use chacha20poly1305::{
+    aead::{Aead, AeadCore, KeyInit, OsRng},
+    ChaCha20Poly1305, Key, Nonce,
+};
+
+#[derive(Debug)]
+pub enum TokenCipherError {
+    InvalidKey,
+    InvalidCiphertext,
+}
+
+#[derive(Clone)]
+pub struct TokenCipher {
+    cipher: ChaCha20Poly1305,
+}
+
+impl std::fmt::Debug for TokenCipher {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("TokenCipher { key: [redacted] }")
+    }
+}
+
+impl TokenCipher {
+    const NONCE_LENGTH: usize = 12;
+
+    pub fn new(key: &[u8]) -> Result<Self, TokenCipherError> {
+        if key.len() != 32 {
+            tracing::error!(
+                key_length = key.len(),
+                "token encryption key must be 32 bytes"
+            );
+            return Err(TokenCipherError::InvalidKey);
+        }
+        Ok(Self {
+            cipher: ChaCha20Poly1305::new(Key::from_slice(key)),
+        })
+    }
+
+    pub fn from_base64(key: &str) -> Result<Self, TokenCipherError> {
+        let key = STANDARD
+            .decode(key.trim())
+            .inspect_err(|error| tracing::error!(?error, "token encryption key is not base64"))
+            .map_err(|_| TokenCipherError::InvalidKey)?;
+        Self::new(&key)
+    }
+
+    pub fn encrypt(&self, plaintext: &str) -> Result<String, TokenCipherError> {
+        let nonce = ChaCha20Poly1305::generate_nonce(&mut OsRng);
+        let ciphertext = self
+            .cipher
+            .encrypt(&nonce, plaintext.as_bytes())
+            .map_err(|_| TokenCipherError::InvalidKey)?;
+        let mut sealed = nonce.to_vec();
+        sealed.extend_from_slice(&ciphertext);
+        Ok(STANDARD.encode(sealed))
+    }
+
+    pub fn decrypt(&self, sealed: &str) -> Result<String, TokenCipherError> {
+        let sealed = STANDARD
+            .decode(sealed)
+            .map_err(|_| TokenCipherError::InvalidCiphertext)?;
+        if sealed.len() < Self::NONCE_LENGTH {
+            return Err(TokenCipherError::InvalidCiphertext);
+        }
+        let (nonce, ciphertext) = sealed.split_at(Self::NONCE_LENGTH);
+        let plaintext = self
+            .cipher
+            .decrypt(Nonce::from_slice(nonce), ciphertext)
+            .inspect_err(|_| tracing::error!("unable to decrypt token"))
+            .map_err(|_| TokenCipherError::InvalidCiphertext)?;
+        String::from_utf8(plaintext).map_err(|_| TokenCipherError::InvalidCiphertext)
+    }
+}
@@ -0,0 +1,204 @@
+use std::{sync::Arc, time::Duration};
+
+use time::OffsetDateTime;
+
+use crate::{
+    models::StoredUserTokens,
+    twitch::{TwitchAuthenticator, TwitchAuthenticatorError, TwitchUserTokens},
+};
+
+use super::{
+    db::{Database, DbError},
+    token_cipher::{TokenCipher, TokenCipherError},
+};
+
+#[derive(Debug)]
+pub enum TokenStoreError {
+    NoTokens,
+    NoRefreshToken,
+    UnexpectedResponse,
+    Cipher(TokenCipherError),
+    Database(DbError),
+    Authenticator(TwitchAuthenticatorError),
+}
+
+pub struct TwitchTokenStore {
+    database: Arc<dyn Database>,
+    authenticator: Arc<Box<dyn TwitchAuthenticator>>,
+    cipher: TokenCipher,
+    refresh_margin: Duration,
+}
+
+impl TwitchTokenStore {
+    pub fn new(
+        database: Arc<dyn Database>,
+        authenticator: Arc<Box<dyn TwitchAuthenticator>>,
+        cipher: TokenCipher,
+    ) -> Self {
+        Self {
+            database,
+            authenticator,
+            cipher,
+            refresh_margin: Duration::from_secs(300),
+        }
+    }
+
+    pub fn with_refresh_margin(mut self, refresh_margin: Duration) -> Self {
+        self.refresh_margin = refresh_margin;
+        self
+    }
+
+    pub async fn store(
+        &self,
+        username: &str,
+        tokens: &TwitchUserTokens,
+    ) -> Result<(), TokenStoreError> {
+        let stored_tokens = self.seal(username, tokens)?;
+        self.database
+            .save_user_tokens(&stored_tokens)
+            .map_err(TokenStoreError::Database)
+    }
+
+    pub async fn get_tokens(&self, username: &str) -> Result<TwitchUserTokens, TokenStoreError> {
+        let stored_tokens = self
+            .database
+            .get_user_tokens(username)
+            .map_err(TokenStoreError::Database)?
+            .ok_or(TokenStoreError::NoTokens)?;
+        if self.is_expiring(&stored_tokens) {
+            tracing::debug!(?username, "refreshing expiring tokens");
+            return self.refresh(stored_tokens).await;
+        }
+        self.open(&stored_tokens)
+    }
+
+    pub async fn helix_get(
+        &self,
+        username: &str,
+        path_and_query: &str,
+    ) -> Result<serde_json::Value, TokenStoreError> {
+        let tokens = self.get_tokens(username).await?;
+        self.authenticator
+            .helix_get(&tokens, path_and_query)
+            .await
+            .map_err(TokenStoreError::Authenticator)
+    }
+
+    pub async fn user_id(&self, username: &str) -> Result<String, TokenStoreError> {
+        self.helix_get(username, "users")
+            .await?
+            .pointer("/data/0/id")
+            .and_then(|id| id.as_str())
+            .map(str::to_string)
+            .ok_or(TokenStoreError::UnexpectedResponse)
+    }
+
+    pub async fn forget(&self, username: &str) -> Result<(), TokenStoreError> {
+        self.database
+            .delete_user_tokens(username)
+            .map_err(TokenStoreError::Database)
+    }
+
+    pub async fn refresh_expiring(&self) -> Result<usize, TokenStoreError> {
+        let before =
+            OffsetDateTime::now_utc().unix_timestamp() + self.refresh_margin.as_secs() as i64;
+        let expiring_tokens = self
+            .database
+            .get_expiring_user_tokens(before)
+            .map_err(TokenStoreError::Database)?;
+        let mut refreshed = 0;
+        for stored_tokens in expiring_tokens {
+            let username = stored_tokens.username.clone();
+            match self.refresh(stored_tokens).await {
+                Ok(_) => refreshed += 1,
+                Err(error) => tracing::warn!(?username, ?error, "unable to refresh tokens"),
+            }
+        }
+        Ok(refreshed)
+    }
+
+    pub async fn run_refresh(self: Arc<Self>, period: Duration) {
+        let mut interval = tokio::time::interval(period);
+        loop {
+            interval.tick().await;
+            match self.refresh_expiring().await {
+                Ok(refreshed) => tracing::debug!(?refreshed, "refreshed twitch tokens"),
+                Err(error) => tracing::error!(?error, "unable to refresh twitch tokens"),
+            }
+        }
+    }
+
+    async fn refresh(
+        &self,
+        stored_tokens: StoredUserTokens,
+    ) -> Result<TwitchUserTokens, TokenStoreError> {
+        let current_tokens = self.open(&stored_tokens)?;
+        let refresh_token = current_tokens
+            .refresh_token
+            .as_deref()
+            .ok_or(TokenStoreError::NoRefreshToken)?;
+        let mut tokens = self
+            .authenticator
+            .refresh_tokens(refresh_token)
+            .await
+            .map_err(TokenStoreError::Authenticator)?;
+        if tokens.refresh_token.is_none() {
+            tokens.refresh_token = current_tokens.refresh_token;
+        }
+        self.store(&stored_tokens.username, &tokens).await?;
+        Ok(tokens)
+    }
+
+    fn is_expiring(&self, stored_tokens: &StoredUserTokens) -> bool {
+        let now = OffsetDateTime::now_utc().unix_timestamp();
+        stored_tokens.expires_at <= now + self.refresh_margin.as_secs() as i64
+    }
+
+    fn seal(
+        &self,
+        username: &str,
+        tokens: &TwitchUserTokens,
+    ) -> Result<StoredUserTokens, TokenStoreError> {
+        let refresh_token = tokens
+            .refresh_token
+            .as_deref()
+            .map(|refresh_token| self.cipher.encrypt(refresh_token))
+            .transpose()
+            .map_err(TokenStoreError::Cipher)?;
+        Ok(StoredUserTokens {
+            username: username.to_string(),
+            access_token: self
+                .cipher
+                .encrypt(&tokens.access_token)
+                .map_err(TokenStoreError::Cipher)?,
+            refresh_token,
+            expires_at: OffsetDateTime::now_utc().unix_timestamp() + tokens.expires_in as i64,
+            scope: tokens.scope.join(" "),
+            token_type: tokens.token_type.clone(),
+        })
+    }
+
+    fn open(&self, stored_tokens: &StoredUserTokens) -> Result<TwitchUserTokens, TokenStoreError> {
+        let refresh_token = stored_tokens
+            .refresh_token
+            .as_deref()
+            .map(|refresh_token| self.cipher.decrypt(refresh_token))
+            .transpose()
+            .map_err(TokenStoreError::Cipher)?;
+        let now = OffsetDateTime::now_utc().unix_timestamp();
+        Ok(TwitchUserTokens {
+            access_token: self
+                .cipher
+                .decrypt(&stored_tokens.access_token)
+                .map_err(TokenStoreError::Cipher)?,
+            refresh_token,
+            expires_in: (stored_tokens.expires_at - now).max(0) as u64,
+            scope: stored_tokens
+                .scope
+                .split_whitespace()
+                .map(str::to_string)
+                .collect(),
+            token_type: stored_tokens.token_type.clone(),
+        })
+    }
+}
@@ -1,15 +1,21 @@
 #![feature(duration_constructors)]
-use std::{net::SocketAddr, sync::Arc};
+use std::{future::IntoFuture, net::SocketAddr, sync::Arc};
 
 use axum::{
-    routing::{get, post, put},
+    extract::DefaultBodyLimit,
+    routing::{delete, get, post, put},
     Router,
 };
-use domain::{db::SqliteDbService, middleware::log_requests, AppState, ChannelController};
+use domain::{
+    db::Database,
+    middleware::{authenticate_api_token, log_requests},
+    rate_limit::rate_limit,
+    shutdown, AppSessionStore, AppState, ChannelController, EventSubService, RateLimitConfig,
+    RateLimiter, ShutdownConfig, TwitchTokenStore,
+};
 use time::Duration;
-use tokio::sync::RwLock;
 use tower_http::services::{ServeDir, ServeFile};
-use tower_sessions::{cookie::SameSite, Expiry, MemoryStore, SessionManagerLayer};
+use tower_sessions::{cookie::SameSite, Expiry, SessionManagerLayer};
 use twitch::TwitchAuthenticator;
 
 pub mod domain;
@@ -18,53 +24,192 @@ pub mod routes;
 pub mod twitch;
 
 pub async fn run(
-    twitch_authenticator: Box<dyn TwitchAuthenticator>,
-    controller: ChannelController,
-    database: RwLock<SqliteDbService>,
+    twitch_authenticator: Arc<Box<dyn TwitchAuthenticator>>,
+    controller: Arc<ChannelController>,
+    database: Arc<dyn Database>,
+    token_store: Arc<TwitchTokenStore>,
+    eventsub: Option<Arc<EventSubService>>,
+    session_store: AppSessionStore,
+    rate_limits: RateLimitConfig,
+    shutdown_config: ShutdownConfig,
     asset_dir: String,
     static_dir: String,
     not_found_page: String,
     host: impl std::fmt::Display,
     port: impl std::fmt::Display,
-) {
-    let session_store = MemoryStore::default();
+) -> std::io::Result<()> {
+    controller.restore_saved_states(database.as_ref()).await;
     let session_layer = SessionManagerLayer::new(session_store)
         .with_secure(cfg!(debug_assertions))
         .with_same_site(SameSite::Strict)
         .with_expiry(Expiry::OnInactivity(Duration::days(7)));
+    let authenticate =
+        axum::middleware::from_fn_with_state(Arc::clone(&database), authenticate_api_token);
+    let eventsub_routes = match &eventsub {
+        Some(eventsub) => Router::new()
+            .route("/api/eventsub", post(routes::api::eventsub::post))
+            .with_state(Arc::clone(eventsub)),
+        None => Router::new(),
+    };
     let app_state = AppState::new(
-        Arc::new(controller),
-        Arc::new(twitch_authenticator),
-        Arc::new(database),
+        Arc::clone(&controller),
+        twitch_authenticator,
+        Arc::clone(&database),
+        token_store,
+        eventsub,
         asset_dir,
     );
+    let upload_limiter = Arc::new(RateLimiter::new("uploads", rate_limits.uploads));
+    let socket_limiter = Arc::new(RateLimiter::new("sockets", rate_limits.sockets));
+    for limiter in [&upload_limiter, &socket_limiter] {
+        tokio::spawn(Arc::clone(limiter).run_cleanup(std::time::Duration::from_secs(300)));
+    }
+    let limit_uploads = axum::middleware::from_fn_with_state(upload_limiter, rate_limit);
+    let limit_sockets = axum::middleware::from_fn_with_state(socket_limiter, rate_limit);
     let static_dir = ServeDir::new(static_dir).not_found_service(ServeFile::new(not_found_page));
     let app = Router::new()
+        .route("/api/openapi.json", get(routes::api::openapi::get))
         .route("/api/whoami", get(routes::api::whoami::get))
+        .route(
+            "/api/tokens",
+            get(routes::api::api_token::get).post(routes::api::api_token::post),
+        )
+        .route("/api/tokens/:id", delete(routes::api::api_token::delete))
         .route("/api/assets/:username", get(routes::api::asset::get))
-        .route("/api/assets/:username", post(routes::api::asset::post))
+        .route(
+            "/api/assets/:username",
+            post(routes::api::asset::post).layer(limit_uploads.clone()),
+        )
         .route(
             "/api/assets/:username/:filename",
             get(routes::api::asset::file),
         )
+        .route(
+            "/api/assets/:username/:filename/metadata",
+            put(routes::api::asset::metadata),
+        )
+        .route(
+            "/api/archive/:username",
+            get(routes::api::archive::get)
+                .post(routes::api::archive::post)
+                .layer(DefaultBodyLimit::max(
+                    routes::api::archive::MAX_ARCHIVE_SIZE,
+                )),
+        )
+        .route(
+            "/api/channels/:username/assets",
+            get(routes::api::scene::get).post(routes::api::scene::post),
+        )
+        .route(
+            "/api/channels/:username/assets/:id",
+            put(routes::api::scene::put).delete(routes::api::scene::delete),
+        )
+        .route("/api/folders/:username", get(routes::api::folder::get))
+        .route("/api/tags/:username", get(routes::api::tag::get))
+        .route("/api/triggers/:username", get(routes::api::trigger::get))
+        .route("/api/triggers/:username", post(routes::api::trigger::post))
+        .route(
+            "/api/triggers/:username/:id",
+            delete(routes::api::trigger::delete),
+        )
+        .route(
+            "/api/submissions/:username",
+            get(routes::api::submission::get),
+        )
+        .route(
+            "/api/submissions/:username",
+            post(routes::api::submission::post).layer(limit_uploads),
+        )
+        .route(
+            "/api/submissions/:username/:id",
+            post(routes::api::submission::review),
+        )
+        .route(
+            "/api/submissions/:username/:id/file",
+            get(routes::api::submission::file),
+        )
+        .route(
+            "/api/channel-admins",
+            get(routes::api::channel_admin::get).post(routes::api::channel_admin::post),
+        )
+        .route(
+            "/api/channel-admins/sync",
+            post(routes::api::channel_admin::sync),
+        )
+        .route(
+            "/api/channel-admins/:username",
+            put(routes::api::channel_admin::put).delete(routes::api::channel_admin::delete),
+        )
         .route("/api/settings", get(routes::api::settings::get))
         .route("/api/settings", put(routes::api::settings::put))
+        .route(
+            "/api/settings/overlay-token",
+            get(routes::api::overlay_token::get)
+                .post(routes::api::overlay_token::post)
+                .delete(routes::api::overlay_token::delete),
+        )
         .route("/auth/login", get(routes::auth::login::get))
         .route("/auth/logout", get(routes::auth::logout::get))
         .route("/auth/callback", get(routes::auth::callback::get))
-        .route("/ws/read/:username", get(routes::ws::read::get))
-        .route("/ws/write/:username", get(routes::ws::write::get))
+        .route(
+            "/ws/read/:username",
+            get(routes::ws::read::get).layer(limit_sockets.clone()),
+        )
+        .route(
+            "/ws/write/:username",
+            get(routes::ws::write::get).layer(limit_sockets),
+        )
+        .merge(eventsub_routes)
         .fallback_service(static_dir)
         .with_state(app_state)
+        .layer(authenticate)
         .layer(axum::middleware::from_fn(log_requests))
         .layer(session_layer);
     let address = format!("{host}:{port}");
     tracing::info!(?address, "binding socket");
-    let listener = tokio::net::TcpListener::bind(address).await.unwrap();
-    axum::serve(
-        listener,
-        app.into_make_service_with_connect_info::<SocketAddr>(),
-    )
-    .await
-    .unwrap();
+    let listener = tokio::net::TcpListener::bind(&address)
+        .await
+        .inspect_err(|error| tracing::error!(?error, ?address, "unable to bind socket"))?;
+    let (stop, stopped) = tokio::sync::oneshot::channel::<()>();
+    let server = tokio::spawn(
+        axum::serve(
+            listener,
+            app.into_make_service_with_connect_info::<SocketAddr>(),
+        )
+        .with_graceful_shutdown(async {
+            stopped.await.ok();
+        })
+        .into_future(),
+    );
+
+    shutdown::signal().await;
+    tracing::info!(?shutdown_config, "shutting down");
+    stop.send(()).ok();
+    // upgraded sockets outlive the server's own connection tracking, so they
+    // are drained through the controller
+    let drained = tokio::time::timeout(shutdown_config.timeout, async {
+        controller.shutdown(shutdown_config.reconnect_delay).await;
+        server.await
+    })
+    .await;
+    let result = match drained {
+        Ok(Ok(Ok(()))) => {
+            tracing::info!("connections drained");
+            Ok(())
+        }
+        Ok(Ok(Err(error))) => {
+            tracing::error!(?error, "server error during shutdown");
+            Err(error)
+        }
+        Ok(Err(error)) => {
+            tracing::error!(?error, "server task failed");
+            Err(std::io::Error::other(error))
+        }
+        Err(_) => {
+            tracing::warn!("shutdown timed out with connections still open");
+            Ok(())
+        }
+    };
+    controller.save_states(database.as_ref()).await;
+    result
 }
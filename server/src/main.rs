@@ -1,8 +1,16 @@
+use std::{process::ExitCode, sync::Arc, time::Duration};
+
+use clap::Parser;
 use dotenvy::dotenv;
-use imgfloat::domain::db::SqliteDbService;
-use imgfloat::domain::{ChannelController, EnvVar};
-use imgfloat::twitch::{TwitchAuthenticator, TwitchCredentials, TwitchHttpAuthenticator};
-use tokio::sync::RwLock;
+use imgfloat::domain::backplane;
+use imgfloat::domain::cli::{Cli, Command};
+use imgfloat::domain::db::{Database, SqlDbService};
+use imgfloat::domain::{
+    AppSessionStore, AssetStorage, ChannelController, ChatBot, ChatConfig, Config, ConfigErrors,
+    ConfigSource, EnvVar, EventSubService, FilesystemAssetStorage, ModeratorSync,
+    StorageConsistencyChecker, TokenCipher, TwitchTokenStore,
+};
+use imgfloat::twitch::{HelixEventSubClient, TwitchAuthenticator, TwitchHttpAuthenticator};
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt, EnvFilter};
 
 const LOG_CONFIG: &'static [&'static str] = &[
@@ -16,7 +24,8 @@ const LOG_CONFIG: &'static [&'static str] = &[
 ];
 
 #[tokio::main]
-async fn main() {
+async fn main() -> ExitCode {
+    let cli = Cli::parse();
     dotenv().ok();
     tracing_subscriber::registry()
         .with(tracing_subscriber::fmt::layer())
@@ -29,46 +38,211 @@ async fn main() {
         tracing::debug!(?git_sha, "version");
     }
 
-    let http_host = EnvVar::new("HTTP_HOST")
-        .with_default_value("127.0.0.1")
-        .unwrap();
-    let http_port = EnvVar::new("HTTP_PORT").with_default_value("3000").unwrap();
-    let client_id = EnvVar::new("TWITCH_CLIENT_ID").unwrap();
-    let client_secret = EnvVar::new("TWITCH_CLIENT_SECRET").unwrap();
-    let redirect_uri = EnvVar::new("TWITCH_REDIRECT_URI").unwrap();
-    let database_url = EnvVar::new("DATABASE_URL").ensure_file().unwrap();
-    let asset_dir = EnvVar::new("ASSET_DIRECTORY").ensure_directory().unwrap();
-    let static_dir = EnvVar::new("STATIC_DIRECTORY").ensure_directory().unwrap();
-    let not_found_page = EnvVar::new("NOT_FOUND_PAGE").ensure_file().unwrap();
+    let config = ConfigSource::from_environment(cli.config.as_deref())
+        .map_err(ConfigErrors::from)
+        .and_then(|source| Config::load(&source));
+    let config = match config {
+        Ok(config) => config,
+        Err(ConfigErrors(errors)) => {
+            for error in &errors {
+                tracing::error!(%error, "invalid setting");
+            }
+            tracing::error!(errors = errors.len(), "refusing to start");
+            return ExitCode::FAILURE;
+        }
+    };
+    if cli.print_config {
+        return match config.redacted() {
+            Ok(config) => {
+                print!("{config}");
+                ExitCode::SUCCESS
+            }
+            Err(error) => {
+                tracing::error!(%error, "unable to print config");
+                ExitCode::FAILURE
+            }
+        };
+    }
 
-    let twitch_credentials = TwitchCredentials {
-        client_id,
-        client_secret,
-        redirect_uri,
+    let database_url = config.database_url.clone();
+    if cli.no_migrate && !SqlDbService::is_postgres_url(&database_url) {
+        let database_file = EnvVar::from_value("DATABASE_URL", Some(database_url.clone()))
+            .ensure_file()
+            .into_result();
+        if let Err(error) = database_file {
+            tracing::error!(%error, "refusing to start");
+            return ExitCode::FAILURE;
+        }
+    }
+    let asset_dir = config.asset_directory.clone();
+    let db_service = match SqlDbService::new(&database_url) {
+        Ok(db_service) => db_service,
+        Err(error) => {
+            tracing::error!(%error, "refusing to start");
+            return ExitCode::FAILURE;
+        }
     };
-    let twitch_authenticator: Box<dyn TwitchAuthenticator> =
-        Box::new(TwitchHttpAuthenticator::new(
-            "https://id.twitch.tv/oauth2",
-            "https://api.twitch.tv/helix",
-            twitch_credentials,
+    tracing::debug!("connected to database");
+    let schema = if cli.no_migrate {
+        db_service.check_schema().map(|()| vec![])
+    } else {
+        db_service.migrate()
+    };
+    match schema {
+        Ok(applied) => tracing::info!(?applied, "database schema is current"),
+        Err(error) => {
+            tracing::error!(%error, "refusing to start");
+            return ExitCode::FAILURE;
+        }
+    }
+    let database: Arc<dyn Database> = Arc::new(db_service);
+    let asset_storage: Arc<dyn AssetStorage> = Arc::new(FilesystemAssetStorage::new(&asset_dir));
+
+    if let Some(Command::CheckStorage {
+        delete_orphans,
+        grace_period,
+    }) = cli.command
+    {
+        let checker = StorageConsistencyChecker::new(Duration::from_secs(grace_period))
+            .with_orphan_deletion(delete_orphans);
+        return match checker
+            .check_database(database.as_ref(), asset_storage)
+            .await
+        {
+            Ok(report) => {
+                println!("{}", serde_json::to_string_pretty(&report).unwrap());
+                if report.is_consistent() {
+                    ExitCode::SUCCESS
+                } else {
+                    ExitCode::FAILURE
+                }
+            }
+            Err(error) => {
+                tracing::error!(?error, "storage check failed");
+                ExitCode::FAILURE
+            }
+        };
+    }
+
+    let twitch = &config.twitch;
+    let twitch_credentials = twitch.credentials();
+    let twitch_authenticator: Arc<Box<dyn TwitchAuthenticator>> =
+        Arc::new(Box::new(TwitchHttpAuthenticator::new(
+            &twitch.oauth_url,
+            &twitch.helix_url,
+            twitch_credentials.clone(),
+        )));
+    let token_cipher = match TokenCipher::from_base64(&config.token_encryption_key) {
+        Ok(token_cipher) => token_cipher,
+        Err(error) => {
+            tracing::error!(?error, "refusing to start");
+            return ExitCode::FAILURE;
+        }
+    };
+    let token_store = Arc::new(TwitchTokenStore::new(
+        Arc::clone(&database),
+        Arc::clone(&twitch_authenticator),
+        token_cipher,
+    ));
+    tokio::spawn(
+        Arc::clone(&token_store).run_refresh(Duration::from_secs(config.token_refresh_interval)),
+    );
+    let moderator_sync = ModeratorSync::new(Arc::clone(&database), Arc::clone(&token_store));
+    tokio::spawn(
+        moderator_sync.run_periodically(Duration::from_secs(config.moderator_sync_interval)),
+    );
+    let backplane = match backplane::connect(&config.backplane_url).await {
+        Ok(backplane) => backplane,
+        Err(error) => {
+            tracing::error!(%error, "refusing to start");
+            return ExitCode::FAILURE;
+        }
+    };
+    let controller = Arc::new(
+        ChannelController::new()
+            .with_writer_rate_limit(config.rate_limits.writer_messages)
+            .with_heartbeat(config.heartbeat)
+            .with_backplane(backplane),
+    );
+    let eventsub = if twitch.eventsub_enabled() {
+        Some(Arc::new(EventSubService::new(
+            Arc::clone(&database),
+            Arc::clone(&controller),
+            Arc::clone(&token_store),
+            Box::new(HelixEventSubClient::new(
+                &twitch.oauth_url,
+                &twitch.helix_url,
+                twitch_credentials,
+            )),
+            twitch.eventsub_secret.clone(),
+            twitch.eventsub_callback_url.clone(),
+        )))
+    } else {
+        tracing::warn!("no eventsub callback url, event triggers are disabled");
+        None
+    };
+    if config.chat.enabled {
+        let chat_config = ChatConfig {
+            host: config.chat.host.clone(),
+            port: config.chat.port,
+            tls: config.chat.tls,
+            sync_interval: Duration::from_secs(config.chat.sync_interval),
+            reconnect_delay: Duration::from_secs(config.chat.reconnect_delay),
+        };
+        let mut chat_bot =
+            ChatBot::new(Arc::clone(&database), Arc::clone(&controller), chat_config);
+        if !config.chat.bot_username.is_empty() {
+            chat_bot =
+                chat_bot.with_bot_account(Arc::clone(&token_store), &config.chat.bot_username);
+        }
+        tokio::spawn(chat_bot.run());
+    }
+    let Some(session_store) =
+        AppSessionStore::from_config(&config.session_store, Arc::clone(&database))
+    else {
+        tracing::error!(session_store = ?config.session_store, "refusing to start");
+        return ExitCode::FAILURE;
+    };
+    if let AppSessionStore::Database(store) = &session_store {
+        tokio::spawn(
+            store
+                .clone()
+                .run_expiry_cleanup(Duration::from_secs(config.session_cleanup_interval)),
+        );
+    }
+    if config.asset_gc.interval > 0 {
+        let checker =
+            StorageConsistencyChecker::new(Duration::from_secs(config.asset_gc.grace_period))
+                .with_orphan_deletion(config.asset_gc.delete_orphans);
+        tokio::spawn(checker.run_periodically(
+            Arc::clone(&database),
+            asset_storage,
+            Duration::from_secs(config.asset_gc.interval),
         ));
-    let controller = ChannelController::new();
-    let db_service = SqliteDbService::new(&database_url)
-        .inspect(|_| tracing::debug!(?database_url, "connected to database"))
-        .inspect_err(|error| tracing::error!(?error, "error creating db connection"))
-        .unwrap();
-    let database: RwLock<SqliteDbService> = RwLock::new(db_service);
-    tracing::debug!(?static_dir, ?not_found_page, "static assets");
+    }
+    tracing::debug!(static_dir = ?config.static_directory, not_found_page = ?config.not_found_page, "static assets");
     tracing::debug!(?asset_dir, "dynamic assets");
-    imgfloat::run(
+    let served = imgfloat::run(
         twitch_authenticator,
         controller,
         database,
+        token_store,
+        eventsub,
+        session_store,
+        config.rate_limits,
+        config.shutdown,
         asset_dir,
-        static_dir,
-        not_found_page,
-        http_host,
-        http_port,
+        config.static_directory.clone(),
+        config.not_found_page.clone(),
+        &config.http.host,
+        config.http.port,
     )
     .await;
+    match served {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(error) => {
+            tracing::error!(%error, "server failed");
+            ExitCode::FAILURE
+        }
+    }
 }
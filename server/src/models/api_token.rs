@@ -0,0 +1,142 @@
+use diesel::prelude::*;
+
+use super::{secret, Capability, User};
+
+#[derive(Clone, Debug, PartialEq, Identifiable, Queryable, Selectable)]
+#[diesel(table_name = crate::models::schema::api_tokens)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite, diesel::pg::Pg))]
+pub struct ApiToken {
+    pub id: i32,
+    pub username: String,
+    pub name: String,
+    pub token_hash: String,
+    pub scopes: String,
+    pub created_at: i64,
+    pub expires_at: Option<i64>,
+    pub last_used_at: Option<i64>,
+}
+
+impl ApiToken {
+    pub const PREFIX: &'static str = "imgf_";
+
+    pub fn scopes(&self) -> Vec<Capability> {
+        self.scopes
+            .split(',')
+            .filter_map(|scope| scope.parse().ok())
+            .collect()
+    }
+
+    pub fn allows(&self, capability: Capability) -> bool {
+        self.scopes().contains(&capability)
+    }
+
+    pub fn is_expired(&self, now: i64) -> bool {
+        self.expires_at.is_some_and(|expires_at| expires_at <= now)
+    }
+}
+
+#[derive(Debug, Insertable)]
+#[diesel(table_name = crate::models::schema::api_tokens)]
+pub struct NewApiToken {
+    pub username: String,
+    pub name: String,
+    pub token_hash: String,
+    pub scopes: String,
+    pub created_at: i64,
+    pub expires_at: Option<i64>,
+}
+
+#[derive(Debug, PartialEq, serde::Deserialize, utoipa::ToSchema)]
+pub struct UnownedApiToken {
+    pub name: String,
+    pub scopes: Vec<Capability>,
+    pub expires_in_days: Option<u32>,
+}
+
+#[derive(Debug)]
+pub struct ValidatedApiToken(UnownedApiToken);
+
+impl UnownedApiToken {
+    pub const MAX_NAME_LENGTH: usize = 64;
+    pub const MAX_EXPIRY_DAYS: u32 = 365;
+
+    pub fn validate(mut self) -> Result<ValidatedApiToken, String> {
+        self.name = self.name.trim().to_string();
+        if self.name.is_empty() || self.name.len() > Self::MAX_NAME_LENGTH {
+            tracing::error!(name = ?self.name, "invalid api token name");
+            return Err("invalid name".to_string());
+        }
+        self.scopes.sort_by_key(Capability::as_str);
+        self.scopes.dedup();
+        if self.scopes.is_empty() {
+            tracing::error!("api token without scopes");
+            return Err("at least one scope is required".to_string());
+        }
+        if self
+            .expires_in_days
+            .is_some_and(|days| days == 0 || days > Self::MAX_EXPIRY_DAYS)
+        {
+            tracing::error!(expires_in_days = ?self.expires_in_days, "invalid api token expiry");
+            return Err(format!(
+                "expiry must be between 1 and {} days",
+                Self::MAX_EXPIRY_DAYS
+            ));
+        }
+        Ok(ValidatedApiToken(self))
+    }
+}
+
+impl ValidatedApiToken {
+    pub fn with_owner(self, owner: &User) -> (NewApiToken, String) {
+        let UnownedApiToken {
+            name,
+            scopes,
+            expires_in_days,
+        } = self.0;
+        let token = format!("{}{}", ApiToken::PREFIX, secret::generate());
+        let now = time::OffsetDateTime::now_utc().unix_timestamp();
+        let new_token = NewApiToken {
+            username: owner.username.clone(),
+            name,
+            token_hash: secret::hash(&token),
+            scopes: scopes
+                .iter()
+                .map(Capability::as_str)
+                .collect::<Vec<_>>()
+                .join(","),
+            created_at: now,
+            expires_at: expires_in_days.map(|days| now + i64::from(days) * 86400),
+        };
+        (new_token, token)
+    }
+}
+
+#[derive(Debug, PartialEq, serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
+pub struct UserFacingApiToken {
+    pub id: i32,
+    pub name: String,
+    pub scopes: Vec<Capability>,
+    pub created_at: i64,
+    pub expires_at: Option<i64>,
+    pub last_used_at: Option<i64>,
+}
+
+impl From<&ApiToken> for UserFacingApiToken {
+    fn from(value: &ApiToken) -> Self {
+        Self {
+            id: value.id,
+            name: value.name.clone(),
+            scopes: value.scopes(),
+            created_at: value.created_at,
+            expires_at: value.expires_at,
+            last_used_at: value.last_used_at,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
+pub struct IssuedApiToken {
+    pub token: String,
+    #[serde(flatten)]
+    pub api_token: UserFacingApiToken,
+}
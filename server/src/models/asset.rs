@@ -5,32 +5,54 @@ use uuid::Uuid;
 
 use super::User;
 
-#[derive(Queryable, Selectable, Insertable)]
+#[derive(Clone, Debug, PartialEq, Queryable, Selectable, Insertable)]
 #[diesel(table_name = crate::models::schema::assets)]
-#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite, diesel::pg::Pg))]
 pub struct Asset {
     pub local_filename: String,
     pub original_filename: String,
     pub checksum: String,
     pub content_type: String,
     pub username: String,
+    pub folder_id: Option<i32>,
+    pub uploaded_at: i64,
 }
 
-#[derive(serde::Serialize)]
+#[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
 pub struct UserFacingAsset {
     pub filename: String,
+    pub original_filename: String,
     pub content_type: String,
+    pub folder: Option<String>,
+    pub tags: Vec<String>,
+    pub uploaded_at: i64,
 }
 
 impl From<Asset> for UserFacingAsset {
     fn from(value: Asset) -> Self {
         Self {
             filename: value.local_filename,
+            original_filename: value.original_filename,
             content_type: value.content_type,
+            folder: None,
+            tags: vec![],
+            uploaded_at: value.uploaded_at,
         }
     }
 }
 
+impl UserFacingAsset {
+    pub fn with_folder(mut self, folder: Option<String>) -> Self {
+        self.folder = folder;
+        self
+    }
+
+    pub fn with_tags(mut self, tags: Vec<String>) -> Self {
+        self.tags = tags;
+        self
+    }
+}
+
 #[derive(Debug)]
 pub struct UnownedAsset {
     pub local_filename: String,
@@ -40,11 +62,44 @@ pub struct UnownedAsset {
 }
 
 impl UnownedAsset {
+    /// Raster formats the overlay can draw. Anything else, SVG in particular,
+    /// could run script when it is served back from our origin.
+    pub const SUPPORTED_CONTENT_TYPES: &'static [&'static str] = &[
+        "image/png",
+        "image/jpeg",
+        "image/gif",
+        "image/webp",
+        "image/avif",
+        "image/bmp",
+    ];
+
+    pub fn is_supported_content_type(content_type: &str) -> bool {
+        Self::SUPPORTED_CONTENT_TYPES.contains(&content_type)
+    }
+
+    /// Longest original filename we keep, in characters.
+    pub const MAX_FILENAME_LENGTH: usize = 255;
+
+    /// Reduces a client supplied filename to a plain name that is safe to
+    /// show: no directories, no control or markup characters, bounded length.
+    pub fn sanitize_filename(filename: &str) -> String {
+        let basename = filename.rsplit(['/', '\\']).next().unwrap_or_default();
+        let sanitized: String = basename
+            .chars()
+            .filter(|c| !c.is_control() && !matches!(c, '"' | '\'' | '`' | '<' | '>' | '&'))
+            .take(Self::MAX_FILENAME_LENGTH)
+            .collect();
+        match sanitized.trim() {
+            "" => "unknown".to_string(),
+            trimmed => trimmed.to_string(),
+        }
+    }
+
     pub async fn from_mutlipart(
         field: axum::extract::multipart::Field<'_>,
         asset_dir: String,
     ) -> Result<Self, Box<dyn std::error::Error>> {
-        let original_filename = field.file_name().unwrap_or("unknown").to_string();
+        let original_filename = Self::sanitize_filename(field.file_name().unwrap_or_default());
         let content_type = field
             .content_type()
             .map(|mime| mime.to_string())
@@ -90,6 +145,8 @@ impl UnownedAsset {
             checksum: self.checksum,
             content_type: self.content_type,
             username: owner.username.clone(),
+            folder_id: None,
+            uploaded_at: time::OffsetDateTime::now_utc().unix_timestamp(),
         }
     }
 }
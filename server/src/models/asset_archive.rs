@@ -0,0 +1,69 @@
+use crate::domain::message::ImgfloatState;
+
+use super::{Asset, ValidatedAssetMetadata};
+
+#[derive(Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct AssetArchiveEntry {
+    pub filename: String,
+    pub original_filename: String,
+    pub content_type: String,
+    pub checksum: String,
+    pub uploaded_at: i64,
+    pub folder: Option<String>,
+    pub tags: Vec<String>,
+}
+
+impl AssetArchiveEntry {
+    pub fn new(asset: Asset, metadata: ValidatedAssetMetadata) -> Self {
+        Self {
+            filename: asset.local_filename,
+            original_filename: asset.original_filename,
+            content_type: asset.content_type,
+            checksum: asset.checksum,
+            uploaded_at: asset.uploaded_at,
+            folder: metadata.folder,
+            tags: metadata.tags,
+        }
+    }
+
+    pub fn metadata(&self) -> ValidatedAssetMetadata {
+        ValidatedAssetMetadata {
+            folder: self.folder.clone(),
+            tags: self.tags.clone(),
+        }
+    }
+}
+
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+pub struct AssetArchiveManifest {
+    pub version: u32,
+    pub username: String,
+    pub exported_at: i64,
+    pub assets: Vec<AssetArchiveEntry>,
+    #[serde(default)]
+    pub scenes: Vec<ImgfloatState>,
+}
+
+impl AssetArchiveManifest {
+    pub const VERSION: u32 = 1;
+    pub const FILENAME: &'static str = "manifest.json";
+    pub const ASSET_PREFIX: &'static str = "assets/";
+
+    pub fn new(username: &str, assets: Vec<AssetArchiveEntry>, scenes: Vec<ImgfloatState>) -> Self {
+        Self {
+            version: Self::VERSION,
+            username: username.to_string(),
+            exported_at: time::OffsetDateTime::now_utc().unix_timestamp(),
+            assets,
+            scenes,
+        }
+    }
+}
+
+#[derive(Debug, Default, PartialEq, serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
+pub struct AssetImportSummary {
+    pub imported: Vec<String>,
+    pub duplicates: Vec<String>,
+    pub conflicts: Vec<String>,
+    pub scenes_restored: usize,
+}
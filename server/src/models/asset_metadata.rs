@@ -0,0 +1,46 @@
+#[derive(Debug, Eq, PartialEq, serde::Deserialize, utoipa::ToSchema)]
+pub struct UnownedAssetMetadata {
+    pub folder: Option<String>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+}
+
+#[derive(
+    Clone, Debug, Default, Eq, PartialEq, serde::Serialize, serde::Deserialize, utoipa::ToSchema,
+)]
+pub struct ValidatedAssetMetadata {
+    pub folder: Option<String>,
+    pub tags: Vec<String>,
+}
+
+impl UnownedAssetMetadata {
+    pub const MAX_NAME_LENGTH: usize = 64;
+    pub const MAX_TAGS: usize = 32;
+
+    pub fn validate(self) -> Result<ValidatedAssetMetadata, String> {
+        let folder = match self.folder.as_deref().map(str::trim) {
+            Some("") | None => None,
+            Some(folder) => Some(Self::validate_name(folder)?),
+        };
+        let mut tags = Vec::with_capacity(self.tags.len());
+        for tag in &self.tags {
+            let tag = Self::validate_name(&tag.trim().to_lowercase())?;
+            if !tags.contains(&tag) {
+                tags.push(tag);
+            }
+        }
+        if tags.len() > Self::MAX_TAGS {
+            tracing::error!(tag_count = tags.len(), "too many tags");
+            return Err(format!("at most {} tags are allowed", Self::MAX_TAGS));
+        }
+        Ok(ValidatedAssetMetadata { folder, tags })
+    }
+
+    fn validate_name(name: &str) -> Result<String, String> {
+        if name.is_empty() || name.chars().count() > Self::MAX_NAME_LENGTH {
+            tracing::error!(?name, "invalid folder or tag name");
+            return Err(name.to_string());
+        }
+        Ok(name.to_string())
+    }
+}
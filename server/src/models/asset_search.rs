@@ -0,0 +1,58 @@
+use super::UserFacingAsset;
+
+#[derive(Debug, Default, serde::Deserialize, utoipa::IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct AssetSearchQuery {
+    pub name: Option<String>,
+    pub tag: Option<String>,
+    pub folder: Option<String>,
+    pub content_type: Option<String>,
+    pub uploaded_after: Option<i64>,
+    pub uploaded_before: Option<i64>,
+    pub page: Option<u32>,
+    pub per_page: Option<u32>,
+}
+
+impl AssetSearchQuery {
+    pub const DEFAULT_PER_PAGE: u32 = 50;
+    pub const MAX_PER_PAGE: u32 = 200;
+
+    pub fn page(&self) -> u32 {
+        self.page.unwrap_or(1).max(1)
+    }
+
+    pub fn per_page(&self) -> u32 {
+        self.per_page
+            .unwrap_or(Self::DEFAULT_PER_PAGE)
+            .clamp(1, Self::MAX_PER_PAGE)
+    }
+
+    pub fn offset(&self) -> i64 {
+        i64::from(self.page() - 1) * i64::from(self.per_page())
+    }
+
+    pub fn name_pattern(&self) -> Option<String> {
+        let name = self.name.as_ref()?;
+        Some(format!("%{}%", escape_like(name)))
+    }
+
+    pub fn content_type_pattern(&self) -> Option<String> {
+        let content_type = self.content_type.as_ref()?;
+        Some(format!("{}%", escape_like(content_type)))
+    }
+}
+
+fn escape_like(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('%', "\\%")
+        .replace('_', "\\_")
+}
+
+#[derive(Debug, PartialEq, serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
+pub struct AssetPage {
+    pub assets: Vec<UserFacingAsset>,
+    pub page: u32,
+    pub per_page: u32,
+    pub total: i64,
+}
@@ -1,8 +1,9 @@
 use diesel::prelude::*;
 
-use super::User;
+use super::{ChannelRole, User};
 
 #[derive(
+    Clone,
     Debug,
     PartialEq,
     Identifiable,
@@ -11,20 +12,59 @@ use super::User;
     Insertable,
     serde::Serialize,
     serde::Deserialize,
+    utoipa::ToSchema,
 )]
 #[diesel(table_name = crate::models::schema::channel_admins)]
-#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite, diesel::pg::Pg))]
 #[diesel(primary_key(username, broadcaster_username))]
 pub struct ChannelAdmin {
     pub username: String,
     pub broadcaster_username: String,
+    pub source: String,
+    pub role: String,
 }
 
 impl ChannelAdmin {
+    pub const MANUAL: &'static str = "manual";
+    pub const TWITCH_MODERATOR: &'static str = "twitch";
+    pub const MAX_LOGIN_LENGTH: usize = 25;
+
     pub fn new(user: &User, broadcaster: &User) -> Self {
+        Self::for_login(&user.username, broadcaster, Self::MANUAL)
+    }
+
+    pub fn for_login(login: &str, broadcaster: &User, source: &str) -> Self {
         Self {
-            username: user.username.clone(),
+            username: login.to_string(),
             broadcaster_username: broadcaster.username.clone(),
+            source: source.to_string(),
+            role: ChannelRole::Editor.as_str().to_string(),
+        }
+    }
+
+    pub fn with_role(mut self, role: ChannelRole) -> Self {
+        self.role = role.as_str().to_string();
+        self
+    }
+
+    pub fn role(&self) -> ChannelRole {
+        self.role
+            .parse()
+            .inspect_err(|role| tracing::warn!(?role, "unknown channel role"))
+            .unwrap_or(ChannelRole::ViewerSubmitter)
+    }
+
+    pub fn validate_login(login: &str) -> Result<String, String> {
+        let login = login.trim().to_lowercase();
+        if login.is_empty()
+            || login.len() > Self::MAX_LOGIN_LENGTH
+            || !login
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-'))
+        {
+            tracing::error!(?login, "invalid twitch login");
+            return Err(login);
         }
+        Ok(login)
     }
 }
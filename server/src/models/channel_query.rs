@@ -0,0 +1,11 @@
+#[derive(Debug, Default, serde::Deserialize, utoipa::IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ChannelQuery {
+    pub channel: Option<String>,
+}
+
+impl ChannelQuery {
+    pub fn channel_or<'a>(&'a self, login: &'a str) -> &'a str {
+        self.channel.as_deref().unwrap_or(login)
+    }
+}
@@ -0,0 +1,126 @@
+#[derive(
+    Clone, Copy, Debug, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize, utoipa::ToSchema,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum Capability {
+    /// Send an image to the channel's moderation queue.
+    Submit,
+    Upload,
+    PlaceAssets,
+    DeleteAssets,
+    ManageScenes,
+    ManageAdmins,
+    ChangeSettings,
+}
+
+impl Capability {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Submit => "submit",
+            Self::Upload => "upload",
+            Self::PlaceAssets => "place_assets",
+            Self::DeleteAssets => "delete_assets",
+            Self::ManageScenes => "manage_scenes",
+            Self::ManageAdmins => "manage_admins",
+            Self::ChangeSettings => "change_settings",
+        }
+    }
+}
+
+impl std::str::FromStr for Capability {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "submit" => Ok(Self::Submit),
+            "upload" => Ok(Self::Upload),
+            "place_assets" => Ok(Self::PlaceAssets),
+            "delete_assets" => Ok(Self::DeleteAssets),
+            "manage_scenes" => Ok(Self::ManageScenes),
+            "manage_admins" => Ok(Self::ManageAdmins),
+            "change_settings" => Ok(Self::ChangeSettings),
+            other => Err(other.to_string()),
+        }
+    }
+}
+
+#[derive(
+    Clone,
+    Copy,
+    Debug,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Hash,
+    serde::Serialize,
+    serde::Deserialize,
+    utoipa::ToSchema,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum ChannelRole {
+    ViewerSubmitter,
+    Editor,
+    Manager,
+    Broadcaster,
+}
+
+impl ChannelRole {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::ViewerSubmitter => "viewer_submitter",
+            Self::Editor => "editor",
+            Self::Manager => "manager",
+            Self::Broadcaster => "broadcaster",
+        }
+    }
+
+    pub fn capabilities(&self) -> &'static [Capability] {
+        match self {
+            Self::ViewerSubmitter => &[Capability::Submit],
+            Self::Editor => &[
+                Capability::Submit,
+                Capability::Upload,
+                Capability::PlaceAssets,
+                Capability::DeleteAssets,
+                Capability::ManageScenes,
+            ],
+            Self::Manager | Self::Broadcaster => &[
+                Capability::Submit,
+                Capability::Upload,
+                Capability::PlaceAssets,
+                Capability::DeleteAssets,
+                Capability::ManageScenes,
+                Capability::ManageAdmins,
+                Capability::ChangeSettings,
+            ],
+        }
+    }
+
+    pub fn allows(&self, capability: Capability) -> bool {
+        self.capabilities().contains(&capability)
+    }
+
+    pub fn is_assignable(&self) -> bool {
+        *self != Self::Broadcaster
+    }
+}
+
+impl std::str::FromStr for ChannelRole {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "viewer_submitter" => Ok(Self::ViewerSubmitter),
+            "editor" => Ok(Self::Editor),
+            "manager" => Ok(Self::Manager),
+            "broadcaster" => Ok(Self::Broadcaster),
+            other => Err(other.to_string()),
+        }
+    }
+}
+
+#[derive(Debug, serde::Deserialize, utoipa::ToSchema)]
+pub struct ChannelRoleRequest {
+    pub role: ChannelRole,
+}
@@ -0,0 +1,123 @@
+use diesel::prelude::*;
+
+use super::User;
+
+#[derive(
+    Clone,
+    Debug,
+    PartialEq,
+    Identifiable,
+    Queryable,
+    Selectable,
+    serde::Serialize,
+    serde::Deserialize,
+    utoipa::ToSchema,
+)]
+#[diesel(table_name = crate::models::schema::event_triggers)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite, diesel::pg::Pg))]
+pub struct EventTrigger {
+    pub id: i32,
+    pub username: String,
+    pub event_type: String,
+    pub reward_id: Option<String>,
+    pub asset_filename: String,
+    pub x: f32,
+    pub y: f32,
+    pub w: f32,
+    pub h: f32,
+    pub duration_seconds: i32,
+}
+
+#[derive(Debug, Insertable)]
+#[diesel(table_name = crate::models::schema::event_triggers)]
+pub struct NewEventTrigger {
+    pub username: String,
+    pub event_type: String,
+    pub reward_id: Option<String>,
+    pub asset_filename: String,
+    pub x: f32,
+    pub y: f32,
+    pub w: f32,
+    pub h: f32,
+    pub duration_seconds: i32,
+}
+
+#[derive(Debug, PartialEq, serde::Deserialize, utoipa::ToSchema)]
+pub struct UnownedEventTrigger {
+    pub event_type: String,
+    pub reward_id: Option<String>,
+    pub asset_filename: String,
+    pub x: f32,
+    pub y: f32,
+    pub w: f32,
+    pub h: f32,
+    pub duration_seconds: u32,
+}
+
+#[derive(Debug)]
+pub struct ValidatedEventTrigger(UnownedEventTrigger);
+
+impl UnownedEventTrigger {
+    pub const SUPPORTED_EVENTS: &'static [&'static str] = &[
+        "channel.follow",
+        "channel.subscribe",
+        "channel.cheer",
+        "channel.raid",
+        "channel.channel_points_custom_reward_redemption.add",
+    ];
+    pub const MAX_DURATION_SECONDS: u32 = 3600;
+
+    pub fn validate(self) -> Result<ValidatedEventTrigger, String> {
+        if !Self::SUPPORTED_EVENTS.contains(&self.event_type.as_str()) {
+            tracing::error!(event_type = ?self.event_type, "unsupported event type");
+            return Err(self.event_type);
+        }
+        if self.duration_seconds > Self::MAX_DURATION_SECONDS {
+            tracing::error!(duration_seconds = ?self.duration_seconds, "duration too long");
+            return Err(format!(
+                "duration must be at most {} seconds",
+                Self::MAX_DURATION_SECONDS
+            ));
+        }
+        if [self.x, self.y, self.w, self.h]
+            .iter()
+            .any(|value| !value.is_finite())
+            || self.w <= 0.0
+            || self.h <= 0.0
+        {
+            tracing::error!(trigger = ?self, "invalid trigger placement");
+            return Err("invalid placement".to_string());
+        }
+        Ok(ValidatedEventTrigger(self))
+    }
+}
+
+impl ValidatedEventTrigger {
+    pub fn asset_filename(&self) -> &str {
+        &self.0.asset_filename
+    }
+
+    pub fn with_owner(self, owner: &User) -> NewEventTrigger {
+        let UnownedEventTrigger {
+            event_type,
+            reward_id,
+            asset_filename,
+            x,
+            y,
+            w,
+            h,
+            duration_seconds,
+        } = self.0;
+        NewEventTrigger {
+            username: owner.username.clone(),
+            event_type,
+            reward_id: reward_id.filter(|reward_id| !reward_id.is_empty()),
+            asset_filename,
+            x,
+            y,
+            w,
+            h,
+            duration_seconds: duration_seconds as i32,
+        }
+    }
+}
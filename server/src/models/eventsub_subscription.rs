@@ -0,0 +1,11 @@
+use diesel::prelude::*;
+
+#[derive(Clone, Debug, PartialEq, AsChangeset, Queryable, Selectable, Insertable)]
+#[diesel(table_name = crate::models::schema::eventsub_subscriptions)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite, diesel::pg::Pg))]
+pub struct StoredEventSubSubscription {
+    pub id: String,
+    pub username: String,
+    pub event_type: String,
+    pub status: String,
+}
@@ -0,0 +1,38 @@
+use diesel::prelude::*;
+
+use super::User;
+
+#[derive(
+    Clone,
+    Debug,
+    PartialEq,
+    Identifiable,
+    Queryable,
+    Selectable,
+    serde::Serialize,
+    serde::Deserialize,
+    utoipa::ToSchema,
+)]
+#[diesel(table_name = crate::models::schema::folders)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite, diesel::pg::Pg))]
+pub struct Folder {
+    pub id: i32,
+    pub username: String,
+    pub name: String,
+}
+
+#[derive(Debug, Insertable)]
+#[diesel(table_name = crate::models::schema::folders)]
+pub struct NewFolder {
+    pub username: String,
+    pub name: String,
+}
+
+impl NewFolder {
+    pub fn new(owner: &User, name: &str) -> Self {
+        Self {
+            username: owner.username.clone(),
+            name: name.to_string(),
+        }
+    }
+}
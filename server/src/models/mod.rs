@@ -1,13 +1,70 @@
+pub mod api_token;
 pub mod asset;
+pub mod asset_archive;
+pub mod asset_metadata;
+pub mod asset_search;
 pub mod channel_admin;
+pub mod channel_query;
+pub mod channel_role;
+pub mod event_trigger;
+pub mod eventsub_subscription;
+pub mod folder;
+pub mod overlay_token;
+pub mod scene;
 pub mod schema;
+pub mod secret;
+pub mod stored_channel_state;
+pub mod stored_session;
+pub mod stored_user_tokens;
+pub mod submission;
+pub mod tag;
 pub mod user;
 pub mod user_settings;
 
+pub use api_token::ApiToken;
+pub use api_token::IssuedApiToken;
+pub use api_token::NewApiToken;
+pub use api_token::UnownedApiToken;
+pub use api_token::UserFacingApiToken;
+pub use api_token::ValidatedApiToken;
 pub use asset::Asset;
 pub use asset::UnownedAsset;
 pub use asset::UserFacingAsset;
+pub use asset_archive::AssetArchiveEntry;
+pub use asset_archive::AssetArchiveManifest;
+pub use asset_archive::AssetImportSummary;
+pub use asset_metadata::UnownedAssetMetadata;
+pub use asset_metadata::ValidatedAssetMetadata;
+pub use asset_search::AssetPage;
+pub use asset_search::AssetSearchQuery;
 pub use channel_admin::ChannelAdmin;
+pub use channel_query::ChannelQuery;
+pub use channel_role::Capability;
+pub use channel_role::ChannelRole;
+pub use channel_role::ChannelRoleRequest;
+pub use event_trigger::EventTrigger;
+pub use event_trigger::NewEventTrigger;
+pub use event_trigger::UnownedEventTrigger;
+pub use event_trigger::ValidatedEventTrigger;
+pub use eventsub_subscription::StoredEventSubSubscription;
+pub use folder::Folder;
+pub use folder::NewFolder;
+pub use overlay_token::IssuedOverlayToken;
+pub use overlay_token::OverlayToken;
+pub use overlay_token::OverlayTokenQuery;
+pub use scene::NewSceneAsset;
+pub use scene::ScenePlacement;
+pub use stored_channel_state::StoredChannelState;
+pub use stored_session::StoredSession;
+pub use stored_user_tokens::StoredUserTokens;
+pub use submission::NewSubmission;
+pub use submission::Submission;
+pub use submission::SubmissionDecision;
+pub use submission::SubmissionQuery;
+pub use submission::SubmissionReview;
+pub use tag::AssetTag;
+pub use tag::NewTag;
+pub use tag::Tag;
 pub use user::User;
 pub use user_settings::UnownedUserSettings;
 pub use user_settings::UserSettings;
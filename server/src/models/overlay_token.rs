@@ -0,0 +1,50 @@
+use diesel::prelude::*;
+
+use super::{secret, User};
+
+#[derive(
+    Clone,
+    Debug,
+    PartialEq,
+    AsChangeset,
+    Queryable,
+    Selectable,
+    Insertable,
+    serde::Serialize,
+    utoipa::ToSchema,
+)]
+#[diesel(table_name = crate::models::schema::overlay_tokens)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite, diesel::pg::Pg))]
+pub struct OverlayToken {
+    pub username: String,
+    #[serde(skip_serializing)]
+    pub token_hash: String,
+    pub created_at: i64,
+}
+
+impl OverlayToken {
+    pub fn generate(owner: &User) -> (Self, String) {
+        let token = secret::generate();
+        let overlay_token = Self {
+            username: owner.username.clone(),
+            token_hash: secret::hash(&token),
+            created_at: time::OffsetDateTime::now_utc().unix_timestamp(),
+        };
+        (overlay_token, token)
+    }
+
+    pub fn matches(&self, token: &str) -> bool {
+        self.token_hash == secret::hash(token)
+    }
+}
+
+#[derive(Debug, PartialEq, serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
+pub struct IssuedOverlayToken {
+    pub token: String,
+    pub created_at: i64,
+}
+
+#[derive(Debug, Default, serde::Deserialize)]
+pub struct OverlayTokenQuery {
+    pub token: Option<String>,
+}
@@ -0,0 +1,31 @@
+#[derive(Clone, Copy, Debug, PartialEq, serde::Deserialize, utoipa::ToSchema)]
+pub struct ScenePlacement {
+    pub x: f32,
+    pub y: f32,
+    pub w: f32,
+    pub h: f32,
+    #[serde(default)]
+    pub theta: f32,
+}
+
+impl ScenePlacement {
+    pub fn validate(self) -> Result<Self, String> {
+        if [self.x, self.y, self.w, self.h, self.theta]
+            .iter()
+            .any(|value| !value.is_finite())
+            || self.w <= 0.0
+            || self.h <= 0.0
+        {
+            tracing::error!(placement = ?self, "invalid scene placement");
+            return Err("invalid placement".to_string());
+        }
+        Ok(self)
+    }
+}
+
+#[derive(Debug, PartialEq, serde::Deserialize, utoipa::ToSchema)]
+pub struct NewSceneAsset {
+    pub filename: String,
+    #[serde(flatten)]
+    pub placement: ScenePlacement,
+}
@@ -1,5 +1,25 @@
 // @generated automatically by Diesel CLI.
 
+diesel::table! {
+    api_tokens (id) {
+        id -> Integer,
+        username -> Text,
+        name -> Text,
+        token_hash -> Text,
+        scopes -> Text,
+        created_at -> BigInt,
+        expires_at -> Nullable<BigInt>,
+        last_used_at -> Nullable<BigInt>,
+    }
+}
+
+diesel::table! {
+    asset_tags (local_filename, tag_id) {
+        local_filename -> Text,
+        tag_id -> Integer,
+    }
+}
+
 diesel::table! {
     assets (local_filename) {
         local_filename -> Text,
@@ -7,6 +27,8 @@ diesel::table! {
         checksum -> Text,
         content_type -> Text,
         username -> Text,
+        folder_id -> Nullable<Integer>,
+        uploaded_at -> BigInt,
     }
 }
 
@@ -14,6 +36,88 @@ diesel::table! {
     channel_admins (username, broadcaster_username) {
         username -> Text,
         broadcaster_username -> Text,
+        source -> Text,
+        role -> Text,
+    }
+}
+
+diesel::table! {
+    channel_states (username) {
+        username -> Text,
+        state -> Text,
+        saved_at -> BigInt,
+    }
+}
+
+diesel::table! {
+    event_triggers (id) {
+        id -> Integer,
+        username -> Text,
+        event_type -> Text,
+        reward_id -> Nullable<Text>,
+        asset_filename -> Text,
+        x -> Float,
+        y -> Float,
+        w -> Float,
+        h -> Float,
+        duration_seconds -> Integer,
+    }
+}
+
+diesel::table! {
+    eventsub_subscriptions (id) {
+        id -> Text,
+        username -> Text,
+        event_type -> Text,
+        status -> Text,
+    }
+}
+
+diesel::table! {
+    folders (id) {
+        id -> Integer,
+        username -> Text,
+        name -> Text,
+    }
+}
+
+diesel::table! {
+    sessions (id) {
+        id -> Text,
+        data -> Text,
+        expiry_date -> BigInt,
+    }
+}
+
+diesel::table! {
+    submissions (id) {
+        id -> Integer,
+        broadcaster_username -> Text,
+        submitter -> Text,
+        local_filename -> Text,
+        original_filename -> Text,
+        checksum -> Text,
+        content_type -> Text,
+        status -> Text,
+        submitted_at -> BigInt,
+        reviewed_by -> Nullable<Text>,
+        reviewed_at -> Nullable<BigInt>,
+    }
+}
+
+diesel::table! {
+    tags (id) {
+        id -> Integer,
+        username -> Text,
+        name -> Text,
+    }
+}
+
+diesel::table! {
+    overlay_tokens (username) {
+        username -> Text,
+        token_hash -> Text,
+        created_at -> BigInt,
     }
 }
 
@@ -22,6 +126,19 @@ diesel::table! {
         username -> Text,
         background_opacity -> Float,
         fps_target -> Integer,
+        chat_commands -> Bool,
+        sync_moderators -> Bool,
+    }
+}
+
+diesel::table! {
+    user_tokens (username) {
+        username -> Text,
+        access_token -> Text,
+        refresh_token -> Nullable<Text>,
+        expires_at -> BigInt,
+        scope -> Text,
+        token_type -> Text,
     }
 }
 
@@ -31,12 +148,36 @@ diesel::table! {
     }
 }
 
+diesel::joinable!(api_tokens -> users (username));
+diesel::joinable!(asset_tags -> assets (local_filename));
+diesel::joinable!(asset_tags -> tags (tag_id));
+diesel::joinable!(assets -> folders (folder_id));
 diesel::joinable!(assets -> users (username));
+diesel::joinable!(event_triggers -> assets (asset_filename));
+diesel::joinable!(event_triggers -> users (username));
+diesel::joinable!(channel_states -> users (username));
+diesel::joinable!(eventsub_subscriptions -> users (username));
+diesel::joinable!(folders -> users (username));
+diesel::joinable!(overlay_tokens -> users (username));
+diesel::joinable!(submissions -> users (broadcaster_username));
+diesel::joinable!(tags -> users (username));
 diesel::joinable!(user_settings -> users (username));
+diesel::joinable!(user_tokens -> users (username));
 
 diesel::allow_tables_to_appear_in_same_query!(
+    api_tokens,
+    asset_tags,
     assets,
     channel_admins,
+    channel_states,
+    event_triggers,
+    eventsub_subscriptions,
+    folders,
+    overlay_tokens,
+    sessions,
+    submissions,
+    tags,
     user_settings,
+    user_tokens,
     users,
 );
@@ -0,0 +1,27 @@
+use diesel::prelude::*;
+
+use crate::domain::message::ImgfloatState;
+
+/// A channel's scene as it was when the server last shut down.
+#[derive(Clone, Debug, PartialEq, AsChangeset, Queryable, Selectable, Insertable)]
+#[diesel(table_name = crate::models::schema::channel_states)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite, diesel::pg::Pg))]
+pub struct StoredChannelState {
+    pub username: String,
+    pub state: String,
+    pub saved_at: i64,
+}
+
+impl StoredChannelState {
+    pub fn new(username: &str, state: &ImgfloatState) -> Result<Self, serde_json::Error> {
+        Ok(Self {
+            username: username.to_string(),
+            state: serde_json::to_string(state)?,
+            saved_at: time::OffsetDateTime::now_utc().unix_timestamp(),
+        })
+    }
+
+    pub fn parse(&self) -> Result<ImgfloatState, serde_json::Error> {
+        serde_json::from_str(&self.state)
+    }
+}
@@ -0,0 +1,10 @@
+use diesel::prelude::*;
+
+#[derive(Clone, Debug, PartialEq, AsChangeset, Queryable, Selectable, Insertable)]
+#[diesel(table_name = crate::models::schema::sessions)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite, diesel::pg::Pg))]
+pub struct StoredSession {
+    pub id: String,
+    pub data: String,
+    pub expiry_date: i64,
+}
@@ -0,0 +1,14 @@
+use diesel::prelude::*;
+
+#[derive(Clone, Debug, PartialEq, AsChangeset, Queryable, Selectable, Insertable)]
+#[diesel(table_name = crate::models::schema::user_tokens)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite, diesel::pg::Pg))]
+#[diesel(treat_none_as_null = true)]
+pub struct StoredUserTokens {
+    pub username: String,
+    pub access_token: String,
+    pub refresh_token: Option<String>,
+    pub expires_at: i64,
+    pub scope: String,
+    pub token_type: String,
+}
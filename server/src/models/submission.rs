@@ -0,0 +1,99 @@
+use diesel::prelude::*;
+
+use super::{Asset, UnownedAsset, User};
+
+#[derive(
+    Clone,
+    Debug,
+    PartialEq,
+    Identifiable,
+    Queryable,
+    Selectable,
+    serde::Serialize,
+    serde::Deserialize,
+    utoipa::ToSchema,
+)]
+#[diesel(table_name = crate::models::schema::submissions)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite, diesel::pg::Pg))]
+pub struct Submission {
+    pub id: i32,
+    pub broadcaster_username: String,
+    pub submitter: String,
+    pub local_filename: String,
+    pub original_filename: String,
+    pub checksum: String,
+    pub content_type: String,
+    pub status: String,
+    pub submitted_at: i64,
+    pub reviewed_by: Option<String>,
+    pub reviewed_at: Option<i64>,
+}
+
+impl Submission {
+    pub const PENDING: &'static str = "pending";
+    pub const APPROVED: &'static str = "approved";
+    pub const REJECTED: &'static str = "rejected";
+
+    pub fn is_pending(&self) -> bool {
+        self.status == Self::PENDING
+    }
+
+    pub fn as_asset(&self) -> Asset {
+        Asset {
+            local_filename: self.local_filename.clone(),
+            original_filename: self.original_filename.clone(),
+            checksum: self.checksum.clone(),
+            content_type: self.content_type.clone(),
+            username: self.broadcaster_username.clone(),
+            folder_id: None,
+            uploaded_at: time::OffsetDateTime::now_utc().unix_timestamp(),
+        }
+    }
+}
+
+#[derive(Debug, Insertable)]
+#[diesel(table_name = crate::models::schema::submissions)]
+pub struct NewSubmission {
+    pub broadcaster_username: String,
+    pub submitter: String,
+    pub local_filename: String,
+    pub original_filename: String,
+    pub checksum: String,
+    pub content_type: String,
+    pub status: String,
+    pub submitted_at: i64,
+}
+
+impl NewSubmission {
+    pub fn new(asset: UnownedAsset, broadcaster: &User, submitter: &str) -> Self {
+        Self {
+            broadcaster_username: broadcaster.username.clone(),
+            submitter: submitter.to_string(),
+            local_filename: asset.local_filename,
+            original_filename: asset.original_filename,
+            checksum: asset.checksum,
+            content_type: asset.content_type,
+            status: Submission::PENDING.to_string(),
+            submitted_at: time::OffsetDateTime::now_utc().unix_timestamp(),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum SubmissionDecision {
+    Approve,
+    ApproveAndShow,
+    Reject,
+}
+
+#[derive(Debug, serde::Deserialize, utoipa::ToSchema)]
+pub struct SubmissionReview {
+    pub decision: SubmissionDecision,
+}
+
+#[derive(Debug, Default, serde::Deserialize, utoipa::IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct SubmissionQuery {
+    pub status: Option<String>,
+}
@@ -0,0 +1,46 @@
+use diesel::prelude::*;
+
+use super::User;
+
+#[derive(
+    Clone,
+    Debug,
+    PartialEq,
+    Identifiable,
+    Queryable,
+    Selectable,
+    serde::Serialize,
+    serde::Deserialize,
+    utoipa::ToSchema,
+)]
+#[diesel(table_name = crate::models::schema::tags)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite, diesel::pg::Pg))]
+pub struct Tag {
+    pub id: i32,
+    pub username: String,
+    pub name: String,
+}
+
+#[derive(Debug, Insertable)]
+#[diesel(table_name = crate::models::schema::tags)]
+pub struct NewTag {
+    pub username: String,
+    pub name: String,
+}
+
+impl NewTag {
+    pub fn new(owner: &User, name: &str) -> Self {
+        Self {
+            username: owner.username.clone(),
+            name: name.to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Queryable, Insertable)]
+#[diesel(table_name = crate::models::schema::asset_tags)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite, diesel::pg::Pg))]
+pub struct AssetTag {
+    pub local_filename: String,
+    pub tag_id: i32,
+}
@@ -2,9 +2,9 @@ use diesel::prelude::*;
 
 use crate::twitch::TwitchUser;
 
-#[derive(Debug, PartialEq, Identifiable, Queryable, Selectable, Insertable)]
+#[derive(Clone, Debug, PartialEq, Identifiable, Queryable, Selectable, Insertable)]
 #[diesel(table_name = crate::models::schema::users)]
-#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite, diesel::pg::Pg))]
 #[diesel(primary_key(username))]
 pub struct User {
     pub username: String,
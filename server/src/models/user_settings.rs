@@ -5,6 +5,7 @@ use crate::domain::Percentage;
 use super::User;
 
 #[derive(
+    Clone,
     AsChangeset,
     Queryable,
     Selectable,
@@ -13,19 +14,26 @@ use super::User;
     PartialEq,
     serde::Serialize,
     serde::Deserialize,
+    utoipa::ToSchema,
 )]
 #[diesel(table_name = crate::models::schema::user_settings)]
-#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite, diesel::pg::Pg))]
 pub struct UserSettings {
     pub username: String,
     pub background_opacity: f32,
     pub fps_target: i32,
+    pub chat_commands: bool,
+    pub sync_moderators: bool,
 }
 
-#[derive(Debug, Eq, PartialEq, serde::Deserialize)]
+#[derive(Debug, Eq, PartialEq, serde::Deserialize, utoipa::ToSchema)]
 pub struct UnownedUserSettings {
     pub background_opacity: u8,
     pub fps_target: u16,
+    #[serde(default)]
+    pub chat_commands: bool,
+    #[serde(default)]
+    pub sync_moderators: bool,
 }
 
 impl UnownedUserSettings {
@@ -34,6 +42,8 @@ impl UnownedUserSettings {
         Ok(ValidatedUnownedUserSettings {
             background_opacity,
             fps_target: self.fps_target,
+            chat_commands: self.chat_commands,
+            sync_moderators: self.sync_moderators,
         })
     }
 }
@@ -41,6 +51,8 @@ impl UnownedUserSettings {
 pub struct ValidatedUnownedUserSettings {
     pub background_opacity: Percentage,
     pub fps_target: u16,
+    pub chat_commands: bool,
+    pub sync_moderators: bool,
 }
 
 impl ValidatedUnownedUserSettings {
@@ -49,6 +61,8 @@ impl ValidatedUnownedUserSettings {
             username: owner.username.clone(),
             background_opacity: self.background_opacity.into(),
             fps_target: self.fps_target.into(),
+            chat_commands: self.chat_commands,
+            sync_moderators: self.sync_moderators,
         }
     }
 }
@@ -58,6 +72,8 @@ impl Default for ValidatedUnownedUserSettings {
         ValidatedUnownedUserSettings {
             background_opacity: Percentage(40.0),
             fps_target: 60,
+            chat_commands: false,
+            sync_moderators: false,
         }
     }
 }
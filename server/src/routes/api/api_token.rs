@@ -0,0 +1,103 @@
+use std::sync::Arc;
+
+use axum::{extract::State, http::StatusCode, response::IntoResponse};
+
+use crate::{
+    domain::{
+        db::Database,
+        extract::{Json, Path},
+        ApiError, JsonResponse, UserSession,
+    },
+    models::{IssuedApiToken, UnownedApiToken, User, UserFacingApiToken},
+};
+
+fn session_owner(session: &UserSession) -> Result<User, ApiError> {
+    let session_user = session.user.as_ref().ok_or_else(ApiError::unauthorized)?;
+    if session.api_token.is_some() {
+        tracing::warn!(user = ?session_user.login, "api tokens cannot manage api tokens");
+        return Err(ApiError::forbidden("api tokens cannot manage api tokens"));
+    }
+    Ok(User::new(&session_user.login))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/tokens",
+    tag = "api tokens",
+    responses(
+        (status = 200, body = Vec<UserFacingApiToken>),
+        (status = 401, body = crate::domain::ApiErrorBody),
+        (status = 403, body = crate::domain::ApiErrorBody),
+    )
+)]
+#[axum::debug_handler(state = crate::domain::AppState)]
+pub async fn get(
+    State(database): State<Arc<dyn Database>>,
+    session: UserSession,
+) -> Result<Json<Vec<UserFacingApiToken>>, ApiError> {
+    let owner = session_owner(&session)?;
+    let tokens = database.get_api_tokens(&owner)?;
+    Ok(Json(tokens.iter().map(UserFacingApiToken::from).collect()))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/tokens",
+    tag = "api tokens",
+    request_body = UnownedApiToken,
+    responses(
+        (status = 201, body = IssuedApiToken),
+        (status = 400, body = crate::domain::ApiErrorBody),
+        (status = 401, body = crate::domain::ApiErrorBody),
+        (status = 403, body = crate::domain::ApiErrorBody),
+    )
+)]
+#[axum::debug_handler(state = crate::domain::AppState)]
+pub async fn post(
+    State(database): State<Arc<dyn Database>>,
+    session: UserSession,
+    Json(token_request): Json<UnownedApiToken>,
+) -> Result<impl IntoResponse, ApiError> {
+    let owner = session_owner(&session)?;
+    let (new_token, token) = token_request
+        .validate()
+        .map_err(|message| ApiError::bad_request("invalid_api_token", message))?
+        .with_owner(&owner);
+    let created = database.create_api_token(&new_token)?;
+    tracing::info!(?owner, id = ?created.id, name = ?created.name, "issued api token");
+    Ok(JsonResponse::new(IssuedApiToken {
+        token,
+        api_token: UserFacingApiToken::from(&created),
+    })
+    .with_status(StatusCode::CREATED))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/tokens/{id}",
+    tag = "api tokens",
+    params(("id" = i32, Path, description = "API token id")),
+    responses(
+        (status = 204),
+        (status = 401, body = crate::domain::ApiErrorBody),
+        (status = 403, body = crate::domain::ApiErrorBody),
+        (status = 404, body = crate::domain::ApiErrorBody),
+    )
+)]
+#[axum::debug_handler(state = crate::domain::AppState)]
+pub async fn delete(
+    State(database): State<Arc<dyn Database>>,
+    session: UserSession,
+    Path(id): Path<i32>,
+) -> Result<StatusCode, ApiError> {
+    let owner = session_owner(&session)?;
+    let deleted = database.delete_api_token(&owner, id)?;
+    if deleted == 0 {
+        return Err(ApiError::not_found(
+            "unknown_api_token",
+            "no such api token",
+        ));
+    }
+    tracing::info!(?owner, ?id, "revoked api token");
+    Ok(StatusCode::NO_CONTENT)
+}
@@ -0,0 +1,154 @@
+use std::{
+    io::{BufWriter, ErrorKind, Write},
+    sync::Arc,
+};
+
+use axum::{
+    body::{Body, Bytes},
+    extract::State,
+    http::{header, StatusCode},
+    response::IntoResponse,
+};
+use tokio::sync::mpsc;
+
+use crate::{
+    domain::{
+        db::Database, extract::Path, ApiError, AssetArchive, AssetArchiveError, AssetDirectory,
+        ChannelController, JsonResponse, PermissionService, UserSession,
+    },
+    models::{AssetArchiveEntry, AssetArchiveManifest, Capability},
+};
+
+pub const MAX_ARCHIVE_SIZE: usize = AssetArchive::MAX_SIZE as usize;
+
+#[utoipa::path(
+    get,
+    path = "/api/archive/{username}",
+    tag = "archive",
+    params(("username" = String, Path, description = "Broadcaster login")),
+    responses(
+        (status = 200, description = "Tar archive of assets and scenes", content_type = "application/x-tar"),
+        (status = 403, body = crate::domain::ApiErrorBody),
+    )
+)]
+#[axum::debug_handler(state = crate::domain::AppState)]
+pub async fn get(
+    State(database): State<Arc<dyn Database>>,
+    State(controller): State<Arc<ChannelController>>,
+    State(permissions): State<Arc<PermissionService>>,
+    State(AssetDirectory(asset_dir)): State<AssetDirectory>,
+    session: UserSession,
+    Path(username): Path<String>,
+) -> Result<impl IntoResponse, ApiError> {
+    let broadcaster = permissions
+        .authorize(&session, &username, Capability::ChangeSettings)
+        .await?;
+    let assets = database.get_broadcaster_assets(&broadcaster)?;
+    let mut metadata = database.get_assets_metadata(&assets)?;
+    let entries = assets
+        .into_iter()
+        .map(|asset| {
+            let asset_metadata = metadata.remove(&asset.local_filename).unwrap_or_default();
+            AssetArchiveEntry::new(asset, asset_metadata)
+        })
+        .collect();
+    let scenes = controller.get_state(&username).await.into_iter().collect();
+    let manifest = AssetArchiveManifest::new(&username, entries, scenes);
+    tracing::info!(
+        ?username,
+        asset_count = manifest.assets.len(),
+        "exporting assets"
+    );
+
+    let manifest = tokio::task::spawn_blocking(move || {
+        AssetArchive::check_files(&manifest, &asset_dir).map(|()| (manifest, asset_dir))
+    })
+    .await
+    .map_err(|_| ApiError::internal("unable to check asset files"))?;
+    let (manifest, asset_dir) = manifest?;
+
+    let (sender, mut receiver) = mpsc::channel(16);
+    tokio::task::spawn_blocking(move || {
+        let errors = sender.clone();
+        let writer = BufWriter::with_capacity(64 * 1024, BodyWriter(sender));
+        if let Err(error) = AssetArchive::write(writer, &manifest, &asset_dir) {
+            tracing::error!(?error, ?manifest.username, "unable to write asset archive");
+            // ends the body with an error instead of a truncated archive
+            errors
+                .blocking_send(Err(std::io::Error::other("unable to write asset archive")))
+                .ok();
+        }
+    });
+    let body = futures::stream::poll_fn(move |context| receiver.poll_recv(context));
+    let headers = [
+        (header::CONTENT_TYPE, "application/x-tar".to_string()),
+        (
+            header::CONTENT_DISPOSITION,
+            format!("attachment; filename=\"imgfloat-{username}.tar\""),
+        ),
+    ];
+    Ok((headers, Body::from_stream(body)))
+}
+
+/// Hands what a blocking writer produces to a response body.
+struct BodyWriter(mpsc::Sender<std::io::Result<Bytes>>);
+
+impl Write for BodyWriter {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0
+            .blocking_send(Ok(Bytes::copy_from_slice(buf)))
+            .map_err(|_| std::io::Error::new(ErrorKind::BrokenPipe, "client went away"))?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/archive/{username}",
+    tag = "archive",
+    params(("username" = String, Path, description = "Broadcaster login")),
+    request_body(content = Vec<u8>, content_type = "application/x-tar"),
+    responses(
+        (status = 201, body = crate::models::AssetImportSummary),
+        (status = 400, body = crate::domain::ApiErrorBody),
+        (status = 403, body = crate::domain::ApiErrorBody),
+    )
+)]
+#[axum::debug_handler(state = crate::domain::AppState)]
+pub async fn post(
+    State(database): State<Arc<dyn Database>>,
+    State(controller): State<Arc<ChannelController>>,
+    State(permissions): State<Arc<PermissionService>>,
+    State(AssetDirectory(asset_dir)): State<AssetDirectory>,
+    session: UserSession,
+    Path(username): Path<String>,
+    body: Bytes,
+) -> Result<impl IntoResponse, ApiError> {
+    let broadcaster = permissions
+        .authorize(&session, &username, Capability::ChangeSettings)
+        .await?;
+    let archive = tokio::task::spawn_blocking(move || AssetArchive::read(body.as_ref()))
+        .await
+        .map_err(|_| ApiError::internal("unable to read asset archive"))?
+        .inspect_err(|error| tracing::error!(?error, "invalid asset archive"))
+        .map_err(|error| match error {
+            AssetArchiveError::Io(error) => {
+                ApiError::bad_request("invalid_archive", error.to_string())
+            }
+            error => error.into(),
+        })?;
+    let (summary, scenes) = archive.import(&*database, &broadcaster, &asset_dir).await?;
+    if let Some(scene) = scenes.into_iter().next() {
+        controller.restore_state(&username, scene).await;
+    }
+    let status_code = if summary.imported.is_empty() {
+        StatusCode::OK
+    } else {
+        StatusCode::CREATED
+    };
+    Ok(JsonResponse::new(summary).with_status(status_code))
+}
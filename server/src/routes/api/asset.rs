@@ -1,99 +1,214 @@
 use std::sync::Arc;
 
 use axum::{
-    extract::{Multipart, Path, State},
-    http::{header, StatusCode},
+    extract::{Multipart, State},
+    http::{header, HeaderName, StatusCode},
     response::IntoResponse,
-    Json,
 };
-use tokio::sync::RwLock;
 
 use crate::{
-    domain::{db::SqliteDbService, AssetDirectory},
-    models::{UnownedAsset, UserFacingAsset},
+    domain::{
+        db::Database,
+        extract::{Json, Path, Query},
+        ApiError, AssetDirectory, JsonResponse, PermissionService, UserSession,
+    },
+    models::{
+        AssetPage, AssetSearchQuery, Capability, UnownedAsset, UnownedAssetMetadata,
+        UserFacingAsset,
+    },
 };
 
-// TODO: Check credentials
+fn unknown_channel() -> ApiError {
+    ApiError::not_found("unknown_channel", "no such channel")
+}
+
+fn unknown_asset() -> ApiError {
+    ApiError::not_found("unknown_asset", "no such asset")
+}
+
+/// Headers for serving stored files, so even a file that slipped past the
+/// content type checks can't be sniffed into a script on our origin.
+pub(crate) fn file_headers(content_type: String) -> [(HeaderName, String); 3] {
+    [
+        (header::CONTENT_TYPE, content_type),
+        (header::X_CONTENT_TYPE_OPTIONS, "nosniff".to_string()),
+        (
+            header::CONTENT_SECURITY_POLICY,
+            "default-src 'none'; sandbox".to_string(),
+        ),
+    ]
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/assets/{username}",
+    tag = "assets",
+    params(("username" = String, Path, description = "Broadcaster login")),
+    request_body(content_type = "multipart/form-data", description = "A single file field"),
+    responses(
+        (status = 200, description = "Stored filename", body = String),
+        (status = 400, body = crate::domain::ApiErrorBody),
+        (status = 403, body = crate::domain::ApiErrorBody),
+        (status = 415, body = crate::domain::ApiErrorBody),
+        (status = 429, body = crate::domain::ApiErrorBody),
+    )
+)]
 #[axum::debug_handler(state = crate::domain::AppState)]
 pub async fn post(
-    State(database): State<Arc<RwLock<SqliteDbService>>>,
+    State(database): State<Arc<dyn Database>>,
+    State(permissions): State<Arc<PermissionService>>,
     State(AssetDirectory(asset_dir)): State<AssetDirectory>,
+    session: UserSession,
     Path(username): Path<String>,
     mut multipart: Multipart,
-) -> Result<impl IntoResponse, StatusCode> {
-    let broadcaster = match database.read().await.get_user(&username) {
-        Some(user) => user,
-        None => {
-            tracing::error!(?username, "unknown broadcaster");
-            return Err(StatusCode::NOT_FOUND);
-        }
-    };
+) -> Result<impl IntoResponse, ApiError> {
+    let broadcaster = permissions
+        .authorize(&session, &username, Capability::Upload)
+        .await?;
     while let Some(field) = multipart
         .next_field()
         .await
         .inspect_err(|error| tracing::error!(?error, "no multipart request body"))
-        .map_err(|_| StatusCode::BAD_REQUEST)?
+        .map_err(|error| ApiError::bad_request("invalid_multipart", error.body_text()))?
     {
-        let asset = UnownedAsset::from_mutlipart(field, asset_dir)
+        let content_type = field.content_type().unwrap_or_default().to_string();
+        if !UnownedAsset::is_supported_content_type(&content_type) {
+            tracing::warn!(?broadcaster, ?content_type, "unsupported upload");
+            return Err(ApiError::new(
+                StatusCode::UNSUPPORTED_MEDIA_TYPE,
+                "unsupported_media_type",
+                format!("{content_type} cannot be uploaded"),
+            ));
+        }
+        let asset = UnownedAsset::from_mutlipart(field, asset_dir.clone())
             .await
-            .map_err(|_| StatusCode::SERVICE_UNAVAILABLE)?
+            .map_err(|_| ApiError::storage("unable to store the uploaded file"))?
             .with_owner(&broadcaster);
-        database
-            .write()
-            .await
-            .create_asset(&asset)
-            .map_err(|_| StatusCode::SERVICE_UNAVAILABLE)?;
+        if let Err(error) = database.create_asset(&asset) {
+            let asset_path = format!("{}/{}", asset_dir, asset.local_filename);
+            let _ = tokio::fs::remove_file(&asset_path)
+                .await
+                .inspect_err(|error| tracing::error!(?error, ?asset_path, "unable to clean up"));
+            return Err(error.into());
+        }
         return Ok(asset.local_filename);
     }
 
-    Err(StatusCode::BAD_REQUEST)
+    Err(ApiError::bad_request(
+        "missing_file",
+        "the request did not contain a file",
+    ))
 }
 
 // TODO: Check credentials
+#[utoipa::path(
+    get,
+    path = "/api/assets/{username}",
+    tag = "assets",
+    params(
+        ("username" = String, Path, description = "Broadcaster login"),
+        AssetSearchQuery,
+    ),
+    responses(
+        (status = 200, body = AssetPage),
+        (status = 404, body = crate::domain::ApiErrorBody),
+    )
+)]
 #[axum::debug_handler(state = crate::domain::AppState)]
 pub async fn get(
-    State(database): State<Arc<RwLock<SqliteDbService>>>,
+    State(database): State<Arc<dyn Database>>,
     Path(username): Path<String>,
-) -> Result<Json<Vec<UserFacingAsset>>, StatusCode> {
-    let broadcaster = match database.read().await.get_user(&username) {
+    Query(query): Query<AssetSearchQuery>,
+) -> Result<Json<AssetPage>, ApiError> {
+    let broadcaster = match database.get_user(&username)? {
         Some(user) => user,
         None => {
             tracing::error!(?username, "unknown broadcaster");
-            return Err(StatusCode::NOT_FOUND);
+            return Err(unknown_channel());
         }
     };
-    let assets: Json<Vec<UserFacingAsset>> = database
-        .read()
-        .await
-        .get_broadcaster_assets(&broadcaster)
-        .map_err(|_| StatusCode::SERVICE_UNAVAILABLE)?
-        .into_iter()
-        .map(|asset| asset.into())
-        .collect::<Vec<UserFacingAsset>>()
-        .into();
-    Ok(assets)
+    tracing::trace!(?broadcaster, ?query, "searching assets");
+    let page = database.search_assets(&broadcaster, &query)?;
+    Ok(Json(page))
+}
+
+#[utoipa::path(
+    put,
+    path = "/api/assets/{username}/{filename}/metadata",
+    tag = "assets",
+    params(
+        ("username" = String, Path, description = "Broadcaster login"),
+        ("filename" = String, Path, description = "Stored asset filename"),
+    ),
+    request_body = UnownedAssetMetadata,
+    responses(
+        (status = 200, body = crate::models::ValidatedAssetMetadata),
+        (status = 400, body = crate::domain::ApiErrorBody),
+        (status = 403, body = crate::domain::ApiErrorBody),
+        (status = 404, body = crate::domain::ApiErrorBody),
+    )
+)]
+#[axum::debug_handler(state = crate::domain::AppState)]
+pub async fn metadata(
+    State(database): State<Arc<dyn Database>>,
+    State(permissions): State<Arc<PermissionService>>,
+    session: UserSession,
+    Path((username, filename)): Path<(String, String)>,
+    Json(metadata_request): Json<UnownedAssetMetadata>,
+) -> Result<impl IntoResponse, ApiError> {
+    let broadcaster = permissions
+        .authorize(&session, &username, Capability::Upload)
+        .await?;
+    let asset = database
+        .get_asset(&filename)?
+        .filter(|asset| asset.username == broadcaster.username)
+        .ok_or_else(unknown_asset)?;
+    let metadata = metadata_request
+        .validate()
+        .map_err(|message| ApiError::bad_request("invalid_metadata", message))?;
+    tracing::trace!(?asset.local_filename, ?metadata, "new asset metadata");
+    database.set_asset_metadata(&asset, &metadata)?;
+    let mut tags = metadata.tags;
+    tags.sort();
+    let updated_asset = UserFacingAsset::from(asset)
+        .with_folder(metadata.folder)
+        .with_tags(tags);
+    Ok(JsonResponse::new(updated_asset).with_status(StatusCode::OK))
 }
 
 // TODO: Check credentials
+#[utoipa::path(
+    get,
+    path = "/api/assets/{username}/{filename}",
+    tag = "assets",
+    params(
+        ("username" = String, Path, description = "Broadcaster login"),
+        ("filename" = String, Path, description = "Stored asset filename"),
+    ),
+    responses(
+        (status = 200, description = "Asset contents"),
+        (status = 404, body = crate::domain::ApiErrorBody),
+    )
+)]
 #[axum::debug_handler(state = crate::domain::AppState)]
 pub async fn file(
-    State(database): State<Arc<RwLock<SqliteDbService>>>,
+    State(database): State<Arc<dyn Database>>,
     State(AssetDirectory(asset_dir)): State<AssetDirectory>,
     Path((username, filename)): Path<(String, String)>,
-) -> Result<impl IntoResponse, StatusCode> {
-    let broadcaster = match database.read().await.get_user(&username) {
+) -> Result<impl IntoResponse, ApiError> {
+    let broadcaster = match database.get_user(&username)? {
         Some(user) => user,
         None => {
             tracing::error!(?username, "unknown broadcaster");
-            return Err(StatusCode::NOT_FOUND);
+            return Err(unknown_channel());
         }
     };
 
-    let asset = match database.read().await.get_asset(&filename) {
+    let asset = match database.get_asset(&filename)? {
         Some(asset) => asset,
         None => {
             tracing::error!(?filename, "asset not found in database");
-            return Err(StatusCode::NOT_FOUND);
+            return Err(unknown_asset());
         }
     };
 
@@ -103,16 +218,14 @@ pub async fn file(
             requested_by = ?broadcaster.username,
             "user does not own this asset"
         );
-        return Err(StatusCode::NOT_FOUND);
+        return Err(unknown_asset());
     }
 
     let asset_path = format!("{}/{}", asset_dir, asset.local_filename);
     let data = tokio::fs::read(&asset_path).await.map_err(|err| {
         tracing::error!(?err, ?asset_path, "unable to read file from disk");
-        StatusCode::NOT_FOUND
+        unknown_asset()
     })?;
 
-    let content_type_header = [(header::CONTENT_TYPE, asset.content_type.clone())];
-
-    Ok((StatusCode::OK, content_type_header, data))
+    Ok((StatusCode::OK, file_headers(asset.content_type), data))
 }
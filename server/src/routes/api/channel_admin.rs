@@ -1,66 +1,199 @@
 use std::sync::Arc;
 
 use axum::{extract::State, http::StatusCode, response::IntoResponse};
-use tokio::sync::RwLock;
 
 use crate::{
-    domain::{db::SqliteDbService, JsonResponse, UserSession},
-    models::ChannelAdmin,
+    domain::{
+        db::Database,
+        extract::{Json, Path, Query},
+        ApiError, JsonResponse, ModeratorSync, PermissionService, TwitchTokenStore, UserSession,
+    },
+    models::{Capability, ChannelAdmin, ChannelQuery, ChannelRoleRequest, User},
 };
 
+fn unknown_channel_admin() -> ApiError {
+    ApiError::not_found("unknown_channel_admin", "no such channel admin")
+}
+
+fn validate_login(login: &str) -> Result<String, ApiError> {
+    ChannelAdmin::validate_login(login)
+        .map_err(|message| ApiError::bad_request("invalid_login", message))
+}
+
+async fn authorized_broadcaster(
+    permissions: &PermissionService,
+    session: &UserSession,
+    query: &ChannelQuery,
+) -> Result<User, ApiError> {
+    let session_user = session.user.as_ref().ok_or_else(ApiError::unauthorized)?;
+    let broadcaster = permissions
+        .authorize(
+            session,
+            query.channel_or(&session_user.login),
+            Capability::ManageAdmins,
+        )
+        .await?;
+    Ok(broadcaster)
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/channel-admins",
+    tag = "channel admins",
+    params(ChannelQuery),
+    request_body(content = String, description = "Twitch login", content_type = "text/plain"),
+    responses(
+        (status = 201, body = ChannelAdmin),
+        (status = 200, body = ChannelAdmin),
+        (status = 400, body = crate::domain::ApiErrorBody),
+        (status = 403, body = crate::domain::ApiErrorBody),
+    )
+)]
 #[axum::debug_handler(state = crate::domain::AppState)]
 pub async fn post(
-    State(database): State<Arc<RwLock<SqliteDbService>>>,
+    State(database): State<Arc<dyn Database>>,
+    State(permissions): State<Arc<PermissionService>>,
     session: UserSession,
+    Query(query): Query<ChannelQuery>,
     channel_admin_username: String,
-) -> Result<impl IntoResponse, StatusCode> {
-    let session_user = session.user.ok_or(StatusCode::UNAUTHORIZED)?;
-    let broadcaster = database
-        .read()
-        .await
-        .get_user(&session_user.login)
-        .ok_or(StatusCode::NOT_FOUND)?;
+) -> Result<impl IntoResponse, ApiError> {
+    let broadcaster = authorized_broadcaster(&permissions, &session, &query).await?;
     tracing::trace!(?broadcaster, ?channel_admin_username, "new channel admin");
-    let channel_admin_user = database
-        .read()
-        .await
-        .get_user(&channel_admin_username)
-        .ok_or(StatusCode::NOT_FOUND)?;
+    let channel_admin_username = validate_login(&channel_admin_username)?;
 
-    let existing_channel_admin = database
-        .read()
-        .await
-        .get_channel_admin(&channel_admin_username, &broadcaster);
+    let existing_channel_admin =
+        database.get_channel_admin(&channel_admin_username, &broadcaster)?;
     let response = match existing_channel_admin {
         Some(channel_admin) => JsonResponse::new(channel_admin).with_status(StatusCode::OK),
         None => {
-            let channel_admin = ChannelAdmin::new(&channel_admin_user, &broadcaster);
-            let channel_admin = database
-                .write()
-                .await
-                .create_channel_admin(&channel_admin)
-                .map_err(|_| StatusCode::SERVICE_UNAVAILABLE)?;
+            let channel_admin = ChannelAdmin::for_login(
+                &channel_admin_username,
+                &broadcaster,
+                ChannelAdmin::MANUAL,
+            );
+            let channel_admin = database.create_channel_admin(&channel_admin)?;
             JsonResponse::new(channel_admin).with_status(StatusCode::CREATED)
         }
     };
     Ok(response)
 }
 
+#[utoipa::path(
+    get,
+    path = "/api/channel-admins",
+    tag = "channel admins",
+    params(ChannelQuery),
+    responses(
+        (status = 200, body = Vec<ChannelAdmin>),
+        (status = 403, body = crate::domain::ApiErrorBody),
+    )
+)]
 pub async fn get(
-    State(database): State<Arc<RwLock<SqliteDbService>>>,
+    State(database): State<Arc<dyn Database>>,
+    State(permissions): State<Arc<PermissionService>>,
     session: UserSession,
-) -> Result<impl IntoResponse, StatusCode> {
-    let session_user = session.user.ok_or(StatusCode::UNAUTHORIZED)?;
-    let user = database
-        .read()
-        .await
-        .get_user(&session_user.login)
-        .ok_or(StatusCode::NOT_FOUND)?;
-    let channel_admins = database
-        .read()
-        .await
-        .get_channel_admins(&user)
-        .map_err(|_| StatusCode::SERVICE_UNAVAILABLE)?;
+    Query(query): Query<ChannelQuery>,
+) -> Result<impl IntoResponse, ApiError> {
+    let user = authorized_broadcaster(&permissions, &session, &query).await?;
+    let channel_admins = database.get_channel_admins(&user)?;
     let response = JsonResponse::new(channel_admins).with_status(StatusCode::OK);
     Ok(response)
 }
+
+#[utoipa::path(
+    put,
+    path = "/api/channel-admins/{username}",
+    tag = "channel admins",
+    params(
+        ChannelQuery,
+        ("username" = String, Path, description = "Channel admin login"),
+    ),
+    request_body = ChannelRoleRequest,
+    responses(
+        (status = 200, body = ChannelAdmin),
+        (status = 400, body = crate::domain::ApiErrorBody),
+        (status = 403, body = crate::domain::ApiErrorBody),
+        (status = 404, body = crate::domain::ApiErrorBody),
+    )
+)]
+#[axum::debug_handler(state = crate::domain::AppState)]
+pub async fn put(
+    State(database): State<Arc<dyn Database>>,
+    State(permissions): State<Arc<PermissionService>>,
+    session: UserSession,
+    Query(query): Query<ChannelQuery>,
+    Path(channel_admin_username): Path<String>,
+    Json(role_request): Json<ChannelRoleRequest>,
+) -> Result<impl IntoResponse, ApiError> {
+    let broadcaster = authorized_broadcaster(&permissions, &session, &query).await?;
+    let channel_admin_username = validate_login(&channel_admin_username)?;
+    if !role_request.role.is_assignable() {
+        tracing::warn!(?broadcaster, ?role_request, "role cannot be assigned");
+        return Err(ApiError::bad_request(
+            "invalid_role",
+            "this role cannot be assigned",
+        ));
+    }
+    let channel_admin = database
+        .set_channel_admin_role(&channel_admin_username, &broadcaster, role_request.role)?
+        .ok_or_else(unknown_channel_admin)?;
+    Ok(JsonResponse::new(channel_admin).with_status(StatusCode::OK))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/channel-admins/{username}",
+    tag = "channel admins",
+    params(
+        ChannelQuery,
+        ("username" = String, Path, description = "Channel admin login"),
+    ),
+    responses(
+        (status = 204),
+        (status = 403, body = crate::domain::ApiErrorBody),
+        (status = 404, body = crate::domain::ApiErrorBody),
+    )
+)]
+#[axum::debug_handler(state = crate::domain::AppState)]
+pub async fn delete(
+    State(database): State<Arc<dyn Database>>,
+    State(permissions): State<Arc<PermissionService>>,
+    session: UserSession,
+    Query(query): Query<ChannelQuery>,
+    Path(channel_admin_username): Path<String>,
+) -> Result<impl IntoResponse, ApiError> {
+    let broadcaster = authorized_broadcaster(&permissions, &session, &query).await?;
+    let channel_admin_username = validate_login(&channel_admin_username)?;
+    let deleted = database.delete_channel_admin(&channel_admin_username, &broadcaster)?;
+    if deleted == 0 {
+        return Err(unknown_channel_admin());
+    }
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/channel-admins/sync",
+    tag = "channel admins",
+    params(ChannelQuery),
+    responses(
+        (status = 200, body = crate::domain::moderator_sync::ModeratorSyncSummary),
+        (status = 403, body = crate::domain::ApiErrorBody),
+        (status = 502, body = crate::domain::ApiErrorBody),
+    )
+)]
+#[axum::debug_handler(state = crate::domain::AppState)]
+pub async fn sync(
+    State(database): State<Arc<dyn Database>>,
+    State(permissions): State<Arc<PermissionService>>,
+    State(token_store): State<Arc<TwitchTokenStore>>,
+    session: UserSession,
+    Query(query): Query<ChannelQuery>,
+) -> Result<impl IntoResponse, ApiError> {
+    let broadcaster = authorized_broadcaster(&permissions, &session, &query).await?;
+    let summary = ModeratorSync::new(Arc::clone(&database), token_store)
+        .sync(&broadcaster)
+        .await
+        .inspect_err(|error| tracing::error!(?error, "moderator sync failed"))?;
+    Ok(JsonResponse::new(summary).with_status(StatusCode::OK))
+}
@@ -0,0 +1,63 @@
+use std::sync::Arc;
+
+use axum::{
+    body::Bytes,
+    extract::State,
+    http::{header, HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+};
+
+use crate::{
+    domain::{ApiError, EventSubError, EventSubService},
+    twitch::EventSubMessageType,
+};
+
+#[utoipa::path(
+    post,
+    path = "/api/eventsub",
+    tag = "twitch",
+    request_body(content = String, description = "Twitch EventSub webhook payload"),
+    responses(
+        (status = 200, description = "Verification challenge"),
+        (status = 204),
+        (status = 400, body = crate::domain::ApiErrorBody),
+        (status = 403, body = crate::domain::ApiErrorBody),
+    )
+)]
+/// Only routed when eventsub is configured, so it has its own state.
+#[axum::debug_handler(state = Arc<EventSubService>)]
+pub async fn post(
+    State(eventsub): State<Arc<EventSubService>>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Result<Response, ApiError> {
+    let (message_type, payload) = match eventsub.verify(&headers, &body).await {
+        Ok(message) => message,
+        Err(EventSubError::DuplicateMessage) => return Ok(StatusCode::NO_CONTENT.into_response()),
+        Err(error) => {
+            tracing::warn!(?error, "rejected eventsub message");
+            return Err(error.into());
+        }
+    };
+    match message_type {
+        EventSubMessageType::Verification => {
+            let challenge = eventsub.confirm(&payload).await?;
+            Ok((
+                StatusCode::OK,
+                [(header::CONTENT_TYPE, "text/plain")],
+                challenge,
+            )
+                .into_response())
+        }
+        EventSubMessageType::Notification => {
+            if let Err(error) = eventsub.notify(&payload).await {
+                tracing::error!(?error, "unable to handle eventsub notification");
+            }
+            Ok(StatusCode::NO_CONTENT.into_response())
+        }
+        EventSubMessageType::Revocation => {
+            eventsub.revoke(&payload).await?;
+            Ok(StatusCode::NO_CONTENT.into_response())
+        }
+    }
+}
@@ -0,0 +1,34 @@
+use std::sync::Arc;
+
+use axum::extract::State;
+
+use crate::{
+    domain::{
+        db::Database,
+        extract::{Json, Path},
+        ApiError,
+    },
+    models::Folder,
+};
+
+// TODO: Check credentials
+#[utoipa::path(
+    get,
+    path = "/api/folders/{username}",
+    tag = "assets",
+    params(("username" = String, Path, description = "Broadcaster login")),
+    responses(
+        (status = 200, body = Vec<Folder>),
+    )
+)]
+#[axum::debug_handler(state = crate::domain::AppState)]
+pub async fn get(
+    State(database): State<Arc<dyn Database>>,
+    Path(username): Path<String>,
+) -> Result<Json<Vec<Folder>>, ApiError> {
+    let broadcaster = database
+        .get_user(&username)?
+        .ok_or_else(|| ApiError::not_found("unknown_channel", "no such channel"))?;
+    let folders = database.get_folders(&broadcaster)?;
+    Ok(Json(folders))
+}
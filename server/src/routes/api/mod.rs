@@ -1,4 +1,14 @@
+pub mod api_token;
+pub mod archive;
 pub mod asset;
 pub mod channel_admin;
+pub mod eventsub;
+pub mod folder;
+pub mod openapi;
+pub mod overlay_token;
+pub mod scene;
 pub mod settings;
+pub mod submission;
+pub mod tag;
+pub mod trigger;
 pub mod whoami;
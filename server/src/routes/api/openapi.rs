@@ -0,0 +1,133 @@
+use axum::Json;
+use utoipa::{
+    openapi::security::{ApiKey, ApiKeyValue, HttpAuthScheme, HttpBuilder, SecurityScheme},
+    Modify, OpenApi,
+};
+
+use crate::{
+    domain::{
+        message::{
+            ImgfloatAsset, ImgfloatAssetStateMessage, ImgfloatServerMessage, ImgfloatState,
+            ImgfloatWriterNotification,
+        },
+        moderator_sync::ModeratorSyncSummary,
+        ApiErrorBody,
+    },
+    models::{
+        AssetImportSummary, AssetPage, Capability, ChannelAdmin, ChannelRole, ChannelRoleRequest,
+        EventTrigger, Folder, IssuedApiToken, IssuedOverlayToken, NewSceneAsset, OverlayToken,
+        ScenePlacement, Submission, SubmissionDecision, SubmissionReview, Tag, UnownedApiToken,
+        UnownedAssetMetadata, UnownedEventTrigger, UnownedUserSettings, UserFacingApiToken,
+        UserFacingAsset, UserSettings, ValidatedAssetMetadata,
+    },
+    twitch::TwitchUser,
+};
+
+struct SecuritySchemes;
+
+impl Modify for SecuritySchemes {
+    fn modify(&self, openapi: &mut utoipa::openapi::OpenApi) {
+        let components = openapi.components.get_or_insert_with(Default::default);
+        components.add_security_scheme(
+            "session",
+            SecurityScheme::ApiKey(ApiKey::Cookie(ApiKeyValue::new("id"))),
+        );
+        components.add_security_scheme(
+            "api_token",
+            SecurityScheme::Http(HttpBuilder::new().scheme(HttpAuthScheme::Bearer).build()),
+        );
+    }
+}
+
+#[derive(OpenApi)]
+#[openapi(
+    info(title = "imgfloat", description = "Overlay asset and scene management"),
+    paths(
+        super::whoami::get,
+        super::api_token::get,
+        super::api_token::post,
+        super::api_token::delete,
+        super::asset::get,
+        super::asset::post,
+        super::asset::file,
+        super::asset::metadata,
+        super::scene::get,
+        super::scene::post,
+        super::scene::put,
+        super::scene::delete,
+        super::archive::get,
+        super::archive::post,
+        super::folder::get,
+        super::tag::get,
+        super::trigger::get,
+        super::trigger::post,
+        super::trigger::delete,
+        super::submission::get,
+        super::submission::post,
+        super::submission::review,
+        super::submission::file,
+        super::eventsub::post,
+        super::channel_admin::get,
+        super::channel_admin::post,
+        super::channel_admin::sync,
+        super::channel_admin::put,
+        super::channel_admin::delete,
+        super::settings::get,
+        super::settings::put,
+        super::overlay_token::get,
+        super::overlay_token::post,
+        super::overlay_token::delete,
+        get,
+    ),
+    components(schemas(
+        ApiErrorBody,
+        AssetImportSummary,
+        AssetPage,
+        Capability,
+        ChannelAdmin,
+        ChannelRole,
+        ChannelRoleRequest,
+        EventTrigger,
+        Folder,
+        ImgfloatAsset,
+        ImgfloatAssetStateMessage,
+        ImgfloatServerMessage,
+        ImgfloatState,
+        ImgfloatWriterNotification,
+        IssuedApiToken,
+        IssuedOverlayToken,
+        ModeratorSyncSummary,
+        NewSceneAsset,
+        OverlayToken,
+        ScenePlacement,
+        Submission,
+        SubmissionDecision,
+        SubmissionReview,
+        Tag,
+        TwitchUser,
+        UnownedApiToken,
+        UnownedAssetMetadata,
+        UnownedEventTrigger,
+        UnownedUserSettings,
+        UserFacingApiToken,
+        UserFacingAsset,
+        UserSettings,
+        ValidatedAssetMetadata,
+    )),
+    modifiers(&SecuritySchemes),
+    security(("session" = []), ("api_token" = [])),
+)]
+pub struct ApiDoc;
+
+#[utoipa::path(
+    get,
+    path = "/api/openapi.json",
+    tag = "meta",
+    security(()),
+    responses(
+        (status = 200, description = "This document"),
+    )
+)]
+pub async fn get() -> Json<utoipa::openapi::OpenApi> {
+    Json(ApiDoc::openapi())
+}
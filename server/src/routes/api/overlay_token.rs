@@ -0,0 +1,116 @@
+use std::sync::Arc;
+
+use axum::{extract::State, http::StatusCode, response::IntoResponse};
+
+use crate::{
+    domain::{
+        db::Database,
+        extract::{Json, Query},
+        ApiError, ChannelController, JsonResponse, PermissionService, UserSession,
+    },
+    models::{Capability, ChannelQuery, IssuedOverlayToken, OverlayToken, User},
+};
+
+fn unknown_overlay_token() -> ApiError {
+    ApiError::not_found("unknown_overlay_token", "no overlay token has been issued")
+}
+
+async fn authorize(
+    permissions: &PermissionService,
+    session: &UserSession,
+    query: &ChannelQuery,
+) -> Result<User, ApiError> {
+    let session_user = session.user.as_ref().ok_or_else(ApiError::unauthorized)?;
+    let user = permissions
+        .authorize(
+            session,
+            query.channel_or(&session_user.login),
+            Capability::ChangeSettings,
+        )
+        .await?;
+    Ok(user)
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/settings/overlay-token",
+    tag = "settings",
+    params(ChannelQuery),
+    responses(
+        (status = 200, body = OverlayToken),
+        (status = 403, body = crate::domain::ApiErrorBody),
+        (status = 404, body = crate::domain::ApiErrorBody),
+    )
+)]
+#[axum::debug_handler(state = crate::domain::AppState)]
+pub async fn get(
+    State(database): State<Arc<dyn Database>>,
+    State(permissions): State<Arc<PermissionService>>,
+    session: UserSession,
+    Query(query): Query<ChannelQuery>,
+) -> Result<Json<OverlayToken>, ApiError> {
+    let user = authorize(&permissions, &session, &query).await?;
+    let overlay_token = database
+        .get_overlay_token(&user)?
+        .ok_or_else(unknown_overlay_token)?;
+    Ok(Json(overlay_token))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/settings/overlay-token",
+    tag = "settings",
+    params(ChannelQuery),
+    responses(
+        (status = 201, body = IssuedOverlayToken),
+        (status = 403, body = crate::domain::ApiErrorBody),
+    )
+)]
+#[axum::debug_handler(state = crate::domain::AppState)]
+pub async fn post(
+    State(database): State<Arc<dyn Database>>,
+    State(controller): State<Arc<ChannelController>>,
+    State(permissions): State<Arc<PermissionService>>,
+    session: UserSession,
+    Query(query): Query<ChannelQuery>,
+) -> Result<impl IntoResponse, ApiError> {
+    let user = authorize(&permissions, &session, &query).await?;
+    let (overlay_token, token) = OverlayToken::generate(&user);
+    database.save_overlay_token(&overlay_token)?;
+    tracing::info!(?user, "issued overlay token");
+    controller.close_readers(&user.username).await;
+    Ok(JsonResponse::new(IssuedOverlayToken {
+        token,
+        created_at: overlay_token.created_at,
+    })
+    .with_status(StatusCode::CREATED))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/settings/overlay-token",
+    tag = "settings",
+    params(ChannelQuery),
+    responses(
+        (status = 204),
+        (status = 403, body = crate::domain::ApiErrorBody),
+        (status = 404, body = crate::domain::ApiErrorBody),
+    )
+)]
+#[axum::debug_handler(state = crate::domain::AppState)]
+pub async fn delete(
+    State(database): State<Arc<dyn Database>>,
+    State(controller): State<Arc<ChannelController>>,
+    State(permissions): State<Arc<PermissionService>>,
+    session: UserSession,
+    Query(query): Query<ChannelQuery>,
+) -> Result<StatusCode, ApiError> {
+    let user = authorize(&permissions, &session, &query).await?;
+    let deleted = database.delete_overlay_token(&user)?;
+    if deleted == 0 {
+        return Err(unknown_overlay_token());
+    }
+    tracing::info!(?user, "revoked overlay token");
+    controller.close_readers(&user.username).await;
+    Ok(StatusCode::NO_CONTENT)
+}
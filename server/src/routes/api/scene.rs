@@ -0,0 +1,179 @@
+use std::sync::Arc;
+
+use axum::{extract::State, http::StatusCode, response::IntoResponse};
+
+use crate::{
+    domain::{
+        db::Database,
+        extract::{Json, Path},
+        message::{ImgfloatAsset, ImgfloatAssetStateMessage},
+        ApiError, ChannelController, JsonResponse, PermissionService, UserSession,
+    },
+    models::{Capability, NewSceneAsset, ScenePlacement},
+};
+
+fn unknown_scene_asset() -> ApiError {
+    ApiError::not_found("unknown_scene_asset", "asset is not shown in this scene")
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/channels/{username}/assets",
+    tag = "scene",
+    params(("username" = String, Path, description = "Broadcaster login")),
+    responses(
+        (status = 200, body = Vec<ImgfloatAsset>),
+        (status = 403, body = crate::domain::ApiErrorBody),
+    )
+)]
+#[axum::debug_handler(state = crate::domain::AppState)]
+pub async fn get(
+    State(controller): State<Arc<ChannelController>>,
+    State(permissions): State<Arc<PermissionService>>,
+    session: UserSession,
+    Path(username): Path<String>,
+) -> Result<Json<Vec<ImgfloatAsset>>, ApiError> {
+    let broadcaster = permissions
+        .authorize(&session, &username, Capability::PlaceAssets)
+        .await?;
+    let assets = controller
+        .get_state(&broadcaster.username)
+        .await
+        .map(|state| state.assets)
+        .unwrap_or_default();
+    Ok(Json(assets))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/channels/{username}/assets",
+    tag = "scene",
+    params(("username" = String, Path, description = "Broadcaster login")),
+    request_body = NewSceneAsset,
+    responses(
+        (status = 201, body = ImgfloatAsset),
+        (status = 400, body = crate::domain::ApiErrorBody),
+        (status = 403, body = crate::domain::ApiErrorBody),
+        (status = 404, body = crate::domain::ApiErrorBody),
+    )
+)]
+#[axum::debug_handler(state = crate::domain::AppState)]
+pub async fn post(
+    State(database): State<Arc<dyn Database>>,
+    State(controller): State<Arc<ChannelController>>,
+    State(permissions): State<Arc<PermissionService>>,
+    session: UserSession,
+    Path(username): Path<String>,
+    Json(scene_asset): Json<NewSceneAsset>,
+) -> Result<impl IntoResponse, ApiError> {
+    let broadcaster = permissions
+        .authorize(&session, &username, Capability::PlaceAssets)
+        .await?;
+    let placement = scene_asset
+        .placement
+        .validate()
+        .map_err(|message| ApiError::bad_request("invalid_placement", message))?;
+    let asset = database
+        .get_asset(&scene_asset.filename)?
+        .filter(|asset| asset.username == broadcaster.username)
+        .ok_or_else(|| ApiError::not_found("unknown_asset", "no such asset"))?;
+    let shown = ImgfloatAsset {
+        id: uuid::Uuid::new_v4().to_string(),
+        x: placement.x,
+        y: placement.y,
+        w: placement.w,
+        h: placement.h,
+        theta: placement.theta,
+        url: format!(
+            "/api/assets/{}/{}",
+            broadcaster.username, asset.local_filename
+        ),
+    };
+    tracing::info!(?broadcaster, ?shown, "scene asset added");
+    controller
+        .show_asset(&broadcaster.username, shown.clone())
+        .await;
+    Ok(JsonResponse::new(shown).with_status(StatusCode::CREATED))
+}
+
+#[utoipa::path(
+    put,
+    path = "/api/channels/{username}/assets/{id}",
+    tag = "scene",
+    params(
+        ("username" = String, Path, description = "Broadcaster login"),
+        ("id" = String, Path, description = "Live asset id"),
+    ),
+    request_body = ScenePlacement,
+    responses(
+        (status = 200, body = ImgfloatAsset),
+        (status = 400, body = crate::domain::ApiErrorBody),
+        (status = 403, body = crate::domain::ApiErrorBody),
+        (status = 404, body = crate::domain::ApiErrorBody),
+    )
+)]
+#[axum::debug_handler(state = crate::domain::AppState)]
+pub async fn put(
+    State(controller): State<Arc<ChannelController>>,
+    State(permissions): State<Arc<PermissionService>>,
+    session: UserSession,
+    Path((username, id)): Path<(String, String)>,
+    Json(placement): Json<ScenePlacement>,
+) -> Result<Json<ImgfloatAsset>, ApiError> {
+    let broadcaster = permissions
+        .authorize(&session, &username, Capability::PlaceAssets)
+        .await?;
+    let placement = placement
+        .validate()
+        .map_err(|message| ApiError::bad_request("invalid_placement", message))?;
+    let mut asset = controller
+        .get_asset(&broadcaster.username, &id)
+        .await
+        .ok_or_else(unknown_scene_asset)?;
+    asset.x = placement.x;
+    asset.y = placement.y;
+    asset.w = placement.w;
+    asset.h = placement.h;
+    asset.theta = placement.theta;
+    tracing::info!(?broadcaster, ?asset, "scene asset updated");
+    controller
+        .publish(
+            &broadcaster.username,
+            ImgfloatAssetStateMessage::Update(asset.clone()),
+        )
+        .await;
+    Ok(Json(asset))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/channels/{username}/assets/{id}",
+    tag = "scene",
+    params(
+        ("username" = String, Path, description = "Broadcaster login"),
+        ("id" = String, Path, description = "Live asset id"),
+    ),
+    responses(
+        (status = 204),
+        (status = 403, body = crate::domain::ApiErrorBody),
+        (status = 404, body = crate::domain::ApiErrorBody),
+    )
+)]
+#[axum::debug_handler(state = crate::domain::AppState)]
+pub async fn delete(
+    State(controller): State<Arc<ChannelController>>,
+    State(permissions): State<Arc<PermissionService>>,
+    session: UserSession,
+    Path((username, id)): Path<(String, String)>,
+) -> Result<StatusCode, ApiError> {
+    let broadcaster = permissions
+        .authorize(&session, &username, Capability::DeleteAssets)
+        .await?;
+    controller
+        .get_asset(&broadcaster.username, &id)
+        .await
+        .ok_or_else(unknown_scene_asset)?;
+    tracing::info!(?broadcaster, ?id, "scene asset removed");
+    controller.hide_asset(&broadcaster.username, &id).await;
+    Ok(StatusCode::NO_CONTENT)
+}
@@ -1,32 +1,59 @@
 use std::sync::Arc;
 
-use axum::{extract::State, http::StatusCode, response::IntoResponse, Json};
-use tokio::sync::RwLock;
+use axum::{extract::State, http::StatusCode, response::IntoResponse};
 
 use crate::{
-    domain::{db::SqliteDbService, JsonResponse, UserSession},
-    models::{user_settings::ValidatedUnownedUserSettings, UnownedUserSettings},
+    domain::{
+        db::{Database, DbError},
+        extract::{Json, Query},
+        ApiError, JsonResponse, PermissionService, UserSession,
+    },
+    models::{
+        user_settings::ValidatedUnownedUserSettings, Capability, ChannelQuery, UnownedUserSettings,
+    },
 };
 
+#[utoipa::path(
+    put,
+    path = "/api/settings",
+    tag = "settings",
+    params(ChannelQuery),
+    request_body = UnownedUserSettings,
+    responses(
+        (status = 200, body = crate::models::UserSettings),
+        (status = 201, body = crate::models::UserSettings),
+        (status = 400, body = crate::domain::ApiErrorBody),
+        (status = 403, body = crate::domain::ApiErrorBody),
+    )
+)]
 #[axum::debug_handler(state = crate::domain::AppState)]
 pub async fn put(
-    State(database): State<Arc<RwLock<SqliteDbService>>>,
+    State(database): State<Arc<dyn Database>>,
+    State(permissions): State<Arc<PermissionService>>,
     session: UserSession,
+    Query(query): Query<ChannelQuery>,
     Json(settings_request): Json<UnownedUserSettings>,
-) -> Result<impl IntoResponse, StatusCode> {
-    let session_user = session.user.ok_or(StatusCode::UNAUTHORIZED)?;
-    let user = database
-        .read()
-        .await
-        .get_user(&session_user.login)
-        .ok_or(StatusCode::NOT_FOUND)?;
+) -> Result<impl IntoResponse, ApiError> {
+    let session_user = session.user.as_ref().ok_or_else(ApiError::unauthorized)?;
+    let user = permissions
+        .authorize(
+            &session,
+            query.channel_or(&session_user.login),
+            Capability::ChangeSettings,
+        )
+        .await?;
     tracing::trace!(?user, ?settings_request, "new settings");
     let settings = settings_request
         .validate()
-        .map_err(|_| StatusCode::BAD_REQUEST)?
+        .map_err(|opacity| {
+            ApiError::bad_request(
+                "invalid_settings",
+                format!("background opacity {opacity} is out of range"),
+            )
+        })?
         .with_owner(&user);
     tracing::trace!(?user, ?settings, "change to settings validated");
-    let current_settings = database.read().await.get_user_settings(&user);
+    let current_settings = database.get_user_settings(&user)?;
     match current_settings {
         Some(current_settings) => {
             let status_code = if current_settings == settings {
@@ -34,51 +61,58 @@ pub async fn put(
                 StatusCode::OK
             } else {
                 tracing::trace!(?user, ?settings, "writing new settings");
-                database
-                    .write()
-                    .await
-                    .update_user_settings(&settings)
-                    .map_err(|_| StatusCode::SERVICE_UNAVAILABLE)?;
+                database.update_user_settings(&settings)?;
+                if current_settings.sync_moderators && !settings.sync_moderators {
+                    // nothing keeps synced moderators current once sync is off
+                    let (_, removed) = database.replace_synced_moderators(&user, &[])?;
+                    tracing::debug!(?user, ?removed, "removed synced moderators");
+                }
                 StatusCode::CREATED
             };
             Ok(JsonResponse::new(settings).with_status(status_code))
         }
         None => {
-            let new_settings = database
-                .write()
-                .await
-                .create_user_settings(&settings)
-                .map_err(|_| StatusCode::SERVICE_UNAVAILABLE)?;
+            let new_settings = match database.create_user_settings(&settings) {
+                // a concurrent request created them first
+                Err(DbError::Conflict(_)) => database.update_user_settings(&settings)?,
+                result => result?,
+            };
             Ok(JsonResponse::new(new_settings).with_status(StatusCode::CREATED))
         }
     }
 }
 
+#[utoipa::path(
+    get,
+    path = "/api/settings",
+    tag = "settings",
+    params(ChannelQuery),
+    responses(
+        (status = 200, body = crate::models::UserSettings),
+        (status = 201, body = crate::models::UserSettings),
+        (status = 403, body = crate::domain::ApiErrorBody),
+    )
+)]
 pub async fn get(
-    State(database): State<Arc<RwLock<SqliteDbService>>>,
+    State(database): State<Arc<dyn Database>>,
+    State(permissions): State<Arc<PermissionService>>,
     session: UserSession,
-) -> Result<impl IntoResponse, StatusCode> {
-    let session_user = session.user.ok_or(StatusCode::UNAUTHORIZED)?;
-    let user = database
-        .read()
-        .await
-        .get_user(&session_user.login)
-        .ok_or(StatusCode::NOT_FOUND)?;
-
-    let existing_settings = {
-        let db = database.read().await;
-        db.get_user_settings(&user)
-    };
+    Query(query): Query<ChannelQuery>,
+) -> Result<impl IntoResponse, ApiError> {
+    let session_user = session.user.as_ref().ok_or_else(ApiError::unauthorized)?;
+    let user = permissions
+        .authorize(
+            &session,
+            query.channel_or(&session_user.login),
+            Capability::ChangeSettings,
+        )
+        .await?;
 
-    match existing_settings {
+    match database.get_user_settings(&user)? {
         Some(settings) => Ok(JsonResponse::new(settings).with_status(StatusCode::OK)),
         None => {
             let new_settings = ValidatedUnownedUserSettings::default().with_owner(&user);
-            database
-                .write()
-                .await
-                .create_user_settings(&new_settings)
-                .map_err(|_| StatusCode::SERVICE_UNAVAILABLE)?;
+            database.create_user_settings(&new_settings)?;
             Ok(JsonResponse::new(new_settings).with_status(StatusCode::CREATED))
         }
     }
@@ -0,0 +1,166 @@
+use std::sync::Arc;
+
+use axum::{
+    extract::{Multipart, State},
+    http::StatusCode,
+    response::IntoResponse,
+};
+
+use crate::{
+    domain::{
+        db::Database,
+        extract::{Json, Path, Query},
+        ApiError, JsonResponse, PermissionService, SubmissionQueue, UserSession,
+    },
+    models::{Capability, Submission, SubmissionQuery, SubmissionReview},
+};
+
+use super::asset::file_headers;
+
+fn unknown_submission() -> ApiError {
+    ApiError::not_found("unknown_submission", "no such submission")
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/submissions/{username}",
+    tag = "submissions",
+    params(("username" = String, Path, description = "Broadcaster login")),
+    request_body(content_type = "multipart/form-data", description = "A single image field"),
+    responses(
+        (status = 201, body = Submission),
+        (status = 401, body = crate::domain::ApiErrorBody),
+        (status = 403, body = crate::domain::ApiErrorBody),
+        (status = 415, body = crate::domain::ApiErrorBody),
+        (status = 429, body = crate::domain::ApiErrorBody),
+    )
+)]
+#[axum::debug_handler(state = crate::domain::AppState)]
+pub async fn post(
+    State(permissions): State<Arc<PermissionService>>,
+    State(submissions): State<Arc<SubmissionQueue>>,
+    session: UserSession,
+    Path(username): Path<String>,
+    mut multipart: Multipart,
+) -> Result<impl IntoResponse, ApiError> {
+    // any logged-in viewer may submit, as a viewer-submitter by default
+    let broadcaster = permissions
+        .authorize(&session, &username, Capability::Submit)
+        .await?;
+    let session_user = session.user.ok_or_else(ApiError::unauthorized)?;
+    let field = multipart
+        .next_field()
+        .await
+        .inspect_err(|error| tracing::error!(?error, "no multipart request body"))
+        .map_err(|error| ApiError::bad_request("invalid_multipart", error.body_text()))?
+        .ok_or_else(|| {
+            ApiError::bad_request("missing_file", "the request did not contain a file")
+        })?;
+    let submission = submissions
+        .submit(&broadcaster, &session_user.login, field)
+        .await?;
+    Ok(JsonResponse::new(submission).with_status(StatusCode::CREATED))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/submissions/{username}",
+    tag = "submissions",
+    params(
+        ("username" = String, Path, description = "Broadcaster login"),
+        SubmissionQuery,
+    ),
+    responses(
+        (status = 200, body = Vec<Submission>),
+        (status = 403, body = crate::domain::ApiErrorBody),
+    )
+)]
+#[axum::debug_handler(state = crate::domain::AppState)]
+pub async fn get(
+    State(database): State<Arc<dyn Database>>,
+    State(permissions): State<Arc<PermissionService>>,
+    session: UserSession,
+    Path(username): Path<String>,
+    Query(query): Query<SubmissionQuery>,
+) -> Result<Json<Vec<Submission>>, ApiError> {
+    let broadcaster = permissions
+        .authorize(&session, &username, Capability::PlaceAssets)
+        .await?;
+    let status = query.status.as_deref().unwrap_or(Submission::PENDING);
+    let submissions = database.get_submissions(&broadcaster, Some(status))?;
+    Ok(Json(submissions))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/submissions/{username}/{id}/file",
+    tag = "submissions",
+    params(
+        ("username" = String, Path, description = "Broadcaster login"),
+        ("id" = i32, Path, description = "Submission id"),
+    ),
+    responses(
+        (status = 200, description = "Submitted image"),
+        (status = 403, body = crate::domain::ApiErrorBody),
+        (status = 404, body = crate::domain::ApiErrorBody),
+    )
+)]
+#[axum::debug_handler(state = crate::domain::AppState)]
+pub async fn file(
+    State(database): State<Arc<dyn Database>>,
+    State(permissions): State<Arc<PermissionService>>,
+    State(submissions): State<Arc<SubmissionQueue>>,
+    session: UserSession,
+    Path((username, id)): Path<(String, i32)>,
+) -> Result<impl IntoResponse, ApiError> {
+    let broadcaster = permissions
+        .authorize(&session, &username, Capability::PlaceAssets)
+        .await?;
+    let submission = database
+        .get_submission(&broadcaster, id)?
+        .filter(Submission::is_pending)
+        .ok_or_else(unknown_submission)?;
+    let data = submissions
+        .read_file(&submission)
+        .await
+        .map_err(|_| unknown_submission())?;
+    Ok((StatusCode::OK, file_headers(submission.content_type), data))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/submissions/{username}/{id}",
+    tag = "submissions",
+    params(
+        ("username" = String, Path, description = "Broadcaster login"),
+        ("id" = i32, Path, description = "Submission id"),
+    ),
+    request_body = SubmissionReview,
+    responses(
+        (status = 200, body = Submission),
+        (status = 403, body = crate::domain::ApiErrorBody),
+        (status = 404, body = crate::domain::ApiErrorBody),
+        (status = 409, body = crate::domain::ApiErrorBody),
+    )
+)]
+#[axum::debug_handler(state = crate::domain::AppState)]
+pub async fn review(
+    State(database): State<Arc<dyn Database>>,
+    State(permissions): State<Arc<PermissionService>>,
+    State(submissions): State<Arc<SubmissionQueue>>,
+    session: UserSession,
+    Path((username, id)): Path<(String, i32)>,
+    Json(review): Json<SubmissionReview>,
+) -> Result<impl IntoResponse, ApiError> {
+    let broadcaster = permissions
+        .authorize(&session, &username, Capability::PlaceAssets)
+        .await?;
+    let reviewer = session.user.ok_or_else(ApiError::unauthorized)?.login;
+    let submission = database
+        .get_submission(&broadcaster, id)?
+        .ok_or_else(unknown_submission)?;
+    let reviewed = submissions
+        .review(&broadcaster, &submission, &reviewer, review.decision)
+        .await?;
+    Ok(JsonResponse::new(reviewed).with_status(StatusCode::OK))
+}
@@ -0,0 +1,126 @@
+use std::sync::Arc;
+
+use axum::{extract::State, http::StatusCode, response::IntoResponse};
+
+use crate::{
+    domain::{
+        db::Database,
+        extract::{Json, Path},
+        ApiError, EventSubService, JsonResponse, PermissionService, UserSession,
+    },
+    models::{Capability, EventTrigger, UnownedEventTrigger},
+};
+
+#[utoipa::path(
+    get,
+    path = "/api/triggers/{username}",
+    tag = "triggers",
+    params(("username" = String, Path, description = "Broadcaster login")),
+    responses(
+        (status = 200, body = Vec<EventTrigger>),
+        (status = 403, body = crate::domain::ApiErrorBody),
+    )
+)]
+#[axum::debug_handler(state = crate::domain::AppState)]
+pub async fn get(
+    State(database): State<Arc<dyn Database>>,
+    State(permissions): State<Arc<PermissionService>>,
+    session: UserSession,
+    Path(username): Path<String>,
+) -> Result<Json<Vec<EventTrigger>>, ApiError> {
+    let broadcaster = permissions
+        .authorize(&session, &username, Capability::ManageScenes)
+        .await?;
+    let triggers = database.get_event_triggers(&broadcaster)?;
+    Ok(Json(triggers))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/triggers/{username}",
+    tag = "triggers",
+    params(("username" = String, Path, description = "Broadcaster login")),
+    request_body = UnownedEventTrigger,
+    responses(
+        (status = 201, body = EventTrigger),
+        (status = 400, body = crate::domain::ApiErrorBody),
+        (status = 403, body = crate::domain::ApiErrorBody),
+        (status = 404, body = crate::domain::ApiErrorBody),
+        (status = 503, body = crate::domain::ApiErrorBody),
+    )
+)]
+#[axum::debug_handler(state = crate::domain::AppState)]
+pub async fn post(
+    State(database): State<Arc<dyn Database>>,
+    State(permissions): State<Arc<PermissionService>>,
+    State(eventsub): State<Option<Arc<EventSubService>>>,
+    session: UserSession,
+    Path(username): Path<String>,
+    Json(trigger_request): Json<UnownedEventTrigger>,
+) -> Result<impl IntoResponse, ApiError> {
+    let broadcaster = permissions
+        .authorize(&session, &username, Capability::ManageScenes)
+        .await?;
+    // without eventsub nothing could ever fire the trigger
+    let eventsub = eventsub.ok_or_else(|| {
+        ApiError::new(
+            StatusCode::SERVICE_UNAVAILABLE,
+            "eventsub_disabled",
+            "event triggers are not enabled on this server",
+        )
+    })?;
+    let trigger = trigger_request
+        .validate()
+        .map_err(|message| ApiError::bad_request("invalid_trigger", message))?;
+    database
+        .get_asset(trigger.asset_filename())?
+        .filter(|asset| asset.username == broadcaster.username)
+        .ok_or_else(|| ApiError::not_found("unknown_asset", "no such asset"))?;
+    let trigger = trigger.with_owner(&broadcaster);
+    eventsub
+        .subscribe(&broadcaster.username, &trigger.event_type)
+        .await
+        .inspect_err(|error| tracing::error!(?error, "unable to subscribe to event"))?;
+    let trigger = database.create_event_trigger(&trigger)?;
+    Ok(JsonResponse::new(trigger).with_status(StatusCode::CREATED))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/triggers/{username}/{id}",
+    tag = "triggers",
+    params(
+        ("username" = String, Path, description = "Broadcaster login"),
+        ("id" = i32, Path, description = "Trigger id"),
+    ),
+    responses(
+        (status = 204),
+        (status = 403, body = crate::domain::ApiErrorBody),
+        (status = 404, body = crate::domain::ApiErrorBody),
+    )
+)]
+#[axum::debug_handler(state = crate::domain::AppState)]
+pub async fn delete(
+    State(database): State<Arc<dyn Database>>,
+    State(permissions): State<Arc<PermissionService>>,
+    State(eventsub): State<Option<Arc<EventSubService>>>,
+    session: UserSession,
+    Path((username, id)): Path<(String, i32)>,
+) -> Result<impl IntoResponse, ApiError> {
+    let broadcaster = permissions
+        .authorize(&session, &username, Capability::ManageScenes)
+        .await?;
+    let trigger = database
+        .delete_event_trigger(&broadcaster, id)?
+        .ok_or_else(|| ApiError::not_found("unknown_trigger", "no such trigger"))?;
+    let Some(eventsub) = eventsub else {
+        return Ok(StatusCode::NO_CONTENT);
+    };
+    if let Err(error) = eventsub
+        .unsubscribe_unused(&broadcaster.username, &trigger.event_type)
+        .await
+    {
+        tracing::warn!(?error, "unable to remove unused eventsub subscription");
+    }
+    Ok(StatusCode::NO_CONTENT)
+}
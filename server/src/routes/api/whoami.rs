@@ -1,8 +1,17 @@
-use axum::{http::StatusCode, response::IntoResponse, Json};
+use axum::{response::IntoResponse, Json};
 
-use crate::domain::UserSession;
+use crate::domain::{ApiError, UserSession};
 
+#[utoipa::path(
+    get,
+    path = "/api/whoami",
+    tag = "session",
+    responses(
+        (status = 200, body = crate::twitch::TwitchUser),
+        (status = 401, body = crate::domain::ApiErrorBody),
+    )
+)]
 #[axum::debug_handler]
 pub async fn get(session: UserSession) -> impl IntoResponse {
-    session.user.ok_or(StatusCode::UNAUTHORIZED).map(Json)
+    session.user.ok_or_else(ApiError::unauthorized).map(Json)
 }
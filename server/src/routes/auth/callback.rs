@@ -1,13 +1,16 @@
 use std::sync::Arc;
 
 use axum::{
-    extract::{Query, State},
-    response::{IntoResponse, Redirect},
+    extract::State,
+    response::{IntoResponse, Redirect, Response},
 };
-use tokio::sync::RwLock;
 
 use crate::{
-    domain::{db::SqliteDbService, UserSession},
+    domain::{
+        db::{Database, DbError},
+        extract::Query,
+        ApiError, TwitchTokenStore, UserSession,
+    },
     models::User,
     twitch::{AuthCallbackQuery, TwitchAuthenticator},
 };
@@ -19,6 +22,11 @@ impl AuthCallbackRedirect {
         Self(Redirect::temporary("/"))
     }
 
+    /// Back to the sign in page, which explains that signing in failed.
+    pub fn failed() -> Self {
+        Self(Redirect::temporary("/#login-failed"))
+    }
+
     pub fn new_with_user(user_login: &str) -> Self {
         Self(Redirect::temporary(&format!("/read.html#{}", user_login)))
     }
@@ -34,21 +42,51 @@ impl IntoResponse for AuthCallbackRedirect {
 #[axum::debug_handler(state = crate::domain::AppState)]
 pub async fn get(
     State(authenticator): State<Arc<Box<dyn TwitchAuthenticator>>>,
-    State(database): State<Arc<RwLock<SqliteDbService>>>,
+    State(database): State<Arc<dyn Database>>,
+    State(token_store): State<Arc<TwitchTokenStore>>,
     session: UserSession,
-    query: Query<AuthCallbackQuery>,
-) -> Result<impl IntoResponse, impl IntoResponse> {
+    Query(query): Query<AuthCallbackQuery>,
+) -> Result<impl IntoResponse, Response> {
+    let state_verification =
+        UserSession::verify_login_state(&session.session, query.state.as_deref()).await;
     if query.error.is_some() {
         tracing::error!(query = ?query.as_failure(), "twitch oauth error");
-        return Err(AuthCallbackRedirect::new());
+        return Err(AuthCallbackRedirect::new().into_response());
     }
-    let user_login = UserSession::update(&query.as_success(), &session.session, authenticator)
+    state_verification.map_err(|_| AuthCallbackRedirect::new().into_response())?;
+    let Some(success) = query.as_success() else {
+        tracing::warn!(?query, "oauth callback without code or error");
+        return Err(
+            ApiError::bad_request("missing_code", "the callback did not include a code")
+                .into_response(),
+        );
+    };
+    let (user_login, tokens) = UserSession::update(&success, &session.session, authenticator)
         .await
         .inspect_err(|_| tracing::warn!(?query, ?session, "failed to update user session"))
-        .map_err(|_| AuthCallbackRedirect::new())?;
-    if database.read().await.get_user(&user_login).is_none() {
-        let user = User::new(&user_login);
-        let _ = database.write().await.create_user(&user);
+        .map_err(|_| AuthCallbackRedirect::new().into_response())?;
+    let user = match database.get_user(&user_login) {
+        Ok(None) => match database.create_user(&User::new(&user_login)) {
+            // a concurrent login created them first
+            Err(DbError::Conflict(_)) => Ok(()),
+            result => result.map(|_| ()),
+        },
+        result => result.map(|_| ()),
+    };
+    if let Err(error) = user {
+        tracing::error!(?error, ?user_login, "unable to create user");
+        // clears the user that was just written, so the session isn't saved
+        // again as logged in
+        session
+            .session
+            .flush()
+            .await
+            .inspect_err(|error| tracing::error!(?error, "unable to destroy session"))
+            .ok();
+        return Err(AuthCallbackRedirect::failed().into_response());
+    }
+    if let Err(error) = token_store.store(&user_login, &tokens).await {
+        tracing::error!(?error, ?user_login, "unable to store twitch tokens");
     }
     return Ok(AuthCallbackRedirect::new_with_user(&user_login));
 }
@@ -3,27 +3,30 @@ use std::sync::Arc;
 use axum::{
     body::Body,
     extract::State,
-    http::Response,
+    http::{Response, StatusCode},
     response::{IntoResponse, Redirect},
 };
 
 use crate::{
-    domain::UserSession,
+    domain::{ApiError, UserSession},
     twitch::{TwitchAuthenticator, TwitchUser},
 };
 
+pub const SCOPE: &str = "user:read:email+channel:read:redemptions+channel:read:subscriptions+bits:read+moderator:read:followers+moderation:read";
+
 #[derive(Debug)]
 pub struct LoginRedirect(pub String);
 
 impl LoginRedirect {
-    pub fn new(
-        user: Option<&TwitchUser>,
+    pub fn for_user(user: &TwitchUser) -> Self {
+        Self(format!("/read.html#{}", user.login))
+    }
+
+    pub fn for_authorization(
         authenticator: Arc<Box<dyn TwitchAuthenticator>>,
+        state: &str,
     ) -> Self {
-        match user {
-            Some(u) => Self(format!("/read.html#{}", u.login)),
-            None => Self(authenticator.create_auth_url("user:read:email")),
-        }
+        Self(authenticator.create_auth_url(SCOPE, state))
     }
 }
 
@@ -37,8 +40,22 @@ impl IntoResponse for LoginRedirect {
 pub async fn get(
     State(authenticator): State<Arc<Box<dyn TwitchAuthenticator>>>,
     session: UserSession,
-) -> impl IntoResponse {
-    let redirect = LoginRedirect::new(session.user.as_ref(), authenticator);
+) -> Result<LoginRedirect, ApiError> {
+    let redirect = match session.user.as_ref() {
+        Some(user) => LoginRedirect::for_user(user),
+        None => {
+            let state = UserSession::begin_login(&session.session)
+                .await
+                .map_err(|_| {
+                    ApiError::new(
+                        StatusCode::SERVICE_UNAVAILABLE,
+                        "session_unavailable",
+                        "unable to start a login session",
+                    )
+                })?;
+            LoginRedirect::for_authorization(authenticator, &state)
+        }
+    };
     tracing::debug!(?redirect, "serving auth url");
-    redirect
+    Ok(redirect)
 }
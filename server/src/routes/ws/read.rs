@@ -1,20 +1,32 @@
 use std::sync::Arc;
 
 use axum::{
-    extract::{ws::WebSocketUpgrade, Path, State},
-    response::IntoResponse,
+    extract::{ws::WebSocketUpgrade, State},
+    response::Response,
 };
 
-use crate::domain::ChannelController;
+use crate::{
+    domain::{
+        extract::{Path, Query},
+        ApiError, ChannelController, PermissionService, UserSession,
+    },
+    models::OverlayTokenQuery,
+};
 
-#[axum::debug_handler]
+#[axum::debug_handler(state = crate::domain::AppState)]
 pub async fn get(
     ws: WebSocketUpgrade,
     State(controller): State<Arc<ChannelController>>,
+    State(permissions): State<Arc<PermissionService>>,
+    session: UserSession,
     Path(username): Path<String>,
-) -> impl IntoResponse {
+    Query(query): Query<OverlayTokenQuery>,
+) -> Result<Response, ApiError> {
+    permissions
+        .authorize_reader(&session, &username, query.token.as_deref())
+        .await?;
     tracing::info!(?username, "read socket requested");
-    ws.on_upgrade(move |socket| async move {
+    Ok(ws.on_upgrade(move |socket| async move {
         controller.add_reader(socket, &username).await;
-    })
+    }))
 }
@@ -1,29 +1,40 @@
 use std::sync::Arc;
 
 use axum::{
-    extract::{ws::WebSocketUpgrade, Path, State},
+    extract::{ws::WebSocketUpgrade, State},
     response::Response,
 };
 
-use crate::domain::ChannelController;
+use crate::{
+    domain::{extract::Path, ApiError, ChannelController, PermissionService, UserSession},
+    models::Capability,
+};
+
+const WRITER_CAPABILITIES: [Capability; 3] = [
+    Capability::PlaceAssets,
+    Capability::DeleteAssets,
+    Capability::ManageScenes,
+];
 
 #[axum::debug_handler(state = crate::domain::AppState)]
 pub async fn get(
     ws: WebSocketUpgrade,
     State(controller): State<Arc<ChannelController>>,
+    State(permissions): State<Arc<PermissionService>>,
+    session: UserSession,
     Path(username): Path<String>,
-    // session: UserSession,
-) -> Response {
-    tracing::info!(?username, "write socket requested");
-    ws.on_upgrade(move |socket| async move {
-        controller.add_writer(socket, &username).await;
-    })
-
-    // // TODO: Check username in list..
-    // match session.user().await {
-    //     Some(_) => ws.on_upgrade(move |socket| async move {
-    //         controller.add_writer(socket, &username).await;
-    //     }),
-    //     None => Redirect::temporary("/").into_response(),
-    // }
+) -> Result<Response, ApiError> {
+    let (_, role) = permissions.session_role(&session, &username).await?;
+    let capabilities: Vec<Capability> = WRITER_CAPABILITIES
+        .into_iter()
+        .filter(|capability| role.allows(*capability) && session.allows(*capability))
+        .collect();
+    if capabilities.is_empty() {
+        tracing::warn!(?username, ?role, "write socket denied");
+        return Err(ApiError::forbidden("missing permission to edit this scene"));
+    }
+    tracing::info!(?username, ?role, ?capabilities, "write socket requested");
+    Ok(ws.on_upgrade(move |socket| async move {
+        controller.add_writer(socket, &username, capabilities).await;
+    }))
 }
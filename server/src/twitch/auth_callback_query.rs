@@ -3,6 +3,7 @@ pub struct AuthCallbackQuery {
     pub code: Option<String>,
     pub error: Option<String>,
     pub error_description: Option<String>,
+    pub state: Option<String>,
 }
 
 pub struct AuthCallbackSuccessQuery {
@@ -16,16 +17,16 @@ pub struct AuthCallbackFailureQuery {
 }
 
 impl AuthCallbackQuery {
-    pub fn as_success(&self) -> AuthCallbackSuccessQuery {
-        AuthCallbackSuccessQuery {
-            code: self.code.clone().unwrap(),
-        }
+    pub fn as_success(&self) -> Option<AuthCallbackSuccessQuery> {
+        Some(AuthCallbackSuccessQuery {
+            code: self.code.clone()?,
+        })
     }
 
     pub fn as_failure(&self) -> AuthCallbackFailureQuery {
         AuthCallbackFailureQuery {
-            error: self.error.clone().unwrap(),
-            error_description: self.error_description.clone().unwrap(),
+            error: self.error.clone().unwrap_or_default(),
+            error_description: self.error_description.clone().unwrap_or_default(),
         }
     }
 }
@@ -10,8 +10,17 @@ pub trait TwitchAuthenticator: Send + Sync {
         &self,
         tokens: &TwitchUserTokens,
     ) -> Result<TwitchUser, TwitchAuthenticatorError>;
+    async fn refresh_tokens(
+        &self,
+        refresh_token: &str,
+    ) -> Result<TwitchUserTokens, TwitchAuthenticatorError>;
+    async fn helix_get(
+        &self,
+        tokens: &TwitchUserTokens,
+        path_and_query: &str,
+    ) -> Result<serde_json::Value, TwitchAuthenticatorError>;
 
-    fn create_auth_url(&self, scope: &str) -> String;
+    fn create_auth_url(&self, scope: &str, state: &str) -> String;
 }
 
 pub struct TwitchHttpAuthenticator {
@@ -79,13 +88,65 @@ impl TwitchAuthenticator for TwitchHttpAuthenticator {
             .inspect_err(|error| tracing::error!(?error, "user not found"))
     }
 
-    fn create_auth_url(&self, scope: &str) -> String {
+    async fn refresh_tokens(
+        &self,
+        refresh_token: &str,
+    ) -> Result<TwitchUserTokens, TwitchAuthenticatorError> {
+        reqwest::Client::new()
+            .post(format!("{}/token", self.twitch_oauth_api_url))
+            .form(&[
+                ("client_id", self.credentials.client_id.clone()),
+                ("client_secret", self.credentials.client_secret.clone()),
+                ("refresh_token", refresh_token.to_string()),
+                ("grant_type", "refresh_token".to_string()),
+            ])
+            .send()
+            .await
+            .inspect_err(|error| tracing::error!(?error, "token refresh request error"))
+            .map_err(|error| TwitchAuthenticatorError(error.to_string()))?
+            .error_for_status()
+            .inspect_err(|error| tracing::error!(?error, "token refresh rejected"))
+            .map_err(|error| TwitchAuthenticatorError(error.to_string()))?
+            .json::<TwitchUserTokens>()
+            .await
+            .inspect_err(|error| tracing::error!(?error, "invalid token refresh response"))
+            .map_err(|error| TwitchAuthenticatorError(error.to_string()))
+    }
+
+    async fn helix_get(
+        &self,
+        tokens: &TwitchUserTokens,
+        path_and_query: &str,
+    ) -> Result<serde_json::Value, TwitchAuthenticatorError> {
+        reqwest::Client::new()
+            .get(format!(
+                "{}/{}",
+                self.twitch_helix_api_url,
+                path_and_query.trim_start_matches('/')
+            ))
+            .header("Authorization", format!("Bearer {}", tokens.access_token))
+            .header("Client-Id", &self.credentials.client_id)
+            .send()
+            .await
+            .inspect_err(|error| tracing::error!(?error, "helix request error"))
+            .map_err(|error| TwitchAuthenticatorError(error.to_string()))?
+            .error_for_status()
+            .inspect_err(|error| tracing::error!(?error, "helix request rejected"))
+            .map_err(|error| TwitchAuthenticatorError(error.to_string()))?
+            .json::<serde_json::Value>()
+            .await
+            .inspect_err(|error| tracing::error!(?error, "invalid helix response"))
+            .map_err(|error| TwitchAuthenticatorError(error.to_string()))
+    }
+
+    fn create_auth_url(&self, scope: &str, state: &str) -> String {
         format!(
-            "{}/authorize?client_id={}&redirect_uri={}&response_type=code&scope={}",
+            "{}/authorize?client_id={}&redirect_uri={}&response_type=code&scope={}&state={}",
             self.twitch_oauth_api_url,
             self.credentials.client_id,
             self.credentials.redirect_uri,
-            scope
+            scope,
+            state
         )
     }
 }
@@ -5,15 +5,6 @@ pub struct TwitchCredentials {
     pub redirect_uri: String,
 }
 
-impl TwitchCredentials {
-    pub fn into_auth_url(&self, scope: impl std::fmt::Display) -> String {
-        format!(
-        "https://id.twitch.tv/oauth2/authorize?client_id={}&redirect_uri={}&response_type=code&scope={}",
-        self.client_id, self.redirect_uri, scope
-    )
-    }
-}
-
 impl std::fmt::Debug for TwitchCredentials {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.write_str(&format!(
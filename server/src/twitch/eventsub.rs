@@ -0,0 +1,91 @@
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+
+pub const EVENTSUB_MESSAGE_ID: &str = "Twitch-Eventsub-Message-Id";
+pub const EVENTSUB_MESSAGE_TIMESTAMP: &str = "Twitch-Eventsub-Message-Timestamp";
+pub const EVENTSUB_MESSAGE_SIGNATURE: &str = "Twitch-Eventsub-Message-Signature";
+pub const EVENTSUB_MESSAGE_TYPE: &str = "Twitch-Eventsub-Message-Type";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EventSubMessageType {
+    Notification,
+    Verification,
+    Revocation,
+}
+
+impl std::str::FromStr for EventSubMessageType {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "notification" => Ok(Self::Notification),
+            "webhook_callback_verification" => Ok(Self::Verification),
+            "revocation" => Ok(Self::Revocation),
+            other => Err(other.to_string()),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct EventSubTransport {
+    pub method: String,
+    pub callback: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub secret: Option<String>,
+}
+
+#[derive(Clone, Debug, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct EventSubSubscription {
+    pub id: String,
+    pub status: String,
+    pub r#type: String,
+    pub version: String,
+    pub condition: serde_json::Value,
+}
+
+#[derive(Clone, Debug, PartialEq, serde::Serialize)]
+pub struct EventSubSubscriptionRequest {
+    pub r#type: String,
+    pub version: String,
+    pub condition: serde_json::Value,
+    pub transport: EventSubTransport,
+}
+
+#[derive(Clone, Debug, serde::Deserialize)]
+pub struct EventSubPayload {
+    pub subscription: EventSubSubscription,
+    pub challenge: Option<String>,
+    pub event: Option<serde_json::Value>,
+}
+
+fn eventsub_mac(secret: &str, message_id: &str, timestamp: &str, body: &[u8]) -> Hmac<Sha256> {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("hmac accepts keys of any length");
+    mac.update(message_id.as_bytes());
+    mac.update(timestamp.as_bytes());
+    mac.update(body);
+    mac
+}
+
+pub fn eventsub_signature(secret: &str, message_id: &str, timestamp: &str, body: &[u8]) -> String {
+    let mac = eventsub_mac(secret, message_id, timestamp, body);
+    format!("sha256={}", hex::encode(mac.finalize().into_bytes()))
+}
+
+pub fn verify_eventsub_signature(
+    secret: &str,
+    message_id: &str,
+    timestamp: &str,
+    body: &[u8],
+    signature: &str,
+) -> bool {
+    let Some(signature) = signature
+        .strip_prefix("sha256=")
+        .and_then(|signature| hex::decode(signature).ok())
+    else {
+        return false;
+    };
+    eventsub_mac(secret, message_id, timestamp, body)
+        .verify_slice(&signature)
+        .is_ok()
+}
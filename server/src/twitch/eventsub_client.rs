@@ -0,0 +1,131 @@
+use std::time::{Duration, Instant};
+
+use tokio::sync::Mutex;
+
+use super::{
+    EventSubSubscription, EventSubSubscriptionRequest, TwitchApiResponse, TwitchCredentials,
+};
+
+#[derive(Debug)]
+pub struct EventSubClientError(pub String);
+
+#[async_trait::async_trait]
+pub trait EventSubClient: Send + Sync {
+    async fn create_subscription(
+        &self,
+        request: &EventSubSubscriptionRequest,
+    ) -> Result<EventSubSubscription, EventSubClientError>;
+    async fn delete_subscription(&self, id: &str) -> Result<(), EventSubClientError>;
+}
+
+#[derive(serde::Deserialize)]
+struct AppAccessToken {
+    access_token: String,
+    expires_in: u64,
+}
+
+pub struct HelixEventSubClient {
+    twitch_oauth_api_url: String,
+    twitch_helix_api_url: String,
+    credentials: TwitchCredentials,
+    app_token: Mutex<Option<(String, Instant)>>,
+}
+
+impl HelixEventSubClient {
+    pub fn new(
+        twitch_id_api_url: impl Into<String>,
+        twitch_helix_api_url: impl Into<String>,
+        credentials: TwitchCredentials,
+    ) -> Self {
+        Self {
+            twitch_oauth_api_url: twitch_id_api_url.into(),
+            twitch_helix_api_url: twitch_helix_api_url.into(),
+            credentials,
+            app_token: Mutex::new(None),
+        }
+    }
+
+    async fn app_token(&self) -> Result<String, EventSubClientError> {
+        let mut app_token = self.app_token.lock().await;
+        if let Some((token, expires_at)) = app_token.as_ref() {
+            if Instant::now() < *expires_at {
+                return Ok(token.clone());
+            }
+        }
+        let response = reqwest::Client::new()
+            .post(format!("{}/token", self.twitch_oauth_api_url))
+            .form(&[
+                ("client_id", self.credentials.client_id.clone()),
+                ("client_secret", self.credentials.client_secret.clone()),
+                ("grant_type", "client_credentials".to_string()),
+            ])
+            .send()
+            .await
+            .inspect_err(|error| tracing::error!(?error, "app token request error"))
+            .map_err(|error| EventSubClientError(error.to_string()))?
+            .error_for_status()
+            .inspect_err(|error| tracing::error!(?error, "app token request rejected"))
+            .map_err(|error| EventSubClientError(error.to_string()))?
+            .json::<AppAccessToken>()
+            .await
+            .inspect_err(|error| tracing::error!(?error, "invalid app token response"))
+            .map_err(|error| EventSubClientError(error.to_string()))?;
+        let expires_at =
+            Instant::now() + Duration::from_secs(response.expires_in.saturating_sub(60));
+        *app_token = Some((response.access_token.clone(), expires_at));
+        Ok(response.access_token)
+    }
+}
+
+#[async_trait::async_trait]
+impl EventSubClient for HelixEventSubClient {
+    async fn create_subscription(
+        &self,
+        request: &EventSubSubscriptionRequest,
+    ) -> Result<EventSubSubscription, EventSubClientError> {
+        let app_token = self.app_token().await?;
+        reqwest::Client::new()
+            .post(format!(
+                "{}/eventsub/subscriptions",
+                self.twitch_helix_api_url
+            ))
+            .header("Authorization", format!("Bearer {}", app_token))
+            .header("Client-Id", &self.credentials.client_id)
+            .json(request)
+            .send()
+            .await
+            .inspect_err(|error| tracing::error!(?error, "eventsub subscription request error"))
+            .map_err(|error| EventSubClientError(error.to_string()))?
+            .error_for_status()
+            .inspect_err(|error| tracing::error!(?error, "eventsub subscription rejected"))
+            .map_err(|error| EventSubClientError(error.to_string()))?
+            .json::<TwitchApiResponse<Vec<EventSubSubscription>>>()
+            .await
+            .inspect_err(|error| tracing::error!(?error, "invalid eventsub subscription response"))
+            .map_err(|error| EventSubClientError(error.to_string()))?
+            .data
+            .into_iter()
+            .next()
+            .ok_or(EventSubClientError("No subscription created".to_string()))
+    }
+
+    async fn delete_subscription(&self, id: &str) -> Result<(), EventSubClientError> {
+        let app_token = self.app_token().await?;
+        reqwest::Client::new()
+            .delete(format!(
+                "{}/eventsub/subscriptions",
+                self.twitch_helix_api_url
+            ))
+            .query(&[("id", id)])
+            .header("Authorization", format!("Bearer {}", app_token))
+            .header("Client-Id", &self.credentials.client_id)
+            .send()
+            .await
+            .inspect_err(|error| tracing::error!(?error, "eventsub delete request error"))
+            .map_err(|error| EventSubClientError(error.to_string()))?
+            .error_for_status()
+            .inspect_err(|error| tracing::error!(?error, "eventsub delete rejected"))
+            .map_err(|error| EventSubClientError(error.to_string()))?;
+        Ok(())
+    }
+}
@@ -0,0 +1,98 @@
+use std::collections::HashMap;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct IrcMessage {
+    pub tags: HashMap<String, String>,
+    pub prefix: Option<String>,
+    pub command: String,
+    pub params: Vec<String>,
+}
+
+impl IrcMessage {
+    pub fn parse(line: &str) -> Option<Self> {
+        let mut rest = line.trim_end_matches(['\r', '\n']);
+        let mut tags = HashMap::new();
+        if let Some(tagged) = rest.strip_prefix('@') {
+            let (raw_tags, remainder) = tagged.split_once(' ')?;
+            for tag in raw_tags.split(';') {
+                let (key, value) = tag.split_once('=').unwrap_or((tag, ""));
+                tags.insert(key.to_string(), Self::unescape_tag_value(value));
+            }
+            rest = remainder.trim_start();
+        }
+        let mut prefix = None;
+        if let Some(prefixed) = rest.strip_prefix(':') {
+            let (raw_prefix, remainder) = prefixed.split_once(' ')?;
+            prefix = Some(raw_prefix.to_string());
+            rest = remainder.trim_start();
+        }
+        let (command, mut rest) = rest.split_once(' ').unwrap_or((rest, ""));
+        if command.is_empty() {
+            return None;
+        }
+        let mut params = Vec::new();
+        while !rest.is_empty() {
+            if let Some(trailing) = rest.strip_prefix(':') {
+                params.push(trailing.to_string());
+                break;
+            }
+            let (param, remainder) = rest.split_once(' ').unwrap_or((rest, ""));
+            if !param.is_empty() {
+                params.push(param.to_string());
+            }
+            rest = remainder;
+        }
+        Some(Self {
+            tags,
+            prefix,
+            command: command.to_string(),
+            params,
+        })
+    }
+
+    pub fn nick(&self) -> Option<&str> {
+        let prefix = self.prefix.as_deref()?;
+        Some(prefix.split_once('!').map_or(prefix, |(nick, _)| nick))
+    }
+
+    pub fn channel(&self) -> Option<&str> {
+        self.params.first()?.strip_prefix('#')
+    }
+
+    pub fn text(&self) -> Option<&str> {
+        self.params.get(1).map(String::as_str)
+    }
+
+    pub fn badges(&self) -> Vec<&str> {
+        self.tags
+            .get("badges")
+            .map(|badges| {
+                badges
+                    .split(',')
+                    .filter_map(|badge| badge.split('/').next())
+                    .filter(|badge| !badge.is_empty())
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    fn unescape_tag_value(value: &str) -> String {
+        let mut unescaped = String::with_capacity(value.len());
+        let mut chars = value.chars();
+        while let Some(c) = chars.next() {
+            if c != '\\' {
+                unescaped.push(c);
+                continue;
+            }
+            match chars.next() {
+                Some(':') => unescaped.push(';'),
+                Some('s') => unescaped.push(' '),
+                Some('r') => unescaped.push('\r'),
+                Some('n') => unescaped.push('\n'),
+                Some(other) => unescaped.push(other),
+                None => {}
+            }
+        }
+        unescaped
+    }
+}
@@ -1,6 +1,9 @@
 pub mod auth_callback_query;
 pub mod authenticator;
 pub mod credentials;
+pub mod eventsub;
+pub mod eventsub_client;
+pub mod irc;
 pub mod response;
 pub mod user;
 pub mod user_tokens;
@@ -12,6 +15,15 @@ pub use authenticator::TwitchAuthenticator;
 pub use authenticator::TwitchAuthenticatorError;
 pub use authenticator::TwitchHttpAuthenticator;
 pub use credentials::TwitchCredentials;
+pub use eventsub::EventSubMessageType;
+pub use eventsub::EventSubPayload;
+pub use eventsub::EventSubSubscription;
+pub use eventsub::EventSubSubscriptionRequest;
+pub use eventsub::EventSubTransport;
+pub use eventsub_client::EventSubClient;
+pub use eventsub_client::EventSubClientError;
+pub use eventsub_client::HelixEventSubClient;
+pub use irc::IrcMessage;
 pub use response::TwitchApiResponse;
 pub use user::TwitchUser;
 pub use user_tokens::TwitchUserTokens;
@@ -1,4 +1,4 @@
-#[derive(serde::Deserialize, serde::Serialize, Clone, Debug)]
+#[derive(serde::Deserialize, serde::Serialize, Clone, Debug, utoipa::ToSchema)]
 pub struct TwitchUser {
     pub id: String,
     pub login: String,
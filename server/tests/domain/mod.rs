@@ -0,0 +1,15 @@
+pub mod test_api_error;
+pub mod test_backplane;
+pub mod test_chat;
+pub mod test_config;
+pub mod test_eventsub_client;
+pub mod test_heartbeat;
+pub mod test_migrations;
+pub mod test_moderator_sync;
+pub mod test_permission;
+pub mod test_rate_limit;
+pub mod test_repository;
+pub mod test_session_store;
+pub mod test_shutdown;
+pub mod test_storage_check;
+pub mod test_token_store;
@@ -0,0 +1,73 @@
+use axum::{
+    http::{header, StatusCode},
+    response::IntoResponse,
+};
+use http_body_util::BodyExt;
+use imgfloat::domain::{db::DbError, ApiError, ApiErrorBody, PermissionError};
+
+#[rstest::rstest]
+#[case(
+    PermissionError::Unauthenticated,
+    StatusCode::UNAUTHORIZED,
+    "unauthenticated"
+)]
+#[case(
+    PermissionError::UnknownBroadcaster,
+    StatusCode::NOT_FOUND,
+    "unknown_channel"
+)]
+#[case(PermissionError::Forbidden, StatusCode::FORBIDDEN, "forbidden")]
+fn test_permission_error_codes(
+    #[case] error: PermissionError,
+    #[case] status: StatusCode,
+    #[case] code: &str,
+) {
+    let error = ApiError::from(error);
+    assert_eq!(error.status(), status);
+    assert_eq!(error.code(), code);
+}
+
+#[rstest::rstest]
+#[case(DbError::NotFound, StatusCode::NOT_FOUND, "not_found")]
+#[case(DbError::Conflict("users.username".to_string()), StatusCode::CONFLICT, "conflict")]
+#[case(
+    DbError::Query(diesel::result::Error::RollbackTransaction),
+    StatusCode::INTERNAL_SERVER_ERROR,
+    "internal_error"
+)]
+fn test_db_error_codes(#[case] error: DbError, #[case] status: StatusCode, #[case] code: &str) {
+    let error = ApiError::from(error);
+    assert_eq!(error.status(), status);
+    assert_eq!(error.code(), code);
+}
+
+#[rstest::rstest]
+#[tokio::test]
+async fn test_response_has_json_body() {
+    let response = ApiError::not_found("unknown_asset", "no such asset").into_response();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    assert_eq!(
+        response.headers().get(header::CONTENT_TYPE).unwrap(),
+        "application/json"
+    );
+    let body = response.into_body().collect().await.unwrap().to_bytes();
+    let actual: ApiErrorBody = serde_json::from_slice(&body).unwrap();
+    assert_eq!(
+        actual,
+        ApiErrorBody {
+            code: "unknown_asset".to_string(),
+            message: "no such asset".to_string(),
+        }
+    );
+}
+
+#[rstest::rstest]
+#[tokio::test]
+async fn test_rate_limited_sets_retry_after() {
+    let response = ApiError::rate_limited(7).into_response();
+    assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+    assert_eq!(response.headers().get(header::RETRY_AFTER).unwrap(), "7");
+    let body = response.into_body().collect().await.unwrap().to_bytes();
+    let actual: ApiErrorBody = serde_json::from_slice(&body).unwrap();
+    assert_eq!(actual.code, "rate_limited");
+}
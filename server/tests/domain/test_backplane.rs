@@ -0,0 +1,258 @@
+use std::{sync::Arc, time::Duration};
+
+use imgfloat::{
+    domain::{
+        backplane::{Backplane, LocalBackplane, RedisBackplane},
+        message::{
+            ImgfloatAsset, ImgfloatAssetStateMessage, ImgfloatState, ImgfloatWriterNotification,
+        },
+        ChannelController,
+    },
+    models::Submission,
+};
+
+use futures::StreamExt;
+use tokio_tungstenite::tungstenite::Message;
+
+use crate::fixture::{TestBackplane, TestSocketServer};
+
+fn asset(id: &str) -> ImgfloatAsset {
+    ImgfloatAsset {
+        id: id.to_string(),
+        x: 0.0,
+        y: 0.0,
+        w: 10.0,
+        h: 10.0,
+        theta: 0.0,
+        url: format!("/api/assets/test-broadcaster/{id}.png"),
+    }
+}
+
+fn asset_ids(state: Option<ImgfloatState>) -> Vec<String> {
+    state
+        .map(|state| state.assets.into_iter().map(|asset| asset.id).collect())
+        .unwrap_or_default()
+}
+
+fn instance(backplane: &Arc<impl Backplane + 'static>) -> ChannelController {
+    ChannelController::new().with_backplane(Arc::clone(backplane) as Arc<dyn Backplane>)
+}
+
+#[rstest::rstest]
+async fn test_changes_reach_every_instance() {
+    let backplane = Arc::new(LocalBackplane::new());
+    let first = instance(&backplane);
+    let second = instance(&backplane);
+
+    first.show_asset("test-broadcaster", asset("1")).await;
+    second.show_asset("test-broadcaster", asset("2")).await;
+    first.hide_asset("test-broadcaster", "1").await;
+
+    for controller in [&first, &second] {
+        assert_eq!(
+            asset_ids(controller.get_state("test-broadcaster").await),
+            vec!["2"]
+        );
+    }
+}
+
+#[rstest::rstest]
+async fn test_late_instance_gets_state_from_owner() {
+    let backplane = Arc::new(LocalBackplane::new());
+    let owner = instance(&backplane);
+    let _reader = instance(&backplane);
+    owner.show_asset("test-broadcaster", asset("1")).await;
+
+    let late = instance(&backplane);
+    assert!(late.get_state("test-broadcaster").await.is_none());
+    late.request_state("test-broadcaster").await;
+    assert_eq!(
+        asset_ids(late.get_state("test-broadcaster").await),
+        vec!["1"]
+    );
+}
+
+#[rstest::rstest]
+async fn test_partial_update_on_missing_state_requests_snapshot() {
+    let backplane = Arc::new(LocalBackplane::new());
+    let owner = instance(&backplane);
+    owner.show_asset("test-broadcaster", asset("1")).await;
+    let late = instance(&backplane);
+
+    let mut moved = asset("1");
+    moved.x = 42.0;
+    owner
+        .publish("test-broadcaster", ImgfloatAssetStateMessage::Update(moved))
+        .await;
+
+    let state = late.get_state("test-broadcaster").await.unwrap();
+    assert_eq!(state.assets[0].x, 42.0);
+}
+
+#[rstest::rstest]
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_readers_only_get_changes_that_applied() {
+    let controller = Arc::new(ChannelController::new());
+    let server = TestSocketServer::start(&controller).await;
+    let (mut reader, _) = tokio_tungstenite::connect_async(server.read_url())
+        .await
+        .unwrap();
+    tokio::time::timeout(Duration::from_secs(5), async {
+        while controller.connected_channels().await.is_empty() {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+    })
+    .await
+    .unwrap();
+
+    // there is no state to move the asset in yet
+    controller
+        .publish(
+            "test-broadcaster",
+            ImgfloatAssetStateMessage::Update(asset("1")),
+        )
+        .await;
+    controller.show_asset("test-broadcaster", asset("2")).await;
+
+    let Some(Ok(Message::Text(text))) = reader.next().await else {
+        panic!("expected a state message");
+    };
+    match serde_json::from_str(&text).unwrap() {
+        ImgfloatAssetStateMessage::New(state) => assert_eq!(asset_ids(Some(state)), vec!["2"]),
+        other => panic!("unexpected message {other:?}"),
+    }
+}
+
+#[rstest::rstest]
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_idle_owner_hands_state_to_instances_with_readers() {
+    const IDLE_STATE_TTL: Duration = Duration::from_millis(100);
+    let backplane = Arc::new(LocalBackplane::new());
+    let owner = Arc::new(instance(&backplane).with_idle_state_ttl(IDLE_STATE_TTL));
+    let other = Arc::new(instance(&backplane).with_idle_state_ttl(IDLE_STATE_TTL));
+    let owner_server = TestSocketServer::start(&owner).await;
+    let other_server = TestSocketServer::start(&other).await;
+    let (mut owner_reader, _) = tokio_tungstenite::connect_async(owner_server.read_url())
+        .await
+        .unwrap();
+    let (_other_reader, _) = tokio_tungstenite::connect_async(other_server.read_url())
+        .await
+        .unwrap();
+    tokio::time::timeout(Duration::from_secs(5), async {
+        while owner.connected_channels().await.is_empty()
+            || other.connected_channels().await.is_empty()
+        {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+    })
+    .await
+    .unwrap();
+    owner.show_asset("test-broadcaster", asset("1")).await;
+
+    owner_reader.close(None).await.unwrap();
+    tokio::time::timeout(Duration::from_secs(5), async {
+        while owner.get_state("test-broadcaster").await.is_some() {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+    })
+    .await
+    .unwrap();
+
+    // the remaining reader's instance answers for the channel now
+    let late = instance(&backplane);
+    late.request_state("test-broadcaster").await;
+    assert_eq!(
+        asset_ids(late.get_state("test-broadcaster").await),
+        vec!["1"]
+    );
+    let mut moved = asset("1");
+    moved.x = 42.0;
+    owner
+        .publish("test-broadcaster", ImgfloatAssetStateMessage::Update(moved))
+        .await;
+    for controller in [&owner, &other] {
+        let state = controller.get_state("test-broadcaster").await.unwrap();
+        assert_eq!(state.assets[0].x, 42.0);
+    }
+}
+
+#[rstest::rstest]
+async fn test_resync_drops_states_owned_elsewhere() {
+    let backplane = TestBackplane::new();
+    let first = instance(&backplane);
+    let second = instance(&backplane);
+    first.show_asset("test-broadcaster", asset("1")).await;
+    second.show_asset("test-other", asset("2")).await;
+
+    backplane.reconnect(1).await;
+
+    assert_eq!(
+        asset_ids(second.get_state("test-broadcaster").await),
+        vec!["1"]
+    );
+    assert_eq!(asset_ids(second.get_state("test-other").await), vec!["2"]);
+}
+
+#[rstest::rstest]
+#[tokio::test]
+async fn test_notifications_reach_writers_on_other_instances() {
+    let backplane = Arc::new(LocalBackplane::new());
+    let first = instance(&backplane);
+    let second = instance(&backplane);
+    let mut notifications = second.subscribe_notifications("test-broadcaster").await;
+    let submission = Submission {
+        id: 1,
+        broadcaster_username: "test-broadcaster".to_string(),
+        submitter: "test-viewer".to_string(),
+        local_filename: "1.png".to_string(),
+        original_filename: "hype.png".to_string(),
+        checksum: "checksum".to_string(),
+        content_type: "image/png".to_string(),
+        status: Submission::PENDING.to_string(),
+        submitted_at: 0,
+        reviewed_by: None,
+        reviewed_at: None,
+    };
+
+    first
+        .notify_writers(
+            "test-broadcaster",
+            ImgfloatWriterNotification::SubmissionReceived(submission.clone()),
+        )
+        .await;
+
+    let notification: ImgfloatWriterNotification =
+        serde_json::from_str(&notifications.recv().await.unwrap()).unwrap();
+    assert!(matches!(
+        notification,
+        ImgfloatWriterNotification::SubmissionReceived(received) if received == submission
+    ));
+}
+
+/// Runs against the server at `TEST_REDIS_URL` and is skipped without one.
+#[rstest::rstest]
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_redis_backplane_shares_state() {
+    let Ok(url) = std::env::var("TEST_REDIS_URL") else {
+        return;
+    };
+    let first = instance(&Arc::new(RedisBackplane::connect(&url).await.unwrap()));
+    let second = instance(&Arc::new(RedisBackplane::connect(&url).await.unwrap()));
+    let username = format!("test-broadcaster-{}", uuid::Uuid::new_v4());
+
+    first.show_asset(&username, asset("1")).await;
+    second.show_asset(&username, asset("2")).await;
+
+    let converged = tokio::time::timeout(Duration::from_secs(5), async {
+        loop {
+            let first_ids = asset_ids(first.get_state(&username).await);
+            if first_ids.len() == 2 && first_ids == asset_ids(second.get_state(&username).await) {
+                return first_ids;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+    })
+    .await
+    .unwrap();
+    assert!(converged.contains(&"1".to_string()) && converged.contains(&"2".to_string()));
+}
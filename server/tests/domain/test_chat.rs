@@ -0,0 +1,177 @@
+use std::{sync::Arc, time::Duration};
+
+use imgfloat::{
+    domain::{
+        chat::ChatCommand,
+        db::{AdminRepository, AssetRepository, Database, SettingsRepository, UserRepository},
+        message::ImgfloatAsset,
+        ChannelController, ChatBot, ChatConfig,
+    },
+    models::{user_settings::ValidatedUnownedUserSettings, ChannelAdmin},
+    twitch::IrcMessage,
+};
+
+use crate::fixture::{FakeIrcServer, TestAsset, TestDbService, TestUser};
+
+fn setup() -> (Arc<dyn Database>, String) {
+    let TestDbService(dbservice) = TestDbService::new();
+    let broadcaster = TestUser::new("test-user").as_db_user();
+    let admin = TestUser::new("test-admin").as_db_user();
+    let asset = TestAsset::new("Hype.png").as_db_asset(&broadcaster);
+    let settings = ValidatedUnownedUserSettings {
+        chat_commands: true,
+        ..Default::default()
+    };
+    dbservice.create_user(&broadcaster).unwrap();
+    dbservice.create_user(&admin).unwrap();
+    dbservice
+        .create_user_settings(&settings.with_owner(&broadcaster))
+        .unwrap();
+    dbservice
+        .create_channel_admin(&ChannelAdmin::new(&admin, &broadcaster))
+        .unwrap();
+    dbservice.create_asset(&asset).unwrap();
+    (Arc::new(dbservice), asset.local_filename)
+}
+
+fn privmsg(badges: &str, nick: &str, text: &str) -> String {
+    format!("@badges={badges};display-name={nick} :{nick}!{nick}@{nick}.tmi.twitch.tv PRIVMSG #test-user :{text}")
+}
+
+#[rstest::rstest]
+#[case::show("!show hype", Some(ChatCommand::Show("hype".to_string())))]
+#[case::hide("!HIDE hype extra words", Some(ChatCommand::Hide("hype".to_string())))]
+#[case::missing_name("!show", None)]
+#[case::unknown_command("!lurk hype", None)]
+#[case::plain_chat("show hype", None)]
+fn test_parse_command(#[case] text: &str, #[case] expected: Option<ChatCommand>) {
+    assert_eq!(ChatCommand::parse(text), expected);
+}
+
+#[rstest::rstest]
+fn test_parse_irc_message() {
+    let message = IrcMessage::parse(
+        "@badges=moderator/1,subscriber/12;display-name=Cool\\sUser :cool_user!cool_user@cool_user.tmi.twitch.tv PRIVMSG #test-user :!show hype\r\n",
+    )
+    .unwrap();
+
+    assert_eq!(message.command, "PRIVMSG");
+    assert_eq!(message.nick(), Some("cool_user"));
+    assert_eq!(message.channel(), Some("test-user"));
+    assert_eq!(message.text(), Some("!show hype"));
+    assert_eq!(message.badges(), vec!["moderator", "subscriber"]);
+    assert_eq!(message.tags["display-name"], "Cool User");
+    assert_eq!(
+        IrcMessage::parse("PING :tmi.twitch.tv").unwrap().params,
+        vec!["tmi.twitch.tv".to_string()]
+    );
+}
+
+#[rstest::rstest]
+#[tokio::test]
+async fn test_chat_commands_against_fake_server() {
+    let (database, filename) = setup();
+    let controller = Arc::new(ChannelController::new());
+    let server = FakeIrcServer::new().await;
+    let bot = Arc::new(ChatBot::new(
+        Arc::clone(&database),
+        Arc::clone(&controller),
+        ChatConfig {
+            host: "127.0.0.1".to_string(),
+            port: server.port(),
+            tls: false,
+            sync_interval: Duration::from_secs(60),
+            reconnect_delay: Duration::from_secs(1),
+        },
+    ));
+    let session = tokio::spawn({
+        let bot = Arc::clone(&bot);
+        async move { bot.connect().await }
+    });
+    let mut connection = server.accept().await;
+
+    assert_eq!(
+        connection.next_line().await,
+        "CAP REQ :twitch.tv/tags twitch.tv/commands"
+    );
+    assert_eq!(connection.next_line().await, "PASS anonymous");
+    assert!(connection.next_line().await.starts_with("NICK justinfan"));
+    assert_eq!(connection.next_line().await, "JOIN #test-user");
+
+    connection
+        .send(&privmsg("subscriber/1", "viewer", "!show hype"))
+        .await;
+    connection.round_trip().await;
+    assert!(controller.get_state("test-user").await.is_none());
+
+    connection
+        .send(&privmsg("moderator/1", "cool_mod", "!show hype"))
+        .await;
+    connection.round_trip().await;
+    let state = controller.get_state("test-user").await.unwrap();
+    assert_eq!(state.assets.len(), 1);
+    assert_eq!(
+        state.assets[0].url,
+        format!("/api/assets/test-user/{filename}")
+    );
+
+    connection
+        .send(&privmsg("", "test-admin", "!hide hype"))
+        .await;
+    connection.round_trip().await;
+    assert!(controller
+        .get_state("test-user")
+        .await
+        .unwrap()
+        .assets
+        .is_empty());
+
+    connection.send(":tmi.twitch.tv RECONNECT").await;
+    assert!(session.await.unwrap().is_ok());
+}
+
+#[rstest::rstest]
+#[tokio::test]
+async fn test_show_keeps_the_scene_placement() {
+    let (database, filename) = setup();
+    let controller = Arc::new(ChannelController::new());
+    let bot = ChatBot::new(
+        Arc::clone(&database),
+        Arc::clone(&controller),
+        ChatConfig {
+            host: "127.0.0.1".to_string(),
+            port: 0,
+            tls: false,
+            sync_interval: Duration::from_secs(60),
+            reconnect_delay: Duration::from_secs(1),
+        },
+    );
+    let url = format!("/api/assets/test-user/{filename}");
+    controller
+        .show_asset(
+            "test-user",
+            ImgfloatAsset {
+                id: "test-placed".to_string(),
+                x: 40.0,
+                y: 60.0,
+                w: 200.0,
+                h: 100.0,
+                theta: 90.0,
+                url: url.clone(),
+            },
+        )
+        .await;
+
+    let message = IrcMessage::parse(&privmsg("moderator/1", "cool_mod", "!show hype")).unwrap();
+    assert!(bot.handle_message(&message).await.is_some());
+
+    let assets = controller.get_state("test-user").await.unwrap().assets;
+    assert_eq!(assets.len(), 2);
+    let shown = &assets[1];
+    assert_ne!(shown.id, "test-placed");
+    assert_eq!(shown.url, url);
+    assert_eq!(
+        (shown.x, shown.y, shown.w, shown.h, shown.theta),
+        (40.0, 60.0, 200.0, 100.0, 90.0)
+    );
+}
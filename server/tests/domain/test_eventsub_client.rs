@@ -0,0 +1,155 @@
+use std::sync::{Arc, Mutex};
+
+use axum::{
+    extract::{Query, State},
+    http::{HeaderMap, StatusCode},
+    routing::post,
+    Json, Router,
+};
+use imgfloat::twitch::{
+    EventSubClient, EventSubSubscriptionRequest, EventSubTransport, HelixEventSubClient,
+    TwitchCredentials,
+};
+
+use crate::fixture::eventsub::HELIX_CREATE_SUBSCRIPTION_RESPONSE;
+
+#[derive(Clone, Default)]
+struct HelixStandIn {
+    token_requests: Arc<Mutex<usize>>,
+    subscriptions: Arc<Mutex<Vec<serde_json::Value>>>,
+    deleted: Arc<Mutex<Vec<String>>>,
+}
+
+fn authorized(headers: &HeaderMap) -> bool {
+    headers
+        .get("Authorization")
+        .and_then(|value| value.to_str().ok())
+        == Some("Bearer app-token")
+        && headers
+            .get("Client-Id")
+            .and_then(|value| value.to_str().ok())
+            == Some("client-id")
+}
+
+async fn start_helix(stand_in: HelixStandIn) -> String {
+    let app = Router::new()
+        .route(
+            "/oauth2/token",
+            post(|State(stand_in): State<HelixStandIn>| async move {
+                *stand_in.token_requests.lock().unwrap() += 1;
+                Json(serde_json::json!({
+                    "access_token": "app-token",
+                    "expires_in": 3600,
+                    "token_type": "bearer",
+                }))
+            }),
+        )
+        .route(
+            "/helix/eventsub/subscriptions",
+            post(
+                |State(stand_in): State<HelixStandIn>,
+                 headers: HeaderMap,
+                 Json(request): Json<serde_json::Value>| async move {
+                    if !authorized(&headers) {
+                        return Err(StatusCode::UNAUTHORIZED);
+                    }
+                    stand_in.subscriptions.lock().unwrap().push(request);
+                    let response: serde_json::Value =
+                        serde_json::from_str(HELIX_CREATE_SUBSCRIPTION_RESPONSE).unwrap();
+                    Ok((StatusCode::ACCEPTED, Json(response)))
+                },
+            )
+            .delete(
+                |State(stand_in): State<HelixStandIn>,
+                 headers: HeaderMap,
+                 Query(query): Query<std::collections::HashMap<String, String>>| async move {
+                    if !authorized(&headers) {
+                        return StatusCode::UNAUTHORIZED;
+                    }
+                    stand_in
+                        .deleted
+                        .lock()
+                        .unwrap()
+                        .push(query.get("id").cloned().unwrap_or_default());
+                    StatusCode::NO_CONTENT
+                },
+            ),
+        )
+        .with_state(stand_in);
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let address = listener.local_addr().unwrap();
+    tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+    format!("http://{address}")
+}
+
+fn client(base_url: &str) -> HelixEventSubClient {
+    HelixEventSubClient::new(
+        format!("{base_url}/oauth2"),
+        format!("{base_url}/helix"),
+        TwitchCredentials {
+            client_id: "client-id".to_string(),
+            client_secret: "client-secret".to_string(),
+            redirect_uri: "http://localhost:3000/auth/callback".to_string(),
+        },
+    )
+}
+
+#[rstest::rstest]
+#[tokio::test]
+async fn test_create_and_delete_subscription() {
+    let stand_in = HelixStandIn::default();
+    let base_url = start_helix(stand_in.clone()).await;
+    let client = client(&base_url);
+    let request = EventSubSubscriptionRequest {
+        r#type: "channel.channel_points_custom_reward_redemption.add".to_string(),
+        version: "1".to_string(),
+        condition: serde_json::json!({ "broadcaster_user_id": "12826" }),
+        transport: EventSubTransport {
+            method: "webhook".to_string(),
+            callback: "https://example.com/api/eventsub".to_string(),
+            secret: Some("test-eventsub-secret".to_string()),
+        },
+    };
+
+    let subscription = client.create_subscription(&request).await.unwrap();
+    client.delete_subscription(&subscription.id).await.unwrap();
+
+    assert_eq!(subscription.id, "f1c2a387-161a-49f9-a165-0f21d7a4e1c4");
+    assert_eq!(subscription.status, "webhook_callback_verification_pending");
+    assert_eq!(*stand_in.token_requests.lock().unwrap(), 1);
+    assert_eq!(
+        *stand_in.subscriptions.lock().unwrap(),
+        vec![serde_json::to_value(&request).unwrap()]
+    );
+    assert_eq!(
+        *stand_in.deleted.lock().unwrap(),
+        vec![subscription.id.clone()]
+    );
+}
+
+#[rstest::rstest]
+#[tokio::test]
+async fn test_create_subscription_rejected() {
+    let base_url = start_helix(HelixStandIn::default()).await;
+    let client = HelixEventSubClient::new(
+        format!("{base_url}/oauth2"),
+        format!("{base_url}/helix"),
+        TwitchCredentials {
+            client_id: "other-client".to_string(),
+            client_secret: "client-secret".to_string(),
+            redirect_uri: "http://localhost:3000/auth/callback".to_string(),
+        },
+    );
+    let request = EventSubSubscriptionRequest {
+        r#type: "channel.follow".to_string(),
+        version: "2".to_string(),
+        condition: serde_json::json!({}),
+        transport: EventSubTransport {
+            method: "webhook".to_string(),
+            callback: "https://example.com/api/eventsub".to_string(),
+            secret: None,
+        },
+    };
+
+    assert!(client.create_subscription(&request).await.is_err());
+}
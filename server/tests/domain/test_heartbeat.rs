@@ -0,0 +1,134 @@
+use std::{sync::Arc, time::Duration};
+
+use futures::StreamExt;
+use imgfloat::domain::{message::ImgfloatAsset, ChannelController, HeartbeatConfig};
+
+use crate::fixture::TestSocketServer;
+
+const HEARTBEAT: HeartbeatConfig = HeartbeatConfig {
+    interval: Duration::from_millis(50),
+    timeout: Duration::from_millis(200),
+};
+const IDLE_STATE_TTL: Duration = Duration::from_millis(100);
+
+async fn wait_for_channels(controller: &ChannelController, expected: Vec<&str>) {
+    tokio::time::timeout(Duration::from_secs(5), async {
+        while controller.connected_channels().await != expected {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+    })
+    .await
+    .unwrap();
+}
+
+#[rstest::rstest]
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_silent_reader_is_dropped() {
+    let controller = Arc::new(ChannelController::new().with_heartbeat(HEARTBEAT));
+    let server = TestSocketServer::start(&controller).await;
+
+    // never polled, so pings go unanswered
+    let (_reader, _) = tokio_tungstenite::connect_async(server.read_url())
+        .await
+        .unwrap();
+    wait_for_channels(&controller, vec!["test-broadcaster"]).await;
+
+    wait_for_channels(&controller, vec![]).await;
+}
+
+#[rstest::rstest]
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_responsive_writer_stays_connected() {
+    let controller = Arc::new(ChannelController::new().with_heartbeat(HEARTBEAT));
+    let server = TestSocketServer::start(&controller).await;
+    let (mut writer, _) = tokio_tungstenite::connect_async(server.write_url())
+        .await
+        .unwrap();
+    // reading answers the server's pings
+    let reading = tokio::spawn(async move { while let Some(Ok(_)) = writer.next().await {} });
+    wait_for_channels(&controller, vec!["test-broadcaster"]).await;
+
+    tokio::time::sleep(HEARTBEAT.timeout * 3).await;
+
+    assert_eq!(
+        controller.connected_channels().await,
+        vec!["test-broadcaster"]
+    );
+    reading.abort();
+}
+
+#[rstest::rstest]
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_channel_is_removed_when_last_socket_leaves() {
+    let controller = Arc::new(ChannelController::new());
+    let server = TestSocketServer::start(&controller).await;
+    let (mut reader, _) = tokio_tungstenite::connect_async(server.read_url())
+        .await
+        .unwrap();
+    let (mut writer, _) = tokio_tungstenite::connect_async(server.write_url())
+        .await
+        .unwrap();
+    wait_for_channels(&controller, vec!["test-broadcaster"]).await;
+
+    reader.close(None).await.unwrap();
+    writer.close(None).await.unwrap();
+
+    wait_for_channels(&controller, vec![]).await;
+}
+
+fn asset(id: &str) -> ImgfloatAsset {
+    ImgfloatAsset {
+        id: id.to_string(),
+        x: 0.0,
+        y: 0.0,
+        w: 10.0,
+        h: 10.0,
+        theta: 0.0,
+        url: format!("/api/assets/test-broadcaster/{id}.png"),
+    }
+}
+
+#[rstest::rstest]
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_idle_channel_state_is_evicted() {
+    let controller = Arc::new(ChannelController::new().with_idle_state_ttl(IDLE_STATE_TTL));
+    let server = TestSocketServer::start(&controller).await;
+    controller.show_asset("test-broadcaster", asset("1")).await;
+    let (mut reader, _) = tokio_tungstenite::connect_async(server.read_url())
+        .await
+        .unwrap();
+    wait_for_channels(&controller, vec!["test-broadcaster"]).await;
+
+    reader.close(None).await.unwrap();
+    wait_for_channels(&controller, vec![]).await;
+
+    tokio::time::timeout(Duration::from_secs(5), async {
+        while controller.get_state("test-broadcaster").await.is_some() {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+    })
+    .await
+    .unwrap();
+}
+
+#[rstest::rstest]
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_state_survives_a_reconnect() {
+    let controller = Arc::new(ChannelController::new().with_idle_state_ttl(IDLE_STATE_TTL));
+    let server = TestSocketServer::start(&controller).await;
+    controller.show_asset("test-broadcaster", asset("1")).await;
+    let (mut reader, _) = tokio_tungstenite::connect_async(server.read_url())
+        .await
+        .unwrap();
+    wait_for_channels(&controller, vec!["test-broadcaster"]).await;
+    reader.close(None).await.unwrap();
+    wait_for_channels(&controller, vec![]).await;
+
+    let (_reader, _) = tokio_tungstenite::connect_async(server.read_url())
+        .await
+        .unwrap();
+    wait_for_channels(&controller, vec!["test-broadcaster"]).await;
+    tokio::time::sleep(IDLE_STATE_TTL * 3).await;
+
+    assert!(controller.get_state("test-broadcaster").await.is_some());
+}
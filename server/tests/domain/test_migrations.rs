@@ -0,0 +1,60 @@
+use diesel::backend::Backend;
+use diesel::migration::MigrationSource;
+use diesel_migrations::EmbeddedMigrations;
+use imgfloat::domain::db::{MigrationError, SchemaStatus, POSTGRES_MIGRATIONS, SQLITE_MIGRATIONS};
+
+use crate::fixture::TestDbService;
+
+fn versions<DB: Backend>(migrations: &EmbeddedMigrations) -> Vec<String> {
+    MigrationSource::<DB>::migrations(migrations)
+        .unwrap()
+        .iter()
+        .map(|migration| migration.name().version().to_string())
+        .collect()
+}
+
+fn known_versions() -> Vec<String> {
+    versions::<diesel::sqlite::Sqlite>(&SQLITE_MIGRATIONS)
+}
+
+#[rstest::rstest]
+fn test_migration_sets_have_the_same_versions() {
+    assert_eq!(
+        versions::<diesel::pg::Pg>(&POSTGRES_MIGRATIONS),
+        known_versions()
+    );
+}
+
+#[rstest::rstest]
+fn test_migrate_applies_every_pending_migration() {
+    let TestDbService(database) = TestDbService::unmigrated();
+    let status = database.schema_status().unwrap();
+    assert_eq!(status.pending, known_versions());
+    assert!(matches!(
+        database.check_schema(),
+        Err(MigrationError::Pending(_))
+    ));
+
+    assert_eq!(database.migrate().unwrap(), known_versions());
+    assert_eq!(database.schema_status().unwrap(), SchemaStatus::default());
+    assert!(database.check_schema().is_ok());
+    assert!(database.migrate().unwrap().is_empty());
+}
+
+#[rstest::rstest]
+fn test_unknown_schema_version_is_refused() {
+    let dbservice = TestDbService::new();
+    dbservice.execute("INSERT INTO __diesel_schema_migrations (version) VALUES ('29990101000000')");
+    let TestDbService(database) = dbservice;
+
+    let expected = vec!["29990101000000".to_string()];
+    assert_eq!(database.schema_status().unwrap().unknown, expected);
+    assert!(matches!(
+        database.check_schema(),
+        Err(MigrationError::UnknownVersions(versions)) if versions == expected
+    ));
+    assert!(matches!(
+        database.migrate(),
+        Err(MigrationError::UnknownVersions(_))
+    ));
+}
@@ -0,0 +1,148 @@
+use std::sync::Arc;
+
+use imgfloat::{
+    domain::{
+        db::{Database, UserRepository},
+        ModeratorSync,
+    },
+    models::{user_settings::ValidatedUnownedUserSettings, ChannelAdmin},
+    twitch::{TwitchAuthenticator, TwitchUserTokens},
+};
+
+use crate::fixture::{
+    eventsub::HELIX_USERS_RESPONSE, TestAuthenticator, TestDbService, TestTokenStore, TestUser,
+};
+
+const MODERATORS_PAGE_1: &str = include_str!("../fixture/helix/moderators_page_1.json");
+const MODERATORS_PAGE_2: &str = include_str!("../fixture/helix/moderators_page_2.json");
+const MODERATORS_PATH: &str = "moderation/moderators?broadcaster_id=12826&first=100";
+
+async fn setup() -> (Arc<dyn Database>, ModeratorSync) {
+    let TestDbService(dbservice) = TestDbService::new();
+    dbservice
+        .create_user(&TestUser::new("test-user").as_db_user())
+        .unwrap();
+    let database: Arc<dyn Database> = Arc::new(dbservice);
+    let page_1: serde_json::Value = serde_json::from_str(MODERATORS_PAGE_1).unwrap();
+    let cursor = page_1
+        .pointer("/pagination/cursor")
+        .and_then(|cursor| cursor.as_str())
+        .unwrap()
+        .to_string();
+    let authenticator: Arc<Box<dyn TwitchAuthenticator>> = Arc::new(Box::new(
+        TestAuthenticator::new()
+            .with_helix_response("users", serde_json::from_str(HELIX_USERS_RESPONSE).unwrap())
+            .with_helix_response(MODERATORS_PATH, page_1)
+            .with_helix_response(
+                // the cursor is padded base64, so it has to arrive encoded
+                &format!("{MODERATORS_PATH}&after={}", cursor.replace('=', "%3D")),
+                serde_json::from_str(MODERATORS_PAGE_2).unwrap(),
+            ),
+    ));
+    let TestTokenStore(token_store) = TestTokenStore::new(&database, &authenticator);
+    token_store
+        .store(
+            "test-user",
+            &TwitchUserTokens {
+                access_token: "access".to_string(),
+                refresh_token: Some("refresh".to_string()),
+                expires_in: 3600,
+                scope: vec!["moderation:read".to_string()],
+                token_type: "bearer".to_string(),
+            },
+        )
+        .await
+        .unwrap();
+    let moderator_sync = ModeratorSync::new(Arc::clone(&database), token_store);
+    (database, moderator_sync)
+}
+
+fn admin_logins(database: &dyn Database, source: &str) -> Vec<String> {
+    let broadcaster = database.get_user("test-user").unwrap().unwrap();
+    let mut logins: Vec<String> = database
+        .get_channel_admins(&broadcaster)
+        .unwrap()
+        .into_iter()
+        .filter(|channel_admin| channel_admin.source == source)
+        .map(|channel_admin| channel_admin.username)
+        .collect();
+    logins.sort();
+    logins
+}
+
+#[rstest::rstest]
+#[tokio::test]
+async fn test_sync_adds_moderators_across_pages() {
+    let (database, moderator_sync) = setup().await;
+    let broadcaster = database.get_user("test-user").unwrap().unwrap();
+
+    let summary = moderator_sync.sync(&broadcaster).await.unwrap();
+
+    assert_eq!(summary.moderators, 2);
+    assert_eq!(summary.added, 2);
+    assert_eq!(summary.removed, 0);
+    assert_eq!(
+        admin_logins(database.as_ref(), ChannelAdmin::TWITCH_MODERATOR),
+        vec!["test-moderator-1", "test-moderator-2"]
+    );
+}
+
+#[rstest::rstest]
+#[tokio::test]
+async fn test_sync_keeps_manual_admins_and_removes_stale_moderators() {
+    let (database, moderator_sync) = setup().await;
+    let broadcaster = database.get_user("test-user").unwrap().unwrap();
+    database
+        .create_channel_admin(&ChannelAdmin::for_login(
+            "test-helper",
+            &broadcaster,
+            ChannelAdmin::MANUAL,
+        ))
+        .unwrap();
+    database
+        .create_channel_admin(&ChannelAdmin::for_login(
+            "test-former-moderator",
+            &broadcaster,
+            ChannelAdmin::TWITCH_MODERATOR,
+        ))
+        .unwrap();
+
+    let summary = moderator_sync.sync(&broadcaster).await.unwrap();
+    let repeated = moderator_sync.sync(&broadcaster).await.unwrap();
+
+    assert_eq!(summary.added, 2);
+    assert_eq!(summary.removed, 1);
+    assert_eq!(repeated.added, 0);
+    assert_eq!(repeated.removed, 0);
+    assert_eq!(
+        admin_logins(database.as_ref(), ChannelAdmin::MANUAL),
+        vec!["test-helper"]
+    );
+    assert_eq!(
+        admin_logins(database.as_ref(), ChannelAdmin::TWITCH_MODERATOR),
+        vec!["test-moderator-1", "test-moderator-2"]
+    );
+}
+
+#[rstest::rstest]
+#[tokio::test]
+async fn test_sync_all_only_syncs_opted_in_broadcasters() {
+    let (database, moderator_sync) = setup().await;
+
+    assert_eq!(moderator_sync.sync_all().await.unwrap(), 0);
+
+    let broadcaster = database.get_user("test-user").unwrap().unwrap();
+    let settings = ValidatedUnownedUserSettings {
+        sync_moderators: true,
+        ..Default::default()
+    };
+    database
+        .create_user_settings(&settings.with_owner(&broadcaster))
+        .unwrap();
+
+    assert_eq!(moderator_sync.sync_all().await.unwrap(), 1);
+    assert_eq!(
+        admin_logins(database.as_ref(), ChannelAdmin::TWITCH_MODERATOR).len(),
+        2
+    );
+}
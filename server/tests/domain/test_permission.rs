@@ -0,0 +1,153 @@
+use std::sync::Arc;
+
+use imgfloat::{
+    domain::{
+        db::{AdminRepository, UserRepository},
+        PermissionError, PermissionService,
+    },
+    models::{Capability, ChannelAdmin, ChannelRole},
+};
+
+use crate::fixture::{EmptySession, TestDbService, TestUser};
+
+#[rstest::rstest]
+#[case(ChannelRole::ViewerSubmitter, Capability::Submit, true)]
+#[case(ChannelRole::ViewerSubmitter, Capability::Upload, false)]
+#[case(ChannelRole::ViewerSubmitter, Capability::PlaceAssets, false)]
+#[case(ChannelRole::Editor, Capability::Submit, true)]
+#[case(ChannelRole::Editor, Capability::PlaceAssets, true)]
+#[case(ChannelRole::Editor, Capability::DeleteAssets, true)]
+#[case(ChannelRole::Editor, Capability::ManageScenes, true)]
+#[case(ChannelRole::Editor, Capability::ManageAdmins, false)]
+#[case(ChannelRole::Editor, Capability::ChangeSettings, false)]
+#[case(ChannelRole::Manager, Capability::ManageAdmins, true)]
+#[case(ChannelRole::Manager, Capability::ChangeSettings, true)]
+#[case(ChannelRole::Broadcaster, Capability::ChangeSettings, true)]
+fn test_role_capabilities(
+    #[case] role: ChannelRole,
+    #[case] capability: Capability,
+    #[case] expected: bool,
+) {
+    assert_eq!(role.allows(capability), expected);
+}
+
+#[rstest::rstest]
+#[case("viewer_submitter", ChannelRole::ViewerSubmitter)]
+#[case("editor", ChannelRole::Editor)]
+#[case("manager", ChannelRole::Manager)]
+#[case("something-else", ChannelRole::ViewerSubmitter)]
+fn test_stored_role(#[case] stored: &str, #[case] expected: ChannelRole) {
+    let broadcaster = TestUser::new("test-broadcaster").as_db_user();
+    let mut channel_admin =
+        ChannelAdmin::for_login("test-user", &broadcaster, ChannelAdmin::MANUAL);
+    channel_admin.role = stored.to_string();
+    assert_eq!(channel_admin.role(), expected);
+}
+
+#[rstest::rstest]
+#[tokio::test]
+async fn test_authorize() {
+    let TestDbService(dbservice) = TestDbService::new();
+    let broadcaster = TestUser::new("test-broadcaster");
+    let editor = TestUser::new("test-editor");
+    let submitter = TestUser::new("test-submitter");
+    let viewer = TestUser::new("test-viewer");
+    dbservice.create_user(&broadcaster.as_db_user()).unwrap();
+    dbservice
+        .create_channel_admin(&ChannelAdmin::new(
+            &editor.as_db_user(),
+            &broadcaster.as_db_user(),
+        ))
+        .unwrap();
+    dbservice
+        .create_channel_admin(
+            &ChannelAdmin::new(&submitter.as_db_user(), &broadcaster.as_db_user())
+                .with_role(ChannelRole::ViewerSubmitter),
+        )
+        .unwrap();
+    let permissions = PermissionService::new(Arc::new(dbservice));
+
+    let authorized = permissions
+        .authorize(
+            &broadcaster.create_session(),
+            "test-broadcaster",
+            Capability::ManageAdmins,
+        )
+        .await
+        .unwrap();
+    assert_eq!(authorized, broadcaster.as_db_user());
+    assert!(permissions
+        .authorize(
+            &editor.create_session(),
+            "test-broadcaster",
+            Capability::PlaceAssets
+        )
+        .await
+        .is_ok());
+    assert_eq!(
+        permissions
+            .authorize(
+                &editor.create_session(),
+                "test-broadcaster",
+                Capability::ManageAdmins
+            )
+            .await,
+        Err(PermissionError::Forbidden)
+    );
+    assert_eq!(
+        permissions
+            .authorize(
+                &submitter.create_session(),
+                "test-broadcaster",
+                Capability::PlaceAssets
+            )
+            .await,
+        Err(PermissionError::Forbidden)
+    );
+    assert_eq!(
+        permissions
+            .authorize(
+                &submitter.create_session(),
+                "test-broadcaster",
+                Capability::Upload
+            )
+            .await,
+        Err(PermissionError::Forbidden)
+    );
+    assert_eq!(
+        permissions
+            .authorize(
+                &viewer.create_session(),
+                "test-broadcaster",
+                Capability::Upload
+            )
+            .await,
+        Err(PermissionError::Forbidden)
+    );
+    // viewers who aren't admins still submit as viewer-submitters
+    assert!(permissions
+        .authorize(
+            &viewer.create_session(),
+            "test-broadcaster",
+            Capability::Submit
+        )
+        .await
+        .is_ok());
+    let EmptySession(anonymous) = EmptySession::new();
+    assert_eq!(
+        permissions
+            .authorize(&anonymous, "test-broadcaster", Capability::Upload)
+            .await,
+        Err(PermissionError::Unauthenticated)
+    );
+    assert_eq!(
+        permissions
+            .authorize(
+                &broadcaster.create_session(),
+                "test-nobody",
+                Capability::Upload
+            )
+            .await,
+        Err(PermissionError::UnknownBroadcaster)
+    );
+}
@@ -0,0 +1,86 @@
+use std::{
+    net::SocketAddr,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use axum::{
+    body::Body,
+    extract::ConnectInfo,
+    http::{header, Request, StatusCode},
+    routing::get,
+    Router,
+};
+use imgfloat::domain::{rate_limit::rate_limit, RateLimit, RateLimiter};
+use tower::ServiceExt;
+
+#[rstest::rstest]
+#[case("20/60", Some(RateLimit::new(20, Duration::from_secs(60))))]
+#[case(" 5 / 1 ", Some(RateLimit::new(5, Duration::from_secs(1))))]
+#[case("0/60", None)]
+#[case("20/0", None)]
+#[case("20", None)]
+#[case("twenty/60", None)]
+fn test_parse_rate_limit(#[case] value: &str, #[case] expected: Option<RateLimit>) {
+    assert_eq!(value.parse::<RateLimit>().ok(), expected);
+}
+
+#[rstest::rstest]
+#[tokio::test]
+async fn test_bucket_refills_over_time() {
+    let limiter = RateLimiter::new("test", RateLimit::new(2, Duration::from_secs(10)));
+    let start = Instant::now();
+
+    assert!(limiter.check_at("test-user", start).await.is_ok());
+    assert!(limiter.check_at("test-user", start).await.is_ok());
+    let retry_after = limiter.check_at("test-user", start).await.unwrap_err();
+    assert_eq!(retry_after, Duration::from_secs(5));
+
+    assert!(limiter.check_at("test-other-user", start).await.is_ok());
+    assert!(limiter
+        .check_at("test-user", start + Duration::from_secs(4))
+        .await
+        .is_err());
+    assert!(limiter
+        .check_at("test-user", start + Duration::from_secs(10))
+        .await
+        .is_ok());
+}
+
+fn request_from(address: &str) -> Request<Body> {
+    let mut request = Request::builder().uri("/").body(Body::empty()).unwrap();
+    request
+        .extensions_mut()
+        .insert(ConnectInfo(address.parse::<SocketAddr>().unwrap()));
+    request
+}
+
+#[rstest::rstest]
+#[tokio::test]
+async fn test_middleware_limits_per_ip() {
+    let limiter = Arc::new(RateLimiter::new(
+        "test",
+        RateLimit::new(1, Duration::from_secs(60)),
+    ));
+    let app = Router::new()
+        .route("/", get(|| async { "ok" }))
+        .layer(axum::middleware::from_fn_with_state(limiter, rate_limit));
+
+    let response = app
+        .clone()
+        .oneshot(request_from("10.0.0.1:1234"))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let response = app
+        .clone()
+        .oneshot(request_from("10.0.0.1:4321"))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+    assert_eq!(response.headers()[header::RETRY_AFTER], "60");
+
+    let response = app.oneshot(request_from("10.0.0.2:1234")).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+}
@@ -0,0 +1,133 @@
+use imgfloat::{
+    domain::{
+        db::{Database, DbError, InMemoryDbService},
+        message::ImgfloatState,
+    },
+    models::{
+        user_settings::ValidatedUnownedUserSettings, AssetSearchQuery, ChannelAdmin, ChannelRole,
+        StoredChannelState,
+    },
+};
+
+use crate::fixture::{TestAsset, TestDbService, TestUser};
+
+fn sql() -> Box<dyn Database> {
+    let TestDbService(dbservice) = TestDbService::new();
+    Box::new(dbservice)
+}
+
+fn in_memory() -> Box<dyn Database> {
+    Box::new(InMemoryDbService::new())
+}
+
+#[rstest::rstest]
+#[case::sql(sql())]
+#[case::in_memory(in_memory())]
+fn test_users(#[case] database: Box<dyn Database>) {
+    let user = TestUser::new("test-user").as_db_user();
+
+    assert_eq!(database.get_user("test-user"), Ok(None));
+    assert_eq!(database.create_user(&user), Ok(user.clone()));
+    assert_eq!(database.get_user("test-user"), Ok(Some(user.clone())));
+    assert!(matches!(
+        database.create_user(&user),
+        Err(DbError::Conflict(_))
+    ));
+}
+
+#[rstest::rstest]
+#[case::sql(sql())]
+#[case::in_memory(in_memory())]
+fn test_settings(#[case] database: Box<dyn Database>) {
+    let user = TestUser::new("test-user").as_db_user();
+    database.create_user(&user).unwrap();
+    let settings = ValidatedUnownedUserSettings::default().with_owner(&user);
+
+    assert_eq!(
+        database.update_user_settings(&settings),
+        Err(DbError::NotFound)
+    );
+    database.create_user_settings(&settings).unwrap();
+    assert_eq!(database.get_user_settings(&user), Ok(Some(settings)));
+}
+
+#[rstest::rstest]
+#[case::sql(sql())]
+#[case::in_memory(in_memory())]
+fn test_asset_search(#[case] database: Box<dyn Database>) {
+    let user = TestUser::new("test-user").as_db_user();
+    let old = TestAsset::new("Hype.png")
+        .with_uploaded_at(100)
+        .as_db_asset(&user);
+    let new = TestAsset::new("hype-2.png")
+        .with_uploaded_at(200)
+        .as_db_asset(&user);
+    let other = TestAsset::new("sad.png").as_db_asset(&user);
+    database.create_user(&user).unwrap();
+    for asset in [&old, &new, &other] {
+        database.create_asset(asset).unwrap();
+    }
+
+    let query = AssetSearchQuery {
+        name: Some("hype".to_string()),
+        ..Default::default()
+    };
+    let page = database.search_assets(&user, &query).unwrap();
+    let filenames: Vec<_> = page
+        .assets
+        .into_iter()
+        .map(|asset| asset.original_filename)
+        .collect();
+    assert_eq!(page.total, 2);
+    assert_eq!(filenames, vec!["hype-2.png", "Hype.png"]);
+    assert_eq!(
+        database.get_asset_by_checksum(&other.checksum),
+        Ok(Some(other))
+    );
+}
+
+#[rstest::rstest]
+#[case::sql(sql())]
+#[case::in_memory(in_memory())]
+fn test_channel_admins(#[case] database: Box<dyn Database>) {
+    let broadcaster = TestUser::new("test-broadcaster").as_db_user();
+    database.create_user(&broadcaster).unwrap();
+    let helper = ChannelAdmin::for_login("test-helper", &broadcaster, ChannelAdmin::MANUAL);
+    database.create_channel_admin(&helper).unwrap();
+
+    let promoted = database
+        .set_channel_admin_role("test-helper", &broadcaster, ChannelRole::Manager)
+        .unwrap()
+        .unwrap();
+    assert_eq!(promoted.role(), ChannelRole::Manager);
+    assert_eq!(
+        database.set_channel_admin_role("test-nobody", &broadcaster, ChannelRole::Manager),
+        Ok(None)
+    );
+    assert_eq!(
+        database.delete_channel_admin("test-helper", &broadcaster),
+        Ok(1)
+    );
+    assert_eq!(
+        database.get_channel_admin("test-helper", &broadcaster),
+        Ok(None)
+    );
+}
+
+#[rstest::rstest]
+#[case::sql(sql())]
+#[case::in_memory(in_memory())]
+fn test_channel_states(#[case] database: Box<dyn Database>) {
+    let broadcaster = TestUser::new("test-broadcaster").as_db_user();
+    database.create_user(&broadcaster).unwrap();
+    let empty =
+        StoredChannelState::new("test-broadcaster", &ImgfloatState { assets: vec![] }).unwrap();
+    database.save_channel_state(&empty).unwrap();
+    let mut replaced = empty.clone();
+    replaced.saved_at += 1;
+    database.save_channel_state(&replaced).unwrap();
+
+    assert_eq!(database.get_channel_states(), Ok(vec![replaced]));
+    assert_eq!(database.delete_channel_state("test-broadcaster"), Ok(1));
+    assert_eq!(database.get_channel_states(), Ok(vec![]));
+}
@@ -0,0 +1,76 @@
+use std::{collections::HashMap, sync::Arc};
+
+use imgfloat::domain::db::Database;
+use imgfloat::domain::DatabaseSessionStore;
+use time::{Duration, OffsetDateTime};
+use tower_sessions::{
+    session::{Id, Record},
+    ExpiredDeletion, SessionStore,
+};
+
+use crate::fixture::TestDbService;
+
+fn record(expiry_date: OffsetDateTime) -> Record {
+    Record {
+        id: Id::default(),
+        data: HashMap::from([("session.user".to_string(), serde_json::json!("test-user"))]),
+        expiry_date,
+    }
+}
+
+#[rstest::rstest]
+async fn test_session_survives_new_store() {
+    let TestDbService(dbservice) = TestDbService::new();
+    let database: Arc<dyn Database> = Arc::new(dbservice);
+    let mut session = record(OffsetDateTime::now_utc() + Duration::days(1));
+
+    DatabaseSessionStore::new(Arc::clone(&database))
+        .create(&mut session)
+        .await
+        .unwrap();
+    let restarted_store = DatabaseSessionStore::new(Arc::clone(&database));
+    let loaded = restarted_store.load(&session.id).await.unwrap().unwrap();
+
+    assert_eq!(loaded.id, session.id);
+    assert_eq!(loaded.data, session.data);
+    assert_eq!(
+        loaded.expiry_date.unix_timestamp(),
+        session.expiry_date.unix_timestamp()
+    );
+}
+
+#[rstest::rstest]
+async fn test_save_and_delete() {
+    let TestDbService(dbservice) = TestDbService::new();
+    let store = DatabaseSessionStore::new(Arc::new(dbservice));
+    let mut session = record(OffsetDateTime::now_utc() + Duration::days(1));
+
+    store.create(&mut session).await.unwrap();
+    session
+        .data
+        .insert("other".to_string(), serde_json::json!(1));
+    store.save(&session).await.unwrap();
+    let loaded = store.load(&session.id).await.unwrap().unwrap();
+    assert_eq!(loaded.data, session.data);
+
+    store.delete(&session.id).await.unwrap();
+    assert!(store.load(&session.id).await.unwrap().is_none());
+}
+
+#[rstest::rstest]
+async fn test_expired_sessions() {
+    let TestDbService(dbservice) = TestDbService::new();
+    let database: Arc<dyn Database> = Arc::new(dbservice);
+    let store = DatabaseSessionStore::new(Arc::clone(&database));
+    let mut expired = record(OffsetDateTime::now_utc() - Duration::minutes(1));
+    let mut active = record(OffsetDateTime::now_utc() + Duration::days(1));
+
+    store.create(&mut expired).await.unwrap();
+    store.create(&mut active).await.unwrap();
+    assert!(store.load(&expired.id).await.unwrap().is_none());
+
+    store.delete_expired().await.unwrap();
+    let deleted = database.delete_expired_sessions(i64::MAX).unwrap();
+    assert_eq!(deleted, 1);
+    assert!(store.load(&active.id).await.unwrap().is_none());
+}
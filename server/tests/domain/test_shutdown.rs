@@ -0,0 +1,129 @@
+use std::{sync::Arc, time::Duration};
+
+use futures::StreamExt;
+use imgfloat::domain::{
+    backplane::{Backplane, LocalBackplane},
+    db::{ChannelStateRepository, Database, InMemoryDbService},
+    message::{ImgfloatAsset, ImgfloatServerMessage},
+    AppSessionStore, ChannelController, RateLimitConfig, ShutdownConfig,
+};
+use imgfloat::twitch::TwitchAuthenticator;
+use tokio_tungstenite::tungstenite::{protocol::frame::coding::CloseCode, Message};
+
+use crate::fixture::{TestAuthenticator, TestSocketServer, TestTokenStore};
+
+fn asset(id: &str) -> ImgfloatAsset {
+    ImgfloatAsset {
+        id: id.to_string(),
+        x: 0.0,
+        y: 0.0,
+        w: 10.0,
+        h: 10.0,
+        theta: 0.0,
+        url: format!("/api/assets/test-broadcaster/{id}.png"),
+    }
+}
+
+#[rstest::rstest]
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_shutdown_tells_sockets_to_reconnect() {
+    let controller = Arc::new(ChannelController::new());
+    let server = TestSocketServer::start(&controller).await;
+    let (reader, _) = tokio_tungstenite::connect_async(server.read_url())
+        .await
+        .unwrap();
+    let (writer, _) = tokio_tungstenite::connect_async(server.write_url())
+        .await
+        .unwrap();
+
+    tokio::time::timeout(
+        Duration::from_secs(5),
+        controller.shutdown(Duration::from_millis(1500)),
+    )
+    .await
+    .unwrap();
+
+    for mut socket in [reader, writer] {
+        let Some(Ok(Message::Text(text))) = socket.next().await else {
+            panic!("expected a restart message");
+        };
+        let ImgfloatServerMessage::Restarting { reconnect_in_ms } =
+            serde_json::from_str(&text).unwrap();
+        assert_eq!(reconnect_in_ms, 1500);
+        let Some(Ok(Message::Close(Some(frame)))) = socket.next().await else {
+            panic!("expected a close frame");
+        };
+        assert_eq!(frame.code, CloseCode::Restart);
+    }
+}
+
+#[rstest::rstest]
+#[tokio::test]
+async fn test_saved_states_are_restored_once() {
+    let database = InMemoryDbService::new();
+    let before = ChannelController::new();
+    before.show_asset("test-broadcaster", asset("1")).await;
+    before.save_states(&database).await;
+
+    let after = ChannelController::new();
+    after.restore_saved_states(&database).await;
+
+    let state = after.get_state("test-broadcaster").await.unwrap();
+    assert_eq!(state.assets[0].id, "1");
+    assert_eq!(database.get_channel_states().unwrap(), vec![]);
+}
+
+#[rstest::rstest]
+#[tokio::test]
+async fn test_only_owned_states_are_saved() {
+    let database = InMemoryDbService::new();
+    let backplane: Arc<dyn Backplane> = Arc::new(LocalBackplane::new());
+    let first = ChannelController::new().with_backplane(Arc::clone(&backplane));
+    let second = ChannelController::new().with_backplane(backplane);
+    first.show_asset("test-broadcaster", asset("1")).await;
+    second.show_asset("test-other", asset("2")).await;
+
+    first.save_states(&database).await;
+
+    let saved: Vec<_> = database
+        .get_channel_states()
+        .unwrap()
+        .into_iter()
+        .map(|stored| stored.username)
+        .collect();
+    assert_eq!(saved, vec!["test-broadcaster"]);
+}
+
+#[rstest::rstest]
+#[tokio::test]
+async fn test_run_fails_when_the_port_is_taken() {
+    let taken = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let port = taken.local_addr().unwrap().port();
+    let database: Arc<dyn Database> = Arc::new(InMemoryDbService::new());
+    let authenticator: Arc<Box<dyn TwitchAuthenticator>> =
+        Arc::new(Box::new(TestAuthenticator::new()));
+    let TestTokenStore(token_store) = TestTokenStore::new(&database, &authenticator);
+
+    let served = tokio::time::timeout(
+        Duration::from_secs(5),
+        imgfloat::run(
+            authenticator,
+            Arc::new(ChannelController::new()),
+            database,
+            token_store,
+            None,
+            AppSessionStore::Memory(Default::default()),
+            RateLimitConfig::default(),
+            ShutdownConfig::default(),
+            String::new(),
+            String::new(),
+            String::new(),
+            "127.0.0.1",
+            port,
+        ),
+    )
+    .await
+    .unwrap();
+
+    assert_eq!(served.unwrap_err().kind(), std::io::ErrorKind::AddrInUse);
+}
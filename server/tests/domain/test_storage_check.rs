@@ -0,0 +1,73 @@
+use std::{sync::Arc, time::Duration};
+
+use imgfloat::domain::db::Database;
+use imgfloat::domain::{
+    AssetStorage, FilesystemAssetStorage, StorageConsistencyChecker, StorageConsistencyReport,
+};
+
+use crate::fixture::{TestAsset, TestAssetDirectory, TestDbService, TestUser};
+
+#[rstest::rstest]
+#[tokio::test]
+async fn test_reports_discrepancies() {
+    let broadcaster = TestUser::new("test-broadcaster");
+    let asset_dir = TestAssetDirectory::new();
+    let TestDbService(dbservice) = TestDbService::new();
+    let database: Arc<dyn Database> = Arc::new(dbservice);
+    let consistent = TestAsset::new("consistent.png").with_data("consistent");
+    let missing = TestAsset::new("missing.png").with_data("missing");
+    let corrupted = TestAsset::new("corrupted.png").with_data("corrupted");
+    let orphan = TestAsset::new("orphan.png").with_data("orphan");
+    let corrupted_asset = corrupted.as_db_asset(&broadcaster.as_db_user());
+
+    database.create_user(&broadcaster.as_db_user()).unwrap();
+    database
+        .create_asset(&consistent.as_db_asset(&broadcaster.as_db_user()))
+        .unwrap();
+    database
+        .create_asset(&missing.as_db_asset(&broadcaster.as_db_user()))
+        .unwrap();
+    database.create_asset(&corrupted_asset).unwrap();
+    consistent.write_to(&asset_dir.0);
+    orphan.write_to(&asset_dir.0);
+    let corrupted_path = format!("{}/{}", asset_dir.0, corrupted_asset.local_filename);
+    std::fs::write(corrupted_path, "bit rot").unwrap();
+
+    let storage: Arc<dyn AssetStorage> = Arc::new(FilesystemAssetStorage::new(&asset_dir.0));
+    let report = StorageConsistencyChecker::new(Duration::ZERO)
+        .check_database(database.as_ref(), storage)
+        .await
+        .unwrap();
+
+    let filename = |asset: &TestAsset| asset.as_db_asset(&broadcaster.as_db_user()).local_filename;
+    assert_eq!(
+        report,
+        StorageConsistencyReport {
+            orphan_files: vec![filename(&orphan)],
+            missing_files: vec![filename(&missing)],
+            checksum_mismatches: vec![corrupted_asset.local_filename.clone()],
+            deleted_orphans: vec![],
+        }
+    );
+    assert!(!report.is_consistent());
+    assert_eq!(asset_dir.files().len(), 3);
+}
+
+#[rstest::rstest]
+#[case(Duration::ZERO, true)]
+#[case(Duration::from_secs(3600), false)]
+fn test_orphan_deletion_grace_period(#[case] grace_period: Duration, #[case] deleted: bool) {
+    let asset_dir = TestAssetDirectory::new();
+    let orphan = TestAsset::new("orphan.png").with_data("orphan");
+    orphan.write_to(&asset_dir.0);
+
+    let storage = FilesystemAssetStorage::new(&asset_dir.0);
+    let report = StorageConsistencyChecker::new(grace_period)
+        .with_orphan_deletion(true)
+        .check(&[], &storage)
+        .unwrap();
+
+    assert_eq!(report.orphan_files.len(), 1);
+    assert_eq!(report.deleted_orphans.len(), usize::from(deleted));
+    assert_eq!(asset_dir.files().is_empty(), deleted);
+}
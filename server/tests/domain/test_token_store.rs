@@ -0,0 +1,145 @@
+use std::sync::Arc;
+
+use imgfloat::{
+    domain::{
+        db::{Database, UserRepository},
+        token_store::TokenStoreError,
+    },
+    twitch::{TwitchAuthenticator, TwitchUserTokens},
+};
+
+use crate::fixture::{TestAuthenticator, TestDbService, TestTokenStore, TestUser};
+
+fn tokens(access_token: &str, refresh_token: Option<&str>, expires_in: u64) -> TwitchUserTokens {
+    TwitchUserTokens {
+        access_token: access_token.to_string(),
+        refresh_token: refresh_token.map(str::to_string),
+        expires_in,
+        scope: vec!["user:read:email".to_string()],
+        token_type: "bearer".to_string(),
+    }
+}
+
+fn setup(authenticator: TestAuthenticator) -> (Arc<dyn Database>, TestTokenStore) {
+    let TestDbService(dbservice) = TestDbService::new();
+    let user = TestUser::new("test-user");
+    dbservice.create_user(&user.as_db_user()).unwrap();
+    let database: Arc<dyn Database> = Arc::new(dbservice);
+    let authenticator: Arc<Box<dyn TwitchAuthenticator>> = Arc::new(Box::new(authenticator));
+    let token_store = TestTokenStore::new(&database, &authenticator);
+    (database, token_store)
+}
+
+#[rstest::rstest]
+#[tokio::test]
+async fn test_tokens_encrypted_at_rest() {
+    let (database, TestTokenStore(token_store)) = setup(TestAuthenticator::new());
+
+    token_store
+        .store("test-user", &tokens("access-1", Some("refresh-1"), 3600))
+        .await
+        .unwrap();
+
+    let stored_tokens = database.get_user_tokens("test-user").unwrap().unwrap();
+    assert!(!stored_tokens.access_token.contains("access-1"));
+    assert!(!stored_tokens
+        .refresh_token
+        .as_deref()
+        .unwrap()
+        .contains("refresh-1"));
+    assert_eq!(stored_tokens.scope, "user:read:email");
+    let loaded = token_store.get_tokens("test-user").await.unwrap();
+    assert_eq!(loaded.access_token, "access-1");
+    assert_eq!(loaded.refresh_token.as_deref(), Some("refresh-1"));
+    assert_eq!(loaded.scope, vec!["user:read:email".to_string()]);
+}
+
+#[rstest::rstest]
+#[tokio::test]
+async fn test_expiring_tokens_are_refreshed() {
+    let authenticator = TestAuthenticator::new()
+        .with_tokens(tokens("access-1", Some("refresh-1"), 0))
+        .with_refreshed_tokens(tokens("access-2", None, 3600));
+    let (database, TestTokenStore(token_store)) = setup(authenticator);
+    token_store
+        .store("test-user", &tokens("access-1", Some("refresh-1"), 0))
+        .await
+        .unwrap();
+
+    let loaded = token_store.get_tokens("test-user").await.unwrap();
+
+    assert_eq!(loaded.access_token, "access-2");
+    assert_eq!(loaded.refresh_token.as_deref(), Some("refresh-1"));
+    let stored_tokens = database.get_user_tokens("test-user").unwrap().unwrap();
+    assert!(database
+        .get_expiring_user_tokens(stored_tokens.expires_at - 1)
+        .unwrap()
+        .is_empty());
+}
+
+#[rstest::rstest]
+#[tokio::test]
+async fn test_fresh_tokens_are_not_refreshed() {
+    let authenticator = TestAuthenticator::new()
+        .with_tokens(tokens("access-1", Some("refresh-1"), 3600))
+        .with_refreshed_tokens(tokens("access-2", Some("refresh-2"), 3600));
+    let (_database, TestTokenStore(token_store)) = setup(authenticator);
+    token_store
+        .store("test-user", &tokens("access-1", Some("refresh-1"), 3600))
+        .await
+        .unwrap();
+
+    assert_eq!(token_store.refresh_expiring().await.unwrap(), 0);
+    let loaded = token_store.get_tokens("test-user").await.unwrap();
+    assert_eq!(loaded.access_token, "access-1");
+}
+
+#[rstest::rstest]
+#[tokio::test]
+async fn test_refresh_expiring() {
+    let authenticator = TestAuthenticator::new()
+        .with_tokens(tokens("access-1", Some("refresh-1"), 0))
+        .with_refreshed_tokens(tokens("access-2", Some("refresh-2"), 3600));
+    let (_database, TestTokenStore(token_store)) = setup(authenticator);
+    token_store
+        .store("test-user", &tokens("access-1", Some("refresh-1"), 60))
+        .await
+        .unwrap();
+
+    assert_eq!(token_store.refresh_expiring().await.unwrap(), 1);
+    let loaded = token_store.get_tokens("test-user").await.unwrap();
+    assert_eq!(loaded.access_token, "access-2");
+    assert_eq!(loaded.refresh_token.as_deref(), Some("refresh-2"));
+}
+
+#[rstest::rstest]
+#[tokio::test]
+async fn test_missing_tokens() {
+    let (_database, TestTokenStore(token_store)) = setup(TestAuthenticator::new());
+
+    assert!(matches!(
+        token_store.get_tokens("test-user").await,
+        Err(TokenStoreError::NoTokens)
+    ));
+    assert!(matches!(
+        token_store.helix_get("test-user", "users").await,
+        Err(TokenStoreError::NoTokens)
+    ));
+}
+
+#[rstest::rstest]
+#[tokio::test]
+async fn test_helix_get_with_stored_tokens() {
+    let response = serde_json::json!({ "data": [{ "login": "test-user" }] });
+    let authenticator = TestAuthenticator::new().with_helix_response("users", response.clone());
+    let (_database, TestTokenStore(token_store)) = setup(authenticator);
+    token_store
+        .store("test-user", &tokens("access-1", None, 3600))
+        .await
+        .unwrap();
+
+    assert_eq!(
+        token_store.helix_get("test-user", "users").await.unwrap(),
+        response
+    );
+}
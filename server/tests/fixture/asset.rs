@@ -0,0 +1,62 @@
+use imgfloat::models::{Asset, User};
+use sha2::{Digest, Sha256};
+
+pub struct TestAsset {
+    filename: String,
+    original_filename: String,
+    content_type: String,
+    uploaded_at: i64,
+    data: Option<Vec<u8>>,
+}
+
+impl TestAsset {
+    pub fn new(original_filename: impl Into<String>) -> Self {
+        let original_filename = original_filename.into();
+        Self {
+            filename: format!("{}.png", uuid::Uuid::new_v4()),
+            original_filename,
+            content_type: "image/png".to_string(),
+            uploaded_at: 0,
+            data: None,
+        }
+    }
+
+    pub fn with_data(mut self, data: impl Into<Vec<u8>>) -> Self {
+        self.data = Some(data.into());
+        self
+    }
+
+    pub fn write_to(&self, asset_dir: &str) {
+        let data = self.data.as_deref().unwrap_or_default();
+        std::fs::write(format!("{asset_dir}/{}", self.filename), data).unwrap();
+    }
+
+    fn checksum(&self) -> String {
+        match &self.data {
+            Some(data) => format!("{:x}", Sha256::digest(data)),
+            None => self.filename.clone(),
+        }
+    }
+
+    pub fn with_content_type(mut self, content_type: impl Into<String>) -> Self {
+        self.content_type = content_type.into();
+        self
+    }
+
+    pub fn with_uploaded_at(mut self, uploaded_at: i64) -> Self {
+        self.uploaded_at = uploaded_at;
+        self
+    }
+
+    pub fn as_db_asset(&self, owner: &User) -> Asset {
+        Asset {
+            local_filename: self.filename.clone(),
+            original_filename: self.original_filename.clone(),
+            checksum: self.checksum(),
+            content_type: self.content_type.clone(),
+            username: owner.username.clone(),
+            folder_id: None,
+            uploaded_at: self.uploaded_at,
+        }
+    }
+}
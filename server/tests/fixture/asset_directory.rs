@@ -0,0 +1,24 @@
+pub struct TestAssetDirectory(pub String);
+
+impl TestAssetDirectory {
+    pub fn new() -> Self {
+        let path = std::env::temp_dir().join(format!("imgfloat-test-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&path).unwrap();
+        Self(path.to_string_lossy().to_string())
+    }
+
+    pub fn files(&self) -> Vec<String> {
+        let mut files: Vec<String> = std::fs::read_dir(&self.0)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().to_string())
+            .collect();
+        files.sort();
+        files
+    }
+}
+
+impl Drop for TestAssetDirectory {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
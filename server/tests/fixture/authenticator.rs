@@ -1,3 +1,5 @@
+use std::collections::HashMap;
+
 use imgfloat::twitch::{
     TwitchAuthenticator, TwitchAuthenticatorError, TwitchUser, TwitchUserTokens,
 };
@@ -5,6 +7,8 @@ use imgfloat::twitch::{
 pub struct TestAuthenticator {
     user: Option<TwitchUser>,
     tokens: Option<TwitchUserTokens>,
+    refreshed_tokens: Option<TwitchUserTokens>,
+    helix_responses: HashMap<String, serde_json::Value>,
 }
 
 impl TestAuthenticator {
@@ -12,6 +16,8 @@ impl TestAuthenticator {
         Self {
             user: None,
             tokens: None,
+            refreshed_tokens: None,
+            helix_responses: HashMap::new(),
         }
     }
 
@@ -24,6 +30,21 @@ impl TestAuthenticator {
         self.tokens = Some(tokens);
         self
     }
+
+    pub fn with_refreshed_tokens(mut self, tokens: TwitchUserTokens) -> Self {
+        self.refreshed_tokens = Some(tokens);
+        self
+    }
+
+    pub fn with_helix_response(
+        mut self,
+        path_and_query: &str,
+        response: serde_json::Value,
+    ) -> Self {
+        self.helix_responses
+            .insert(path_and_query.to_string(), response);
+        self
+    }
 }
 
 #[async_trait::async_trait]
@@ -46,7 +67,40 @@ impl TwitchAuthenticator for TestAuthenticator {
             .ok_or(TwitchAuthenticatorError("tokens missing".to_string()))
     }
 
-    fn create_auth_url(&self, _scope: &str) -> String {
-        "".to_string()
+    async fn refresh_tokens(
+        &self,
+        refresh_token: &str,
+    ) -> Result<TwitchUserTokens, TwitchAuthenticatorError> {
+        let current_refresh_token = self
+            .tokens
+            .as_ref()
+            .and_then(|tokens| tokens.refresh_token.as_deref());
+        if current_refresh_token != Some(refresh_token) {
+            return Err(TwitchAuthenticatorError(
+                "invalid refresh token".to_string(),
+            ));
+        }
+        self.refreshed_tokens
+            .clone()
+            .ok_or(TwitchAuthenticatorError(
+                "refreshed tokens missing".to_string(),
+            ))
+    }
+
+    async fn helix_get(
+        &self,
+        _tokens: &TwitchUserTokens,
+        path_and_query: &str,
+    ) -> Result<serde_json::Value, TwitchAuthenticatorError> {
+        self.helix_responses
+            .get(path_and_query)
+            .cloned()
+            .ok_or(TwitchAuthenticatorError(
+                "helix response missing".to_string(),
+            ))
+    }
+
+    fn create_auth_url(&self, scope: &str, state: &str) -> String {
+        format!("/authorize?scope={scope}&state={state}")
     }
 }
@@ -0,0 +1,41 @@
+use std::sync::{Arc, RwLock};
+
+use imgfloat::domain::backplane::{
+    Backplane, BackplaneError, BackplaneMessage, BackplaneSubscriber, LocalBackplane,
+};
+
+/// A `LocalBackplane` whose subscribers can be told they lost messages.
+#[derive(Default)]
+pub struct TestBackplane {
+    backplane: LocalBackplane,
+    subscribers: RwLock<Vec<Arc<dyn BackplaneSubscriber>>>,
+}
+
+impl TestBackplane {
+    pub fn new() -> Arc<Self> {
+        Arc::new(Self::default())
+    }
+
+    /// Resyncs the subscriber attached `index`-th, as after a reconnect.
+    pub async fn reconnect(&self, index: usize) {
+        let subscriber = Arc::clone(&self.subscribers.read().unwrap()[index]);
+        for message in subscriber.resync().await {
+            self.backplane.publish(message).await.unwrap();
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl Backplane for TestBackplane {
+    async fn publish(&self, message: BackplaneMessage) -> Result<(), BackplaneError> {
+        self.backplane.publish(message).await
+    }
+
+    fn attach(&self, subscriber: Arc<dyn BackplaneSubscriber>) {
+        self.subscribers
+            .write()
+            .unwrap()
+            .push(Arc::clone(&subscriber));
+        self.backplane.attach(subscriber);
+    }
+}
@@ -1,15 +1,157 @@
-use diesel_migrations::{embed_migrations, EmbeddedMigrations, MigrationHarness};
-use imgfloat::domain::db::SqliteDbService;
+use diesel::connection::SimpleConnection;
+use diesel::r2d2::{ConnectionManager, CustomizeConnection, Pool, PooledConnection};
+use diesel::{Connection, PgConnection, RunQueryDsl, SqliteConnection};
+use imgfloat::domain::db::{ConnectionPool, SqlDbService};
 
-pub const MIGRATIONS: EmbeddedMigrations = embed_migrations!("migrations");
+/// Set to a `postgres://` URL to run the database tests against PostgreSQL
+/// instead of an in-memory SQLite database. Every test gets its own schema,
+/// which is dropped once the test's database service is.
+const TEST_DATABASE_URL: &str = "TEST_DATABASE_URL";
 
-pub struct TestDbService(pub SqliteDbService);
+pub struct TestDbService(pub SqlDbService);
 
 impl TestDbService {
     pub fn new() -> Self {
-        let service = SqliteDbService::new(":memory:").unwrap();
-        let mut conn = service.pool.get().unwrap();
-        conn.run_pending_migrations(MIGRATIONS).unwrap();
-        Self(service)
+        let service = Self::unmigrated();
+        service.0.migrate().unwrap();
+        service
     }
+
+    pub fn unmigrated() -> Self {
+        match postgres_url() {
+            Some(database_url) => Self::postgres(&database_url),
+            None => Self(SqlDbService::new(":memory:").unwrap()),
+        }
+    }
+
+    /// A database file under `directory`, for tests that need more than one
+    /// pooled connection.
+    pub fn in_directory(directory: &str) -> Self {
+        let service = match postgres_url() {
+            Some(database_url) => Self::postgres(&database_url),
+            None => Self(SqlDbService::new(&format!("{directory}/imgfloat.db")).unwrap()),
+        };
+        service.0.migrate().unwrap();
+        service
+    }
+
+    fn postgres(database_url: &str) -> Self {
+        let schema = format!("test_{}", uuid::Uuid::new_v4().simple());
+        PgConnection::establish(database_url)
+            .unwrap()
+            .batch_execute(&format!("CREATE SCHEMA {schema}"))
+            .unwrap();
+        let separator = if database_url.contains('?') { '&' } else { '?' };
+        let schema_url = format!("{database_url}{separator}options=-csearch_path%3D{schema}");
+        // the pool owns the schema, so it goes away with the last connection
+        let pool = Pool::builder()
+            .max_size(16)
+            .connection_customizer(Box::new(TestSchema {
+                database_url: database_url.to_string(),
+                schema,
+            }))
+            .build(ConnectionManager::<PgConnection>::new(schema_url))
+            .unwrap();
+        Self(SqlDbService {
+            pool: ConnectionPool::Postgres(pool),
+        })
+    }
+
+    pub fn execute(&self, query: &str) {
+        match &self.0.pool {
+            ConnectionPool::Sqlite(pool) => {
+                diesel::sql_query(query).execute(&mut pool.get().unwrap())
+            }
+            ConnectionPool::Postgres(pool) => {
+                diesel::sql_query(query).execute(&mut pool.get().unwrap())
+            }
+        }
+        .unwrap();
+    }
+
+    /// Starts writing `username`'s row on a connection of its own and keeps
+    /// the transaction open until the returned value is dropped.
+    pub fn hold_transaction(&self, username: &str) -> HeldTransaction {
+        let update = format!("UPDATE users SET username = username WHERE username = '{username}'");
+        match &self.0.pool {
+            ConnectionPool::Sqlite(pool) => {
+                let mut conn = pool.get().unwrap();
+                conn.batch_execute(&format!("BEGIN IMMEDIATE; {update};"))
+                    .unwrap();
+                HeldTransaction::Sqlite(conn)
+            }
+            ConnectionPool::Postgres(pool) => {
+                let mut conn = pool.get().unwrap();
+                conn.batch_execute(&format!("BEGIN; {update};")).unwrap();
+                HeldTransaction::Postgres(conn)
+            }
+        }
+    }
+
+    pub fn is_postgres(&self) -> bool {
+        matches!(self.0.pool, ConnectionPool::Postgres(_))
+    }
+
+    /// `None` on PostgreSQL, which has no journal mode.
+    pub fn journal_mode(&self) -> Option<String> {
+        #[derive(diesel::QueryableByName)]
+        struct JournalMode {
+            #[diesel(sql_type = diesel::sql_types::Text)]
+            journal_mode: String,
+        }
+        let ConnectionPool::Sqlite(pool) = &self.0.pool else {
+            return None;
+        };
+        let journal_mode = diesel::sql_query("PRAGMA journal_mode")
+            .get_result::<JournalMode>(&mut pool.get().unwrap())
+            .unwrap()
+            .journal_mode;
+        Some(journal_mode)
+    }
+}
+
+/// A test's PostgreSQL schema, dropped along with the pool that uses it.
+#[derive(Debug)]
+struct TestSchema {
+    database_url: String,
+    schema: String,
+}
+
+impl CustomizeConnection<PgConnection, diesel::r2d2::Error> for TestSchema {}
+
+impl Drop for TestSchema {
+    fn drop(&mut self) {
+        let dropped = PgConnection::establish(&self.database_url)
+            .map_err(|error| error.to_string())
+            .and_then(|mut conn| {
+                conn.batch_execute(&format!("DROP SCHEMA {} CASCADE", self.schema))
+                    .map_err(|error| error.to_string())
+            });
+        if let Err(error) = dropped {
+            eprintln!("unable to drop test schema {}: {error}", self.schema);
+        }
+    }
+}
+
+/// An open transaction from [`TestDbService::hold_transaction`], rolled back
+/// on drop.
+pub enum HeldTransaction {
+    Sqlite(PooledConnection<ConnectionManager<SqliteConnection>>),
+    Postgres(PooledConnection<ConnectionManager<PgConnection>>),
+}
+
+impl Drop for HeldTransaction {
+    fn drop(&mut self) {
+        match self {
+            Self::Sqlite(conn) => conn.batch_execute("ROLLBACK"),
+            Self::Postgres(conn) => conn.batch_execute("ROLLBACK"),
+        }
+        .unwrap();
+    }
+}
+
+fn postgres_url() -> Option<String> {
+    std::env::var(TEST_DATABASE_URL)
+        .ok()
+        .filter(|database_url| SqlDbService::is_postgres_url(database_url))
 }
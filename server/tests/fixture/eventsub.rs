@@ -0,0 +1,117 @@
+use std::sync::{Arc, Mutex};
+
+use axum::http::{HeaderMap, HeaderValue};
+use imgfloat::{
+    domain::{db::Database, ChannelController, EventSubService, TwitchTokenStore},
+    twitch::{
+        eventsub::{
+            eventsub_signature, EVENTSUB_MESSAGE_ID, EVENTSUB_MESSAGE_SIGNATURE,
+            EVENTSUB_MESSAGE_TIMESTAMP, EVENTSUB_MESSAGE_TYPE,
+        },
+        EventSubClient, EventSubClientError, EventSubSubscription, EventSubSubscriptionRequest,
+        TwitchAuthenticator,
+    },
+};
+use time::{format_description::well_known::Rfc3339, OffsetDateTime};
+
+use super::{TestAuthenticator, TestTokenStore};
+
+pub const TEST_EVENTSUB_SECRET: &str = "test-eventsub-secret";
+pub const VERIFICATION_PAYLOAD: &str = include_str!("eventsub/webhook_callback_verification.json");
+pub const REDEMPTION_PAYLOAD: &str = include_str!("eventsub/channel_points_redemption.json");
+pub const FOLLOW_PAYLOAD: &str = include_str!("eventsub/channel_follow.json");
+pub const REVOCATION_PAYLOAD: &str = include_str!("eventsub/revocation.json");
+pub const HELIX_USERS_RESPONSE: &str = include_str!("eventsub/helix_users.json");
+pub const HELIX_CREATE_SUBSCRIPTION_RESPONSE: &str =
+    include_str!("eventsub/helix_create_subscription.json");
+
+#[derive(Clone, Default)]
+pub struct TestEventSubClient {
+    pub created: Arc<Mutex<Vec<EventSubSubscriptionRequest>>>,
+    pub deleted: Arc<Mutex<Vec<String>>>,
+}
+
+#[async_trait::async_trait]
+impl EventSubClient for TestEventSubClient {
+    async fn create_subscription(
+        &self,
+        request: &EventSubSubscriptionRequest,
+    ) -> Result<EventSubSubscription, EventSubClientError> {
+        self.created.lock().unwrap().push(request.clone());
+        Ok(EventSubSubscription {
+            id: uuid::Uuid::new_v4().to_string(),
+            status: "webhook_callback_verification_pending".to_string(),
+            r#type: request.r#type.clone(),
+            version: request.version.clone(),
+            condition: request.condition.clone(),
+        })
+    }
+
+    async fn delete_subscription(&self, id: &str) -> Result<(), EventSubClientError> {
+        self.deleted.lock().unwrap().push(id.to_string());
+        Ok(())
+    }
+}
+
+pub struct TestEventSub {
+    pub service: Arc<EventSubService>,
+    pub controller: Arc<ChannelController>,
+    pub token_store: Arc<TwitchTokenStore>,
+    pub client: TestEventSubClient,
+}
+
+impl TestEventSub {
+    pub fn new(database: &Arc<dyn Database>, authenticator: TestAuthenticator) -> Self {
+        let authenticator: Arc<Box<dyn TwitchAuthenticator>> = Arc::new(Box::new(authenticator));
+        let TestTokenStore(token_store) = TestTokenStore::new(database, &authenticator);
+        let controller = Arc::new(ChannelController::new());
+        let client = TestEventSubClient::default();
+        let service = Arc::new(EventSubService::new(
+            Arc::clone(database),
+            Arc::clone(&controller),
+            Arc::clone(&token_store),
+            Box::new(client.clone()),
+            TEST_EVENTSUB_SECRET,
+            "https://example.com/api/eventsub",
+        ));
+        Self {
+            service,
+            controller,
+            token_store,
+            client,
+        }
+    }
+}
+
+pub fn signed_headers(
+    message_type: &str,
+    message_id: &str,
+    sent_at: OffsetDateTime,
+    body: &str,
+) -> HeaderMap {
+    let timestamp = sent_at.format(&Rfc3339).unwrap();
+    let signature = eventsub_signature(
+        TEST_EVENTSUB_SECRET,
+        message_id,
+        &timestamp,
+        body.as_bytes(),
+    );
+    let mut headers = HeaderMap::new();
+    headers.insert(
+        EVENTSUB_MESSAGE_ID,
+        HeaderValue::from_str(message_id).unwrap(),
+    );
+    headers.insert(
+        EVENTSUB_MESSAGE_TIMESTAMP,
+        HeaderValue::from_str(&timestamp).unwrap(),
+    );
+    headers.insert(
+        EVENTSUB_MESSAGE_SIGNATURE,
+        HeaderValue::from_str(&signature).unwrap(),
+    );
+    headers.insert(
+        EVENTSUB_MESSAGE_TYPE,
+        HeaderValue::from_str(message_type).unwrap(),
+    );
+    headers
+}
@@ -0,0 +1,55 @@
+use tokio::{
+    io::{AsyncBufReadExt, AsyncWriteExt, BufReader, Lines},
+    net::{
+        tcp::{OwnedReadHalf, OwnedWriteHalf},
+        TcpListener,
+    },
+};
+
+pub struct FakeIrcServer {
+    listener: TcpListener,
+}
+
+pub struct FakeIrcConnection {
+    lines: Lines<BufReader<OwnedReadHalf>>,
+    writer: OwnedWriteHalf,
+}
+
+impl FakeIrcServer {
+    pub async fn new() -> Self {
+        Self {
+            listener: TcpListener::bind("127.0.0.1:0").await.unwrap(),
+        }
+    }
+
+    pub fn port(&self) -> u16 {
+        self.listener.local_addr().unwrap().port()
+    }
+
+    pub async fn accept(&self) -> FakeIrcConnection {
+        let (stream, _) = self.listener.accept().await.unwrap();
+        let (reader, writer) = stream.into_split();
+        FakeIrcConnection {
+            lines: BufReader::new(reader).lines(),
+            writer,
+        }
+    }
+}
+
+impl FakeIrcConnection {
+    pub async fn next_line(&mut self) -> String {
+        self.lines.next_line().await.unwrap().unwrap()
+    }
+
+    pub async fn send(&mut self, line: &str) {
+        self.writer
+            .write_all(format!("{line}\r\n").as_bytes())
+            .await
+            .unwrap();
+    }
+
+    pub async fn round_trip(&mut self) {
+        self.send("PING :tmi.twitch.tv").await;
+        assert_eq!(self.next_line().await, "PONG :tmi.twitch.tv");
+    }
+}
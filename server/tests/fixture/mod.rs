@@ -1,11 +1,27 @@
+pub mod asset;
+pub mod asset_directory;
 pub mod authenticator;
+pub mod backplane;
 pub mod db;
+pub mod eventsub;
+pub mod irc;
+pub mod permissions;
 pub mod session;
+pub mod socket_server;
+pub mod token_store;
 pub mod tokens;
 pub mod user;
 
+pub use asset::TestAsset;
+pub use asset_directory::TestAssetDirectory;
 pub use authenticator::TestAuthenticator;
+pub use backplane::TestBackplane;
 pub use db::TestDbService;
+pub use eventsub::TestEventSub;
+pub use irc::FakeIrcServer;
+pub use permissions::TestPermissions;
 pub use session::EmptySession;
+pub use socket_server::TestSocketServer;
+pub use token_store::TestTokenStore;
 pub use tokens::TestTwitchTokens;
 pub use user::TestUser;
@@ -0,0 +1,11 @@
+use std::sync::Arc;
+
+use imgfloat::domain::{db::Database, PermissionService};
+
+pub struct TestPermissions(pub Arc<PermissionService>);
+
+impl TestPermissions {
+    pub fn new(database: &Arc<dyn Database>) -> Self {
+        Self(Arc::new(PermissionService::new(Arc::clone(database))))
+    }
+}
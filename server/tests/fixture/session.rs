@@ -11,6 +11,7 @@ impl EmptySession {
         Self(UserSession {
             session,
             user: None,
+            api_token: None,
         })
     }
 }
@@ -0,0 +1,54 @@
+use std::sync::Arc;
+
+use axum::{
+    extract::{State, WebSocketUpgrade},
+    response::Response,
+    routing::get,
+    Router,
+};
+use imgfloat::domain::ChannelController;
+
+/// Serves a controller's reader and writer sockets for `test-broadcaster`
+/// without authentication.
+pub struct TestSocketServer(pub String);
+
+impl TestSocketServer {
+    pub async fn start(controller: &Arc<ChannelController>) -> Self {
+        let app = Router::new()
+            .route("/read", get(Self::read))
+            .route("/write", get(Self::write))
+            .with_state(Arc::clone(controller));
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await });
+        Self(format!("ws://{address}"))
+    }
+
+    pub fn read_url(&self) -> String {
+        format!("{}/read", self.0)
+    }
+
+    pub fn write_url(&self) -> String {
+        format!("{}/write", self.0)
+    }
+
+    async fn read(
+        ws: WebSocketUpgrade,
+        State(controller): State<Arc<ChannelController>>,
+    ) -> Response {
+        ws.on_upgrade(move |socket| async move {
+            controller.add_reader(socket, "test-broadcaster").await;
+        })
+    }
+
+    async fn write(
+        ws: WebSocketUpgrade,
+        State(controller): State<Arc<ChannelController>>,
+    ) -> Response {
+        ws.on_upgrade(move |socket| async move {
+            controller
+                .add_writer(socket, "test-broadcaster", vec![])
+                .await;
+        })
+    }
+}
@@ -0,0 +1,23 @@
+use std::sync::Arc;
+
+use imgfloat::{
+    domain::{db::Database, TokenCipher, TwitchTokenStore},
+    twitch::TwitchAuthenticator,
+};
+
+pub const TEST_TOKEN_KEY: [u8; 32] = [7; 32];
+
+pub struct TestTokenStore(pub Arc<TwitchTokenStore>);
+
+impl TestTokenStore {
+    pub fn new(
+        database: &Arc<dyn Database>,
+        authenticator: &Arc<Box<dyn TwitchAuthenticator>>,
+    ) -> Self {
+        Self(Arc::new(TwitchTokenStore::new(
+            Arc::clone(database),
+            Arc::clone(authenticator),
+            TokenCipher::new(&TEST_TOKEN_KEY).unwrap(),
+        )))
+    }
+}
@@ -38,6 +38,7 @@ impl TestUser {
         UserSession {
             user: Some(self.as_twitch_user()),
             session,
+            api_token: None,
         }
     }
 }
@@ -1,4 +1,14 @@
+pub mod test_api_token;
+pub mod test_archive;
+pub mod test_asset;
 pub mod test_callback;
 pub mod test_channel_admin;
+pub mod test_eventsub;
+pub mod test_load;
 pub mod test_login;
+pub mod test_openapi;
+pub mod test_overlay_token;
+pub mod test_scene;
 pub mod test_settings;
+pub mod test_submission;
+pub mod test_trigger;
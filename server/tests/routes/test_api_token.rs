@@ -0,0 +1,220 @@
+use std::sync::Arc;
+
+use axum::{
+    body::Body,
+    extract::State,
+    http::{header, Request, StatusCode},
+    response::IntoResponse,
+    routing::get,
+    Router,
+};
+use http_body_util::BodyExt;
+use imgfloat::domain::extract::{Json, Path};
+use imgfloat::{
+    domain::{
+        db::{Database, UserRepository},
+        middleware::authenticate_api_token,
+        PermissionError,
+    },
+    models::{
+        secret, ApiToken, Capability, IssuedApiToken, NewApiToken, UnownedApiToken,
+        UserFacingApiToken,
+    },
+    routes::api::{api_token, whoami},
+    twitch::TwitchUser,
+};
+use tower::ServiceExt;
+use tower_sessions::{MemoryStore, SessionManagerLayer};
+
+use crate::fixture::{TestDbService, TestPermissions, TestUser};
+
+fn setup() -> Arc<dyn Database> {
+    let TestDbService(dbservice) = TestDbService::new();
+    dbservice
+        .create_user(&TestUser::new("test-user").as_db_user())
+        .unwrap();
+    Arc::new(dbservice)
+}
+
+async fn issue(
+    database: &Arc<dyn Database>,
+    scopes: Vec<Capability>,
+) -> Result<IssuedApiToken, StatusCode> {
+    let response = api_token::post(
+        State(Arc::clone(database)),
+        TestUser::new("test-user").create_session(),
+        Json(UnownedApiToken {
+            name: " stream deck ".to_string(),
+            scopes,
+            expires_in_days: Some(30),
+        }),
+    )
+    .await
+    .map_err(|error| error.status())?
+    .into_response();
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let body = response.into_body().collect().await.unwrap().to_bytes();
+    Ok(serde_json::from_slice(&body).unwrap())
+}
+
+async fn whoami_with_bearer(database: &Arc<dyn Database>, token: &str) -> StatusCode {
+    let app = Router::new()
+        .route("/api/whoami", get(whoami::get))
+        .layer(axum::middleware::from_fn_with_state(
+            Arc::clone(database),
+            authenticate_api_token,
+        ))
+        .layer(SessionManagerLayer::new(MemoryStore::default()));
+    let request = Request::builder()
+        .uri("/api/whoami")
+        .header(header::AUTHORIZATION, format!("Bearer {token}"))
+        .body(Body::empty())
+        .unwrap();
+    let response = app.oneshot(request).await.unwrap();
+    let status = response.status();
+    if status == StatusCode::OK {
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let user: TwitchUser = serde_json::from_slice(&body).unwrap();
+        assert_eq!(user.login, "test-user");
+    }
+    status
+}
+
+#[rstest::rstest]
+#[tokio::test]
+async fn test_issue_and_list() {
+    let database = setup();
+    let issued = issue(
+        &database,
+        vec![Capability::PlaceAssets, Capability::PlaceAssets],
+    )
+    .await
+    .unwrap();
+
+    assert!(issued.token.starts_with(ApiToken::PREFIX));
+    assert_eq!(issued.api_token.name, "stream deck");
+    assert_eq!(issued.api_token.scopes, vec![Capability::PlaceAssets]);
+    assert_eq!(
+        issued.api_token.expires_at,
+        Some(issued.api_token.created_at + 30 * 86400)
+    );
+    let stored = database
+        .get_api_token_by_hash(&secret::hash(&issued.token))
+        .unwrap()
+        .unwrap();
+    assert_ne!(stored.token_hash, issued.token);
+
+    let listed = api_token::get(
+        State(Arc::clone(&database)),
+        TestUser::new("test-user").create_session(),
+    )
+    .await
+    .unwrap();
+    assert_eq!(listed.0, vec![issued.api_token]);
+}
+
+#[rstest::rstest]
+#[tokio::test]
+async fn test_token_without_scopes_rejected() {
+    let database = setup();
+    assert_eq!(
+        issue(&database, vec![]).await.unwrap_err(),
+        StatusCode::BAD_REQUEST
+    );
+}
+
+#[rstest::rstest]
+#[tokio::test]
+async fn test_bearer_authentication() {
+    let database = setup();
+    let issued = issue(&database, vec![Capability::PlaceAssets])
+        .await
+        .unwrap();
+
+    assert_eq!(
+        whoami_with_bearer(&database, &issued.token).await,
+        StatusCode::OK
+    );
+    let used = database
+        .get_api_token_by_hash(&secret::hash(&issued.token))
+        .unwrap()
+        .unwrap();
+    assert!(used.last_used_at.is_some());
+    assert_eq!(
+        whoami_with_bearer(&database, "imgf_not-a-token").await,
+        StatusCode::UNAUTHORIZED
+    );
+
+    let expired_token = format!("{}expired", ApiToken::PREFIX);
+    database
+        .create_api_token(&NewApiToken {
+            username: "test-user".to_string(),
+            name: "expired".to_string(),
+            token_hash: secret::hash(&expired_token),
+            scopes: "place_assets".to_string(),
+            created_at: 0,
+            expires_at: Some(1),
+        })
+        .unwrap();
+    assert_eq!(
+        whoami_with_bearer(&database, &expired_token).await,
+        StatusCode::UNAUTHORIZED
+    );
+}
+
+#[rstest::rstest]
+#[tokio::test]
+async fn test_token_scopes_limit_capabilities() {
+    let database = setup();
+    let permissions = TestPermissions::new(&database).0;
+    let issued = issue(&database, vec![Capability::PlaceAssets])
+        .await
+        .unwrap();
+    let mut session = TestUser::new("test-user").create_session();
+    session.api_token = database
+        .get_api_token_by_hash(&secret::hash(&issued.token))
+        .unwrap();
+
+    assert!(permissions
+        .authorize(&session, "test-user", Capability::PlaceAssets)
+        .await
+        .is_ok());
+    assert_eq!(
+        permissions
+            .authorize(&session, "test-user", Capability::ChangeSettings)
+            .await
+            .unwrap_err(),
+        PermissionError::Forbidden
+    );
+    match api_token::get(State(Arc::clone(&database)), session).await {
+        Ok(_) => panic!("handler returned success"),
+        Err(error) => assert_eq!(error.status(), StatusCode::FORBIDDEN),
+    }
+}
+
+#[rstest::rstest]
+#[tokio::test]
+async fn test_revoke() {
+    let database = setup();
+    let issued = issue(&database, vec![Capability::PlaceAssets])
+        .await
+        .unwrap();
+    let UserFacingApiToken { id, .. } = issued.api_token;
+
+    let delete = || {
+        api_token::delete(
+            State(Arc::clone(&database)),
+            TestUser::new("test-user").create_session(),
+            Path(id),
+        )
+    };
+    assert_eq!(delete().await, Ok(StatusCode::NO_CONTENT));
+    assert_eq!(
+        delete().await.map_err(|error| error.status()),
+        Err(StatusCode::NOT_FOUND)
+    );
+    assert_eq!(
+        whoami_with_bearer(&database, &issued.token).await,
+        StatusCode::UNAUTHORIZED
+    );
+}
@@ -0,0 +1,405 @@
+use std::sync::Arc;
+
+use axum::{body::Bytes, extract::State, http::StatusCode, response::IntoResponse};
+use http_body_util::BodyExt;
+use imgfloat::domain::db::Database;
+use imgfloat::domain::extract::{Json, Path, Query};
+use imgfloat::{
+    domain::{
+        message::{ImgfloatAsset, ImgfloatState},
+        AssetDirectory, ChannelController,
+    },
+    models::{
+        AssetArchiveEntry, AssetArchiveManifest, AssetImportSummary, AssetSearchQuery,
+        ValidatedAssetMetadata,
+    },
+    routes::api::{archive, asset},
+};
+use sha2::{Digest, Sha256};
+
+use crate::fixture::{TestAsset, TestAssetDirectory, TestDbService, TestPermissions, TestUser};
+
+async fn export(
+    state: &Arc<dyn imgfloat::domain::db::Database>,
+    controller: &Arc<ChannelController>,
+    asset_dir: &TestAssetDirectory,
+    broadcaster: &TestUser,
+) -> Bytes {
+    let response = archive::get(
+        State(Arc::clone(state)),
+        State(Arc::clone(controller)),
+        State(TestPermissions::new(state).0),
+        State(AssetDirectory(asset_dir.0.clone())),
+        broadcaster.create_session(),
+        Path(broadcaster.as_db_user().username),
+    )
+    .await
+    .unwrap()
+    .into_response();
+    assert_eq!(response.status(), StatusCode::OK);
+    response.into_body().collect().await.unwrap().to_bytes()
+}
+
+async fn import(
+    state: &Arc<dyn imgfloat::domain::db::Database>,
+    controller: &Arc<ChannelController>,
+    asset_dir: &TestAssetDirectory,
+    broadcaster: &TestUser,
+    body: Bytes,
+) -> Result<(StatusCode, AssetImportSummary), StatusCode> {
+    let response = archive::post(
+        State(Arc::clone(state)),
+        State(Arc::clone(controller)),
+        State(TestPermissions::new(state).0),
+        State(AssetDirectory(asset_dir.0.clone())),
+        broadcaster.create_session(),
+        Path(broadcaster.as_db_user().username),
+        body,
+    )
+    .await
+    .map_err(|error| error.status())?
+    .into_response();
+    let status = response.status();
+    let body = response.into_body().collect().await.unwrap().to_bytes();
+    Ok((status, serde_json::from_slice(&body).unwrap()))
+}
+
+fn entry(filename: &str, data: &str) -> AssetArchiveEntry {
+    AssetArchiveEntry {
+        filename: filename.to_string(),
+        original_filename: filename.to_string(),
+        content_type: "image/png".to_string(),
+        checksum: format!("{:x}", Sha256::digest(data)),
+        uploaded_at: 0,
+        folder: None,
+        tags: vec![],
+    }
+}
+
+fn archive(entries: Vec<AssetArchiveEntry>, files: &[(&str, &str)]) -> Bytes {
+    let manifest = AssetArchiveManifest::new("test-broadcaster", entries, vec![]);
+    let mut builder = tar::Builder::new(Vec::new());
+    let manifest = serde_json::to_vec(&manifest).unwrap();
+    let mut header = tar::Header::new_gnu();
+    header.set_size(manifest.len() as u64);
+    builder
+        .append_data(
+            &mut header,
+            AssetArchiveManifest::FILENAME,
+            manifest.as_slice(),
+        )
+        .unwrap();
+    for (filename, data) in files {
+        let mut header = tar::Header::new_gnu();
+        header.set_size(data.len() as u64);
+        builder
+            .append_data(
+                &mut header,
+                format!("{}{filename}", AssetArchiveManifest::ASSET_PREFIX),
+                data.as_bytes(),
+            )
+            .unwrap();
+    }
+    Bytes::from(builder.into_inner().unwrap())
+}
+
+async fn import_into_empty(
+    body: Bytes,
+) -> (
+    Result<(StatusCode, AssetImportSummary), StatusCode>,
+    TestAssetDirectory,
+) {
+    let broadcaster = TestUser::new("test-broadcaster");
+    let TestDbService(database) = TestDbService::new();
+    let database: Arc<dyn Database> = Arc::new(database);
+    let asset_dir = TestAssetDirectory::new();
+    database.create_user(&broadcaster.as_db_user()).unwrap();
+    let controller = Arc::new(ChannelController::new());
+    let result = import(&database, &controller, &asset_dir, &broadcaster, body).await;
+    (result, asset_dir)
+}
+
+#[rstest::rstest]
+#[tokio::test]
+async fn test_export_and_import_between_instances() {
+    let broadcaster = TestUser::new("test-broadcaster");
+    let hype = TestAsset::new("hype.png").with_data("hype");
+    let sad = TestAsset::new("sad.png").with_data("sad");
+    let hype_asset = hype.as_db_asset(&broadcaster.as_db_user());
+
+    let TestDbService(source_db) = TestDbService::new();
+    let source_db: Arc<dyn Database> = Arc::new(source_db);
+    let source_dir = TestAssetDirectory::new();
+    let source_controller = Arc::new(ChannelController::new());
+    source_db.create_user(&broadcaster.as_db_user()).unwrap();
+    source_db.create_asset(&hype_asset).unwrap();
+    source_db
+        .create_asset(&sad.as_db_asset(&broadcaster.as_db_user()))
+        .unwrap();
+    let metadata = ValidatedAssetMetadata {
+        folder: Some("memes".to_string()),
+        tags: vec!["hype".to_string()],
+    };
+    source_db
+        .set_asset_metadata(&hype_asset, &metadata)
+        .unwrap();
+    hype.write_to(&source_dir.0);
+    sad.write_to(&source_dir.0);
+    let scene = ImgfloatState {
+        assets: vec![ImgfloatAsset {
+            id: "1".to_string(),
+            x: 0.0,
+            y: 0.0,
+            w: 10.0,
+            h: 10.0,
+            theta: 0.0,
+            url: format!("/api/assets/test-broadcaster/{}", hype_asset.local_filename),
+        }],
+    };
+    source_controller
+        .restore_state("test-broadcaster", scene)
+        .await;
+
+    let archive_bytes = export(&source_db, &source_controller, &source_dir, &broadcaster).await;
+
+    let TestDbService(target_db) = TestDbService::new();
+    let target_db: Arc<dyn Database> = Arc::new(target_db);
+    let target_dir = TestAssetDirectory::new();
+    let target_controller = Arc::new(ChannelController::new());
+    target_db.create_user(&broadcaster.as_db_user()).unwrap();
+
+    let (status, summary) = import(
+        &target_db,
+        &target_controller,
+        &target_dir,
+        &broadcaster,
+        archive_bytes.clone(),
+    )
+    .await
+    .unwrap();
+    assert_eq!(status, StatusCode::CREATED);
+    assert_eq!(summary.imported.len(), 2);
+    assert_eq!(summary.scenes_restored, 1);
+    assert_eq!(target_dir.files().len(), 2);
+
+    let query = AssetSearchQuery {
+        tag: Some("hype".to_string()),
+        ..Default::default()
+    };
+    let Json(page) = asset::get(
+        State(Arc::clone(&target_db)),
+        Path("test-broadcaster".to_string()),
+        Query(query),
+    )
+    .await
+    .unwrap();
+    assert_eq!(page.assets.len(), 1);
+    let imported_hype = &page.assets[0];
+    assert_eq!(imported_hype.original_filename, "hype.png");
+    assert_eq!(imported_hype.folder.as_deref(), Some("memes"));
+    let restored_scene = target_controller
+        .get_state("test-broadcaster")
+        .await
+        .unwrap();
+    assert_eq!(
+        restored_scene.assets[0].url,
+        format!("/api/assets/test-broadcaster/{}", imported_hype.filename)
+    );
+
+    let (status, summary) = import(
+        &target_db,
+        &target_controller,
+        &target_dir,
+        &broadcaster,
+        archive_bytes,
+    )
+    .await
+    .unwrap();
+    assert_eq!(status, StatusCode::OK);
+    assert!(summary.imported.is_empty());
+    assert_eq!(summary.duplicates.len(), 2);
+    assert_eq!(target_dir.files().len(), 2);
+}
+
+#[rstest::rstest]
+#[tokio::test]
+async fn test_import_rejects_checksum_mismatch() {
+    let broadcaster = TestUser::new("test-broadcaster");
+    let hype = TestAsset::new("hype.png").with_data("hype");
+
+    let TestDbService(source_db) = TestDbService::new();
+    let source_db: Arc<dyn Database> = Arc::new(source_db);
+    let source_dir = TestAssetDirectory::new();
+    let controller = Arc::new(ChannelController::new());
+    source_db.create_user(&broadcaster.as_db_user()).unwrap();
+    source_db
+        .create_asset(&hype.as_db_asset(&broadcaster.as_db_user()))
+        .unwrap();
+    let hype_path = format!(
+        "{}/{}",
+        source_dir.0,
+        hype.as_db_asset(&broadcaster.as_db_user()).local_filename
+    );
+    std::fs::write(hype_path, "tampered").unwrap();
+    let archive_bytes = export(&source_db, &controller, &source_dir, &broadcaster).await;
+
+    let TestDbService(target_db) = TestDbService::new();
+    let target_db: Arc<dyn Database> = Arc::new(target_db);
+    let target_dir = TestAssetDirectory::new();
+    target_db.create_user(&broadcaster.as_db_user()).unwrap();
+    let result = import(
+        &target_db,
+        &controller,
+        &target_dir,
+        &broadcaster,
+        archive_bytes,
+    )
+    .await;
+    assert_eq!(result.unwrap_err(), StatusCode::BAD_REQUEST);
+    assert!(target_dir.files().is_empty());
+}
+
+fn export_setup(assets: &[&TestAsset]) -> (Arc<dyn Database>, TestAssetDirectory, TestUser) {
+    let broadcaster = TestUser::new("test-broadcaster");
+    let TestDbService(database) = TestDbService::new();
+    let database: Arc<dyn Database> = Arc::new(database);
+    let asset_dir = TestAssetDirectory::new();
+    database.create_user(&broadcaster.as_db_user()).unwrap();
+    for asset in assets {
+        database
+            .create_asset(&asset.as_db_asset(&broadcaster.as_db_user()))
+            .unwrap();
+        asset.write_to(&asset_dir.0);
+    }
+    (database, asset_dir, broadcaster)
+}
+
+#[rstest::rstest]
+#[tokio::test]
+async fn test_export_fails_before_sending_when_a_file_is_missing() {
+    let hype = TestAsset::new("hype.png").with_data("hype");
+    let (database, asset_dir, broadcaster) = export_setup(&[&hype]);
+    let hype_filename = hype.as_db_asset(&broadcaster.as_db_user()).local_filename;
+    std::fs::remove_file(format!("{}/{hype_filename}", asset_dir.0)).unwrap();
+
+    let result = archive::get(
+        State(Arc::clone(&database)),
+        State(Arc::new(ChannelController::new())),
+        State(TestPermissions::new(&database).0),
+        State(AssetDirectory(asset_dir.0.clone())),
+        broadcaster.create_session(),
+        Path("test-broadcaster".to_string()),
+    )
+    .await;
+    match result {
+        Ok(_) => panic!("handler returned success"),
+        Err(error) => assert_eq!(error.status(), StatusCode::SERVICE_UNAVAILABLE),
+    }
+}
+
+#[rstest::rstest]
+#[tokio::test]
+async fn test_export_aborts_when_a_file_disappears() {
+    // large enough that the export waits for the client within the first
+    // file, whichever that is
+    let first = TestAsset::new("first.png").with_data(vec![1; 8 * 1024 * 1024]);
+    let second = TestAsset::new("second.png").with_data(vec![2; 8 * 1024 * 1024]);
+    let (database, asset_dir, broadcaster) = export_setup(&[&first, &second]);
+    let response = archive::get(
+        State(Arc::clone(&database)),
+        State(Arc::new(ChannelController::new())),
+        State(TestPermissions::new(&database).0),
+        State(AssetDirectory(asset_dir.0.clone())),
+        broadcaster.create_session(),
+        Path("test-broadcaster".to_string()),
+    )
+    .await
+    .unwrap()
+    .into_response();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    for filename in asset_dir.files() {
+        std::fs::remove_file(format!("{}/{filename}", asset_dir.0)).unwrap();
+    }
+
+    assert!(response.into_body().collect().await.is_err());
+}
+
+#[rstest::rstest]
+async fn test_export_forbidden_for_other_users() {
+    let broadcaster = TestUser::new("test-broadcaster");
+    let user = TestUser::new("test-user");
+    let TestDbService(dbservice) = TestDbService::new();
+    let state: Arc<dyn Database> = Arc::new(dbservice);
+    let asset_dir = TestAssetDirectory::new();
+    state.create_user(&broadcaster.as_db_user()).unwrap();
+
+    let result = archive::get(
+        State(Arc::clone(&state)),
+        State(Arc::new(ChannelController::new())),
+        State(TestPermissions::new(&state).0),
+        State(AssetDirectory(asset_dir.0.clone())),
+        user.create_session(),
+        Path("test-broadcaster".to_string()),
+    )
+    .await;
+    match result {
+        Ok(_) => panic!("handler returned success"),
+        Err(error) => assert_eq!(error.status(), StatusCode::FORBIDDEN),
+    }
+}
+
+#[rstest::rstest]
+#[tokio::test]
+async fn test_import_rejects_forged_entry_size() {
+    let mut header = tar::Header::new_gnu();
+    header.set_path(AssetArchiveManifest::FILENAME).unwrap();
+    header.set_size(1 << 40);
+    header.set_cksum();
+    let mut body = header.as_bytes().to_vec();
+    body.extend_from_slice(b"{}");
+
+    let (result, asset_dir) = import_into_empty(Bytes::from(body)).await;
+    assert_eq!(result.unwrap_err(), StatusCode::BAD_REQUEST);
+    assert!(asset_dir.files().is_empty());
+}
+
+#[rstest::rstest]
+#[tokio::test]
+async fn test_import_skips_repeated_checksums() {
+    let body = archive(
+        vec![entry("hype.png", "hype"), entry("hype-again.png", "hype")],
+        &[("hype.png", "hype"), ("hype-again.png", "hype")],
+    );
+
+    let (result, asset_dir) = import_into_empty(body).await;
+    let (status, summary) = result.unwrap();
+    assert_eq!(status, StatusCode::CREATED);
+    assert_eq!(summary.imported, vec!["hype.png"]);
+    assert_eq!(summary.duplicates, vec!["hype-again.png"]);
+    assert_eq!(asset_dir.files().len(), 1);
+}
+
+#[rstest::rstest]
+#[case::svg(
+    AssetArchiveEntry { content_type: "image/svg+xml".to_string(), ..entry("sad.png", "sad") },
+    StatusCode::UNSUPPORTED_MEDIA_TYPE,
+)]
+#[case::long_tag(
+    AssetArchiveEntry { tags: vec!["a".repeat(65)], ..entry("sad.png", "sad") },
+    StatusCode::BAD_REQUEST,
+)]
+#[tokio::test]
+async fn test_import_validates_every_entry_first(
+    #[case] invalid: AssetArchiveEntry,
+    #[case] expected: StatusCode,
+) {
+    let body = archive(
+        vec![entry("hype.png", "hype"), invalid],
+        &[("hype.png", "hype"), ("sad.png", "sad")],
+    );
+
+    let (result, asset_dir) = import_into_empty(body).await;
+    assert_eq!(result.unwrap_err(), expected);
+    assert!(asset_dir.files().is_empty());
+}
@@ -0,0 +1,245 @@
+use axum::{extract::State, http::StatusCode, response::IntoResponse};
+use http_body_util::BodyExt;
+use imgfloat::domain::extract::{Json, Path, Query};
+use imgfloat::domain::{
+    db::{Database, InMemoryDbService},
+    ApiErrorBody,
+};
+use imgfloat::models::{AssetSearchQuery, UnownedAssetMetadata, UserFacingAsset};
+use imgfloat::routes::api::asset;
+use std::sync::Arc;
+
+use crate::fixture::{TestAsset, TestDbService, TestPermissions, TestUser};
+
+#[rstest::rstest]
+async fn test_search_by_name() {
+    let TestDbService(dbservice) = TestDbService::new();
+    let state: Arc<dyn Database> = Arc::new(dbservice);
+    let broadcaster = TestUser::new("test-broadcaster");
+    let hype = TestAsset::new("hype.png").as_db_asset(&broadcaster.as_db_user());
+    let sad = TestAsset::new("sad.png").as_db_asset(&broadcaster.as_db_user());
+
+    state.create_user(&broadcaster.as_db_user()).unwrap();
+    state.create_asset(&hype).unwrap();
+    state.create_asset(&sad).unwrap();
+
+    let query = AssetSearchQuery {
+        name: Some("hyp".to_string()),
+        ..Default::default()
+    };
+    let Json(page) = asset::get(
+        State(Arc::clone(&state)),
+        Path("test-broadcaster".to_string()),
+        Query(query),
+    )
+    .await
+    .unwrap();
+    assert_eq!(page.total, 1);
+    assert_eq!(page.assets, vec![UserFacingAsset::from(hype)]);
+}
+
+#[rstest::rstest]
+async fn test_search_by_type_and_date() {
+    let TestDbService(dbservice) = TestDbService::new();
+    let state: Arc<dyn Database> = Arc::new(dbservice);
+    let broadcaster = TestUser::new("test-broadcaster");
+    let old_image = TestAsset::new("old.png")
+        .with_uploaded_at(100)
+        .as_db_asset(&broadcaster.as_db_user());
+    let new_image = TestAsset::new("new.png")
+        .with_uploaded_at(200)
+        .as_db_asset(&broadcaster.as_db_user());
+    let new_audio = TestAsset::new("new.mp3")
+        .with_content_type("audio/mpeg")
+        .with_uploaded_at(200)
+        .as_db_asset(&broadcaster.as_db_user());
+
+    state.create_user(&broadcaster.as_db_user()).unwrap();
+    state.create_asset(&old_image).unwrap();
+    state.create_asset(&new_image).unwrap();
+    state.create_asset(&new_audio).unwrap();
+
+    let query = AssetSearchQuery {
+        content_type: Some("image".to_string()),
+        uploaded_after: Some(150),
+        ..Default::default()
+    };
+    let Json(page) = asset::get(
+        State(Arc::clone(&state)),
+        Path("test-broadcaster".to_string()),
+        Query(query),
+    )
+    .await
+    .unwrap();
+    assert_eq!(page.assets, vec![UserFacingAsset::from(new_image)]);
+}
+
+#[rstest::rstest]
+async fn test_search_pagination() {
+    let TestDbService(dbservice) = TestDbService::new();
+    let state: Arc<dyn Database> = Arc::new(dbservice);
+    let broadcaster = TestUser::new("test-broadcaster");
+    let assets: Vec<_> = (0..5)
+        .map(|i| {
+            TestAsset::new(format!("{i}.png"))
+                .with_uploaded_at(i)
+                .as_db_asset(&broadcaster.as_db_user())
+        })
+        .collect();
+
+    state.create_user(&broadcaster.as_db_user()).unwrap();
+    for asset in &assets {
+        state.create_asset(asset).unwrap();
+    }
+
+    let query = AssetSearchQuery {
+        page: Some(2),
+        per_page: Some(2),
+        ..Default::default()
+    };
+    let Json(page) = asset::get(
+        State(Arc::clone(&state)),
+        Path("test-broadcaster".to_string()),
+        Query(query),
+    )
+    .await
+    .unwrap();
+    let filenames: Vec<_> = page
+        .assets
+        .into_iter()
+        .map(|a| a.original_filename)
+        .collect();
+    assert_eq!(page.total, 5);
+    assert_eq!(filenames, vec!["2.png", "1.png"]);
+}
+
+#[rstest::rstest]
+async fn test_metadata_and_search_by_folder_and_tag() {
+    let TestDbService(dbservice) = TestDbService::new();
+    let state: Arc<dyn Database> = Arc::new(dbservice);
+    let broadcaster = TestUser::new("test-broadcaster");
+    let session = broadcaster.create_session();
+    let hype = TestAsset::new("hype.png").as_db_asset(&broadcaster.as_db_user());
+    let sad = TestAsset::new("sad.png").as_db_asset(&broadcaster.as_db_user());
+
+    state.create_user(&broadcaster.as_db_user()).unwrap();
+    state.create_asset(&hype).unwrap();
+    state.create_asset(&sad).unwrap();
+
+    let metadata = UnownedAssetMetadata {
+        folder: Some("memes".to_string()),
+        tags: vec!["Hype".to_string(), "cat".to_string(), "hype".to_string()],
+    };
+    let response = asset::metadata(
+        State(Arc::clone(&state)),
+        State(TestPermissions::new(&state).0),
+        session,
+        Path(("test-broadcaster".to_string(), hype.local_filename.clone())),
+        Json(metadata),
+    )
+    .await
+    .unwrap()
+    .into_response();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = response.into_body().collect().await.unwrap().to_bytes();
+    let actual: UserFacingAsset = serde_json::from_slice(&body).unwrap();
+    let expected = UserFacingAsset::from(hype)
+        .with_folder(Some("memes".to_string()))
+        .with_tags(vec!["cat".to_string(), "hype".to_string()]);
+    assert_eq!(actual, expected);
+
+    for query in [
+        AssetSearchQuery {
+            tag: Some("hype".to_string()),
+            ..Default::default()
+        },
+        AssetSearchQuery {
+            folder: Some("memes".to_string()),
+            ..Default::default()
+        },
+    ] {
+        let Json(page) = asset::get(
+            State(Arc::clone(&state)),
+            Path("test-broadcaster".to_string()),
+            Query(query),
+        )
+        .await
+        .unwrap();
+        assert_eq!(page.assets, vec![expected.clone()]);
+    }
+}
+
+#[rstest::rstest]
+async fn test_metadata_forbidden_for_other_users() {
+    let TestDbService(dbservice) = TestDbService::new();
+    let state: Arc<dyn Database> = Arc::new(dbservice);
+    let broadcaster = TestUser::new("test-broadcaster");
+    let user = TestUser::new("test-user");
+    let hype = TestAsset::new("hype.png").as_db_asset(&broadcaster.as_db_user());
+
+    state.create_user(&broadcaster.as_db_user()).unwrap();
+    state.create_user(&user.as_db_user()).unwrap();
+    state.create_asset(&hype).unwrap();
+
+    let metadata = UnownedAssetMetadata {
+        folder: None,
+        tags: vec!["hype".to_string()],
+    };
+    match asset::metadata(
+        State(Arc::clone(&state)),
+        State(TestPermissions::new(&state).0),
+        user.create_session(),
+        Path(("test-broadcaster".to_string(), hype.local_filename.clone())),
+        Json(metadata),
+    )
+    .await
+    {
+        Ok(_) => panic!("handler returned success"),
+        Err(error) => assert_eq!(error.status(), StatusCode::FORBIDDEN),
+    }
+}
+
+#[rstest::rstest]
+async fn test_unknown_channel_returns_error_body() {
+    let TestDbService(dbservice) = TestDbService::new();
+    let state: Arc<dyn Database> = Arc::new(dbservice);
+
+    let response = asset::get(
+        State(Arc::clone(&state)),
+        Path("test-nobody".to_string()),
+        Query(AssetSearchQuery::default()),
+    )
+    .await
+    .into_response();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    let body = response.into_body().collect().await.unwrap().to_bytes();
+    let actual: ApiErrorBody = serde_json::from_slice(&body).unwrap();
+    assert_eq!(actual.code, "unknown_channel");
+    assert_eq!(actual.message, "no such channel");
+}
+
+#[rstest::rstest]
+async fn test_search_with_in_memory_database() {
+    let state: Arc<dyn Database> = Arc::new(InMemoryDbService::new());
+    let broadcaster = TestUser::new("test-broadcaster");
+    let hype = TestAsset::new("hype.png").as_db_asset(&broadcaster.as_db_user());
+    let sad = TestAsset::new("sad.png").as_db_asset(&broadcaster.as_db_user());
+
+    state.create_user(&broadcaster.as_db_user()).unwrap();
+    state.create_asset(&hype).unwrap();
+    state.create_asset(&sad).unwrap();
+
+    let query = AssetSearchQuery {
+        name: Some("hyp".to_string()),
+        ..Default::default()
+    };
+    let Json(page) = asset::get(
+        State(Arc::clone(&state)),
+        Path("test-broadcaster".to_string()),
+        Query(query),
+    )
+    .await
+    .unwrap();
+    assert_eq!(page.total, 1);
+    assert_eq!(page.assets, vec![UserFacingAsset::from(hype)]);
+}
@@ -1,43 +1,48 @@
 use std::sync::Arc;
 
 use axum::{
-    extract::{Query, State},
+    extract::State,
     http::{StatusCode, Uri},
     response::IntoResponse,
 };
+use imgfloat::domain::db::Database;
+use imgfloat::domain::extract::Query;
 use imgfloat::{
+    domain::UserSession,
     routes::auth::callback,
     twitch::{AuthCallbackQuery, TwitchAuthenticator},
 };
-use tokio::sync::RwLock;
 
-use crate::fixture::{EmptySession, TestAuthenticator, TestDbService, TestTwitchTokens, TestUser};
+use crate::fixture::{
+    EmptySession, TestAuthenticator, TestDbService, TestTokenStore, TestTwitchTokens, TestUser,
+};
 
 #[rstest::rstest]
 #[tokio::test]
 async fn test_new_user() {
     let user = TestUser::new("test-user");
     let TestDbService(dbservice) = TestDbService::new();
-    let state_db = Arc::new(RwLock::new(dbservice));
+    let state_db: Arc<dyn Database> = Arc::new(dbservice);
     let EmptySession(session) = EmptySession::new();
     let TestTwitchTokens(tokens) = TestTwitchTokens::default();
     let authenticator = TestAuthenticator::new()
         .with_user(user.as_twitch_user())
         .with_tokens(tokens);
     let authenticator: Arc<Box<dyn TwitchAuthenticator>> = Arc::new(Box::new(authenticator));
+    let state = UserSession::begin_login(&session.session).await.unwrap();
     let auth_query = AuthCallbackQuery {
         code: Some("1".to_string()),
         error: None,
         error_description: None,
+        state: Some(state),
     };
 
-    assert_eq!(
-        None,
-        state_db.read().await.get_user(&user.as_db_user().username)
-    );
+    assert_eq!(Ok(None), state_db.get_user(&user.as_db_user().username));
+    let TestTokenStore(token_store) = TestTokenStore::new(&state_db, &authenticator);
     let response = callback::get(
         State(authenticator),
         State(Arc::clone(&state_db)),
+        State(token_store),
         session,
         Query(auth_query),
     )
@@ -48,9 +53,13 @@ async fn test_new_user() {
         callback::AuthCallbackRedirect::new_with_user(&user.as_twitch_user().login);
     assert_eq!(response.status(), StatusCode::TEMPORARY_REDIRECT);
     assert_eq!(
-        Some(user.as_db_user()),
-        state_db.read().await.get_user(&user.as_db_user().username)
+        Ok(Some(user.as_db_user())),
+        state_db.get_user(&user.as_db_user().username)
     );
+    assert!(state_db
+        .get_user_tokens(&user.as_db_user().username)
+        .unwrap()
+        .is_some());
     assert_eq!(
         response.headers().get("Location"),
         redirect.into_response().headers().get("Location")
@@ -60,18 +69,20 @@ async fn test_new_user() {
 #[rstest::rstest]
 async fn test_failure_no_user() {
     let TestDbService(dbservice) = TestDbService::new();
-    let state_db = Arc::new(RwLock::new(dbservice));
+    let state_db: Arc<dyn Database> = Arc::new(dbservice);
     let EmptySession(session) = EmptySession::new();
     let authenticator = TestAuthenticator::new();
     let authenticator: Arc<Box<dyn TwitchAuthenticator>> = Arc::new(Box::new(authenticator));
     let query_uri: Uri = "http://localhost:3000?error=foo&error_description=bar"
         .parse()
         .unwrap();
+    let TestTokenStore(token_store) = TestTokenStore::new(&state_db, &authenticator);
     let response = callback::get(
         State(authenticator),
         State(Arc::clone(&state_db)),
+        State(token_store),
         session,
-        Query::try_from_uri(&query_uri).unwrap(),
+        Query(axum::extract::Query::try_from_uri(&query_uri).unwrap().0),
     )
     .await
     .into_response();
@@ -100,18 +111,20 @@ async fn test_failure_no_user() {
 async fn test_failure_no_tokens() {
     let user = TestUser::new("test-user");
     let TestDbService(dbservice) = TestDbService::new();
-    let state_db = Arc::new(RwLock::new(dbservice));
+    let state_db: Arc<dyn Database> = Arc::new(dbservice);
     let EmptySession(session) = EmptySession::new();
     let authenticator = TestAuthenticator::new().with_user(user.as_twitch_user());
     let authenticator: Arc<Box<dyn TwitchAuthenticator>> = Arc::new(Box::new(authenticator));
     let query_uri: Uri = "http://localhost:3000?error=foo&error_description=bar"
         .parse()
         .unwrap();
+    let TestTokenStore(token_store) = TestTokenStore::new(&state_db, &authenticator);
     let response = callback::get(
         State(authenticator),
         State(Arc::clone(&state_db)),
+        State(token_store),
         session,
-        Query::try_from_uri(&query_uri).unwrap(),
+        Query(axum::extract::Query::try_from_uri(&query_uri).unwrap().0),
     )
     .await
     .into_response();
@@ -135,3 +148,194 @@ async fn test_failure_no_tokens() {
             .unwrap()
     );
 }
+
+#[rstest::rstest]
+#[case::missing_state(None)]
+#[case::mismatched_state(Some("forged".to_string()))]
+#[tokio::test]
+async fn test_failure_invalid_state(#[case] state: Option<String>) {
+    let user = TestUser::new("test-user");
+    let TestDbService(dbservice) = TestDbService::new();
+    let state_db: Arc<dyn Database> = Arc::new(dbservice);
+    let EmptySession(session) = EmptySession::new();
+    let stored_session = session.session.clone();
+    let TestTwitchTokens(tokens) = TestTwitchTokens::default();
+    let authenticator = TestAuthenticator::new()
+        .with_user(user.as_twitch_user())
+        .with_tokens(tokens);
+    let authenticator: Arc<Box<dyn TwitchAuthenticator>> = Arc::new(Box::new(authenticator));
+    UserSession::begin_login(&session.session).await.unwrap();
+    let auth_query = AuthCallbackQuery {
+        code: Some("1".to_string()),
+        error: None,
+        error_description: None,
+        state,
+    };
+
+    let TestTokenStore(token_store) = TestTokenStore::new(&state_db, &authenticator);
+    let response = callback::get(
+        State(authenticator),
+        State(Arc::clone(&state_db)),
+        State(token_store),
+        session,
+        Query(auth_query),
+    )
+    .await
+    .into_response();
+
+    let callback::AuthCallbackRedirect(redirect) = callback::AuthCallbackRedirect::new();
+    assert_eq!(response.status(), StatusCode::TEMPORARY_REDIRECT);
+    assert_eq!(
+        response.headers().get("Location"),
+        redirect.into_response().headers().get("Location")
+    );
+    assert_eq!(Ok(None), state_db.get_user(&user.as_db_user().username));
+    let logged_in_user: Option<serde_json::Value> =
+        stored_session.get("session.user").await.unwrap();
+    assert!(logged_in_user.is_none());
+}
+
+#[rstest::rstest]
+#[tokio::test]
+async fn test_failure_state_without_login() {
+    let user = TestUser::new("test-user");
+    let TestDbService(dbservice) = TestDbService::new();
+    let state_db: Arc<dyn Database> = Arc::new(dbservice);
+    let EmptySession(session) = EmptySession::new();
+    let TestTwitchTokens(tokens) = TestTwitchTokens::default();
+    let authenticator = TestAuthenticator::new()
+        .with_user(user.as_twitch_user())
+        .with_tokens(tokens);
+    let authenticator: Arc<Box<dyn TwitchAuthenticator>> = Arc::new(Box::new(authenticator));
+    let auth_query = AuthCallbackQuery {
+        code: Some("1".to_string()),
+        error: None,
+        error_description: None,
+        state: Some("attacker-state".to_string()),
+    };
+
+    let TestTokenStore(token_store) = TestTokenStore::new(&state_db, &authenticator);
+    let response = callback::get(
+        State(authenticator),
+        State(Arc::clone(&state_db)),
+        State(token_store),
+        session,
+        Query(auth_query),
+    )
+    .await
+    .into_response();
+
+    assert_eq!(response.status(), StatusCode::TEMPORARY_REDIRECT);
+    assert_eq!(Ok(None), state_db.get_user(&user.as_db_user().username));
+}
+
+#[rstest::rstest]
+#[tokio::test]
+async fn test_failure_database_error() {
+    let user = TestUser::new("test-user");
+    let test_db = TestDbService::new();
+    test_db.execute("ALTER TABLE users RENAME TO unavailable_users");
+    let TestDbService(dbservice) = test_db;
+    let state_db: Arc<dyn Database> = Arc::new(dbservice);
+    let EmptySession(session) = EmptySession::new();
+    let TestTwitchTokens(tokens) = TestTwitchTokens::default();
+    let authenticator = TestAuthenticator::new()
+        .with_user(user.as_twitch_user())
+        .with_tokens(tokens);
+    let authenticator: Arc<Box<dyn TwitchAuthenticator>> = Arc::new(Box::new(authenticator));
+    let state = UserSession::begin_login(&session.session).await.unwrap();
+    let auth_query = AuthCallbackQuery {
+        code: Some("1".to_string()),
+        error: None,
+        error_description: None,
+        state: Some(state),
+    };
+
+    let TestTokenStore(token_store) = TestTokenStore::new(&state_db, &authenticator);
+    let response = callback::get(
+        State(authenticator),
+        State(Arc::clone(&state_db)),
+        State(token_store),
+        session.clone(),
+        Query(auth_query),
+    )
+    .await
+    .into_response();
+
+    let callback::AuthCallbackRedirect(redirect) = callback::AuthCallbackRedirect::failed();
+    assert_eq!(response.status(), StatusCode::TEMPORARY_REDIRECT);
+    assert_eq!(
+        response.headers().get("Location"),
+        redirect.into_response().headers().get("Location")
+    );
+    assert!(session.session.is_empty().await);
+}
+
+#[rstest::rstest]
+#[tokio::test]
+async fn test_login_rotates_session_id() {
+    let user = TestUser::new("test-user");
+    let TestDbService(dbservice) = TestDbService::new();
+    let state_db: Arc<dyn Database> = Arc::new(dbservice);
+    let EmptySession(session) = EmptySession::new();
+    let TestTwitchTokens(tokens) = TestTwitchTokens::default();
+    let authenticator = TestAuthenticator::new()
+        .with_user(user.as_twitch_user())
+        .with_tokens(tokens);
+    let authenticator: Arc<Box<dyn TwitchAuthenticator>> = Arc::new(Box::new(authenticator));
+    let state = UserSession::begin_login(&session.session).await.unwrap();
+    session.session.save().await.unwrap();
+    let planted_id = session.session.id();
+    let auth_query = AuthCallbackQuery {
+        code: Some("1".to_string()),
+        error: None,
+        error_description: None,
+        state: Some(state),
+    };
+
+    let TestTokenStore(token_store) = TestTokenStore::new(&state_db, &authenticator);
+    let response = callback::get(
+        State(authenticator),
+        State(Arc::clone(&state_db)),
+        State(token_store),
+        session.clone(),
+        Query(auth_query),
+    )
+    .await
+    .into_response();
+    session.session.save().await.unwrap();
+
+    assert_eq!(response.status(), StatusCode::TEMPORARY_REDIRECT);
+    assert!(planted_id.is_some());
+    assert_ne!(session.session.id(), planted_id);
+}
+
+#[rstest::rstest]
+#[tokio::test]
+async fn test_missing_code_is_bad_request() {
+    let TestDbService(dbservice) = TestDbService::new();
+    let state_db: Arc<dyn Database> = Arc::new(dbservice);
+    let EmptySession(session) = EmptySession::new();
+    let authenticator: Arc<Box<dyn TwitchAuthenticator>> =
+        Arc::new(Box::new(TestAuthenticator::new()));
+    let state = UserSession::begin_login(&session.session).await.unwrap();
+    let auth_query = AuthCallbackQuery {
+        code: None,
+        error: None,
+        error_description: None,
+        state: Some(state),
+    };
+
+    let TestTokenStore(token_store) = TestTokenStore::new(&state_db, &authenticator);
+    let response = callback::get(
+        State(authenticator),
+        State(Arc::clone(&state_db)),
+        State(token_store),
+        session,
+        Query(auth_query),
+    )
+    .await
+    .into_response();
+
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+}
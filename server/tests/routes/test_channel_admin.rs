@@ -1,17 +1,19 @@
 use axum::{extract::State, http::StatusCode, response::IntoResponse};
 use http_body_util::BodyExt;
-use imgfloat::models::ChannelAdmin;
+use imgfloat::domain::db::Database;
+use imgfloat::domain::extract::{Json, Path, Query};
+use imgfloat::models::{ChannelAdmin, ChannelQuery, ChannelRole, ChannelRoleRequest};
 use imgfloat::routes::api::channel_admin;
 use std::sync::Arc;
-use tokio::sync::RwLock;
 
 use crate::fixture::TestDbService;
+use crate::fixture::TestPermissions;
 use crate::fixture::TestUser;
 
 #[rstest::rstest]
 async fn test_get_all() {
     let TestDbService(dbservice) = TestDbService::new();
-    let state = Arc::new(RwLock::new(dbservice));
+    let state: Arc<dyn Database> = Arc::new(dbservice);
     let user_1 = TestUser::new("test-user-1");
     let user_2 = TestUser::new("test-user-2");
     let broadcaster_1 = TestUser::new("test-broadcaster-1");
@@ -21,21 +23,23 @@ async fn test_get_all() {
     let channel_admin_2 = ChannelAdmin::new(&user_2.as_db_user(), &broadcaster_1.as_db_user());
     let channel_admin_3 = ChannelAdmin::new(&user_2.as_db_user(), &broadcaster_2.as_db_user());
 
-    {
-        let db = state.write().await;
-        db.create_user(&user_1.as_db_user()).unwrap();
-        db.create_user(&user_2.as_db_user()).unwrap();
-        db.create_user(&broadcaster_1.as_db_user()).unwrap();
-        db.create_user(&broadcaster_2.as_db_user()).unwrap();
-        db.create_channel_admin(&channel_admin_1).unwrap();
-        db.create_channel_admin(&channel_admin_2).unwrap();
-        db.create_channel_admin(&channel_admin_3).unwrap();
-    }
+    state.create_user(&user_1.as_db_user()).unwrap();
+    state.create_user(&user_2.as_db_user()).unwrap();
+    state.create_user(&broadcaster_1.as_db_user()).unwrap();
+    state.create_user(&broadcaster_2.as_db_user()).unwrap();
+    state.create_channel_admin(&channel_admin_1).unwrap();
+    state.create_channel_admin(&channel_admin_2).unwrap();
+    state.create_channel_admin(&channel_admin_3).unwrap();
 
-    let response = channel_admin::get(State(Arc::clone(&state)), session)
-        .await
-        .unwrap()
-        .into_response();
+    let response = channel_admin::get(
+        State(Arc::clone(&state)),
+        State(TestPermissions::new(&state).0),
+        session,
+        Query(ChannelQuery::default()),
+    )
+    .await
+    .unwrap()
+    .into_response();
     let body = response.into_body().collect().await.unwrap().to_bytes();
     let actual: Vec<ChannelAdmin> = serde_json::from_slice(&body).unwrap();
     assert_eq!(actual, vec![channel_admin_1, channel_admin_2]);
@@ -44,16 +48,21 @@ async fn test_get_all() {
 #[rstest::rstest]
 async fn test_get_all_empty() {
     let TestDbService(dbservice) = TestDbService::new();
-    let state = Arc::new(RwLock::new(dbservice));
+    let state: Arc<dyn Database> = Arc::new(dbservice);
     let user = TestUser::new("test-user");
     let session = user.create_session();
 
-    state.write().await.create_user(&user.as_db_user()).unwrap();
+    state.create_user(&user.as_db_user()).unwrap();
 
-    let response = channel_admin::get(State(Arc::clone(&state)), session)
-        .await
-        .unwrap()
-        .into_response();
+    let response = channel_admin::get(
+        State(Arc::clone(&state)),
+        State(TestPermissions::new(&state).0),
+        session,
+        Query(ChannelQuery::default()),
+    )
+    .await
+    .unwrap()
+    .into_response();
     let body = response.into_body().collect().await.unwrap().to_bytes();
     let actual: Vec<ChannelAdmin> = serde_json::from_slice(&body).unwrap();
     assert!(actual.is_empty());
@@ -62,21 +71,20 @@ async fn test_get_all_empty() {
 #[rstest::rstest]
 async fn test_create() {
     let TestDbService(dbservice) = TestDbService::new();
-    let state = Arc::new(RwLock::new(dbservice));
+    let state: Arc<dyn Database> = Arc::new(dbservice);
     let broadcaster = TestUser::new("test-broadcaster");
     let user = TestUser::new("test-user");
     let session = broadcaster.create_session();
     let channel_admin_user = ChannelAdmin::new(&user.as_db_user(), &broadcaster.as_db_user());
 
-    {
-        let db = state.write().await;
-        db.create_user(&broadcaster.as_db_user()).unwrap();
-        db.create_user(&user.as_db_user()).unwrap();
-    }
+    state.create_user(&broadcaster.as_db_user()).unwrap();
+    state.create_user(&user.as_db_user()).unwrap();
 
     let response = channel_admin::post(
         State(Arc::clone(&state)),
+        State(TestPermissions::new(&state).0),
         session.clone(),
+        Query(ChannelQuery::default()),
         channel_admin_user.username.clone(),
     )
     .await
@@ -91,22 +99,21 @@ async fn test_create() {
 #[rstest::rstest]
 async fn test_create_duplicate() {
     let TestDbService(dbservice) = TestDbService::new();
-    let state = Arc::new(RwLock::new(dbservice));
+    let state: Arc<dyn Database> = Arc::new(dbservice);
     let broadcaster = TestUser::new("test-broadcaster");
     let user = TestUser::new("test-user");
     let session = broadcaster.create_session();
     let channel_admin_user = ChannelAdmin::new(&user.as_db_user(), &broadcaster.as_db_user());
 
-    {
-        let db = state.write().await;
-        db.create_user(&broadcaster.as_db_user()).unwrap();
-        db.create_user(&user.as_db_user()).unwrap();
-        db.create_channel_admin(&channel_admin_user).unwrap();
-    }
+    state.create_user(&broadcaster.as_db_user()).unwrap();
+    state.create_user(&user.as_db_user()).unwrap();
+    state.create_channel_admin(&channel_admin_user).unwrap();
 
     let response = channel_admin::post(
         State(Arc::clone(&state)),
+        State(TestPermissions::new(&state).0),
         session.clone(),
+        Query(ChannelQuery::default()),
         channel_admin_user.username.clone(),
     )
     .await
@@ -121,18 +128,271 @@ async fn test_create_duplicate() {
 #[rstest::rstest]
 async fn test_missing_user() {
     let TestDbService(dbservice) = TestDbService::new();
-    let state = Arc::new(RwLock::new(dbservice));
+    let state: Arc<dyn Database> = Arc::new(dbservice);
     let user = TestUser::new("test-user");
     let session = user.create_session();
 
     match channel_admin::post(
         State(Arc::clone(&state)),
+        State(TestPermissions::new(&state).0),
         session.clone(),
+        Query(ChannelQuery::default()),
         "not-found".to_string(),
     )
     .await
     {
         Ok(_) => panic!("handler returned success"),
-        Err(status_code) => assert_eq!(status_code, StatusCode::NOT_FOUND),
+        Err(error) => assert_eq!(error.status(), StatusCode::NOT_FOUND),
+    }
+}
+
+#[rstest::rstest]
+async fn test_create_before_first_login() {
+    let TestDbService(dbservice) = TestDbService::new();
+    let state: Arc<dyn Database> = Arc::new(dbservice);
+    let broadcaster = TestUser::new("test-broadcaster");
+    let session = broadcaster.create_session();
+
+    state.create_user(&broadcaster.as_db_user()).unwrap();
+
+    let response = channel_admin::post(
+        State(Arc::clone(&state)),
+        State(TestPermissions::new(&state).0),
+        session.clone(),
+        Query(ChannelQuery::default()),
+        " Test-Helper ".to_string(),
+    )
+    .await
+    .unwrap()
+    .into_response();
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let body = response.into_body().collect().await.unwrap().to_bytes();
+    let actual: ChannelAdmin = serde_json::from_slice(&body).unwrap();
+    assert_eq!(actual.username, "test-helper");
+    assert_eq!(actual.source, ChannelAdmin::MANUAL);
+}
+
+#[rstest::rstest]
+#[case("")]
+#[case("test user")]
+#[case("../test-user")]
+#[case("a-login-that-is-far-too-long")]
+async fn test_create_invalid_login(#[case] login: &str) {
+    let TestDbService(dbservice) = TestDbService::new();
+    let state: Arc<dyn Database> = Arc::new(dbservice);
+    let broadcaster = TestUser::new("test-broadcaster");
+    let session = broadcaster.create_session();
+
+    state.create_user(&broadcaster.as_db_user()).unwrap();
+
+    match channel_admin::post(
+        State(Arc::clone(&state)),
+        State(TestPermissions::new(&state).0),
+        session,
+        Query(ChannelQuery::default()),
+        login.to_string(),
+    )
+    .await
+    {
+        Ok(_) => panic!("handler returned success"),
+        Err(error) => assert_eq!(error.status(), StatusCode::BAD_REQUEST),
+    }
+}
+
+#[rstest::rstest]
+async fn test_delete() {
+    let TestDbService(dbservice) = TestDbService::new();
+    let state: Arc<dyn Database> = Arc::new(dbservice);
+    let broadcaster = TestUser::new("test-broadcaster");
+    let user = TestUser::new("test-user");
+    let session = broadcaster.create_session();
+    let channel_admin_user = ChannelAdmin::new(&user.as_db_user(), &broadcaster.as_db_user());
+
+    state.create_user(&broadcaster.as_db_user()).unwrap();
+    state.create_channel_admin(&channel_admin_user).unwrap();
+
+    let response = channel_admin::delete(
+        State(Arc::clone(&state)),
+        State(TestPermissions::new(&state).0),
+        session.clone(),
+        Query(ChannelQuery::default()),
+        Path("test-user".to_string()),
+    )
+    .await
+    .unwrap()
+    .into_response();
+    assert_eq!(response.status(), StatusCode::NO_CONTENT);
+    assert!(state
+        .get_channel_admin("test-user", &broadcaster.as_db_user())
+        .unwrap()
+        .is_none());
+
+    match channel_admin::delete(
+        State(Arc::clone(&state)),
+        State(TestPermissions::new(&state).0),
+        session,
+        Query(ChannelQuery::default()),
+        Path("test-user".to_string()),
+    )
+    .await
+    {
+        Ok(_) => panic!("handler returned success"),
+        Err(error) => assert_eq!(error.status(), StatusCode::NOT_FOUND),
+    }
+}
+
+#[rstest::rstest]
+async fn test_path_login_is_normalized() {
+    let TestDbService(dbservice) = TestDbService::new();
+    let state: Arc<dyn Database> = Arc::new(dbservice);
+    let broadcaster = TestUser::new("test-broadcaster");
+    let session = broadcaster.create_session();
+    state.create_user(&broadcaster.as_db_user()).unwrap();
+    state
+        .create_channel_admin(&ChannelAdmin::for_login(
+            "somemod",
+            &broadcaster.as_db_user(),
+            ChannelAdmin::MANUAL,
+        ))
+        .unwrap();
+
+    let response = channel_admin::put(
+        State(Arc::clone(&state)),
+        State(TestPermissions::new(&state).0),
+        session.clone(),
+        Query(ChannelQuery::default()),
+        Path("SomeMod".to_string()),
+        Json(ChannelRoleRequest {
+            role: ChannelRole::Manager,
+        }),
+    )
+    .await
+    .unwrap()
+    .into_response();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    match channel_admin::delete(
+        State(Arc::clone(&state)),
+        State(TestPermissions::new(&state).0),
+        session.clone(),
+        Query(ChannelQuery::default()),
+        Path("some mod".to_string()),
+    )
+    .await
+    {
+        Ok(_) => panic!("handler returned success"),
+        Err(error) => assert_eq!(error.status(), StatusCode::BAD_REQUEST),
+    }
+
+    let response = channel_admin::delete(
+        State(Arc::clone(&state)),
+        State(TestPermissions::new(&state).0),
+        session,
+        Query(ChannelQuery::default()),
+        Path("SomeMod".to_string()),
+    )
+    .await
+    .unwrap()
+    .into_response();
+    assert_eq!(response.status(), StatusCode::NO_CONTENT);
+    assert!(state
+        .get_channel_admin("somemod", &broadcaster.as_db_user())
+        .unwrap()
+        .is_none());
+}
+
+#[rstest::rstest]
+async fn test_manager_assigns_roles_on_broadcaster_channel() {
+    let TestDbService(dbservice) = TestDbService::new();
+    let state: Arc<dyn Database> = Arc::new(dbservice);
+    let broadcaster = TestUser::new("test-broadcaster");
+    let manager = TestUser::new("test-manager");
+    let query = || ChannelQuery {
+        channel: Some("test-broadcaster".to_string()),
+    };
+
+    state.create_user(&broadcaster.as_db_user()).unwrap();
+    state
+        .create_channel_admin(
+            &ChannelAdmin::new(&manager.as_db_user(), &broadcaster.as_db_user())
+                .with_role(ChannelRole::Manager),
+        )
+        .unwrap();
+
+    let response = channel_admin::post(
+        State(Arc::clone(&state)),
+        State(TestPermissions::new(&state).0),
+        manager.create_session(),
+        Query(query()),
+        "test-helper".to_string(),
+    )
+    .await
+    .unwrap()
+    .into_response();
+    assert_eq!(response.status(), StatusCode::CREATED);
+
+    let response = channel_admin::put(
+        State(Arc::clone(&state)),
+        State(TestPermissions::new(&state).0),
+        manager.create_session(),
+        Query(query()),
+        Path("test-helper".to_string()),
+        Json(ChannelRoleRequest {
+            role: ChannelRole::ViewerSubmitter,
+        }),
+    )
+    .await
+    .unwrap()
+    .into_response();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = response.into_body().collect().await.unwrap().to_bytes();
+    let actual: ChannelAdmin = serde_json::from_slice(&body).unwrap();
+    assert_eq!(actual.role(), ChannelRole::ViewerSubmitter);
+
+    match channel_admin::put(
+        State(Arc::clone(&state)),
+        State(TestPermissions::new(&state).0),
+        manager.create_session(),
+        Query(query()),
+        Path("test-helper".to_string()),
+        Json(ChannelRoleRequest {
+            role: ChannelRole::Broadcaster,
+        }),
+    )
+    .await
+    {
+        Ok(_) => panic!("handler returned success"),
+        Err(error) => assert_eq!(error.status(), StatusCode::BAD_REQUEST),
+    }
+}
+
+#[rstest::rstest]
+async fn test_editor_cannot_manage_admins() {
+    let TestDbService(dbservice) = TestDbService::new();
+    let state: Arc<dyn Database> = Arc::new(dbservice);
+    let broadcaster = TestUser::new("test-broadcaster");
+    let editor = TestUser::new("test-editor");
+
+    state.create_user(&broadcaster.as_db_user()).unwrap();
+    state
+        .create_channel_admin(&ChannelAdmin::new(
+            &editor.as_db_user(),
+            &broadcaster.as_db_user(),
+        ))
+        .unwrap();
+
+    match channel_admin::post(
+        State(Arc::clone(&state)),
+        State(TestPermissions::new(&state).0),
+        editor.create_session(),
+        Query(ChannelQuery {
+            channel: Some("test-broadcaster".to_string()),
+        }),
+        "test-helper".to_string(),
+    )
+    .await
+    {
+        Ok(_) => panic!("handler returned success"),
+        Err(error) => assert_eq!(error.status(), StatusCode::FORBIDDEN),
     }
 }
@@ -0,0 +1,235 @@
+use std::sync::Arc;
+
+use axum::{body::Bytes, extract::State, http::StatusCode, response::IntoResponse};
+use http_body_util::BodyExt;
+use imgfloat::{
+    domain::db::{AssetRepository, Database, TriggerRepository, UserRepository},
+    models::{NewEventTrigger, StoredEventSubSubscription},
+    routes::api::eventsub,
+};
+use time::{Duration, OffsetDateTime};
+
+use crate::fixture::{
+    eventsub::{
+        signed_headers, FOLLOW_PAYLOAD, REDEMPTION_PAYLOAD, REVOCATION_PAYLOAD,
+        VERIFICATION_PAYLOAD,
+    },
+    TestAsset, TestAuthenticator, TestDbService, TestEventSub, TestUser,
+};
+
+const REDEMPTION_SUBSCRIPTION_ID: &str = "f1c2a387-161a-49f9-a165-0f21d7a4e1c4";
+const REWARD_ID: &str = "92af127c-7326-4483-a52b-b0da0be61c01";
+
+fn setup() -> (Arc<dyn Database>, TestEventSub, String) {
+    let TestDbService(dbservice) = TestDbService::new();
+    let broadcaster = TestUser::new("test-user").as_db_user();
+    let asset = TestAsset::new("hype.png").as_db_asset(&broadcaster);
+    dbservice.create_user(&broadcaster).unwrap();
+    dbservice.create_asset(&asset).unwrap();
+    dbservice
+        .save_eventsub_subscription(&StoredEventSubSubscription {
+            id: REDEMPTION_SUBSCRIPTION_ID.to_string(),
+            username: broadcaster.username.clone(),
+            event_type: "channel.channel_points_custom_reward_redemption.add".to_string(),
+            status: "webhook_callback_verification_pending".to_string(),
+        })
+        .unwrap();
+    let database: Arc<dyn Database> = Arc::new(dbservice);
+    let test_eventsub = TestEventSub::new(&database, TestAuthenticator::new());
+    (database, test_eventsub, asset.local_filename)
+}
+
+fn trigger(
+    asset_filename: &str,
+    reward_id: Option<&str>,
+    duration_seconds: i32,
+) -> NewEventTrigger {
+    NewEventTrigger {
+        username: "test-user".to_string(),
+        event_type: "channel.channel_points_custom_reward_redemption.add".to_string(),
+        reward_id: reward_id.map(str::to_string),
+        asset_filename: asset_filename.to_string(),
+        x: 10.0,
+        y: 20.0,
+        w: 300.0,
+        h: 200.0,
+        duration_seconds,
+    }
+}
+
+#[rstest::rstest]
+#[tokio::test]
+async fn test_verification_challenge() {
+    let (database, TestEventSub { service, .. }, _) = setup();
+    let headers = signed_headers(
+        "webhook_callback_verification",
+        "message-1",
+        OffsetDateTime::now_utc(),
+        VERIFICATION_PAYLOAD,
+    );
+
+    let response = eventsub::post(State(service), headers, Bytes::from(VERIFICATION_PAYLOAD))
+        .await
+        .into_response();
+
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = response.into_body().collect().await.unwrap().to_bytes();
+    assert_eq!(body, "pogchamp-kappa-360noscope-vohiyo");
+    let subscription = database
+        .get_eventsub_subscription(REDEMPTION_SUBSCRIPTION_ID)
+        .unwrap()
+        .unwrap();
+    assert_eq!(subscription.status, "enabled");
+}
+
+#[rstest::rstest]
+#[case::tampered_body(
+    signed_headers("notification", "message-1", OffsetDateTime::now_utc(), FOLLOW_PAYLOAD),
+    StatusCode::FORBIDDEN
+)]
+#[case::stale_message(
+    signed_headers(
+        "notification",
+        "message-1",
+        OffsetDateTime::now_utc() - Duration::minutes(11),
+        REDEMPTION_PAYLOAD
+    ),
+    StatusCode::FORBIDDEN
+)]
+#[case::missing_headers(axum::http::HeaderMap::new(), StatusCode::BAD_REQUEST)]
+#[tokio::test]
+async fn test_rejected_messages(
+    #[case] headers: axum::http::HeaderMap,
+    #[case] expected_status: StatusCode,
+) {
+    let (
+        database,
+        TestEventSub {
+            service,
+            controller,
+            ..
+        },
+        filename,
+    ) = setup();
+    database
+        .create_event_trigger(&trigger(&filename, None, 0))
+        .unwrap();
+
+    let response = eventsub::post(State(service), headers, Bytes::from(REDEMPTION_PAYLOAD))
+        .await
+        .into_response();
+
+    assert_eq!(response.status(), expected_status);
+    assert!(controller.get_state("test-user").await.is_none());
+}
+
+#[rstest::rstest]
+#[tokio::test]
+async fn test_redemption_shows_asset_once() {
+    let (
+        database,
+        TestEventSub {
+            service,
+            controller,
+            ..
+        },
+        filename,
+    ) = setup();
+    database
+        .create_event_trigger(&trigger(&filename, Some(REWARD_ID), 0))
+        .unwrap();
+    database
+        .create_event_trigger(&trigger(&filename, Some("other-reward"), 0))
+        .unwrap();
+    let headers = signed_headers(
+        "notification",
+        "message-1",
+        OffsetDateTime::now_utc(),
+        REDEMPTION_PAYLOAD,
+    );
+
+    for _ in 0..2 {
+        let response = eventsub::post(
+            State(Arc::clone(&service)),
+            headers.clone(),
+            Bytes::from(REDEMPTION_PAYLOAD),
+        )
+        .await
+        .into_response();
+        assert_eq!(response.status(), StatusCode::NO_CONTENT);
+    }
+
+    let state = controller.get_state("test-user").await.unwrap();
+    assert_eq!(state.assets.len(), 1);
+    assert_eq!(
+        state.assets[0].url,
+        format!("/api/assets/test-user/{filename}")
+    );
+    assert_eq!((state.assets[0].x, state.assets[0].y), (10.0, 20.0));
+}
+
+#[rstest::rstest]
+#[tokio::test]
+async fn test_triggered_asset_expires() {
+    let (
+        database,
+        TestEventSub {
+            service,
+            controller,
+            ..
+        },
+        filename,
+    ) = setup();
+    database
+        .create_event_trigger(&trigger(&filename, None, 1))
+        .unwrap();
+    let headers = signed_headers(
+        "notification",
+        "message-1",
+        OffsetDateTime::now_utc(),
+        REDEMPTION_PAYLOAD,
+    );
+
+    eventsub::post(State(service), headers, Bytes::from(REDEMPTION_PAYLOAD))
+        .await
+        .unwrap();
+    assert_eq!(
+        controller
+            .get_state("test-user")
+            .await
+            .unwrap()
+            .assets
+            .len(),
+        1
+    );
+
+    tokio::time::sleep(std::time::Duration::from_millis(1500)).await;
+    assert!(controller
+        .get_state("test-user")
+        .await
+        .unwrap()
+        .assets
+        .is_empty());
+}
+
+#[rstest::rstest]
+#[tokio::test]
+async fn test_revocation_removes_subscription() {
+    let (database, TestEventSub { service, .. }, _) = setup();
+    let headers = signed_headers(
+        "revocation",
+        "message-1",
+        OffsetDateTime::now_utc(),
+        REVOCATION_PAYLOAD,
+    );
+
+    let response = eventsub::post(State(service), headers, Bytes::from(REVOCATION_PAYLOAD))
+        .await
+        .into_response();
+
+    assert_eq!(response.status(), StatusCode::NO_CONTENT);
+    assert!(database
+        .get_eventsub_subscription(REDEMPTION_SUBSCRIPTION_ID)
+        .unwrap()
+        .is_none());
+}
@@ -0,0 +1,216 @@
+use std::{sync::Arc, time::Duration};
+
+use axum::{
+    body::Body,
+    extract::{FromRequest, Multipart, State},
+    http::{header, Request, StatusCode},
+    response::IntoResponse,
+};
+use imgfloat::domain::extract::{Json, Path, Query};
+use imgfloat::{
+    domain::{
+        db::{Database, UserRepository},
+        AssetDirectory,
+    },
+    models::{AssetSearchQuery, ChannelQuery, UnownedUserSettings},
+    routes::api::{asset, settings},
+};
+
+use crate::fixture::{TestAssetDirectory, TestDbService, TestPermissions, TestUser};
+
+const BROADCASTERS: usize = 8;
+const UPLOADS_PER_BROADCASTER: usize = 4;
+/// Well below the sqlite busy timeout, so a request that queued behind the
+/// open transaction can't pass.
+const WHILE_HELD: Duration = Duration::from_secs(2);
+
+async fn multipart(filename: &str, data: &str) -> Multipart {
+    let boundary = "imgfloat-test-boundary";
+    let body = format!(
+        "--{boundary}\r\n\
+         Content-Disposition: form-data; name=\"file\"; filename=\"{filename}\"\r\n\
+         Content-Type: image/png\r\n\r\n\
+         {data}\r\n\
+         --{boundary}--\r\n"
+    );
+    let request = Request::builder()
+        .header(
+            header::CONTENT_TYPE,
+            format!("multipart/form-data; boundary={boundary}"),
+        )
+        .body(Body::from(body))
+        .unwrap();
+    Multipart::from_request(request, &()).await.unwrap()
+}
+
+#[rstest::rstest]
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn test_concurrent_uploads_and_settings_changes() {
+    let asset_dir = TestAssetDirectory::new();
+    let dbservice = TestDbService::in_directory(&asset_dir.0);
+    if let Some(journal_mode) = dbservice.journal_mode() {
+        assert_eq!(journal_mode, "wal");
+    }
+    let TestDbService(dbservice) = dbservice;
+    let database: Arc<dyn Database> = Arc::new(dbservice);
+    let TestPermissions(permissions) = TestPermissions::new(&database);
+    let broadcasters: Vec<TestUser> = (0..BROADCASTERS)
+        .map(|i| TestUser::new(format!("test-broadcaster-{i}")))
+        .collect();
+    for broadcaster in &broadcasters {
+        database.create_user(&broadcaster.as_db_user()).unwrap();
+    }
+
+    let mut tasks = tokio::task::JoinSet::new();
+    for broadcaster in &broadcasters {
+        for upload in 0..UPLOADS_PER_BROADCASTER {
+            let database = Arc::clone(&database);
+            let permissions = Arc::clone(&permissions);
+            let asset_dir = AssetDirectory(asset_dir.0.clone());
+            let session = broadcaster.create_session();
+            let username = broadcaster.as_db_user().username;
+            tasks.spawn(async move {
+                let data =
+                    multipart(&format!("{upload}.png"), &format!("{username}-{upload}")).await;
+                let response = asset::post(
+                    State(database),
+                    State(permissions),
+                    State(asset_dir),
+                    session,
+                    Path(username),
+                    data,
+                )
+                .await
+                .into_response();
+                response.status()
+            });
+        }
+        for fps_target in [30, 60] {
+            let database = Arc::clone(&database);
+            let permissions = Arc::clone(&permissions);
+            let session = broadcaster.create_session();
+            tasks.spawn(async move {
+                let response = settings::put(
+                    State(database),
+                    State(permissions),
+                    session,
+                    Query(ChannelQuery::default()),
+                    Json(UnownedUserSettings {
+                        background_opacity: 50,
+                        fps_target,
+                        chat_commands: false,
+                        sync_moderators: false,
+                    }),
+                )
+                .await
+                .into_response();
+                response.status()
+            });
+        }
+    }
+    while let Some(status) = tasks.join_next().await {
+        let status = status.unwrap();
+        assert!(
+            status == StatusCode::OK || status == StatusCode::CREATED,
+            "request failed with {status}"
+        );
+    }
+
+    for broadcaster in &broadcasters {
+        let page = database
+            .search_assets(&broadcaster.as_db_user(), &AssetSearchQuery::default())
+            .unwrap();
+        assert_eq!(page.total, UPLOADS_PER_BROADCASTER as i64);
+        assert!(database
+            .get_user_settings(&broadcaster.as_db_user())
+            .unwrap()
+            .is_some());
+    }
+}
+
+#[rstest::rstest]
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn test_requests_do_not_queue_behind_an_open_transaction() {
+    let asset_dir = TestAssetDirectory::new();
+    let dbservice = TestDbService::in_directory(&asset_dir.0);
+    let busy = TestUser::new("test-busy-broadcaster");
+    let other = TestUser::new("test-other-broadcaster");
+    for broadcaster in [&busy, &other] {
+        dbservice.0.create_user(&broadcaster.as_db_user()).unwrap();
+    }
+    let held = dbservice.hold_transaction("test-busy-broadcaster");
+    // sqlite allows a single writer, so there only readers can get past it
+    let writes_overlap = dbservice.is_postgres();
+    let TestDbService(dbservice) = dbservice;
+    let database: Arc<dyn Database> = Arc::new(dbservice);
+    let TestPermissions(permissions) = TestPermissions::new(&database);
+
+    let upload = tokio::spawn({
+        let database = Arc::clone(&database);
+        let permissions = Arc::clone(&permissions);
+        let asset_dir = AssetDirectory(asset_dir.0.clone());
+        let session = other.create_session();
+        async move {
+            asset::post(
+                State(database),
+                State(permissions),
+                State(asset_dir),
+                session,
+                Path("test-other-broadcaster".to_string()),
+                multipart("0.png", "test-other-broadcaster-0").await,
+            )
+            .await
+            .into_response()
+            .status()
+        }
+    });
+    let search = tokio::time::timeout(
+        WHILE_HELD,
+        asset::get(
+            State(Arc::clone(&database)),
+            Path("test-other-broadcaster".to_string()),
+            Query(AssetSearchQuery::default()),
+        ),
+    )
+    .await
+    .expect("reading queued behind the open transaction");
+    assert!(search.is_ok());
+    tokio::time::timeout(WHILE_HELD, async {
+        while asset_dir.files().is_empty() {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+    })
+    .await
+    .expect("the upload queued behind the open transaction");
+    if writes_overlap {
+        let status = tokio::time::timeout(
+            WHILE_HELD,
+            settings::put(
+                State(Arc::clone(&database)),
+                State(Arc::clone(&permissions)),
+                other.create_session(),
+                Query(ChannelQuery::default()),
+                Json(UnownedUserSettings {
+                    background_opacity: 50,
+                    fps_target: 60,
+                    chat_commands: false,
+                    sync_moderators: false,
+                }),
+            ),
+        )
+        .await
+        .expect("writing queued behind the open transaction")
+        .into_response()
+        .status();
+        assert_eq!(status, StatusCode::CREATED);
+    }
+    let upload = if writes_overlap {
+        tokio::time::timeout(WHILE_HELD, upload)
+            .await
+            .expect("the upload queued behind the open transaction")
+    } else {
+        drop(held);
+        upload.await
+    };
+    assert_eq!(upload.unwrap(), StatusCode::OK);
+}
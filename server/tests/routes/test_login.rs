@@ -8,12 +8,19 @@ use crate::fixture::{EmptySession, TestAuthenticator, TestUser};
 #[rstest::rstest]
 async fn test_redirect_no_session() {
     let EmptySession(session) = EmptySession::new();
+    let stored_session = session.session.clone();
     let authenticator = TestAuthenticator::new();
     let authenticator: Arc<Box<dyn TwitchAuthenticator>> = Arc::new(Box::new(authenticator));
     let response = login::get(State(Arc::clone(&authenticator)), session)
         .await
         .into_response();
-    let login::LoginRedirect(expected_url) = login::LoginRedirect::new(None, authenticator);
+    let state: String = stored_session
+        .get("session.oauth_state")
+        .await
+        .unwrap()
+        .unwrap();
+    let login::LoginRedirect(expected_url) =
+        login::LoginRedirect::for_authorization(authenticator, &state);
     let actual_url = response
         .headers()
         .get("Location")
@@ -22,6 +29,22 @@ async fn test_redirect_no_session() {
         .unwrap();
     assert_eq!(response.status(), StatusCode::TEMPORARY_REDIRECT);
     assert_eq!(actual_url, expected_url);
+    assert!(actual_url.ends_with(&format!("&state={state}")));
+}
+
+#[rstest::rstest]
+async fn test_redirect_states_are_unique() {
+    let authenticator = TestAuthenticator::new();
+    let authenticator: Arc<Box<dyn TwitchAuthenticator>> = Arc::new(Box::new(authenticator));
+    let mut urls = vec![];
+    for _ in 0..2 {
+        let EmptySession(session) = EmptySession::new();
+        let response = login::get(State(Arc::clone(&authenticator)), session)
+            .await
+            .into_response();
+        urls.push(response.headers().get("Location").unwrap().clone());
+    }
+    assert_ne!(urls[0], urls[1]);
 }
 
 #[rstest::rstest]
@@ -33,8 +56,7 @@ async fn test_redirect_existing_session() {
     let response = login::get(State(Arc::clone(&authenticator)), session)
         .await
         .into_response();
-    let login::LoginRedirect(expected_url) =
-        login::LoginRedirect::new(Some(&user.as_twitch_user()), authenticator);
+    let login::LoginRedirect(expected_url) = login::LoginRedirect::for_user(&user.as_twitch_user());
     let actual_url = response
         .headers()
         .get("Location")
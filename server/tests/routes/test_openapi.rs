@@ -0,0 +1,109 @@
+use std::collections::BTreeSet;
+
+use imgfloat::routes::api::openapi::{self, ApiDoc};
+use utoipa::OpenApi;
+
+const ROUTER_SOURCE: &str = include_str!("../../src/lib.rs");
+
+fn route_calls(source: &str) -> Vec<&str> {
+    let mut calls = vec![];
+    let mut rest = source;
+    while let Some(start) = rest.find(".route(") {
+        let call = &rest[start + ".route(".len()..];
+        let mut depth = 1;
+        let end = call
+            .char_indices()
+            .find(|(_, c)| {
+                match c {
+                    '(' => depth += 1,
+                    ')' => depth -= 1,
+                    _ => {}
+                }
+                depth == 0
+            })
+            .map(|(index, _)| index)
+            .unwrap();
+        calls.push(&call[..end]);
+        rest = &call[end..];
+    }
+    calls
+}
+
+fn registered_api_operations() -> BTreeSet<(String, String)> {
+    let path_pattern = regex::Regex::new(r#"^\s*"([^"]+)""#).unwrap();
+    let param_pattern = regex::Regex::new(r":(\w+)").unwrap();
+    let method_pattern = regex::Regex::new(r"\b(get|post|put|delete|patch)\(routes::").unwrap();
+    route_calls(ROUTER_SOURCE)
+        .into_iter()
+        .filter_map(|call| {
+            let path = path_pattern.captures(call)?[1].to_string();
+            path.starts_with("/api/").then(|| {
+                let path = param_pattern.replace_all(&path, "{$1}").to_string();
+                method_pattern
+                    .captures_iter(call)
+                    .map(|method| (path.clone(), method[1].to_string()))
+                    .collect::<Vec<_>>()
+            })
+        })
+        .flatten()
+        .collect()
+}
+
+fn documented_operations() -> BTreeSet<(String, String)> {
+    let spec = serde_json::to_value(ApiDoc::openapi()).unwrap();
+    spec["paths"]
+        .as_object()
+        .unwrap()
+        .iter()
+        .flat_map(|(path, item)| {
+            item.as_object()
+                .unwrap()
+                .keys()
+                .map(|method| (path.clone(), method.clone()))
+                .collect::<Vec<_>>()
+        })
+        .collect()
+}
+
+#[rstest::rstest]
+fn test_every_registered_route_is_documented() {
+    let registered = registered_api_operations();
+    assert!(registered.contains(&("/api/assets/{username}".to_string(), "post".to_string())));
+    let documented = documented_operations();
+
+    let undocumented: Vec<_> = registered.difference(&documented).collect();
+    assert!(
+        undocumented.is_empty(),
+        "undocumented routes: {undocumented:?}"
+    );
+    let unregistered: Vec<_> = documented.difference(&registered).collect();
+    assert!(
+        unregistered.is_empty(),
+        "documented but unregistered: {unregistered:?}"
+    );
+}
+
+#[rstest::rstest]
+#[case("UserFacingAsset")]
+#[case("UserSettings")]
+#[case("ChannelAdmin")]
+#[case("ImgfloatAssetStateMessage")]
+#[case("ImgfloatAsset")]
+#[case("ImgfloatServerMessage")]
+#[case("ApiErrorBody")]
+fn test_schema_is_documented(#[case] schema: &str) {
+    let spec = serde_json::to_value(ApiDoc::openapi()).unwrap();
+    assert!(
+        spec["components"]["schemas"].get(schema).is_some(),
+        "missing schema {schema}"
+    );
+}
+
+#[rstest::rstest]
+#[tokio::test]
+async fn test_openapi_document_is_served() {
+    let document = openapi::get().await.0;
+    let spec = serde_json::to_value(document).unwrap();
+    assert!(spec["openapi"].as_str().unwrap().starts_with("3."));
+    assert!(spec["paths"].get("/api/openapi.json").is_some());
+}
@@ -0,0 +1,222 @@
+use std::{sync::Arc, time::Duration};
+
+use axum::{extract::State, http::StatusCode, response::IntoResponse};
+use http_body_util::BodyExt;
+use imgfloat::domain::extract::Query;
+use imgfloat::{
+    domain::{
+        db::{AdminRepository, Database, UserRepository},
+        message::ImgfloatAsset,
+        ChannelController, PermissionError,
+    },
+    models::{ChannelAdmin, ChannelQuery, IssuedOverlayToken},
+    routes::api::overlay_token,
+};
+
+use futures::StreamExt;
+use tokio::net::TcpStream;
+use tokio_tungstenite::{
+    tungstenite::{protocol::frame::coding::CloseCode, Message},
+    MaybeTlsStream, WebSocketStream,
+};
+
+use crate::fixture::{EmptySession, TestDbService, TestPermissions, TestSocketServer, TestUser};
+
+type Reader = WebSocketStream<MaybeTlsStream<TcpStream>>;
+
+fn setup() -> Arc<dyn Database> {
+    let TestDbService(dbservice) = TestDbService::new();
+    let broadcaster = TestUser::new("test-broadcaster").as_db_user();
+    dbservice.create_user(&broadcaster).unwrap();
+    dbservice
+        .create_channel_admin(&ChannelAdmin::new(
+            &TestUser::new("test-editor").as_db_user(),
+            &broadcaster,
+        ))
+        .unwrap();
+    Arc::new(dbservice)
+}
+
+async fn issue(
+    database: &Arc<dyn Database>,
+    controller: &Arc<ChannelController>,
+) -> IssuedOverlayToken {
+    let response = overlay_token::post(
+        State(Arc::clone(database)),
+        State(Arc::clone(controller)),
+        State(TestPermissions::new(database).0),
+        TestUser::new("test-broadcaster").create_session(),
+        Query(ChannelQuery::default()),
+    )
+    .await
+    .unwrap()
+    .into_response();
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let body = response.into_body().collect().await.unwrap().to_bytes();
+    serde_json::from_slice(&body).unwrap()
+}
+
+async fn connect(server: &TestSocketServer) -> Reader {
+    let (mut reader, _) = tokio_tungstenite::connect_async(server.read_url())
+        .await
+        .unwrap();
+    // the cached state is sent once the reader is subscribed
+    let Some(Ok(Message::Text(_))) = reader.next().await else {
+        panic!("expected the channel state");
+    };
+    reader
+}
+
+async fn assert_revoked(reader: &mut Reader) {
+    let frame = tokio::time::timeout(Duration::from_secs(5), async {
+        loop {
+            match reader.next().await {
+                Some(Ok(Message::Close(frame))) => break frame,
+                Some(Ok(_)) => continue,
+                other => panic!("expected a close frame, got {other:?}"),
+            }
+        }
+    })
+    .await
+    .unwrap();
+    assert_eq!(frame.unwrap().code, CloseCode::Policy);
+}
+
+#[rstest::rstest]
+#[tokio::test]
+async fn test_issued_token_is_stored_hashed() {
+    let database = setup();
+    let issued = issue(&database, &Arc::new(ChannelController::new())).await;
+
+    let stored = overlay_token::get(
+        State(Arc::clone(&database)),
+        State(TestPermissions::new(&database).0),
+        TestUser::new("test-broadcaster").create_session(),
+        Query(ChannelQuery::default()),
+    )
+    .await
+    .unwrap();
+    assert_eq!(stored.0.created_at, issued.created_at);
+    assert_ne!(stored.0.token_hash, issued.token);
+    assert!(stored.0.matches(&issued.token));
+    let serialized = serde_json::to_value(&stored.0).unwrap();
+    assert!(serialized.get("token_hash").is_none());
+}
+
+#[rstest::rstest]
+#[tokio::test]
+async fn test_reader_requires_valid_token() {
+    let database = setup();
+    let permissions = TestPermissions::new(&database).0;
+    let EmptySession(anonymous) = EmptySession::new();
+    let issued = issue(&database, &Arc::new(ChannelController::new())).await;
+
+    assert!(permissions
+        .authorize_reader(&anonymous, "test-broadcaster", Some(&issued.token))
+        .await
+        .is_ok());
+    assert_eq!(
+        permissions
+            .authorize_reader(&anonymous, "test-broadcaster", Some("not-the-token"))
+            .await
+            .unwrap_err(),
+        PermissionError::Unauthenticated
+    );
+    assert_eq!(
+        permissions
+            .authorize_reader(&anonymous, "test-broadcaster", None)
+            .await
+            .unwrap_err(),
+        PermissionError::Unauthenticated
+    );
+    assert_eq!(
+        permissions
+            .authorize_reader(&anonymous, "test-nobody", Some(&issued.token))
+            .await
+            .unwrap_err(),
+        PermissionError::UnknownBroadcaster
+    );
+    assert!(permissions
+        .authorize_reader(
+            &TestUser::new("test-editor").create_session(),
+            "test-broadcaster",
+            None
+        )
+        .await
+        .is_ok());
+}
+
+#[rstest::rstest]
+#[tokio::test]
+async fn test_regenerate_and_revoke() {
+    let database = setup();
+    let permissions = TestPermissions::new(&database).0;
+    let EmptySession(anonymous) = EmptySession::new();
+    let controller = Arc::new(ChannelController::new());
+    let server = TestSocketServer::start(&controller).await;
+    controller
+        .show_asset(
+            "test-broadcaster",
+            ImgfloatAsset {
+                id: "1".to_string(),
+                x: 0.0,
+                y: 0.0,
+                w: 10.0,
+                h: 10.0,
+                theta: 0.0,
+                url: "/api/assets/test-broadcaster/1.png".to_string(),
+            },
+        )
+        .await;
+    let first = issue(&database, &controller).await;
+    let mut reader = connect(&server).await;
+    let second = issue(&database, &controller).await;
+
+    assert_ne!(first.token, second.token);
+    assert!(permissions
+        .authorize_reader(&anonymous, "test-broadcaster", Some(&first.token))
+        .await
+        .is_err());
+    assert!(permissions
+        .authorize_reader(&anonymous, "test-broadcaster", Some(&second.token))
+        .await
+        .is_ok());
+    assert_revoked(&mut reader).await;
+
+    let mut reader = connect(&server).await;
+    let status = overlay_token::delete(
+        State(Arc::clone(&database)),
+        State(Arc::clone(&controller)),
+        State(TestPermissions::new(&database).0),
+        TestUser::new("test-broadcaster").create_session(),
+        Query(ChannelQuery::default()),
+    )
+    .await
+    .unwrap();
+    assert_eq!(status, StatusCode::NO_CONTENT);
+    assert!(permissions
+        .authorize_reader(&anonymous, "test-broadcaster", Some(&second.token))
+        .await
+        .is_err());
+    assert_revoked(&mut reader).await;
+}
+
+#[rstest::rstest]
+#[tokio::test]
+async fn test_editor_cannot_issue_token() {
+    let database = setup();
+    match overlay_token::post(
+        State(Arc::clone(&database)),
+        State(Arc::new(ChannelController::new())),
+        State(TestPermissions::new(&database).0),
+        TestUser::new("test-editor").create_session(),
+        Query(ChannelQuery {
+            channel: Some("test-broadcaster".to_string()),
+        }),
+    )
+    .await
+    {
+        Ok(_) => panic!("handler returned success"),
+        Err(error) => assert_eq!(error.status(), StatusCode::FORBIDDEN),
+    }
+}
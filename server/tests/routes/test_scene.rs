@@ -0,0 +1,176 @@
+use std::sync::Arc;
+
+use axum::{extract::State, http::StatusCode, response::IntoResponse};
+use http_body_util::BodyExt;
+use imgfloat::domain::extract::{Json, Path};
+use imgfloat::{
+    domain::{
+        db::{AdminRepository, AssetRepository, Database, UserRepository},
+        message::ImgfloatAsset,
+        ChannelController,
+    },
+    models::{ChannelAdmin, ChannelRole, NewSceneAsset, ScenePlacement},
+    routes::api::scene,
+};
+
+use crate::fixture::{TestAsset, TestDbService, TestPermissions, TestUser};
+
+struct Setup {
+    database: Arc<dyn Database>,
+    controller: Arc<ChannelController>,
+    filename: String,
+}
+
+fn setup() -> Setup {
+    let TestDbService(dbservice) = TestDbService::new();
+    let broadcaster = TestUser::new("test-broadcaster").as_db_user();
+    dbservice.create_user(&broadcaster).unwrap();
+    let asset = TestAsset::new("hype.png").as_db_asset(&broadcaster);
+    dbservice.create_asset(&asset).unwrap();
+    dbservice
+        .create_channel_admin(
+            &ChannelAdmin::new(&TestUser::new("test-viewer").as_db_user(), &broadcaster)
+                .with_role(ChannelRole::ViewerSubmitter),
+        )
+        .unwrap();
+    Setup {
+        database: Arc::new(dbservice),
+        controller: Arc::new(ChannelController::new()),
+        filename: asset.local_filename,
+    }
+}
+
+fn placement(x: f32) -> ScenePlacement {
+    ScenePlacement {
+        x,
+        y: 10.0,
+        w: 20.0,
+        h: 20.0,
+        theta: 0.0,
+    }
+}
+
+async fn add(
+    setup: &Setup,
+    user: &str,
+    filename: &str,
+    x: f32,
+) -> Result<ImgfloatAsset, StatusCode> {
+    let response = scene::post(
+        State(Arc::clone(&setup.database)),
+        State(Arc::clone(&setup.controller)),
+        State(TestPermissions::new(&setup.database).0),
+        TestUser::new(user).create_session(),
+        Path("test-broadcaster".to_string()),
+        Json(NewSceneAsset {
+            filename: filename.to_string(),
+            placement: placement(x),
+        }),
+    )
+    .await
+    .map_err(|error| error.status())?
+    .into_response();
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let body = response.into_body().collect().await.unwrap().to_bytes();
+    Ok(serde_json::from_slice(&body).unwrap())
+}
+
+async fn list(setup: &Setup) -> Vec<ImgfloatAsset> {
+    scene::get(
+        State(Arc::clone(&setup.controller)),
+        State(TestPermissions::new(&setup.database).0),
+        TestUser::new("test-broadcaster").create_session(),
+        Path("test-broadcaster".to_string()),
+    )
+    .await
+    .unwrap()
+    .0
+}
+
+#[rstest::rstest]
+#[tokio::test]
+async fn test_add_asset_to_scene() {
+    let setup = setup();
+    assert!(list(&setup).await.is_empty());
+
+    let added = add(&setup, "test-broadcaster", &setup.filename, 5.0)
+        .await
+        .unwrap();
+
+    assert_eq!(
+        added.url,
+        format!("/api/assets/test-broadcaster/{}", setup.filename)
+    );
+    let assets = list(&setup).await;
+    assert_eq!(assets.len(), 1);
+    assert_eq!(assets[0].id, added.id);
+    assert_eq!(assets[0].x, 5.0);
+}
+
+#[rstest::rstest]
+#[case("test-broadcaster", "missing.png", 5.0, StatusCode::NOT_FOUND)]
+#[case("test-broadcaster", "", f32::NAN, StatusCode::BAD_REQUEST)]
+#[case("test-viewer", "", 5.0, StatusCode::FORBIDDEN)]
+#[case("test-stranger", "", 5.0, StatusCode::FORBIDDEN)]
+#[tokio::test]
+async fn test_add_asset_rejected(
+    #[case] user: &str,
+    #[case] filename: &str,
+    #[case] x: f32,
+    #[case] expected: StatusCode,
+) {
+    let setup = setup();
+    let filename = if filename.is_empty() {
+        setup.filename.clone()
+    } else {
+        filename.to_string()
+    };
+    assert_eq!(add(&setup, user, &filename, x).await.unwrap_err(), expected);
+    assert!(setup
+        .controller
+        .get_state("test-broadcaster")
+        .await
+        .is_none());
+}
+
+#[rstest::rstest]
+#[tokio::test]
+async fn test_update_and_remove_asset() {
+    let setup = setup();
+    let added = add(&setup, "test-broadcaster", &setup.filename, 5.0)
+        .await
+        .unwrap();
+
+    let updated = scene::put(
+        State(Arc::clone(&setup.controller)),
+        State(TestPermissions::new(&setup.database).0),
+        TestUser::new("test-broadcaster").create_session(),
+        Path(("test-broadcaster".to_string(), added.id.clone())),
+        Json(ScenePlacement {
+            theta: 90.0,
+            ..placement(42.0)
+        }),
+    )
+    .await
+    .unwrap();
+    assert_eq!(updated.0.x, 42.0);
+    let assets = list(&setup).await;
+    assert_eq!(assets[0].x, 42.0);
+    assert_eq!(assets[0].theta, 90.0);
+    assert_eq!(assets[0].url, added.url);
+
+    let remove = || {
+        scene::delete(
+            State(Arc::clone(&setup.controller)),
+            State(TestPermissions::new(&setup.database).0),
+            TestUser::new("test-broadcaster").create_session(),
+            Path(("test-broadcaster".to_string(), added.id.clone())),
+        )
+    };
+    assert_eq!(remove().await, Ok(StatusCode::NO_CONTENT));
+    assert!(list(&setup).await.is_empty());
+    assert_eq!(
+        remove().await.map_err(|error| error.status()),
+        Err(StatusCode::NOT_FOUND)
+    );
+}
@@ -1,26 +1,44 @@
-use axum::{extract::State, http::StatusCode, response::IntoResponse};
+use axum::{
+    body::Body,
+    extract::{FromRef, State},
+    http::{header, Request, StatusCode},
+    response::IntoResponse,
+    routing::put,
+    Router,
+};
 use http_body_util::BodyExt;
+use imgfloat::domain::db::Database;
+use imgfloat::domain::extract::{Json, Query};
+use imgfloat::domain::{ApiErrorBody, PermissionService};
 use imgfloat::models::user_settings::ValidatedUnownedUserSettings;
-use imgfloat::models::UserSettings;
+use imgfloat::models::{ChannelAdmin, ChannelQuery, UnownedUserSettings, UserSettings};
 use imgfloat::routes::api::settings;
 use std::sync::Arc;
-use tokio::sync::RwLock;
+use tower::ServiceExt;
+use tower_sessions::{MemoryStore, SessionManagerLayer};
 
 use crate::fixture::TestDbService;
+use crate::fixture::TestPermissions;
 use crate::fixture::TestUser;
 
 #[rstest::rstest]
 async fn test_200_on_existing_settings() {
     let TestDbService(dbservice) = TestDbService::new();
-    let state = Arc::new(RwLock::new(dbservice));
+    let state: Arc<dyn Database> = Arc::new(dbservice);
     let user = TestUser::new("test-user");
     let session = user.create_session();
     let expected = ValidatedUnownedUserSettings::default().with_owner(&user.as_db_user());
 
-    state.write().await.create_user(&user.as_db_user()).unwrap();
-    state.write().await.create_user_settings(&expected).unwrap();
+    state.create_user(&user.as_db_user()).unwrap();
+    state.create_user_settings(&expected).unwrap();
 
-    let response = settings::get(State(Arc::clone(&state)), session).await;
+    let response = settings::get(
+        State(Arc::clone(&state)),
+        State(TestPermissions::new(&state).0),
+        session,
+        Query(ChannelQuery::default()),
+    )
+    .await;
     match response {
         Ok(response) => {
             let response = response.into_response();
@@ -29,18 +47,24 @@ async fn test_200_on_existing_settings() {
             let actual: UserSettings = serde_json::from_slice(&body).unwrap();
             assert_eq!(expected, actual);
         }
-        Err(status_code) => panic!("handler returned failure {status_code}"),
+        Err(error) => panic!("handler returned failure {error:?}"),
     }
 }
 
 #[rstest::rstest]
 async fn test_201_on_missing_settings() {
     let TestDbService(dbservice) = TestDbService::new();
-    let state = Arc::new(RwLock::new(dbservice));
+    let state: Arc<dyn Database> = Arc::new(dbservice);
     let user = TestUser::new("test-user");
     let session = user.create_session();
-    state.write().await.create_user(&user.as_db_user()).unwrap();
-    let response = settings::get(State(Arc::clone(&state)), session).await;
+    state.create_user(&user.as_db_user()).unwrap();
+    let response = settings::get(
+        State(Arc::clone(&state)),
+        State(TestPermissions::new(&state).0),
+        session,
+        Query(ChannelQuery::default()),
+    )
+    .await;
     match response {
         Ok(response) => {
             let response = response.into_response();
@@ -50,19 +74,135 @@ async fn test_201_on_missing_settings() {
             let actual: UserSettings = serde_json::from_slice(&body).unwrap();
             assert_eq!(expected, actual);
         }
-        Err(status_code) => panic!("handler returned failure {status_code}"),
+        Err(error) => panic!("handler returned failure {error:?}"),
     }
 }
 
 #[rstest::rstest]
 async fn test_404_on_missing_user() {
     let TestDbService(dbservice) = TestDbService::new();
-    let state = Arc::new(RwLock::new(dbservice));
+    let state: Arc<dyn Database> = Arc::new(dbservice);
     let user = TestUser::new("test-user");
     let session = user.create_session();
-    let response = settings::get(State(Arc::clone(&state)), session).await;
+    let response = settings::get(
+        State(Arc::clone(&state)),
+        State(TestPermissions::new(&state).0),
+        session,
+        Query(ChannelQuery::default()),
+    )
+    .await;
     match response {
         Ok(_) => panic!("handler returned success"),
-        Err(status_code) => assert_eq!(status_code, StatusCode::NOT_FOUND),
+        Err(error) => assert_eq!(error.status(), StatusCode::NOT_FOUND),
     }
 }
+
+#[rstest::rstest]
+async fn test_disabling_moderator_sync_removes_synced_moderators() {
+    let TestDbService(dbservice) = TestDbService::new();
+    let state: Arc<dyn Database> = Arc::new(dbservice);
+    let user = TestUser::new("test-user");
+    let broadcaster = user.as_db_user();
+    state.create_user(&broadcaster).unwrap();
+    let syncing = ValidatedUnownedUserSettings {
+        sync_moderators: true,
+        ..Default::default()
+    };
+    state
+        .create_user_settings(&syncing.with_owner(&broadcaster))
+        .unwrap();
+    for (login, source) in [
+        ("test-moderator", ChannelAdmin::TWITCH_MODERATOR),
+        ("test-helper", ChannelAdmin::MANUAL),
+    ] {
+        state
+            .create_channel_admin(&ChannelAdmin::for_login(login, &broadcaster, source))
+            .unwrap();
+    }
+
+    let response = settings::put(
+        State(Arc::clone(&state)),
+        State(TestPermissions::new(&state).0),
+        user.create_session(),
+        Query(ChannelQuery::default()),
+        Json(UnownedUserSettings {
+            background_opacity: 40,
+            fps_target: 60,
+            chat_commands: false,
+            sync_moderators: false,
+        }),
+    )
+    .await
+    .unwrap()
+    .into_response();
+
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let remaining: Vec<String> = state
+        .get_channel_admins(&broadcaster)
+        .unwrap()
+        .into_iter()
+        .map(|channel_admin| channel_admin.username)
+        .collect();
+    assert_eq!(remaining, vec!["test-helper"]);
+}
+
+#[derive(Clone)]
+struct SettingsState {
+    database: Arc<dyn Database>,
+    permissions: Arc<PermissionService>,
+}
+
+impl FromRef<SettingsState> for Arc<dyn Database> {
+    fn from_ref(state: &SettingsState) -> Self {
+        Arc::clone(&state.database)
+    }
+}
+
+impl FromRef<SettingsState> for Arc<PermissionService> {
+    fn from_ref(state: &SettingsState) -> Self {
+        Arc::clone(&state.permissions)
+    }
+}
+
+#[rstest::rstest]
+#[case(
+    Some("application/json"),
+    "{\"background_opacity\":",
+    StatusCode::BAD_REQUEST
+)]
+#[case(
+    Some("application/json"),
+    "{\"background_opacity\":\"opaque\"}",
+    StatusCode::UNPROCESSABLE_ENTITY
+)]
+#[case(None, "{}", StatusCode::UNSUPPORTED_MEDIA_TYPE)]
+#[tokio::test]
+async fn test_malformed_body_is_api_error(
+    #[case] content_type: Option<&str>,
+    #[case] body: &'static str,
+    #[case] expected: StatusCode,
+) {
+    let TestDbService(dbservice) = TestDbService::new();
+    let database: Arc<dyn Database> = Arc::new(dbservice);
+    let app = Router::new()
+        .route("/api/settings", put(settings::put))
+        .layer(SessionManagerLayer::new(MemoryStore::default()))
+        .with_state(SettingsState {
+            permissions: TestPermissions::new(&database).0,
+            database,
+        });
+    let mut request = Request::builder().method("PUT").uri("/api/settings");
+    if let Some(content_type) = content_type {
+        request = request.header(header::CONTENT_TYPE, content_type);
+    }
+
+    let response = app
+        .oneshot(request.body(Body::from(body)).unwrap())
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), expected);
+    let body = response.into_body().collect().await.unwrap().to_bytes();
+    let error: ApiErrorBody = serde_json::from_slice(&body).unwrap();
+    assert_eq!(error.code, "invalid_body");
+}
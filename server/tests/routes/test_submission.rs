@@ -0,0 +1,437 @@
+use std::sync::Arc;
+
+use axum::{
+    body::Body,
+    extract::{FromRequest, Multipart, State},
+    http::{header, Request, StatusCode},
+    response::IntoResponse,
+};
+use http_body_util::BodyExt;
+use imgfloat::domain::extract::{Json, Path, Query};
+use imgfloat::{
+    domain::{
+        db::{AdminRepository, Database, UserRepository},
+        message::ImgfloatWriterNotification,
+        ChannelController, SubmissionQueue,
+    },
+    models::{
+        ApiToken, ChannelAdmin, Submission, SubmissionDecision, SubmissionQuery, SubmissionReview,
+        UnownedAsset,
+    },
+    routes::api::submission,
+};
+
+use crate::fixture::{TestAsset, TestAssetDirectory, TestDbService, TestPermissions, TestUser};
+
+struct Setup {
+    database: Arc<dyn Database>,
+    controller: Arc<ChannelController>,
+    submissions: Arc<SubmissionQueue>,
+    asset_dir: TestAssetDirectory,
+}
+
+fn setup() -> Setup {
+    let TestDbService(dbservice) = TestDbService::new();
+    let broadcaster = TestUser::new("test-broadcaster").as_db_user();
+    dbservice.create_user(&broadcaster).unwrap();
+    dbservice
+        .create_channel_admin(&ChannelAdmin::new(
+            &TestUser::new("test-editor").as_db_user(),
+            &broadcaster,
+        ))
+        .unwrap();
+    let database: Arc<dyn Database> = Arc::new(dbservice);
+    let controller = Arc::new(ChannelController::new());
+    let asset_dir = TestAssetDirectory::new();
+    let submissions = Arc::new(SubmissionQueue::new(
+        Arc::clone(&database),
+        Arc::clone(&controller),
+        asset_dir.0.clone(),
+    ));
+    Setup {
+        database,
+        controller,
+        submissions,
+        asset_dir,
+    }
+}
+
+async fn multipart(filename: &str, content_type: &str, data: &str) -> Multipart {
+    let boundary = "imgfloat-test-boundary";
+    let body = format!(
+        "--{boundary}\r\n\
+         Content-Disposition: form-data; name=\"file\"; filename=\"{filename}\"\r\n\
+         Content-Type: {content_type}\r\n\r\n\
+         {data}\r\n\
+         --{boundary}--\r\n"
+    );
+    let request = Request::builder()
+        .header(
+            header::CONTENT_TYPE,
+            format!("multipart/form-data; boundary={boundary}"),
+        )
+        .body(Body::from(body))
+        .unwrap();
+    Multipart::from_request(request, &()).await.unwrap()
+}
+
+async fn submit(
+    setup: &Setup,
+    submitter: &str,
+    content_type: &str,
+) -> Result<Submission, StatusCode> {
+    submit_file(setup, submitter, "hype.png", content_type).await
+}
+
+async fn submit_file(
+    setup: &Setup,
+    submitter: &str,
+    filename: &str,
+    content_type: &str,
+) -> Result<Submission, StatusCode> {
+    let response = submission::post(
+        State(TestPermissions::new(&setup.database).0),
+        State(Arc::clone(&setup.submissions)),
+        TestUser::new(submitter).create_session(),
+        Path("test-broadcaster".to_string()),
+        multipart(filename, content_type, "hype").await,
+    )
+    .await
+    .map_err(|error| error.status())?
+    .into_response();
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let body = response.into_body().collect().await.unwrap().to_bytes();
+    Ok(serde_json::from_slice(&body).unwrap())
+}
+
+async fn review(
+    setup: &Setup,
+    id: i32,
+    decision: SubmissionDecision,
+) -> Result<Submission, StatusCode> {
+    let response = submission::review(
+        State(Arc::clone(&setup.database)),
+        State(TestPermissions::new(&setup.database).0),
+        State(Arc::clone(&setup.submissions)),
+        TestUser::new("test-editor").create_session(),
+        Path(("test-broadcaster".to_string(), id)),
+        Json(SubmissionReview { decision }),
+    )
+    .await
+    .map_err(|error| error.status())?
+    .into_response();
+    let body = response.into_body().collect().await.unwrap().to_bytes();
+    Ok(serde_json::from_slice(&body).unwrap())
+}
+
+#[rstest::rstest]
+#[tokio::test]
+async fn test_viewer_submission_is_queued_and_announced() {
+    let setup = setup();
+    let mut notifications = setup
+        .controller
+        .subscribe_notifications("test-broadcaster")
+        .await;
+
+    let submitted = submit(&setup, "test-viewer", "image/png").await.unwrap();
+
+    assert_eq!(submitted.submitter, "test-viewer");
+    assert!(submitted.is_pending());
+    assert_eq!(
+        setup.asset_dir.files(),
+        vec![submitted.local_filename.clone()]
+    );
+    let broadcaster = TestUser::new("test-broadcaster").as_db_user();
+    assert!(setup
+        .database
+        .get_asset(&submitted.local_filename)
+        .unwrap()
+        .is_none());
+    let notification: ImgfloatWriterNotification =
+        serde_json::from_str(&notifications.recv().await.unwrap()).unwrap();
+    match notification {
+        ImgfloatWriterNotification::SubmissionReceived(received) => {
+            assert_eq!(received, submitted)
+        }
+        other => panic!("unexpected notification {other:?}"),
+    }
+
+    let response = submission::get(
+        State(Arc::clone(&setup.database)),
+        State(TestPermissions::new(&setup.database).0),
+        TestUser::new("test-editor").create_session(),
+        Path(broadcaster.username.clone()),
+        Query(SubmissionQuery::default()),
+    )
+    .await
+    .unwrap();
+    assert_eq!(response.0, vec![submitted]);
+}
+
+#[rstest::rstest]
+#[case("submit", None)]
+#[case("upload,place_assets", Some(StatusCode::FORBIDDEN))]
+#[tokio::test]
+async fn test_api_token_needs_submit_scope(
+    #[case] scopes: &str,
+    #[case] expected: Option<StatusCode>,
+) {
+    let setup = setup();
+    let mut session = TestUser::new("test-viewer").create_session();
+    session.api_token = Some(ApiToken {
+        id: 1,
+        username: "test-viewer".to_string(),
+        name: "test-token".to_string(),
+        token_hash: String::new(),
+        scopes: scopes.to_string(),
+        created_at: 0,
+        expires_at: None,
+        last_used_at: None,
+    });
+
+    let result = submission::post(
+        State(TestPermissions::new(&setup.database).0),
+        State(Arc::clone(&setup.submissions)),
+        session,
+        Path("test-broadcaster".to_string()),
+        multipart("hype.png", "image/png", "hype").await,
+    )
+    .await;
+
+    assert_eq!(result.err().map(|error| error.status()), expected);
+}
+
+#[rstest::rstest]
+#[case("text/html", StatusCode::UNSUPPORTED_MEDIA_TYPE)]
+#[case("application/octet-stream", StatusCode::UNSUPPORTED_MEDIA_TYPE)]
+#[case("image/svg+xml", StatusCode::UNSUPPORTED_MEDIA_TYPE)]
+#[tokio::test]
+async fn test_non_image_submission_rejected(
+    #[case] content_type: &str,
+    #[case] expected: StatusCode,
+) {
+    let setup = setup();
+    assert_eq!(
+        submit(&setup, "test-viewer", content_type).await,
+        Err(expected)
+    );
+    assert!(setup.asset_dir.files().is_empty());
+}
+
+#[rstest::rstest]
+#[case("hype.png", "hype.png")]
+#[case("x\" onerror=\"alert(1).png", "x onerror=alert(1).png")]
+#[case("../../etc/passwd", "passwd")]
+#[case("C:\\Users\\me\\hype.png", "hype.png")]
+#[case(" line\nbreak.png ", "linebreak.png")]
+#[case("\"<>", "unknown")]
+fn test_filename_is_sanitized(#[case] filename: &str, #[case] expected: &str) {
+    assert_eq!(UnownedAsset::sanitize_filename(filename), expected);
+}
+
+#[rstest::rstest]
+#[tokio::test]
+async fn test_submitted_filename_is_sanitized() {
+    let setup = setup();
+    let submitted = submit_file(
+        &setup,
+        "test-viewer",
+        "<img src=x onerror=alert(1)>.png",
+        "image/png",
+    )
+    .await
+    .unwrap();
+    assert_eq!(
+        submitted.original_filename,
+        "img src=x onerror=alert(1).png"
+    );
+}
+
+#[rstest::rstest]
+#[tokio::test]
+async fn test_submission_file_cannot_be_sniffed() {
+    let setup = setup();
+    let submitted = submit(&setup, "test-viewer", "image/png").await.unwrap();
+
+    let response = submission::file(
+        State(Arc::clone(&setup.database)),
+        State(TestPermissions::new(&setup.database).0),
+        State(Arc::clone(&setup.submissions)),
+        TestUser::new("test-editor").create_session(),
+        Path(("test-broadcaster".to_string(), submitted.id)),
+    )
+    .await
+    .unwrap()
+    .into_response();
+
+    let headers = response.headers();
+    assert_eq!(headers[header::CONTENT_TYPE], "image/png");
+    assert_eq!(headers[header::X_CONTENT_TYPE_OPTIONS], "nosniff");
+    assert!(headers[header::CONTENT_SECURITY_POLICY]
+        .to_str()
+        .unwrap()
+        .contains("sandbox"));
+}
+
+#[rstest::rstest]
+#[tokio::test]
+async fn test_pending_submissions_are_limited_per_submitter() {
+    let setup = setup();
+    for _ in 0..SubmissionQueue::MAX_PENDING_PER_SUBMITTER {
+        submit(&setup, "test-viewer", "image/png").await.unwrap();
+    }
+
+    assert_eq!(
+        submit(&setup, "test-viewer", "image/png").await,
+        Err(StatusCode::TOO_MANY_REQUESTS)
+    );
+    assert!(submit(&setup, "test-other-viewer", "image/png")
+        .await
+        .is_ok());
+}
+
+#[rstest::rstest]
+#[tokio::test]
+async fn test_approve_and_show() {
+    let setup = setup();
+    let submitted = submit(&setup, "test-viewer", "image/png").await.unwrap();
+
+    let reviewed = review(&setup, submitted.id, SubmissionDecision::ApproveAndShow)
+        .await
+        .unwrap();
+
+    assert_eq!(reviewed.status, Submission::APPROVED);
+    assert_eq!(reviewed.reviewed_by.as_deref(), Some("test-editor"));
+    let asset = setup
+        .database
+        .get_asset(&submitted.local_filename)
+        .unwrap()
+        .unwrap();
+    assert_eq!(asset.username, "test-broadcaster");
+    let state = setup
+        .controller
+        .get_state("test-broadcaster")
+        .await
+        .unwrap();
+    assert_eq!(state.assets.len(), 1);
+    assert_eq!(
+        state.assets[0].url,
+        format!("/api/assets/test-broadcaster/{}", submitted.local_filename)
+    );
+}
+
+#[rstest::rstest]
+#[tokio::test]
+async fn test_approving_known_image_links_existing_asset() {
+    let setup = setup();
+    let existing = TestAsset::new("old-hype.png")
+        .with_data("hype")
+        .as_db_asset(&TestUser::new("test-broadcaster").as_db_user());
+    setup.database.create_asset(&existing).unwrap();
+    let submitted = submit(&setup, "test-viewer", "image/png").await.unwrap();
+
+    let reviewed = review(&setup, submitted.id, SubmissionDecision::ApproveAndShow)
+        .await
+        .unwrap();
+
+    assert_eq!(reviewed.status, Submission::APPROVED);
+    assert!(setup.asset_dir.files().is_empty());
+    assert!(setup
+        .database
+        .get_asset(&submitted.local_filename)
+        .unwrap()
+        .is_none());
+    let state = setup
+        .controller
+        .get_state("test-broadcaster")
+        .await
+        .unwrap();
+    assert_eq!(
+        state.assets[0].url,
+        format!("/api/assets/test-broadcaster/{}", existing.local_filename)
+    );
+}
+
+#[rstest::rstest]
+#[tokio::test]
+async fn test_approving_image_of_other_channel_conflicts() {
+    let setup = setup();
+    let other = TestUser::new("test-other-broadcaster").as_db_user();
+    setup.database.create_user(&other).unwrap();
+    setup
+        .database
+        .create_asset(
+            &TestAsset::new("hype.png")
+                .with_data("hype")
+                .as_db_asset(&other),
+        )
+        .unwrap();
+    let submitted = submit(&setup, "test-viewer", "image/png").await.unwrap();
+
+    assert_eq!(
+        review(&setup, submitted.id, SubmissionDecision::Approve).await,
+        Err(StatusCode::CONFLICT)
+    );
+    let pending = setup
+        .database
+        .get_submission(
+            &TestUser::new("test-broadcaster").as_db_user(),
+            submitted.id,
+        )
+        .unwrap()
+        .unwrap();
+    assert!(pending.is_pending());
+    assert_eq!(
+        review(&setup, submitted.id, SubmissionDecision::Reject)
+            .await
+            .unwrap()
+            .status,
+        Submission::REJECTED
+    );
+}
+
+#[rstest::rstest]
+#[tokio::test]
+async fn test_reject_removes_file_once() {
+    let setup = setup();
+    let submitted = submit(&setup, "test-viewer", "image/png").await.unwrap();
+
+    let reviewed = review(&setup, submitted.id, SubmissionDecision::Reject)
+        .await
+        .unwrap();
+
+    assert_eq!(reviewed.status, Submission::REJECTED);
+    assert!(setup.asset_dir.files().is_empty());
+    assert!(setup
+        .controller
+        .get_state("test-broadcaster")
+        .await
+        .is_none());
+    assert_eq!(
+        review(&setup, submitted.id, SubmissionDecision::Approve).await,
+        Err(StatusCode::CONFLICT)
+    );
+}
+
+#[rstest::rstest]
+#[tokio::test]
+async fn test_viewers_cannot_review() {
+    let setup = setup();
+    let submitted = submit(&setup, "test-viewer", "image/png").await.unwrap();
+
+    match submission::review(
+        State(Arc::clone(&setup.database)),
+        State(TestPermissions::new(&setup.database).0),
+        State(Arc::clone(&setup.submissions)),
+        TestUser::new("test-viewer").create_session(),
+        Path(("test-broadcaster".to_string(), submitted.id)),
+        Json(SubmissionReview {
+            decision: SubmissionDecision::Approve,
+        }),
+    )
+    .await
+    {
+        Ok(_) => panic!("handler returned success"),
+        Err(error) => assert_eq!(error.status(), StatusCode::FORBIDDEN),
+    }
+}
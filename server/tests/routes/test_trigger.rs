@@ -0,0 +1,216 @@
+use std::sync::Arc;
+
+use axum::{extract::State, http::StatusCode, response::IntoResponse};
+use http_body_util::BodyExt;
+use imgfloat::domain::extract::{Json, Path};
+use imgfloat::{
+    domain::db::{AssetRepository, Database, UserRepository},
+    models::{EventTrigger, UnownedEventTrigger},
+    routes::api::trigger,
+};
+
+use crate::fixture::{
+    eventsub::HELIX_USERS_RESPONSE, TestAsset, TestAuthenticator, TestDbService, TestEventSub,
+    TestPermissions, TestTwitchTokens, TestUser,
+};
+
+async fn setup() -> (Arc<dyn Database>, TestEventSub, String) {
+    let TestDbService(dbservice) = TestDbService::new();
+    let broadcaster = TestUser::new("test-user").as_db_user();
+    let asset = TestAsset::new("hype.png").as_db_asset(&broadcaster);
+    dbservice.create_user(&broadcaster).unwrap();
+    dbservice
+        .create_user(&TestUser::new("test-viewer").as_db_user())
+        .unwrap();
+    dbservice.create_asset(&asset).unwrap();
+    let database: Arc<dyn Database> = Arc::new(dbservice);
+    let authenticator = TestAuthenticator::new()
+        .with_helix_response("users", serde_json::from_str(HELIX_USERS_RESPONSE).unwrap());
+    let test_eventsub = TestEventSub::new(&database, authenticator);
+    let TestTwitchTokens(mut tokens) = TestTwitchTokens::default();
+    tokens.expires_in = 3600;
+    test_eventsub
+        .token_store
+        .store("test-user", &tokens)
+        .await
+        .unwrap();
+    (database, test_eventsub, asset.local_filename)
+}
+
+fn trigger_request(asset_filename: &str, event_type: &str) -> UnownedEventTrigger {
+    UnownedEventTrigger {
+        event_type: event_type.to_string(),
+        reward_id: None,
+        asset_filename: asset_filename.to_string(),
+        x: 0.0,
+        y: 0.0,
+        w: 100.0,
+        h: 100.0,
+        duration_seconds: 10,
+    }
+}
+
+#[rstest::rstest]
+#[tokio::test]
+async fn test_create_trigger_subscribes_once() {
+    let (
+        database,
+        TestEventSub {
+            service, client, ..
+        },
+        filename,
+    ) = setup().await;
+    let broadcaster = TestUser::new("test-user");
+
+    for _ in 0..2 {
+        let response = trigger::post(
+            State(Arc::clone(&database)),
+            State(TestPermissions::new(&database).0),
+            State(Some(Arc::clone(&service))),
+            broadcaster.create_session(),
+            Path("test-user".to_string()),
+            Json(trigger_request(&filename, "channel.follow")),
+        )
+        .await
+        .into_response();
+        assert_eq!(response.status(), StatusCode::CREATED);
+    }
+
+    let created = client.created.lock().unwrap().clone();
+    assert_eq!(created.len(), 1);
+    assert_eq!(created[0].r#type, "channel.follow");
+    assert_eq!(created[0].version, "2");
+    assert_eq!(
+        created[0].condition,
+        serde_json::json!({ "broadcaster_user_id": "12826", "moderator_user_id": "12826" })
+    );
+    assert_eq!(
+        created[0].transport.callback,
+        "https://example.com/api/eventsub"
+    );
+    let response = trigger::get(
+        State(Arc::clone(&database)),
+        State(TestPermissions::new(&database).0),
+        broadcaster.create_session(),
+        Path("test-user".to_string()),
+    )
+    .await
+    .into_response();
+    let body = response.into_body().collect().await.unwrap().to_bytes();
+    let triggers: Vec<EventTrigger> = serde_json::from_slice(&body).unwrap();
+    assert_eq!(triggers.len(), 2);
+}
+
+#[rstest::rstest]
+#[case::unsupported_event("channel.ban", StatusCode::BAD_REQUEST)]
+#[case::unknown_asset("channel.follow", StatusCode::NOT_FOUND)]
+#[tokio::test]
+async fn test_create_trigger_invalid(#[case] event_type: &str, #[case] expected: StatusCode) {
+    let (
+        database,
+        TestEventSub {
+            service, client, ..
+        },
+        _,
+    ) = setup().await;
+
+    let response = trigger::post(
+        State(Arc::clone(&database)),
+        State(TestPermissions::new(&database).0),
+        State(Some(service)),
+        TestUser::new("test-user").create_session(),
+        Path("test-user".to_string()),
+        Json(trigger_request("missing.png", event_type)),
+    )
+    .await
+    .into_response();
+
+    assert_eq!(response.status(), expected);
+    assert!(client.created.lock().unwrap().is_empty());
+}
+
+#[rstest::rstest]
+#[tokio::test]
+async fn test_create_trigger_forbidden() {
+    let (database, TestEventSub { service, .. }, filename) = setup().await;
+
+    let response = trigger::post(
+        State(Arc::clone(&database)),
+        State(TestPermissions::new(&database).0),
+        State(Some(service)),
+        TestUser::new("test-viewer").create_session(),
+        Path("test-user".to_string()),
+        Json(trigger_request(&filename, "channel.follow")),
+    )
+    .await
+    .into_response();
+
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+}
+
+#[rstest::rstest]
+#[tokio::test]
+async fn test_delete_last_trigger_unsubscribes() {
+    let (
+        database,
+        TestEventSub {
+            service, client, ..
+        },
+        filename,
+    ) = setup().await;
+    let broadcaster = TestUser::new("test-user");
+    let response = trigger::post(
+        State(Arc::clone(&database)),
+        State(TestPermissions::new(&database).0),
+        State(Some(Arc::clone(&service))),
+        broadcaster.create_session(),
+        Path("test-user".to_string()),
+        Json(trigger_request(&filename, "channel.raid")),
+    )
+    .await
+    .into_response();
+    let body = response.into_body().collect().await.unwrap().to_bytes();
+    let created: EventTrigger = serde_json::from_slice(&body).unwrap();
+    let subscription = database
+        .get_eventsub_subscription_for("test-user", "channel.raid")
+        .unwrap()
+        .unwrap();
+
+    let response = trigger::delete(
+        State(Arc::clone(&database)),
+        State(TestPermissions::new(&database).0),
+        State(Some(service)),
+        broadcaster.create_session(),
+        Path(("test-user".to_string(), created.id)),
+    )
+    .await
+    .into_response();
+
+    assert_eq!(response.status(), StatusCode::NO_CONTENT);
+    assert_eq!(*client.deleted.lock().unwrap(), vec![subscription.id]);
+    assert!(database
+        .get_eventsub_subscription_for("test-user", "channel.raid")
+        .unwrap()
+        .is_none());
+}
+
+#[rstest::rstest]
+#[tokio::test]
+async fn test_create_trigger_without_eventsub() {
+    let (database, _, filename) = setup().await;
+
+    let response = trigger::post(
+        State(Arc::clone(&database)),
+        State(TestPermissions::new(&database).0),
+        State(None),
+        TestUser::new("test-user").create_session(),
+        Path("test-user".to_string()),
+        Json(trigger_request(&filename, "channel.follow")),
+    )
+    .await
+    .into_response();
+
+    assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+    let user = TestUser::new("test-user").as_db_user();
+    assert!(database.get_event_triggers(&user).unwrap().is_empty());
+}
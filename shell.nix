@@ -5,6 +5,7 @@ pkgs.mkShell {
     pkgs.diesel-cli
     pkgs.libpqxx
     pkgs.sqlite
+    pkgs.postgresql
     pkgs.openssl
     pkgs.pkg-config
     pkgs.gcc